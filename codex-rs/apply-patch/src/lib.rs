@@ -2,7 +2,10 @@ mod parser;
 mod seek_sequence;
 mod standalone_executable;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::Utf8Error;
@@ -15,6 +18,8 @@ pub use parser::ParseError;
 use parser::ParseError::*;
 use parser::UpdateFileChunk;
 pub use parser::parse_patch;
+pub use seek_sequence::Fuzz;
+use seek_sequence::FuzzySeek;
 use similar::TextDiff;
 use thiserror::Error;
 use tree_sitter::LanguageError;
@@ -167,6 +172,9 @@ pub struct ApplyPatchAction {
 
     /// The working directory that was used to resolve relative paths in the patch.
     pub cwd: PathBuf,
+
+    /// Hunks whose lines only matched the files approximately.
+    fuzzy_hunks: Vec<FuzzyHunk>,
 }
 
 impl ApplyPatchAction {
@@ -179,6 +187,12 @@ impl ApplyPatchAction {
         &self.changes
     }
 
+    /// Hunks that will apply where their lines only approximately match the
+    /// files, in patch order.
+    pub fn fuzzy_hunks(&self) -> &[FuzzyHunk] {
+        &self.fuzzy_hunks
+    }
+
     /// Should be used exclusively for testing. (Not worth the overhead of
     /// creating a feature flag for this.)
     pub fn new_add_for_test(path: &Path, content: String) -> Self {
@@ -207,6 +221,7 @@ impl ApplyPatchAction {
                 .expect("path should have parent")
                 .to_path_buf(),
             patch,
+            fuzzy_hunks: Vec::new(),
         }
     }
 }
@@ -252,6 +267,7 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                 })
                 .unwrap_or_else(|| cwd.to_path_buf());
             let mut changes = HashMap::new();
+            let mut fuzzy_hunks = Vec::new();
             for hunk in hunks {
                 let path = hunk.resolve_path(&effective_cwd);
                 match hunk {
@@ -278,12 +294,14 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                         let ApplyPatchFileUpdate {
                             unified_diff,
                             content: contents,
+                            fuzzy_hunks: file_fuzzy_hunks,
                         } = match unified_diff_from_chunks(&path, &chunks) {
                             Ok(diff) => diff,
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(e);
                             }
                         };
+                        fuzzy_hunks.extend(file_fuzzy_hunks);
                         changes.insert(
                             path,
                             ApplyPatchFileChange::Update {
@@ -299,6 +317,7 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                 changes,
                 patch,
                 cwd: effective_cwd,
+                fuzzy_hunks,
             })
         }
         MaybeApplyPatch::ShellParseError(e) => MaybeApplyPatchVerified::ShellParseError(e),
@@ -561,35 +580,99 @@ pub struct AffectedPaths {
     pub added: Vec<PathBuf>,
     pub modified: Vec<PathBuf>,
     pub deleted: Vec<PathBuf>,
+    /// Hunks whose lines only matched the files approximately.
+    pub fuzzy_hunks: Vec<FuzzyHunk>,
+}
+
+/// A hunk of a file update whose lines matched the file only approximately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyHunk {
+    pub path: PathBuf,
+    /// 1-based position of the `@@` hunk among the hunks updating `path`.
+    pub hunk: usize,
+    /// 1-based line of the original file where the hunk's lines start.
+    pub line: usize,
+    pub fuzz: Fuzz,
+}
+
+impl fmt::Display for FuzzyHunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let how = match self.fuzz {
+            Fuzz::Whitespace => "ignoring whitespace and blank lines".to_string(),
+            Fuzz::Edits => format!(
+                "ignoring whitespace, blank lines and up to {} edits per line",
+                seek_sequence::MAX_LINE_EDITS
+            ),
+        };
+        write!(
+            f,
+            "hunk {} of {} applied at line {}, {how}",
+            self.hunk,
+            self.path.display(),
+            self.line
+        )
+    }
 }
 
 /// Apply the hunks to the filesystem, returning which files were added, modified, or deleted.
-/// Returns an error if the patch could not be applied.
+/// Returns an error if the patch could not be applied, in which case no file is changed.
 fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
     if hunks.is_empty() {
         anyhow::bail!("No files were modified.");
     }
 
+    let (files, affected) = stage_hunks(hunks)?;
+    let mut journal = Journal::default();
+    if let Err(err) = journal.commit(files) {
+        let rollback_errors = journal.rollback();
+        if rollback_errors.is_empty() {
+            return Err(err);
+        }
+        anyhow::bail!(
+            "{err}\nRolling back the patch also failed, so these paths may be left modified:\n{}",
+            rollback_errors.join("\n")
+        );
+    }
+    journal.finish();
+    Ok(affected)
+}
+
+/// The final contents of every path the hunks touch, `None` for paths to
+/// delete, computed without writing anything. Later hunks see the result of
+/// earlier ones.
+fn stage_hunks(
+    hunks: &[Hunk],
+) -> anyhow::Result<(BTreeMap<PathBuf, Option<String>>, AffectedPaths)> {
+    let mut files: BTreeMap<PathBuf, Option<String>> = BTreeMap::new();
     let mut added: Vec<PathBuf> = Vec::new();
     let mut modified: Vec<PathBuf> = Vec::new();
     let mut deleted: Vec<PathBuf> = Vec::new();
+    let mut fuzzy_hunks: Vec<FuzzyHunk> = Vec::new();
     for hunk in hunks {
         match hunk {
             Hunk::AddFile { path, contents } => {
-                if let Some(parent) = path.parent()
-                    && !parent.as_os_str().is_empty()
-                {
-                    std::fs::create_dir_all(parent).with_context(|| {
-                        format!("Failed to create parent directories for {}", path.display())
-                    })?;
-                }
-                std::fs::write(path, contents)
-                    .with_context(|| format!("Failed to write file {}", path.display()))?;
+                files.insert(path.clone(), Some(contents.clone()));
                 added.push(path.clone());
             }
             Hunk::DeleteFile { path } => {
-                std::fs::remove_file(path)
-                    .with_context(|| format!("Failed to delete file {}", path.display()))?;
+                match files.get(path) {
+                    Some(Some(_)) => {}
+                    Some(None) => anyhow::bail!(
+                        "Failed to delete file {}: it was already deleted by this patch",
+                        path.display()
+                    ),
+                    None => {
+                        let metadata = std::fs::symlink_metadata(path)
+                            .with_context(|| format!("Failed to delete file {}", path.display()))?;
+                        if metadata.is_dir() {
+                            anyhow::bail!(
+                                "Failed to delete file {}: it is a directory",
+                                path.display()
+                            );
+                        }
+                    }
+                }
+                files.insert(path.clone(), None);
                 deleted.push(path.clone());
             }
             Hunk::UpdateFile {
@@ -597,39 +680,284 @@ fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
                 move_path,
                 chunks,
             } => {
-                let AppliedPatch { new_contents, .. } =
-                    derive_new_contents_from_chunks(path, chunks)?;
+                let original_contents = match files.get(path) {
+                    Some(Some(contents)) => contents.clone(),
+                    Some(None) => anyhow::bail!(
+                        "Failed to update file {}: it was deleted by this patch",
+                        path.display()
+                    ),
+                    None => read_file_to_update(path)?,
+                };
+                let AppliedPatch {
+                    new_contents,
+                    fuzzy_hunks: file_fuzzy_hunks,
+                    ..
+                } = derive_new_contents(path, original_contents, chunks)?;
+                fuzzy_hunks.extend(file_fuzzy_hunks);
                 if let Some(dest) = move_path {
-                    if let Some(parent) = dest.parent()
-                        && !parent.as_os_str().is_empty()
-                    {
-                        std::fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create parent directories for {}", dest.display())
-                        })?;
-                    }
-                    std::fs::write(dest, new_contents)
-                        .with_context(|| format!("Failed to write file {}", dest.display()))?;
-                    std::fs::remove_file(path)
-                        .with_context(|| format!("Failed to remove original {}", path.display()))?;
+                    files.insert(path.clone(), None);
+                    files.insert(dest.clone(), Some(new_contents));
                     modified.push(dest.clone());
                 } else {
-                    std::fs::write(path, new_contents)
-                        .with_context(|| format!("Failed to write file {}", path.display()))?;
+                    files.insert(path.clone(), Some(new_contents));
                     modified.push(path.clone());
                 }
             }
         }
     }
-    Ok(AffectedPaths {
-        added,
-        modified,
-        deleted,
-    })
+    Ok((
+        files,
+        AffectedPaths {
+            added,
+            modified,
+            deleted,
+            fuzzy_hunks,
+        },
+    ))
+}
+
+/// Records the filesystem changes made while committing staged files, so that
+/// they can be undone if a later step fails.
+#[derive(Default)]
+struct Journal {
+    /// Directories created for new files, parents before children.
+    created_dirs: Vec<PathBuf>,
+    /// Temporary files holding new contents that are not in place yet.
+    pending: Vec<PathBuf>,
+    /// Originals moved or copied aside.
+    backups: Vec<Backup>,
+    /// Paths whose new contents were moved into place.
+    written: Vec<PathBuf>,
+}
+
+/// New contents written next to the file they replace.
+struct StagedFile {
+    temp: PathBuf,
+    /// Whether the contents must be copied into the existing file rather than
+    /// renamed over it, because it has other hard links or an owner the new
+    /// file cannot be given.
+    in_place: bool,
+}
+
+/// An original set aside while its path is replaced.
+struct Backup {
+    path: PathBuf,
+    backup: PathBuf,
+    /// The original was copied rather than moved, so it is restored by
+    /// copying it back into the file.
+    in_place: bool,
+}
+
+impl Journal {
+    /// Writes every new file next to its destination first, and only then
+    /// moves the originals aside and the new files into place, so that an
+    /// IO error leaves as little as possible to undo. New contents for a
+    /// symlink go to the file it points to.
+    fn commit(&mut self, files: BTreeMap<PathBuf, Option<String>>) -> anyhow::Result<()> {
+        let mut staged: Vec<(PathBuf, Option<StagedFile>)> = Vec::new();
+        for (path, contents) in files {
+            let Some(contents) = contents else {
+                staged.push((path, None));
+                continue;
+            };
+            let path = resolve_symlink(&path)?;
+            if std::fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_dir()) {
+                anyhow::bail!("Failed to write file {}: it is a directory", path.display());
+            }
+            if staged.iter().any(|(staged_path, _)| staged_path == &path) {
+                anyhow::bail!(
+                    "Failed to write file {}: the patch changes it through more than one path",
+                    path.display()
+                );
+            }
+            let staged_file = self.write_temp(&path, &contents)?;
+            staged.push((path, Some(staged_file)));
+        }
+
+        for (path, staged_file) in staged {
+            match staged_file {
+                Some(StagedFile {
+                    temp,
+                    in_place: true,
+                }) => {
+                    let backup = sibling_path(&path, "orig");
+                    std::fs::copy(&path, &backup)
+                        .with_context(|| format!("Failed to replace file {}", path.display()))?;
+                    self.backups.push(Backup {
+                        path: path.clone(),
+                        backup,
+                        in_place: true,
+                    });
+                    std::fs::copy(&temp, &path)
+                        .with_context(|| format!("Failed to write file {}", path.display()))?;
+                }
+                staged_file => {
+                    if std::fs::symlink_metadata(&path).is_ok() {
+                        let backup = sibling_path(&path, "orig");
+                        std::fs::rename(&path, &backup).with_context(|| {
+                            format!("Failed to replace file {}", path.display())
+                        })?;
+                        self.backups.push(Backup {
+                            path: path.clone(),
+                            backup,
+                            in_place: false,
+                        });
+                    }
+                    if let Some(StagedFile { temp, .. }) = staged_file {
+                        std::fs::rename(&temp, &path)
+                            .with_context(|| format!("Failed to write file {}", path.display()))?;
+                        self.pending.retain(|pending| pending != &temp);
+                        self.written.push(path);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_temp(&mut self, path: &Path, contents: &str) -> anyhow::Result<StagedFile> {
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent).with_context(|| {
+                format!("Failed to create parent directories for {}", path.display())
+            })?;
+        }
+        // Renaming over a read-only file would succeed where writing to it
+        // does not, so refuse it up front.
+        let existing = std::fs::metadata(path).ok();
+        if existing
+            .as_ref()
+            .is_some_and(|metadata| metadata.permissions().readonly())
+        {
+            return Err(anyhow::Error::new(std::io::Error::from(
+                std::io::ErrorKind::PermissionDenied,
+            ))
+            .context(format!("Failed to write file {}", path.display())));
+        }
+        let temp = sibling_path(path, "tmp");
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .with_context(|| format!("Failed to write file {}", path.display()))?;
+        self.pending.push(temp.clone());
+        file.write_all(contents.as_bytes())
+            .with_context(|| format!("Failed to write file {}", path.display()))?;
+        // Keep the mode of the file being replaced, e.g. its executable bit.
+        let mut in_place = false;
+        if let Some(metadata) = existing {
+            file.set_permissions(metadata.permissions())
+                .with_context(|| format!("Failed to write file {}", path.display()))?;
+            in_place = !can_replace_by_rename(&metadata, &file);
+        }
+        Ok(StagedFile { temp, in_place })
+    }
+
+    fn create_dir_all(&mut self, dir: &Path) -> std::io::Result<()> {
+        let mut missing: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        std::fs::create_dir_all(dir)?;
+        missing.reverse();
+        self.created_dirs.append(&mut missing);
+        Ok(())
+    }
+
+    /// Undoes every recorded change, returning a description of each step
+    /// that failed.
+    fn rollback(self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for path in self.written.iter().chain(&self.pending) {
+            if let Err(err) = std::fs::remove_file(path) {
+                errors.push(format!("{}: {err}", path.display()));
+            }
+        }
+        for Backup {
+            path,
+            backup,
+            in_place,
+        } in self.backups.iter().rev()
+        {
+            let restored = if *in_place {
+                std::fs::copy(backup, path).and_then(|_| std::fs::remove_file(backup))
+            } else {
+                std::fs::rename(backup, path)
+            };
+            if let Err(err) = restored {
+                errors.push(format!(
+                    "{}: {err} (the original is at {})",
+                    path.display(),
+                    backup.display()
+                ));
+            }
+        }
+        for dir in self.created_dirs.iter().rev() {
+            if let Err(err) = std::fs::remove_dir(dir) {
+                errors.push(format!("{}: {err}", dir.display()));
+            }
+        }
+        errors
+    }
+
+    /// Removes the originals, and the new contents copied into files, once
+    /// every file is in place.
+    fn finish(self) {
+        for path in self
+            .backups
+            .iter()
+            .map(|backup| &backup.backup)
+            .chain(&self.pending)
+        {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// The file `path` points to if it is a symlink, so that the link is kept
+/// and its target written.
+fn resolve_symlink(path: &Path) -> anyhow::Result<PathBuf> {
+    let is_symlink =
+        std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink());
+    if !is_symlink {
+        return Ok(path.to_path_buf());
+    }
+    std::fs::canonicalize(path)
+        .with_context(|| format!("Failed to resolve symlink {}", path.display()))
+}
+
+/// Whether renaming `file` over the file `metadata` describes keeps what
+/// other processes see of it: its other hard links, and its owner, which
+/// `file` is given here.
+#[cfg(unix)]
+fn can_replace_by_rename(metadata: &std::fs::Metadata, file: &std::fs::File) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.nlink() == 1
+        && std::os::unix::fs::fchown(file, Some(metadata.uid()), Some(metadata.gid())).is_ok()
+}
+
+#[cfg(not(unix))]
+fn can_replace_by_rename(_metadata: &std::fs::Metadata, _file: &std::fs::File) -> bool {
+    true
+}
+
+/// A hidden file next to `path` for its new contents or its original.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{name}.{}.apply_patch.{suffix}",
+        std::process::id()
+    ))
 }
 
 struct AppliedPatch {
     original_contents: String,
     new_contents: String,
+    fuzzy_hunks: Vec<FuzzyHunk>,
 }
 
 /// Return *only* the new file contents (joined into a single `String`) after
@@ -638,16 +966,25 @@ fn derive_new_contents_from_chunks(
     path: &Path,
     chunks: &[UpdateFileChunk],
 ) -> std::result::Result<AppliedPatch, ApplyPatchError> {
-    let original_contents = match std::fs::read_to_string(path) {
-        Ok(contents) => contents,
-        Err(err) => {
-            return Err(ApplyPatchError::IoError(IoError {
-                context: format!("Failed to read file to update {}", path.display()),
-                source: err,
-            }));
-        }
-    };
+    let original_contents = read_file_to_update(path)?;
+    derive_new_contents(path, original_contents, chunks)
+}
 
+fn read_file_to_update(path: &Path) -> std::result::Result<String, ApplyPatchError> {
+    std::fs::read_to_string(path).map_err(|err| {
+        ApplyPatchError::IoError(IoError {
+            context: format!("Failed to read file to update {}", path.display()),
+            source: err,
+        })
+    })
+}
+
+/// Applies the chunks to `original_contents`, the contents of `path`.
+fn derive_new_contents(
+    path: &Path,
+    original_contents: String,
+    chunks: &[UpdateFileChunk],
+) -> std::result::Result<AppliedPatch, ApplyPatchError> {
     let mut original_lines: Vec<String> = original_contents.split('\n').map(String::from).collect();
 
     // Drop the trailing empty element that results from the final newline so
@@ -656,7 +993,7 @@ fn derive_new_contents_from_chunks(
         original_lines.pop();
     }
 
-    let replacements = compute_replacements(&original_lines, path, chunks)?;
+    let (replacements, fuzzy_hunks) = compute_replacements(&original_lines, path, chunks)?;
     let new_lines = apply_replacements(original_lines, &replacements);
     let mut new_lines = new_lines;
     if !new_lines.last().is_some_and(String::is_empty) {
@@ -666,21 +1003,28 @@ fn derive_new_contents_from_chunks(
     Ok(AppliedPatch {
         original_contents,
         new_contents,
+        fuzzy_hunks,
     })
 }
 
+/// Lines `(start_index, old_len, new_lines)` of a file to replace.
+type Replacement = (usize, usize, Vec<String>);
+
 /// Compute a list of replacements needed to transform `original_lines` into the
 /// new lines, given the patch `chunks`. Each replacement is returned as
-/// `(start_index, old_len, new_lines)`.
+/// `(start_index, old_len, new_lines)`, along with the chunks that could only
+/// be placed approximately.
 fn compute_replacements(
     original_lines: &[String],
     path: &Path,
     chunks: &[UpdateFileChunk],
-) -> std::result::Result<Vec<(usize, usize, Vec<String>)>, ApplyPatchError> {
-    let mut replacements: Vec<(usize, usize, Vec<String>)> = Vec::new();
+) -> std::result::Result<(Vec<Replacement>, Vec<FuzzyHunk>), ApplyPatchError> {
+    let mut replacements: Vec<Replacement> = Vec::new();
+    let mut fuzzy_hunks: Vec<FuzzyHunk> = Vec::new();
     let mut line_index: usize = 0;
 
-    for chunk in chunks {
+    for (chunk_index, chunk) in chunks.iter().enumerate() {
+        let hunk = describe_hunk(chunk_index, chunk);
         // If a chunk has a `change_context`, we use seek_sequence to find it, then
         // adjust our `line_index` to continue from there.
         if let Some(ctx_line) = &chunk.change_context {
@@ -693,9 +1037,9 @@ fn compute_replacements(
                 line_index = idx + 1;
             } else {
                 return Err(ApplyPatchError::ComputeReplacements(format!(
-                    "Failed to find context '{}' in {}",
-                    ctx_line,
-                    path.display()
+                    "Failed to apply {hunk} to {}: context '{ctx_line}' was not found{}",
+                    path.display(),
+                    after_line(line_index),
                 )));
             }
         }
@@ -748,18 +1092,138 @@ fn compute_replacements(
         if let Some(start_idx) = found {
             replacements.push((start_idx, pattern.len(), new_slice.to_vec()));
             line_index = start_idx + pattern.len();
-        } else {
-            return Err(ApplyPatchError::ComputeReplacements(format!(
-                "Failed to find expected lines in {}:\n{}",
-                path.display(),
-                chunk.old_lines.join("\n"),
-            )));
+            continue;
+        }
+
+        // As a last resort, place the chunk where its lines match
+        // approximately, as long as that place is unambiguous.
+        match seek_sequence::fuzzy_seek_sequence(
+            original_lines,
+            pattern,
+            line_index,
+            chunk.is_end_of_file,
+        ) {
+            FuzzySeek::Found(matched) => {
+                let new_lines = merge_fuzzy_match(original_lines, &matched, pattern, new_slice);
+                replacements.push((matched.start, matched.len, new_lines));
+                line_index = matched.start + matched.len;
+                fuzzy_hunks.push(FuzzyHunk {
+                    path: path.to_path_buf(),
+                    hunk: chunk_index + 1,
+                    line: matched.start + 1,
+                    fuzz: matched.fuzz,
+                });
+            }
+            FuzzySeek::Ambiguous(starts) => {
+                let lines = starts
+                    .iter()
+                    .map(|start| (start + 1).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(ApplyPatchError::ComputeReplacements(format!(
+                    "Failed to apply {hunk} to {}: its lines match only approximately, and equally well at lines {lines}. Add context lines that tell these places apart. Expected lines:\n{}",
+                    path.display(),
+                    chunk.old_lines.join("\n"),
+                )));
+            }
+            FuzzySeek::NotFound => {
+                return Err(ApplyPatchError::ComputeReplacements(format!(
+                    "Failed to apply {hunk} to {}: {}. Expected lines:\n{}",
+                    path.display(),
+                    describe_mismatch(original_lines, pattern, line_index),
+                    chunk.old_lines.join("\n"),
+                )));
+            }
         }
     }
 
     replacements.sort_by(|(lhs_idx, _, _), (rhs_idx, _, _)| lhs_idx.cmp(rhs_idx));
 
-    Ok(replacements)
+    Ok((replacements, fuzzy_hunks))
+}
+
+/// The lines to put in place of the span of `lines` that `pattern` matched
+/// approximately, turning it into `new_slice`. The file keeps its own version
+/// of the context lines, which may differ from the pattern's in whitespace or
+/// edits, and of the blank lines the pattern skipped; only the lines the
+/// chunk removes are replaced.
+fn merge_fuzzy_match(
+    lines: &[String],
+    matched: &seek_sequence::FuzzyMatch,
+    pattern: &[String],
+    new_slice: &[String],
+) -> Vec<String> {
+    let mut merged = Vec::new();
+    // The first file line of the span not yet kept or replaced.
+    let mut next = matched.start;
+    for op in similar::capture_diff_slices(similar::Algorithm::Myers, pattern, new_slice) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        for pattern_index in old_range {
+            let Some(line) = matched.lines[pattern_index] else {
+                continue;
+            };
+            // Blank lines the pattern skipped stay, and so does a context line.
+            let end = if tag == similar::DiffTag::Equal {
+                line + 1
+            } else {
+                line
+            };
+            merged.extend_from_slice(&lines[next..end]);
+            next = line + 1;
+        }
+        if tag != similar::DiffTag::Equal {
+            merged.extend_from_slice(&new_slice[new_range]);
+        }
+    }
+    merged.extend_from_slice(&lines[next..matched.start + matched.len]);
+    merged
+}
+
+/// Names a chunk the way the patch author wrote it, e.g. `hunk 2 (@@ fn main)`.
+fn describe_hunk(chunk_index: usize, chunk: &UpdateFileChunk) -> String {
+    match &chunk.change_context {
+        Some(context) => format!("hunk {} (@@ {context})", chunk_index + 1),
+        None => format!("hunk {}", chunk_index + 1),
+    }
+}
+
+fn after_line(line_index: usize) -> String {
+    if line_index == 0 {
+        String::new()
+    } else {
+        format!(" after line {line_index}")
+    }
+}
+
+/// Explains which line of `pattern` has no counterpart in `lines`, using the
+/// position where the longest run of its leading lines matches.
+fn describe_mismatch(lines: &[String], pattern: &[String], start: usize) -> String {
+    match seek_sequence::longest_prefix_match(lines, pattern, start) {
+        Some((index, matched)) => match (pattern.get(matched), lines.get(index + matched)) {
+            (Some(expected), Some(actual)) => format!(
+                "line {} of the hunk, '{expected}', does not match line {} of the file, '{actual}'",
+                matched + 1,
+                index + matched + 1,
+            ),
+            (Some(expected), None) => format!(
+                "line {} of the hunk, '{expected}', is past the end of the file",
+                matched + 1,
+            ),
+            // The whole pattern matches, but not where the chunk requires it
+            // (the end of the file).
+            (None, _) => format!(
+                "the lines match at line {}, but the hunk must end the file",
+                index + 1
+            ),
+        },
+        None => match pattern.first() {
+            Some(first) => format!(
+                "line 1 of the hunk, '{first}', was not found{}",
+                after_line(start)
+            ),
+            None => "the hunk is empty".to_string(),
+        },
+    }
 }
 
 /// Apply the `(start_index, old_len, new_lines)` replacements to `original_lines`,
@@ -795,6 +1259,7 @@ fn apply_replacements(
 pub struct ApplyPatchFileUpdate {
     unified_diff: String,
     content: String,
+    fuzzy_hunks: Vec<FuzzyHunk>,
 }
 
 pub fn unified_diff_from_chunks(
@@ -812,12 +1277,14 @@ pub fn unified_diff_from_chunks_with_context(
     let AppliedPatch {
         original_contents,
         new_contents,
+        fuzzy_hunks,
     } = derive_new_contents_from_chunks(path, chunks)?;
     let text_diff = TextDiff::from_lines(&original_contents, &new_contents);
     let unified_diff = text_diff.unified_diff().context_radius(context).to_string();
     Ok(ApplyPatchFileUpdate {
         unified_diff,
         content: new_contents,
+        fuzzy_hunks,
     })
 }
 
@@ -837,6 +1304,15 @@ pub fn print_summary(
     for path in &affected.deleted {
         writeln!(out, "D {}", path.display())?;
     }
+    if !affected.fuzzy_hunks.is_empty() {
+        writeln!(
+            out,
+            "Some hunks matched only approximately; check the result:"
+        )?;
+        for fuzzy_hunk in &affected.fuzzy_hunks {
+            writeln!(out, "  {fuzzy_hunk}")?;
+        }
+    }
     Ok(())
 }
 
@@ -1373,6 +1849,7 @@ PATCH"#,
         let expected = ApplyPatchFileUpdate {
             unified_diff: expected_diff.to_string(),
             content: "foo\nBAR\nbaz\nQUX\n".to_string(),
+            fuzzy_hunks: Vec::new(),
         };
         assert_eq!(expected, diff);
     }
@@ -1409,6 +1886,7 @@ PATCH"#,
         let expected = ApplyPatchFileUpdate {
             unified_diff: expected_diff.to_string(),
             content: "FOO\nbar\nbaz\n".to_string(),
+            fuzzy_hunks: Vec::new(),
         };
         assert_eq!(expected, diff);
     }
@@ -1446,6 +1924,7 @@ PATCH"#,
         let expected = ApplyPatchFileUpdate {
             unified_diff: expected_diff.to_string(),
             content: "foo\nbar\nBAZ\n".to_string(),
+            fuzzy_hunks: Vec::new(),
         };
         assert_eq!(expected, diff);
     }
@@ -1480,6 +1959,7 @@ PATCH"#,
         let expected = ApplyPatchFileUpdate {
             unified_diff: expected_diff.to_string(),
             content: "foo\nbar\nbaz\nquux\n".to_string(),
+            fuzzy_hunks: Vec::new(),
         };
         assert_eq!(expected, diff);
     }
@@ -1535,6 +2015,7 @@ PATCH"#,
         let expected = ApplyPatchFileUpdate {
             unified_diff: expected_diff.to_string(),
             content: "a\nB\nc\nd\nE\nf\ng\n".to_string(),
+            fuzzy_hunks: Vec::new(),
         };
 
         assert_eq!(expected, diff);
@@ -1598,6 +2079,7 @@ g
                 )]),
                 patch: argv[1].clone(),
                 cwd: session_dir.path().to_path_buf(),
+                fuzzy_hunks: Vec::new(),
             })
         );
     }
@@ -1621,4 +2103,218 @@ g
         let result = apply_patch(&patch, &mut stdout, &mut stderr);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_failed_hunk_leaves_every_file_untouched() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        let doomed = dir.path().join("doomed.txt");
+        let added = dir.path().join("new/dir/added.txt");
+        fs::write(&first, "one\n").unwrap();
+        fs::write(&second, "two\n").unwrap();
+        fs::write(&doomed, "three\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+-one
++ONE
+*** Add File: {}
++added
+*** Delete File: {}
+*** Update File: {}
+@@ fn main
+-two
++TWO
+@@
+ two
+-missing
++found"#,
+            first.display(),
+            added.display(),
+            doomed.display(),
+            second.display(),
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let result = apply_patch(&patch, &mut stdout, &mut stderr);
+        assert!(result.is_err());
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            format!(
+                "Failed to apply hunk 1 (@@ fn main) to {}: context 'fn main' was not found\n",
+                second.display()
+            )
+        );
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one\n");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "two\n");
+        assert_eq!(fs::read_to_string(&doomed).unwrap(), "three\n");
+        assert!(!dir.path().join("new").exists());
+    }
+
+    #[test]
+    fn test_failed_write_rolls_back_earlier_files() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let blocked = dir.path().join("z_blocked");
+        fs::write(&first, "one\n").unwrap();
+        // A file where a directory is needed makes the last add fail after
+        // the other files and directories were written.
+        fs::write(&blocked, "").unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n-one\n+ONE\n*** Add File: {}\n+new\n*** Add File: {}\n+nope",
+            first.display(),
+            dir.path().join("new/sub/added.txt").display(),
+            blocked.join("added.txt").display(),
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        assert!(apply_patch(&patch, &mut stdout, &mut stderr).is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one\n");
+        let mut entries: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["first.txt", "z_blocked"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_updates_go_through_symlinks_and_hard_links() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        let symlink = dir.path().join("link.txt");
+        let hard_link = dir.path().join("hard.txt");
+        fs::write(&target, "one\n").unwrap();
+        std::os::unix::fs::symlink(&target, &symlink).unwrap();
+        fs::hard_link(&target, &hard_link).unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n-one\n+ONE",
+            symlink.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+        assert!(
+            fs::symlink_metadata(&symlink)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap(), "ONE\n");
+        assert_eq!(fs::read_to_string(&hard_link).unwrap(), "ONE\n");
+        let mut entries: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["hard.txt", "link.txt", "target.txt"]);
+    }
+
+    #[test]
+    fn test_add_file_refuses_to_replace_a_directory() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let existing = dir.path().join("sub");
+        fs::write(&first, "one\n").unwrap();
+        fs::create_dir(&existing).unwrap();
+        fs::write(existing.join("kept.txt"), "kept\n").unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n-one\n+ONE\n*** Add File: {}\n+new",
+            first.display(),
+            existing.display(),
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        assert!(apply_patch(&patch, &mut stdout, &mut stderr).is_err());
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            format!(
+                "Failed to write file {}: it is a directory\n",
+                existing.display()
+            )
+        );
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one\n");
+        assert_eq!(
+            fs::read_to_string(existing.join("kept.txt")).unwrap(),
+            "kept\n"
+        );
+        let mut entries: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["first.txt", "sub"]);
+    }
+
+    #[test]
+    fn test_mismatch_names_the_first_line_that_differs() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        fs::write(&path, "fn a() {\n    let x = 1;\n    x + 1\n}\n").unwrap();
+        let chunks = vec![UpdateFileChunk {
+            change_context: None,
+            old_lines: vec![
+                "fn a() {".to_string(),
+                "    let x = 1;".to_string(),
+                "    x + 2".to_string(),
+            ],
+            new_lines: vec!["fn a() {".to_string()],
+            is_end_of_file: false,
+        }];
+
+        let err = unified_diff_from_chunks(&path, &chunks).unwrap_err();
+        assert_eq!(
+            err,
+            ApplyPatchError::ComputeReplacements(format!(
+                "Failed to apply hunk 1 to {}: line 3 of the hunk, '    x + 2', does not match line 3 of the file, '    x + 1'. Expected lines:\nfn a() {{\n    let x = 1;\n    x + 2",
+                path.display()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_hunks_are_reported() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("main.py");
+        fs::write(&path, "def main():\n\tx = 1\n\n\treturn x\n").unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n     x = 1\n-    return x\n+    return x + 1",
+            path.display()
+        ));
+        let expected = vec![FuzzyHunk {
+            path: path.clone(),
+            hunk: 1,
+            line: 2,
+            fuzz: Fuzz::Whitespace,
+        }];
+
+        let argv = vec!["apply_patch".to_string(), patch.clone()];
+        match maybe_parse_apply_patch_verified(&argv, dir.path()) {
+            MaybeApplyPatchVerified::Body(action) => {
+                assert_eq!(action.fuzzy_hunks(), expected.as_slice());
+            }
+            other => panic!("expected a patch, got {other:?}"),
+        }
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            format!(
+                "Success. Updated the following files:\nM {0}\nSome hunks matched only approximately; check the result:\n  hunk 1 of {0} applied at line 2, ignoring whitespace and blank lines\n",
+                path.display()
+            )
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "def main():\n\tx = 1\n\n    return x + 1\n"
+        );
+    }
 }
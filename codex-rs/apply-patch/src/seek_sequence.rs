@@ -73,26 +73,6 @@ pub(crate) fn seek_sequence(
     // differences when locating context lines.
     // ------------------------------------------------------------------
 
-    fn normalise(s: &str) -> String {
-        s.trim()
-            .chars()
-            .map(|c| match c {
-                // Various dash / hyphen code-points → ASCII '-'
-                '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}'
-                | '\u{2212}' => '-',
-                // Fancy single quotes → '\''
-                '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
-                // Fancy double quotes → '"'
-                '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
-                // Non-breaking space and other odd spaces → normal space
-                '\u{00A0}' | '\u{2002}' | '\u{2003}' | '\u{2004}' | '\u{2005}' | '\u{2006}'
-                | '\u{2007}' | '\u{2008}' | '\u{2009}' | '\u{200A}' | '\u{202F}' | '\u{205F}'
-                | '\u{3000}' => ' ',
-                other => other,
-            })
-            .collect::<String>()
-    }
-
     for i in search_start..=lines.len().saturating_sub(pattern.len()) {
         let mut ok = true;
         for (p_idx, pat) in pattern.iter().enumerate() {
@@ -109,8 +89,235 @@ pub(crate) fn seek_sequence(
     None
 }
 
+/// How a pattern that only matched approximately differed from the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fuzz {
+    /// Lines differed in indentation or other whitespace, or the pattern
+    /// lacked or added blank lines.
+    Whitespace,
+    /// Additionally, some lines differed by up to [`MAX_LINE_EDITS`]
+    /// characters.
+    Edits,
+}
+
+/// Most single-character edits tolerated in one line by [`Fuzz::Edits`].
+pub const MAX_LINE_EDITS: usize = 2;
+
+/// Shorter lines must match without edits, since two edits can turn one
+/// short line into an unrelated one.
+const MIN_EDITED_LINE_LEN: usize = 8;
+
+/// Where [`fuzzy_seek_sequence`] placed a pattern.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FuzzyMatch {
+    pub(crate) start: usize,
+    /// Number of file lines the match covers, which differs from the
+    /// pattern's when blank lines were skipped.
+    pub(crate) len: usize,
+    /// The file line each pattern line matched, or `None` for a blank
+    /// pattern line the file lacks.
+    pub(crate) lines: Vec<Option<usize>>,
+    pub(crate) fuzz: Fuzz,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum FuzzySeek {
+    Found(FuzzyMatch),
+    /// The pattern matched equally well at each of these indices.
+    Ambiguous(Vec<usize>),
+    NotFound,
+}
+
+/// Fallback for when [`seek_sequence`] finds nothing: first compares lines
+/// ignoring all whitespace differences and tolerates blank lines missing from
+/// either side, then also allows up to [`MAX_LINE_EDITS`] edits per line. A
+/// match is only returned if it is the single one for the first of those
+/// modes that finds any, and at least one non-blank line must match without
+/// edits. When `eof` is true, the match must end the file.
+pub(crate) fn fuzzy_seek_sequence(
+    lines: &[String],
+    pattern: &[String],
+    start: usize,
+    eof: bool,
+) -> FuzzySeek {
+    let pattern: Vec<String> = pattern.iter().map(|line| fuzzy_key(line)).collect();
+    if pattern.iter().all(String::is_empty) {
+        return FuzzySeek::NotFound;
+    }
+    let lines: Vec<String> = lines.iter().map(|line| fuzzy_key(line)).collect();
+
+    for fuzz in [Fuzz::Whitespace, Fuzz::Edits] {
+        // Keyed by the first non-blank line matched, so that a pattern
+        // starting with a blank line is not found twice at the same place.
+        let mut found: Vec<(usize, FuzzyMatch)> = Vec::new();
+        for i in start..lines.len() {
+            let Some((anchor, matched_lines, len)) = match_at(&lines, &pattern, i, fuzz) else {
+                continue;
+            };
+            if eof && !lines[i + len..].iter().all(String::is_empty) {
+                continue;
+            }
+            if found.iter().all(|(other, _)| *other != anchor) {
+                found.push((
+                    anchor,
+                    FuzzyMatch {
+                        start: i,
+                        len,
+                        lines: matched_lines,
+                        fuzz,
+                    },
+                ));
+            }
+        }
+        match found.len() {
+            0 => continue,
+            1 => {
+                let (_, matched) = found.remove(0);
+                return FuzzySeek::Found(matched);
+            }
+            _ => {
+                return FuzzySeek::Ambiguous(
+                    found
+                        .into_iter()
+                        .map(|(_, matched)| matched.start)
+                        .collect(),
+                );
+            }
+        }
+    }
+    FuzzySeek::NotFound
+}
+
+/// Matches `pattern` against `lines` starting exactly at `start`, returning
+/// the index of the first non-blank line matched, the line each pattern line
+/// matched as in [`FuzzyMatch::lines`], and the number of lines covered.
+fn match_at(
+    lines: &[String],
+    pattern: &[String],
+    start: usize,
+    fuzz: Fuzz,
+) -> Option<(usize, Vec<Option<usize>>, usize)> {
+    let mut line = start;
+    let mut anchor = None;
+    let mut matched_lines = Vec::with_capacity(pattern.len());
+    let mut exact_lines = 0;
+    for expected in pattern {
+        if expected.is_empty() {
+            // A blank line in the pattern may be missing from the file.
+            if lines.get(line).is_some_and(String::is_empty) {
+                matched_lines.push(Some(line));
+                line += 1;
+            } else {
+                matched_lines.push(None);
+            }
+            continue;
+        }
+        // Blank lines in the file may be missing from the pattern, except
+        // before the first line matched.
+        if line > start {
+            while lines.get(line).is_some_and(String::is_empty) {
+                line += 1;
+            }
+        }
+        let actual = lines.get(line)?;
+        if actual == expected {
+            exact_lines += 1;
+        } else if !(fuzz == Fuzz::Edits
+            && expected.chars().count() >= MIN_EDITED_LINE_LEN
+            && within_edits(actual, expected, MAX_LINE_EDITS))
+        {
+            return None;
+        }
+        anchor.get_or_insert(line);
+        matched_lines.push(Some(line));
+        line += 1;
+    }
+    let anchor = anchor?;
+    (exact_lines > 0).then_some((anchor, matched_lines, line - start))
+}
+
+/// Whether `a` can be turned into `b` with at most `max` single-character
+/// insertions, deletions or substitutions.
+fn within_edits(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|&distance| distance > max) {
+            return false;
+        }
+        previous = current;
+    }
+    previous[b.len()] <= max
+}
+
+/// `line` as compared by [`fuzzy_seek_sequence`]: normalised like the last
+/// pass of [`seek_sequence`], with runs of whitespace collapsed.
+fn fuzzy_key(line: &str) -> String {
+    normalise(line)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The position after `start` where the longest prefix of `pattern` matches,
+/// ignoring leading and trailing whitespace, as `(index, matched_len)`.
+/// Returns `None` when not even the first line of `pattern` occurs. Used to
+/// report which line of a pattern [`seek_sequence`] failed on.
+pub(crate) fn longest_prefix_match(
+    lines: &[String],
+    pattern: &[String],
+    start: usize,
+) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    for i in start..lines.len() {
+        let matched = lines[i..]
+            .iter()
+            .zip(pattern)
+            .take_while(|(line, pat)| line.trim() == pat.trim())
+            .count();
+        if matched > 0 && best.is_none_or(|(_, best_len)| matched > best_len) {
+            best = Some((i, matched));
+        }
+    }
+    best
+}
+
+fn normalise(s: &str) -> String {
+    s.trim()
+        .chars()
+        .map(|c| match c {
+            // Various dash / hyphen code-points → ASCII '-'
+            '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}'
+            | '\u{2212}' => '-',
+            // Fancy single quotes → '\''
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
+            // Fancy double quotes → '"'
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
+            // Non-breaking space and other odd spaces → normal space
+            '\u{00A0}' | '\u{2002}' | '\u{2003}' | '\u{2004}' | '\u{2005}' | '\u{2006}'
+            | '\u{2007}' | '\u{2008}' | '\u{2009}' | '\u{200A}' | '\u{202F}' | '\u{205F}'
+            | '\u{3000}' => ' ',
+            other => other,
+        })
+        .collect::<String>()
+}
+
 #[cfg(test)]
 mod tests {
+    use super::Fuzz;
+    use super::FuzzyMatch;
+    use super::FuzzySeek;
+    use super::fuzzy_seek_sequence;
+    use super::longest_prefix_match;
     use super::seek_sequence;
     use std::string::ToString;
 
@@ -148,4 +355,79 @@ mod tests {
         // Should not panic – must return None when pattern cannot possibly fit.
         assert_eq!(seek_sequence(&lines, &pattern, 0, false), None);
     }
+
+    #[test]
+    fn test_longest_prefix_match_points_at_first_mismatch() {
+        let lines = to_vec(&["a", "b", "x", "a", "b", "c", "y"]);
+        let pattern = to_vec(&["a", "b", "c", "d"]);
+        assert_eq!(longest_prefix_match(&lines, &pattern, 0), Some((3, 3)));
+        assert_eq!(longest_prefix_match(&lines, &to_vec(&["z"]), 0), None);
+    }
+
+    #[test]
+    fn test_fuzzy_match_tolerates_indentation_and_blank_lines() {
+        let lines = to_vec(&[
+            "fn main() {",
+            "\tlet x = 1;",
+            "",
+            "\tprintln!(\"{x}\");",
+            "}",
+        ]);
+        let pattern = to_vec(&["    let x = 1;", "    println!(\"{x}\");", "}", ""]);
+        assert_eq!(seek_sequence(&lines, &pattern, 0, false), None);
+        assert_eq!(
+            fuzzy_seek_sequence(&lines, &pattern, 0, false),
+            FuzzySeek::Found(FuzzyMatch {
+                start: 1,
+                len: 4,
+                lines: vec![Some(1), Some(3), Some(4), None],
+                fuzz: Fuzz::Whitespace,
+            })
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_match_allows_a_few_edits_per_long_line() {
+        let lines = to_vec(&["let total = a + b;", "return total;", "}"]);
+        let pattern = to_vec(&["let totl = a + b;", "return total;"]);
+        assert_eq!(
+            fuzzy_seek_sequence(&lines, &pattern, 0, false),
+            FuzzySeek::Found(FuzzyMatch {
+                start: 0,
+                len: 2,
+                lines: vec![Some(0), Some(1)],
+                fuzz: Fuzz::Edits,
+            })
+        );
+        // Short lines and lines with more edits must match exactly.
+        let pattern = to_vec(&["let tot = a * c;", "return total;"]);
+        assert_eq!(
+            fuzzy_seek_sequence(&lines, &pattern, 0, false),
+            FuzzySeek::NotFound
+        );
+        let pattern = to_vec(&["retrun total;", "{"]);
+        assert_eq!(
+            fuzzy_seek_sequence(&lines, &pattern, 0, false),
+            FuzzySeek::NotFound
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_match_refuses_ambiguous_matches() {
+        let lines = to_vec(&["  x += 1;", "  y += 1;", "", "\tx += 1;", "\ty += 1;"]);
+        let pattern = to_vec(&["x += 1;", "y += 1;"]);
+        assert_eq!(
+            fuzzy_seek_sequence(&lines, &pattern, 0, false),
+            FuzzySeek::Ambiguous(vec![0, 3])
+        );
+        assert_eq!(
+            fuzzy_seek_sequence(&lines, &to_vec(&["", "y += 1;"]), 0, true),
+            FuzzySeek::Found(FuzzyMatch {
+                start: 4,
+                len: 1,
+                lines: vec![None, Some(4)],
+                fuzz: Fuzz::Whitespace,
+            })
+        );
+    }
 }
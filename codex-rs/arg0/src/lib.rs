@@ -142,6 +142,10 @@ where
 /// install a separate `apply_patch` executable, simplifying the deployment of
 /// Codex CLI.
 ///
+/// On Linux it also holds a `codex-linux-sandbox` symlink, which lets
+/// sandboxed PTY sessions (which cannot override `argv[0]`) start the sandbox
+/// helper.
+///
 /// IMPORTANT: This function modifies the PATH environment variable, so it MUST
 /// be called before multiple threads are spawned.
 fn prepend_path_entry_for_apply_patch() -> std::io::Result<TempDir> {
@@ -172,6 +176,9 @@ fn prepend_path_entry_for_apply_patch() -> std::io::Result<TempDir> {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    symlink(std::env::current_exe()?, path.join(LINUX_SANDBOX_ARG0))?;
+
     #[cfg(unix)]
     const PATH_SEPARATOR: &str = ":";
 
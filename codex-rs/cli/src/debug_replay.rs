@@ -0,0 +1,309 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use codex_common::CliConfigOverrides;
+use codex_common::summarize_sandbox_policy;
+use codex_core::RolloutRecorder;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::error::CodexErr;
+use codex_core::error::SandboxErr;
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::exec_env::create_env;
+use codex_core::get_platform_sandbox;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecCommandBeginEvent;
+use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::RolloutItem;
+use codex_core::protocol::TurnContextItem;
+use similar::TextDiff;
+
+use crate::ReplayCommand;
+
+/// An exec tool call as the rollout recorded it.
+#[derive(Debug)]
+struct RecordedExec {
+    begin: ExecCommandBeginEvent,
+    /// The turn context in effect when the command started.
+    turn_context: TurnContextItem,
+    /// Missing when the session ended before the command did.
+    end: Option<ExecCommandEndEvent>,
+}
+
+/// Re-runs the exec tool call `call_id` of a recorded session with the same
+/// argv, cwd, sandbox and timeout, then diffs its output against the
+/// recorded one. The environment comes from `create_env` with the current
+/// `shell_environment_policy`, as the session's own would have.
+///
+/// Exits with status 1 when the replay diverges from the recording.
+pub async fn run_replay(
+    command: ReplayCommand,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let ReplayCommand {
+        config_overrides,
+        rollout,
+        call_id,
+    } = command;
+
+    let history = RolloutRecorder::get_rollout_history(&rollout)
+        .await
+        .with_context(|| format!("failed to read rollout {}", rollout.display()))?;
+    let recorded = find_recorded_exec(&history.get_rollout_items(), &call_id)?;
+    let config = load_config(
+        config_overrides,
+        codex_linux_sandbox_exe,
+        &recorded.turn_context,
+    )?;
+
+    let sandbox_policy = &recorded.turn_context.sandbox_policy;
+    let sandbox_type = recorded.begin.sandbox_type;
+    if sandbox_type != SandboxType::None && get_platform_sandbox() != Some(sandbox_type) {
+        anyhow::bail!("{call_id} ran under {sandbox_type:?}, which this platform does not have");
+    }
+    let params = ExecParams {
+        command: recorded.begin.command.clone(),
+        cwd: recorded.begin.cwd.clone(),
+        timeout_ms: recorded.begin.timeout_ms,
+        env: create_env(&config.shell_environment_policy),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits: config.sandbox_resources,
+    };
+
+    let command_display = shlex::try_join(recorded.begin.command.iter().map(String::as_str))
+        .unwrap_or_else(|_| recorded.begin.command.join(" "));
+    println!("command: {command_display}");
+    println!("cwd: {}", recorded.begin.cwd.display());
+    println!(
+        "sandbox: {} ({sandbox_type:?})",
+        summarize_sandbox_policy(sandbox_policy)
+    );
+    if let Some(timeout_ms) = recorded.begin.timeout_ms {
+        println!("timeout: {timeout_ms}ms");
+    }
+
+    let replayed = match process_exec_tool_call(
+        params,
+        sandbox_type,
+        sandbox_policy,
+        &recorded.turn_context.cwd,
+        &config.codex_linux_sandbox_exe,
+        None,
+    )
+    .await
+    {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => *output,
+        Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => {
+            println!("replay timed out");
+            *output
+        }
+        Err(CodexErr::Sandbox(SandboxErr::ResourceLimit { limit, output })) => {
+            println!("replay exceeded the {limit}");
+            *output
+        }
+        Err(err) => return Err(err).context("failed to replay command"),
+    };
+
+    let Some(end) = &recorded.end else {
+        println!("the rollout has no recorded result for {call_id}; replay output:");
+        print!("{}", replayed.aggregated_output.text);
+        return Ok(());
+    };
+    match compare_with_recording(end, &replayed) {
+        None => {
+            println!("replay matches the recorded run");
+            Ok(())
+        }
+        Some(report) => {
+            print!("{report}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn load_config(
+    config_overrides: CliConfigOverrides,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    turn_context: &TurnContextItem,
+) -> anyhow::Result<Config> {
+    Ok(Config::load_with_cli_overrides(
+        config_overrides
+            .parse_overrides()
+            .map_err(anyhow::Error::msg)?,
+        ConfigOverrides {
+            cwd: Some(turn_context.cwd.clone()),
+            codex_linux_sandbox_exe,
+            ..Default::default()
+        },
+    )?)
+}
+
+/// Finds the exec call `call_id` in `items`, along with the turn context that
+/// was current when it began.
+fn find_recorded_exec(items: &[RolloutItem], call_id: &str) -> anyhow::Result<RecordedExec> {
+    let mut turn_context = None;
+    let mut recorded: Option<RecordedExec> = None;
+    for item in items {
+        match item {
+            RolloutItem::TurnContext(context) if recorded.is_none() => {
+                turn_context = Some(context.clone());
+            }
+            RolloutItem::EventMsg(EventMsg::ExecCommandBegin(begin))
+                if begin.call_id == call_id =>
+            {
+                let turn_context = turn_context
+                    .clone()
+                    .with_context(|| format!("the rollout has no turn context before {call_id}"))?;
+                recorded = Some(RecordedExec {
+                    begin: begin.clone(),
+                    turn_context,
+                    end: None,
+                });
+            }
+            RolloutItem::EventMsg(EventMsg::ExecCommandEnd(end)) if end.call_id == call_id => {
+                if let Some(recorded) = recorded.as_mut() {
+                    recorded.end = Some(end.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    recorded.with_context(|| {
+        format!(
+            "the rollout has no exec command with call id {call_id}; \
+             exec calls are recorded only with record_exec_results = true"
+        )
+    })
+}
+
+/// Describes how `replayed` differs from the recorded run, or `None` when
+/// the exit code and output are the same.
+fn compare_with_recording(
+    recorded: &ExecCommandEndEvent,
+    replayed: &ExecToolCallOutput,
+) -> Option<String> {
+    let mut report = String::new();
+    if recorded.exit_code != replayed.exit_code {
+        report.push_str(&format!(
+            "exit code: recorded {}, replay {}\n",
+            recorded.exit_code, replayed.exit_code
+        ));
+    }
+    let replayed_output = &replayed.aggregated_output.text;
+    if &recorded.aggregated_output != replayed_output {
+        let diff = TextDiff::from_lines(recorded.aggregated_output.as_str(), replayed_output)
+            .unified_diff()
+            .header("recorded", "replay")
+            .to_string();
+        report.push_str(&diff);
+    }
+    (!report.is_empty()).then_some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::exec::StreamOutput;
+    use codex_core::protocol::AskForApproval;
+    use codex_core::protocol::SandboxPolicy;
+    use codex_protocol::config_types::ReasoningSummary;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn turn_context(cwd: &str) -> RolloutItem {
+        RolloutItem::TurnContext(TurnContextItem {
+            cwd: PathBuf::from(cwd),
+            approval_policy: AskForApproval::OnRequest,
+            sandbox_policy: SandboxPolicy::ReadOnly,
+            model: "gpt-5".to_string(),
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+    }
+
+    fn begin(call_id: &str) -> RolloutItem {
+        RolloutItem::EventMsg(EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+            call_id: call_id.to_string(),
+            command: vec!["echo".to_string(), call_id.to_string()],
+            cwd: PathBuf::from("/repo"),
+            parsed_cmd: Vec::new(),
+            timeout_ms: Some(5_000),
+            sandbox_type: SandboxType::LinuxSeccomp,
+        }))
+    }
+
+    fn end(call_id: &str, exit_code: i32, output: &str) -> ExecCommandEndEvent {
+        ExecCommandEndEvent {
+            call_id: call_id.to_string(),
+            stdout: output.to_string(),
+            stderr: String::new(),
+            aggregated_output: output.to_string(),
+            exit_code,
+            duration: Duration::from_millis(10),
+            formatted_output: output.to_string(),
+        }
+    }
+
+    fn output(exit_code: i32, text: &str) -> ExecToolCallOutput {
+        ExecToolCallOutput {
+            exit_code,
+            stdout: StreamOutput::new(text.to_string()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(text.to_string()),
+            duration: Duration::from_millis(10),
+            timed_out: false,
+        }
+    }
+
+    #[test]
+    fn finds_the_call_with_the_turn_context_it_ran_under() {
+        let items = vec![
+            turn_context("/first"),
+            begin("call-1"),
+            turn_context("/second"),
+            begin("call-2"),
+            RolloutItem::EventMsg(EventMsg::ExecCommandEnd(end("call-2", 0, "call-2\n"))),
+            turn_context("/third"),
+        ];
+
+        let recorded = find_recorded_exec(&items, "call-2").expect("call-2 is recorded");
+
+        assert_eq!(recorded.begin.command, vec!["echo", "call-2"]);
+        assert_eq!(recorded.begin.timeout_ms, Some(5_000));
+        assert_eq!(recorded.begin.sandbox_type, SandboxType::LinuxSeccomp);
+        assert_eq!(recorded.turn_context.cwd, PathBuf::from("/second"));
+        assert_eq!(
+            recorded.end.map(|end| end.aggregated_output),
+            Some("call-2\n".to_string())
+        );
+        assert!(find_recorded_exec(&items, "call-3").is_err());
+    }
+
+    #[test]
+    fn reports_exit_code_and_output_differences() {
+        let recorded = end("call-1", 0, "a\nb\n");
+
+        assert_eq!(
+            compare_with_recording(&recorded, &output(0, "a\nb\n")),
+            None
+        );
+        assert_eq!(
+            compare_with_recording(&recorded, &output(1, "a\nc\n")),
+            Some(
+                "exit code: recorded 0, replay 1\n\
+                 --- recorded\n\
+                 +++ replay\n\
+                 @@ -1,2 +1,2 @@\n \
+                 a\n\
+                 -b\n\
+                 +c\n"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -95,6 +95,7 @@ async fn run_command_under_sandbox(
                 sandbox_policy_cwd.as_path(),
                 stdio_policy,
                 env,
+                config.sandbox_resources,
             )
             .await?
         }
@@ -111,6 +112,7 @@ async fn run_command_under_sandbox(
                 sandbox_policy_cwd.as_path(),
                 stdio_policy,
                 env,
+                config.sandbox_resources,
             )
             .await?
         }
@@ -1,8 +1,11 @@
+pub mod debug_replay;
 pub mod debug_sandbox;
 mod exit_status;
 pub mod login;
 pub mod proto;
 
+use std::path::PathBuf;
+
 use clap::Parser;
 use codex_common::CliConfigOverrides;
 
@@ -33,3 +36,17 @@ pub struct LandlockCommand {
     #[arg(trailing_var_arg = true)]
     pub command: Vec<String>,
 }
+
+#[derive(Debug, Parser)]
+pub struct ReplayCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Rollout file of the session that ran the command.
+    #[arg(value_name = "ROLLOUT")]
+    pub rollout: PathBuf,
+
+    /// Call id of the exec tool call to replay.
+    #[arg(long = "call-id", value_name = "CALL_ID")]
+    pub call_id: String,
+}
@@ -6,6 +6,7 @@ use codex_arg0::arg0_dispatch_or_else;
 use codex_chatgpt::apply_command::ApplyCommand;
 use codex_chatgpt::apply_command::run_apply_command;
 use codex_cli::LandlockCommand;
+use codex_cli::ReplayCommand;
 use codex_cli::SeatbeltCommand;
 use codex_cli::login::run_login_status;
 use codex_cli::login::run_login_with_api_key;
@@ -123,6 +124,9 @@ enum DebugCommand {
 
     /// Run a command under Landlock+seccomp (Linux only).
     Landlock(LandlockCommand),
+
+    /// Re-run an exec tool call recorded in a rollout and diff its output.
+    Replay(ReplayCommand),
 }
 
 #[derive(Debug, Parser)]
@@ -344,6 +348,13 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 )
                 .await?;
             }
+            DebugCommand::Replay(mut replay_cli) => {
+                prepend_config_flags(
+                    &mut replay_cli.config_overrides,
+                    root_config_overrides.clone(),
+                );
+                codex_cli::debug_replay::run_replay(replay_cli, codex_linux_sandbox_exe).await?;
+            }
         },
         Some(Subcommand::Apply(mut apply_cli)) => {
             prepend_config_flags(
@@ -6,7 +6,10 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
         SandboxPolicy::ReadOnly => "read-only".to_string(),
         SandboxPolicy::WorkspaceWrite {
             writable_roots,
+            read_only_subpaths: _,
+            deny_read_paths: _,
             network_access,
+            network_allowlist,
             exclude_tmpdir_env_var,
             exclude_slash_tmp,
         } => {
@@ -29,6 +32,8 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             summary.push_str(&format!(" [{}]", writable_entries.join(", ")));
             if *network_access {
                 summary.push_str(" (network access enabled)");
+            } else if !network_allowlist.is_empty() {
+                summary.push_str(&format!(" (network: {})", network_allowlist.join(", ")));
             }
             summary
         }
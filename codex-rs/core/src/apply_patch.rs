@@ -52,7 +52,7 @@ pub(crate) async fn apply_patch(
             action,
             user_explicitly_approved_this_action: user_explicitly_approved,
         }),
-        SafetyCheck::AskUser => {
+        SafetyCheck::AskUser { .. } => {
             // Compute a readable summary of path changes to include in the
             // approval request so the user can make an informed decision.
             //
@@ -61,7 +61,13 @@ pub(crate) async fn apply_patch(
             // that similar patches can be auto-approved in the future during
             // this session.
             let rx_approve = sess
-                .request_patch_approval(sub_id.to_owned(), call_id.to_owned(), &action, None, None)
+                .request_patch_approval(
+                    sub_id.to_owned(),
+                    call_id.to_owned(),
+                    &action,
+                    fuzzy_hunks_reason(&action),
+                    None,
+                )
                 .await;
             match rx_approve.await.unwrap_or_default() {
                 ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
@@ -83,6 +89,23 @@ pub(crate) async fn apply_patch(
     }
 }
 
+/// Points reviewers at hunks that will be placed where the file only
+/// approximately matches them.
+fn fuzzy_hunks_reason(action: &ApplyPatchAction) -> Option<String> {
+    let fuzzy_hunks = action.fuzzy_hunks();
+    if fuzzy_hunks.is_empty() {
+        return None;
+    }
+    let hunks = fuzzy_hunks
+        .iter()
+        .map(|hunk| format!("- {hunk}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(format!(
+        "Some hunks match the files only approximately:\n{hunks}"
+    ))
+}
+
 pub(crate) fn convert_apply_patch_to_protocol(
     action: &ApplyPatchAction,
 ) -> HashMap<PathBuf, FileChange> {
@@ -0,0 +1,624 @@
+//! Long-running processes started with the `background_job` tool.
+//!
+//! Dev servers and watchers never exit on their own, so they cannot go
+//! through the shell tool without hitting its timeout, and a unified_exec
+//! session running one blocks that session. A background job runs detached
+//! from the turn instead. Its stdout and stderr go to a log file kept below
+//! [`JOB_LOG_MAX_BYTES`], and the model follows the job through status, tail,
+//! grep and kill actions that refer to it by id.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_channel::Sender;
+use regex_lite::Regex;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Child;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::protocol::BackgroundJobSummary;
+use crate::protocol::BackgroundJobsEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+
+pub(crate) const BACKGROUND_JOB_TOOL_NAME: &str = "background_job";
+
+/// Directory under `codex_home` that holds job logs, one subdirectory per
+/// conversation.
+pub(crate) const JOB_LOGS_SUBDIR: &str = "job_logs";
+
+/// Once a job log grows past this size it is cut back to its newest half.
+const JOB_LOG_MAX_BYTES: usize = 2 * 1024 * 1024;
+const MAX_RUNNING_JOBS: usize = 8;
+const DEFAULT_TAIL_LINES: usize = 40;
+const MAX_TAIL_LINES: usize = 200;
+const MAX_GREP_MATCHES: usize = 100;
+const MAX_LINE_CHARS: usize = 400;
+/// How long to wait for a job's pipes to drain once the process is gone.
+const LOG_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a job has to exit after SIGTERM before its process group gets
+/// SIGKILL.
+const TERM_GRACE: Duration = Duration::from_secs(2);
+/// How long `kill` waits for a job to be reaped before reporting back.
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum BackgroundJobArgs {
+    Start {
+        command: Vec<String>,
+        #[serde(default)]
+        workdir: Option<String>,
+    },
+    Status {
+        #[serde(default)]
+        job_id: Option<u32>,
+    },
+    Tail {
+        job_id: u32,
+        #[serde(default)]
+        lines: Option<usize>,
+    },
+    Grep {
+        job_id: u32,
+        pattern: String,
+    },
+    Kill {
+        job_id: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JobState {
+    Running,
+    /// The process exited; `None` when it was ended by a signal.
+    Exited(Option<i32>),
+    Killed,
+}
+
+struct Job {
+    command: String,
+    log_path: PathBuf,
+    state: JobState,
+    started_at: Instant,
+    runtime: Option<Duration>,
+    kill_tx: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Job {
+    fn describe(&self, job_id: u32) -> String {
+        let state = match self.state {
+            JobState::Running => format!("running for {}s", self.started_at.elapsed().as_secs()),
+            JobState::Exited(Some(code)) => format!("exited with code {code}"),
+            JobState::Exited(None) => "terminated by a signal".to_string(),
+            JobState::Killed => "killed".to_string(),
+        };
+        let runtime = self
+            .runtime
+            .map(|runtime| format!(" after {}s", runtime.as_secs()))
+            .unwrap_or_default();
+        format!(
+            "job #{job_id} {state}{runtime}: `{}` (log: {})",
+            self.command,
+            self.log_path.display()
+        )
+    }
+}
+
+type JobTable = Arc<StdMutex<BTreeMap<u32, Job>>>;
+
+/// Tracks the background jobs of one session and reports the running set to
+/// the UI whenever it changes.
+pub(crate) struct BackgroundJobManager {
+    next_job_id: AtomicU32,
+    jobs: JobTable,
+    log_dir: PathBuf,
+    tx_event: Sender<Event>,
+}
+
+impl BackgroundJobManager {
+    pub(crate) fn new(log_dir: PathBuf, tx_event: Sender<Event>) -> Self {
+        Self {
+            next_job_id: AtomicU32::new(1),
+            jobs: Arc::new(StdMutex::new(BTreeMap::new())),
+            log_dir,
+            tx_event,
+        }
+    }
+
+    /// Refuse new jobs once [`MAX_RUNNING_JOBS`] are running. Checked before
+    /// the command is approved so the user is not prompted for a job that
+    /// could not start anyway.
+    pub(crate) fn ensure_capacity(&self) -> Result<(), String> {
+        let running = lock(&self.jobs)
+            .values()
+            .filter(|job| job.state == JobState::Running)
+            .count();
+        if running >= MAX_RUNNING_JOBS {
+            return Err(format!(
+                "{running} background jobs are already running; kill one before starting another"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Take ownership of a freshly spawned `child`, logging its output and
+    /// watching for it to exit. `sub_id` tags the events the job emits.
+    pub(crate) async fn start(
+        &self,
+        sub_id: &str,
+        command: &[String],
+        mut child: Child,
+    ) -> Result<String, String> {
+        tokio::fs::create_dir_all(&self.log_dir)
+            .await
+            .map_err(|err| format!("failed to create job log directory: {err}"))?;
+        let job_id = self.next_job_id.fetch_add(1, Ordering::Relaxed);
+        let log_path = self.log_dir.join(format!("job-{job_id}.log"));
+        let log_file = File::create(&log_path)
+            .await
+            .map_err(|err| format!("failed to create job log: {err}"))?;
+
+        let (chunk_tx, chunk_rx) = mpsc::channel(64);
+        if let Some(stdout) = child.stdout.take() {
+            pipe_to_log(stdout, chunk_tx.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            pipe_to_log(stderr, chunk_tx);
+        }
+        let writer = tokio::spawn(write_log(log_path.clone(), log_file, chunk_rx));
+
+        let command = command.join(" ");
+        let (kill_tx, kill_rx) = oneshot::channel();
+        {
+            let mut jobs = lock(&self.jobs);
+            let task = tokio::spawn(supervise(
+                job_id,
+                child,
+                writer,
+                kill_rx,
+                Arc::clone(&self.jobs),
+                self.tx_event.clone(),
+                sub_id.to_string(),
+            ));
+            jobs.insert(
+                job_id,
+                Job {
+                    command: command.clone(),
+                    log_path: log_path.clone(),
+                    state: JobState::Running,
+                    started_at: Instant::now(),
+                    runtime: None,
+                    kill_tx: Some(kill_tx),
+                    task: Some(task),
+                },
+            );
+            send_running_jobs(&jobs, &self.tx_event, sub_id);
+        }
+
+        Ok(format!(
+            "started job #{job_id}: `{command}`\noutput is logged to {}; use the status, tail, grep and kill actions with job_id {job_id} to follow it",
+            log_path.display()
+        ))
+    }
+
+    pub(crate) fn status(&self, job_id: Option<u32>) -> Result<String, String> {
+        let jobs = lock(&self.jobs);
+        match job_id {
+            Some(job_id) => jobs
+                .get(&job_id)
+                .map(|job| job.describe(job_id))
+                .ok_or_else(|| unknown_job(job_id)),
+            None if jobs.is_empty() => Ok("no background jobs".to_string()),
+            None => Ok(jobs
+                .iter()
+                .map(|(job_id, job)| job.describe(*job_id))
+                .collect::<Vec<_>>()
+                .join("\n")),
+        }
+    }
+
+    pub(crate) async fn tail(&self, job_id: u32, lines: Option<usize>) -> Result<String, String> {
+        let (header, log_path) = self.describe_with_log(job_id)?;
+        let log = read_log(&log_path).await?;
+        let lines = lines.unwrap_or(DEFAULT_TAIL_LINES).clamp(1, MAX_TAIL_LINES);
+        let all: Vec<&str> = log.lines().collect();
+        let tail = &all[all.len().saturating_sub(lines)..];
+
+        let mut out = format!("{header}\nlast {} of {} lines:", tail.len(), all.len());
+        for line in tail {
+            out.push('\n');
+            out.push_str(clip_line(line));
+        }
+        Ok(out)
+    }
+
+    pub(crate) async fn grep(&self, job_id: u32, pattern: &str) -> Result<String, String> {
+        let regex = Regex::new(pattern).map_err(|err| format!("invalid pattern: {err}"))?;
+        let (header, log_path) = self.describe_with_log(job_id)?;
+        let log = read_log(&log_path).await?;
+
+        let matches: Vec<(usize, &str)> = log
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| regex.is_match(line))
+            .collect();
+        let shown = &matches[matches.len().saturating_sub(MAX_GREP_MATCHES)..];
+
+        let mut out = format!("{header}\n{} matching lines", matches.len());
+        if shown.len() < matches.len() {
+            out.push_str(&format!(", showing the last {}", shown.len()));
+        }
+        out.push(':');
+        for (idx, line) in shown {
+            out.push_str(&format!("\n{}: {}", idx + 1, clip_line(line)));
+        }
+        Ok(out)
+    }
+
+    pub(crate) async fn kill(&self, job_id: u32) -> Result<String, String> {
+        let (kill_tx, task) = {
+            let mut jobs = lock(&self.jobs);
+            let job = jobs.get_mut(&job_id).ok_or_else(|| unknown_job(job_id))?;
+            (job.kill_tx.take(), job.task.take())
+        };
+        if let Some(kill_tx) = kill_tx {
+            let _ = kill_tx.send(());
+        }
+        if let Some(task) = task {
+            let _ = tokio::time::timeout(KILL_TIMEOUT, task).await;
+        }
+        self.status(Some(job_id))
+    }
+
+    /// Kill every job that is still running and wait for it to be reaped.
+    pub(crate) async fn kill_all(&self) {
+        let pending: Vec<_> = lock(&self.jobs)
+            .values_mut()
+            .filter_map(|job| Some((job.kill_tx.take()?, job.task.take()?)))
+            .collect();
+        for (kill_tx, task) in pending {
+            let _ = kill_tx.send(());
+            let _ = tokio::time::timeout(KILL_TIMEOUT, task).await;
+        }
+    }
+
+    fn describe_with_log(&self, job_id: u32) -> Result<(String, PathBuf), String> {
+        let jobs = lock(&self.jobs);
+        let job = jobs.get(&job_id).ok_or_else(|| unknown_job(job_id))?;
+        Ok((job.describe(job_id), job.log_path.clone()))
+    }
+}
+
+fn lock(jobs: &JobTable) -> MutexGuard<'_, BTreeMap<u32, Job>> {
+    jobs.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn unknown_job(job_id: u32) -> String {
+    format!("no background job with id {job_id}")
+}
+
+/// Wait for the job to exit or be killed, then record how it ended.
+async fn supervise(
+    job_id: u32,
+    mut child: Child,
+    writer: JoinHandle<()>,
+    kill_rx: oneshot::Receiver<()>,
+    jobs: JobTable,
+    tx_event: Sender<Event>,
+    sub_id: String,
+) {
+    let state = tokio::select! {
+        status = child.wait() => JobState::Exited(status.ok().and_then(|status| status.code())),
+        _ = kill_rx => {
+            terminate(&mut child).await;
+            JobState::Killed
+        }
+    };
+    // Grandchildren may still hold the pipes open; do not wait on them forever.
+    let _ = tokio::time::timeout(LOG_DRAIN_TIMEOUT, writer).await;
+
+    let mut jobs = lock(&jobs);
+    if let Some(job) = jobs.get_mut(&job_id) {
+        job.state = state;
+        job.runtime = Some(job.started_at.elapsed());
+        job.kill_tx = None;
+    }
+    send_running_jobs(&jobs, &tx_event, &sub_id);
+}
+
+/// Stop a job together with everything it started. Jobs run in a session of
+/// their own, so their process group is sent SIGTERM, and whatever is left
+/// of it after [`TERM_GRACE`] is sent SIGKILL.
+#[cfg(unix)]
+async fn terminate(child: &mut Child) {
+    let Some(pgid) = child.id().and_then(|pid| libc::pid_t::try_from(pid).ok()) else {
+        // Already reaped.
+        return;
+    };
+    unsafe {
+        libc::killpg(pgid, libc::SIGTERM);
+    }
+    let _ = tokio::time::timeout(TERM_GRACE, child.wait()).await;
+    unsafe {
+        libc::killpg(pgid, libc::SIGKILL);
+    }
+    // In case the job is not a group leader after all.
+    let _ = child.start_kill();
+    let _ = child.wait().await;
+}
+
+#[cfg(not(unix))]
+async fn terminate(child: &mut Child) {
+    let _ = child.start_kill();
+    let _ = child.wait().await;
+}
+
+/// Report the jobs still running in `jobs`. Called with the table locked so
+/// that reports reach the UI in the order the table changed.
+fn send_running_jobs(jobs: &BTreeMap<u32, Job>, tx_event: &Sender<Event>, sub_id: &str) {
+    let running = jobs
+        .iter()
+        .filter(|(_, job)| job.state == JobState::Running)
+        .map(|(job_id, job)| BackgroundJobSummary {
+            job_id: *job_id,
+            command: job.command.clone(),
+        })
+        .collect();
+    // The event channel is unbounded, so this only fails once the session is
+    // gone.
+    let _ = tx_event.try_send(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::BackgroundJobs(BackgroundJobsEvent { jobs: running }),
+    });
+}
+
+fn pipe_to_log<R>(mut reader: R, tx: mpsc::Sender<Vec<u8>>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 8 * 1024];
+        loop {
+            match reader.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tx.send(buf[..n].to_vec()).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Append output chunks to the job log until every pipe is closed, cutting
+/// the log back whenever it outgrows [`JOB_LOG_MAX_BYTES`].
+async fn write_log(path: PathBuf, mut file: File, mut rx: mpsc::Receiver<Vec<u8>>) {
+    let mut len = 0;
+    while let Some(chunk) = rx.recv().await {
+        // Flush every chunk so `tail` and `grep` see output as it arrives.
+        if file.write_all(&chunk).await.is_err() || file.flush().await.is_err() {
+            return;
+        }
+        len += chunk.len();
+        if len > JOB_LOG_MAX_BYTES {
+            match rotate_log(&path).await {
+                Ok((rotated, rotated_len)) => {
+                    file = rotated;
+                    len = rotated_len;
+                }
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+async fn rotate_log(path: &Path) -> io::Result<(File, usize)> {
+    let log = tokio::fs::read(path).await?;
+    let kept = newest_lines(&log, JOB_LOG_MAX_BYTES / 2);
+    tokio::fs::write(path, kept).await?;
+    let file = OpenOptions::new().append(true).open(path).await?;
+    Ok((file, kept.len()))
+}
+
+/// The suffix of `log` no longer than `max_bytes` that starts on a line
+/// boundary, unless a single line is longer than that.
+fn newest_lines(log: &[u8], max_bytes: usize) -> &[u8] {
+    if log.len() <= max_bytes {
+        return log;
+    }
+    let start = log.len() - max_bytes;
+    match log[start..].iter().position(|b| *b == b'\n') {
+        Some(newline) if start + newline + 1 < log.len() => &log[start + newline + 1..],
+        _ => &log[start..],
+    }
+}
+
+async fn read_log(path: &Path) -> Result<String, String> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|err| format!("failed to read job log: {err}"))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn clip_line(line: &str) -> &str {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((idx, _)) => &line[..idx],
+        None => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::process::Stdio;
+    use tokio::process::Command;
+
+    fn spawn_sh(script: &str) -> Child {
+        let mut command = Command::new("sh");
+        #[cfg(unix)]
+        command.process_group(0);
+        command
+            .arg("-c")
+            .arg(script)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("spawn sh")
+    }
+
+    #[test]
+    fn parses_actions() {
+        let args: BackgroundJobArgs =
+            serde_json::from_str(r#"{"action":"tail","job_id":2}"#).expect("parse tail");
+        assert_eq!(
+            args,
+            BackgroundJobArgs::Tail {
+                job_id: 2,
+                lines: None
+            }
+        );
+        let args: BackgroundJobArgs =
+            serde_json::from_str(r#"{"action":"start","command":["npm","run","dev"]}"#)
+                .expect("parse start");
+        assert_eq!(
+            args,
+            BackgroundJobArgs::Start {
+                command: vec!["npm".to_string(), "run".to_string(), "dev".to_string()],
+                workdir: None
+            }
+        );
+    }
+
+    #[test]
+    fn newest_lines_starts_on_line_boundary() {
+        assert_eq!(newest_lines(b"one\ntwo\nthree\n", 64), b"one\ntwo\nthree\n");
+        assert_eq!(newest_lines(b"one\ntwo\nthree\n", 8), b"three\n");
+        assert_eq!(newest_lines(b"0123456789", 4), b"6789");
+    }
+
+    #[tokio::test]
+    async fn finished_job_output_can_be_tailed_and_grepped() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let manager = BackgroundJobManager::new(dir.path().to_path_buf(), tx_event);
+
+        let command = vec!["build".to_string()];
+        let started = manager
+            .start(
+                "sub",
+                &command,
+                spawn_sh("echo compiling; echo 'error: boom' >&2; exit 3"),
+            )
+            .await
+            .expect("start job");
+        assert!(started.starts_with("started job #1: `build`"));
+
+        let Ok(Event {
+            msg: EventMsg::BackgroundJobs(running),
+            ..
+        }) = rx_event.recv().await
+        else {
+            panic!("expected background jobs event");
+        };
+        assert_eq!(running.jobs.len(), 1);
+        let Ok(Event {
+            msg: EventMsg::BackgroundJobs(running),
+            ..
+        }) = rx_event.recv().await
+        else {
+            panic!("expected background jobs event");
+        };
+        assert!(running.jobs.is_empty());
+
+        let status = manager.status(Some(1)).expect("status");
+        assert!(status.starts_with("job #1 exited with code 3"), "{status}");
+
+        let tail = manager.tail(1, Some(1)).await.expect("tail");
+        assert!(tail.ends_with("last 1 of 2 lines:\nerror: boom"), "{tail}");
+
+        let grep = manager.grep(1, "^compil").await.expect("grep");
+        assert!(grep.ends_with("1 matching lines:\n1: compiling"), "{grep}");
+
+        assert_eq!(manager.tail(7, None).await, Err(unknown_job(7)));
+    }
+
+    #[tokio::test]
+    async fn kill_all_stops_running_jobs() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let manager = BackgroundJobManager::new(dir.path().to_path_buf(), tx_event);
+
+        manager
+            .start("sub", &["watch".to_string()], spawn_sh("sleep 30"))
+            .await
+            .expect("start job");
+        manager.ensure_capacity().expect("capacity");
+
+        manager.kill_all().await;
+        let status = manager.status(Some(1)).expect("status");
+        assert!(status.starts_with("job #1 killed"), "{status}");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn kill_stops_processes_the_job_started() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let manager = BackgroundJobManager::new(dir.path().to_path_buf(), tx_event);
+
+        manager
+            .start(
+                "sub",
+                &["serve".to_string()],
+                spawn_sh("sleep 30 & echo $!; wait"),
+            )
+            .await
+            .expect("start job");
+        let mut grandchild = None;
+        for _ in 0..100 {
+            let log = tokio::fs::read_to_string(dir.path().join("job-1.log"))
+                .await
+                .unwrap_or_default();
+            grandchild = log.trim().parse::<libc::pid_t>().ok();
+            if grandchild.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let grandchild = grandchild.expect("job printed the pid of its child");
+
+        manager.kill(1).await.expect("kill");
+        let mut alive = true;
+        for _ in 0..100 {
+            alive = unsafe { libc::kill(grandchild, 0) } == 0;
+            if !alive {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!alive, "process {grandchild} outlived its job");
+    }
+}
@@ -0,0 +1,345 @@
+//! Failure digests for build and test logs.
+//!
+//! Build logs are trimmed to their last lines before they reach the model, but
+//! the first real compiler error usually sits well above trailing noise such
+//! as `error: could not compile`. The extractors here scan the whole log for
+//! the diagnostics each tool prints and keep the first few distinct ones that
+//! carry a `file:line`, so they can be shown ahead of the tail.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use regex_lite::Regex;
+
+use crate::exec::BuildTool;
+
+/// Lines scanned after a diagnostic header when looking for its location.
+const LOCATION_LOOKAHEAD_LINES: usize = 3;
+/// Lines scanned after a failing test header when looking for its location.
+const TEST_FAILURE_LOOKAHEAD_LINES: usize = 40;
+const MAX_MESSAGE_CHARS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Diagnostic {
+    /// `path:line` or `path:line:column`.
+    pub(crate) location: String,
+    pub(crate) message: String,
+}
+
+type Extractor = fn(&[&str], usize) -> Option<Diagnostic>;
+
+fn extractors_for(tool: BuildTool) -> &'static [Extractor] {
+    match tool {
+        BuildTool::Cargo => &[rustc_diagnostic],
+        BuildTool::Node => &[tsc_diagnostic, jest_failure, vitest_failure],
+        BuildTool::Pytest => &[pytest_failure],
+        BuildTool::Jvm => &[javac_diagnostic, kotlin_diagnostic, maven_diagnostic],
+    }
+}
+
+/// Return the first `max_errors` distinct diagnostics `tool` reported in
+/// `log`, in the order they appear.
+pub(crate) fn extract_failure_digest(
+    tool: BuildTool,
+    log: &str,
+    max_errors: usize,
+) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = log.lines().collect();
+    let extractors = extractors_for(tool);
+    let mut seen = HashSet::new();
+    let mut digest = Vec::new();
+
+    for idx in 0..lines.len() {
+        if digest.len() >= max_errors {
+            break;
+        }
+        let Some(diagnostic) = extractors.iter().find_map(|extract| extract(&lines, idx)) else {
+            continue;
+        };
+        if seen.insert((diagnostic.location.clone(), diagnostic.message.clone())) {
+            digest.push(diagnostic);
+        }
+    }
+
+    digest
+}
+
+/// Render `digest` as the header placed above a trimmed build log.
+pub(crate) fn format_failure_digest(digest: &[Diagnostic]) -> String {
+    let noun = if digest.len() == 1 { "error" } else { "errors" };
+    let mut out = format!("[failure digest: first {} {noun}]\n", digest.len());
+    for diagnostic in digest {
+        out.push_str(&diagnostic.location);
+        out.push_str(": ");
+        out.push_str(&diagnostic.message);
+        out.push('\n');
+    }
+    out
+}
+
+fn diagnostic(location: String, message: &str) -> Diagnostic {
+    let message = message.trim();
+    let message = match message.char_indices().nth(MAX_MESSAGE_CHARS) {
+        Some((end, _)) => format!("{}…", &message[..end]),
+        None => message.to_string(),
+    };
+    Diagnostic { location, message }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    #[expect(clippy::unwrap_used)]
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+/// Find the first line after `idx` matching `re` and return its first
+/// capture group.
+fn find_ahead<'a>(lines: &[&'a str], idx: usize, lookahead: usize, re: &Regex) -> Option<&'a str> {
+    lines
+        .iter()
+        .skip(idx + 1)
+        .take(lookahead)
+        .find_map(|line| re.captures(line))
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str())
+}
+
+/// `error[E0308]: mismatched types` followed by ` --> src/lib.rs:12:5`.
+fn rustc_diagnostic(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(&HEADER, r"^error(\[E\d+\])?: (.+)$").captures(lines[idx])?;
+    let location = find_ahead(
+        lines,
+        idx,
+        LOCATION_LOOKAHEAD_LINES,
+        regex(&LOCATION, r"^\s*--> (\S+:\d+(?::\d+)?)"),
+    )?;
+    let code = caps.get(1).map(|m| m.as_str()).unwrap_or_default();
+    let message = format!("error{code}: {}", &caps[2]);
+    Some(diagnostic(location.to_string(), &message))
+}
+
+/// `src/a.ts(3,7): error TS2322: ...` or `src/a.ts:3:7 - error TS2322: ...`.
+fn tsc_diagnostic(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static PLAIN: OnceLock<Regex> = OnceLock::new();
+    static PRETTY: OnceLock<Regex> = OnceLock::new();
+    let line = lines[idx];
+    if let Some(caps) = regex(&PLAIN, r"^(\S+)\((\d+),(\d+)\): error (TS\d+): (.+)$").captures(line)
+    {
+        let location = format!("{}:{}:{}", &caps[1], &caps[2], &caps[3]);
+        return Some(diagnostic(location, &format!("{}: {}", &caps[4], &caps[5])));
+    }
+    let caps = regex(&PRETTY, r"^(\S+:\d+:\d+) - error (TS\d+): (.+)$").captures(line)?;
+    Some(diagnostic(
+        caps[1].to_string(),
+        &format!("{}: {}", &caps[2], &caps[3]),
+    ))
+}
+
+/// `● Suite › test name` followed by a stack frame pointing into the project.
+fn jest_failure(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    static FRAME: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(&HEADER, r"^\s*● (.+›.+)$").captures(lines[idx])?;
+    let frame = regex(&FRAME, r"^\s*at .*\(([^()\s]+:\d+:\d+)\)$");
+    let location = lines
+        .iter()
+        .skip(idx + 1)
+        .take(TEST_FAILURE_LOOKAHEAD_LINES)
+        .filter_map(|line| frame.captures(line))
+        .filter_map(|caps| caps.get(1).map(|m| m.as_str()))
+        .find(|location| !location.contains("node_modules/") && !location.starts_with("node:"))?;
+    Some(diagnostic(location.to_string(), &caps[1]))
+}
+
+/// ` FAIL  src/a.test.ts > suite > test` followed by ` ❯ src/a.test.ts:12:5`.
+fn vitest_failure(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    static FRAME: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(&HEADER, r"^\s*FAIL\s+\S+ > (.+)$").captures(lines[idx])?;
+    let location = find_ahead(
+        lines,
+        idx,
+        TEST_FAILURE_LOOKAHEAD_LINES,
+        regex(&FRAME, r"^\s*❯ (\S+:\d+:\d+)"),
+    )?;
+    Some(diagnostic(location.to_string(), &caps[1]))
+}
+
+/// `tests/test_app.py:12: AssertionError` as printed at the end of each
+/// pytest failure.
+fn pytest_failure(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(
+        &LOCATION,
+        r"^(\S+\.py:\d+): (\w*(?:Error|Exception|Failed)\b.*)$",
+    )
+    .captures(lines[idx])?;
+    Some(diagnostic(caps[1].to_string(), &caps[2]))
+}
+
+/// `src/main/java/App.java:12: error: ...`, as printed by javac under Gradle.
+fn javac_diagnostic(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(&LOCATION, r"^(\S+\.java:\d+): error: (.+)$").captures(lines[idx])?;
+    Some(diagnostic(caps[1].to_string(), &caps[2]))
+}
+
+/// `e: file:///src/App.kt:12:5 ...` or the older `e: /src/App.kt: (12, 5): ...`.
+fn kotlin_diagnostic(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static CURRENT: OnceLock<Regex> = OnceLock::new();
+    static LEGACY: OnceLock<Regex> = OnceLock::new();
+    let line = lines[idx];
+    if let Some(caps) = regex(&CURRENT, r"^e: (?:file://)?(\S+\.kts?:\d+:\d+) (.+)$").captures(line)
+    {
+        return Some(diagnostic(caps[1].to_string(), &caps[2]));
+    }
+    let caps = regex(
+        &LEGACY,
+        r"^e: (?:file://)?(\S+\.kts?): \((\d+), (\d+)\): (.+)$",
+    )
+    .captures(line)?;
+    let location = format!("{}:{}:{}", &caps[1], &caps[2], &caps[3]);
+    Some(diagnostic(location, &caps[4]))
+}
+
+/// `[ERROR] /src/App.java:[12,5] cannot find symbol`.
+fn maven_diagnostic(lines: &[&str], idx: usize) -> Option<Diagnostic> {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(
+        &LOCATION,
+        r"^\[ERROR\] (\S+\.(?:java|kt)):\[(\d+),(\d+)\] (.+)$",
+    )
+    .captures(lines[idx])?;
+    let location = format!("{}:{}:{}", &caps[1], &caps[2], &caps[3]);
+    Some(diagnostic(location, &caps[4]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn locations(digest: &[Diagnostic]) -> Vec<&str> {
+        digest.iter().map(|d| d.location.as_str()).collect()
+    }
+
+    #[test]
+    fn extracts_first_distinct_rustc_errors() {
+        let log = "\
+   Compiling demo v0.1.0
+error[E0308]: mismatched types
+  --> src/lib.rs:12:5
+   |
+12 |     \"x\"
+   |     ^^^ expected `u32`, found `&str`
+
+error[E0308]: mismatched types
+  --> src/lib.rs:12:5
+
+error: cannot find value `y` in this scope
+ --> src/main.rs:3:13
+
+error: could not compile `demo` (lib) due to 2 previous errors
+";
+        let digest = extract_failure_digest(BuildTool::Cargo, log, 10);
+        assert_eq!(
+            digest,
+            vec![
+                Diagnostic {
+                    location: "src/lib.rs:12:5".to_string(),
+                    message: "error[E0308]: mismatched types".to_string(),
+                },
+                Diagnostic {
+                    location: "src/main.rs:3:13".to_string(),
+                    message: "error: cannot find value `y` in this scope".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn caps_digest_length() {
+        let log = (1..=5)
+            .map(|n| format!("error: bad {n}\n --> src/lib.rs:{n}:1\n"))
+            .collect::<String>();
+        let digest = extract_failure_digest(BuildTool::Cargo, &log, 2);
+        assert_eq!(locations(&digest), vec!["src/lib.rs:1:1", "src/lib.rs:2:1"]);
+    }
+
+    #[test]
+    fn extracts_node_failures() {
+        let log = "\
+src/app.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/util.ts:10:2 - error TS2304: Cannot find name 'foo'.
+  ● math › adds numbers
+
+    expect(received).toBe(expected)
+
+      at Object.<anonymous> (node_modules/expect/build/index.js:1:1)
+      at Object.<anonymous> (src/math.test.ts:8:19)
+ FAIL  src/sum.test.ts > sum > handles negatives
+AssertionError: expected 1 to be 2
+ ❯ src/sum.test.ts:14:22
+";
+        let digest = extract_failure_digest(BuildTool::Node, log, 10);
+        assert_eq!(
+            locations(&digest),
+            vec![
+                "src/app.ts:3:7",
+                "src/util.ts:10:2",
+                "src/math.test.ts:8:19",
+                "src/sum.test.ts:14:22",
+            ]
+        );
+        assert_eq!(digest[2].message, "math › adds numbers");
+    }
+
+    #[test]
+    fn extracts_pytest_failures() {
+        let log = "\
+    def test_add():
+>       assert add(1, 1) == 3
+E       assert 2 == 3
+
+tests/test_math.py:4: AssertionError
+FAILED tests/test_math.py::test_add - assert 2 == 3
+";
+        let digest = extract_failure_digest(BuildTool::Pytest, log, 10);
+        assert_eq!(locations(&digest), vec!["tests/test_math.py:4"]);
+        assert_eq!(digest[0].message, "AssertionError");
+    }
+
+    #[test]
+    fn extracts_jvm_diagnostics() {
+        let log = "\
+> Task :compileJava FAILED
+src/main/java/App.java:12: error: cannot find symbol
+e: file:///work/src/main/kotlin/Main.kt:5:9 Unresolved reference: foo
+e: /work/src/main/kotlin/Old.kt: (7, 3): Type mismatch
+[ERROR] /work/src/main/java/Svc.java:[20,8] class Svc is public
+";
+        let digest = extract_failure_digest(BuildTool::Jvm, log, 10);
+        assert_eq!(
+            locations(&digest),
+            vec![
+                "src/main/java/App.java:12",
+                "/work/src/main/kotlin/Main.kt:5:9",
+                "/work/src/main/kotlin/Old.kt:7:3",
+                "/work/src/main/java/Svc.java:20:8",
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_digest_header() {
+        let digest = vec![Diagnostic {
+            location: "src/lib.rs:1:1".to_string(),
+            message: "error: boom".to_string(),
+        }];
+        assert_eq!(
+            format_failure_digest(&digest),
+            "[failure digest: first 1 error]\nsrc/lib.rs:1:1: error: boom\n"
+        );
+    }
+}
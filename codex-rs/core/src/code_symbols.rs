@@ -0,0 +1,484 @@
+//! Symbol resolution for `read_code`.
+//!
+//! Maps a possibly qualified symbol name (`Session::send_event`,
+//! `Widget.render`, `send_event`) to the line span of its definition using
+//! the bundled tree-sitter grammars. Rust, TypeScript/JavaScript and Python
+//! sources are supported.
+
+use std::path::Path;
+
+use tree_sitter::Language;
+use tree_sitter::Node;
+use tree_sitter::Parser;
+
+/// Maximum number of candidates listed when a symbol is ambiguous.
+const MAX_AMBIGUOUS_CANDIDATES: usize = 5;
+
+/// Inclusive, 1-indexed line span of a resolved symbol definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SymbolSpan {
+    pub(crate) qualified_name: String,
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub(crate) enum SymbolLookupError {
+    #[error("symbol must not be empty")]
+    EmptySymbol,
+
+    #[error("symbol lookups are not supported for {0}; request an explicit line range instead")]
+    UnsupportedLanguage(String),
+
+    #[error("failed to parse {0} for symbol lookup; request an explicit line range instead")]
+    ParseFailed(String),
+
+    #[error("symbol `{0}` was not found; check the spelling or request an explicit line range")]
+    NotFound(String),
+
+    #[error("symbol `{symbol}` is ambiguous; qualify it with one of: {candidates}")]
+    Ambiguous { symbol: String, candidates: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolLanguage {
+    Rust,
+    TypeScript,
+    Tsx,
+    Python,
+}
+
+impl SymbolLanguage {
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        match ext {
+            "rs" => Some(Self::Rust),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            // The TSX grammar is a superset that also parses plain JavaScript.
+            "tsx" | "js" | "jsx" | "mjs" | "cjs" => Some(Self::Tsx),
+            "py" | "pyi" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+        }
+    }
+}
+
+/// A definition discovered while walking the syntax tree.
+#[derive(Debug)]
+struct Definition {
+    path: Vec<String>,
+    start_line: usize,
+    end_line: usize,
+}
+
+impl Definition {
+    fn qualified_name(&self) -> String {
+        self.path.join("::")
+    }
+
+    fn matches(&self, query: &[&str]) -> bool {
+        query.len() <= self.path.len()
+            && self.path[self.path.len() - query.len()..]
+                .iter()
+                .zip(query)
+                .all(|(segment, wanted)| segment == wanted)
+    }
+}
+
+/// How a syntax node participates in symbol naming.
+enum NodeRole<'tree> {
+    /// A named definition; `span` covers the full definition including
+    /// attached attributes, decorators and `export` wrappers.
+    Definition { name: String, span: Node<'tree> },
+    /// A naming scope without a definition of its own (e.g. a Rust `impl`).
+    Scope { name: String },
+}
+
+/// Resolve `symbol` within `source` (the contents of `path`) to the line span
+/// of its definition. Segments may be separated by `::` or `.`; a query
+/// matches any definition whose qualified path ends with those segments.
+pub(crate) fn resolve_symbol(
+    path: &Path,
+    source: &str,
+    symbol: &str,
+) -> Result<SymbolSpan, SymbolLookupError> {
+    let query: Vec<&str> = symbol
+        .split([':', '.'])
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if query.is_empty() {
+        return Err(SymbolLookupError::EmptySymbol);
+    }
+
+    let display = path.display().to_string();
+    let language = SymbolLanguage::from_path(path)
+        .ok_or_else(|| SymbolLookupError::UnsupportedLanguage(display.clone()))?;
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language.grammar())
+        .map_err(|_| SymbolLookupError::ParseFailed(display.clone()))?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| SymbolLookupError::ParseFailed(display.clone()))?;
+
+    let mut definitions = Vec::new();
+    let mut scope = Vec::new();
+    collect_definitions(
+        language,
+        tree.root_node(),
+        source.as_bytes(),
+        &mut scope,
+        &mut definitions,
+    );
+
+    let matches: Vec<&Definition> = definitions.iter().filter(|d| d.matches(&query)).collect();
+    let chosen = match matches.as_slice() {
+        [] => return Err(SymbolLookupError::NotFound(symbol.to_string())),
+        [only] => *only,
+        many => {
+            // Prefer an exact, fully qualified match over suffix matches.
+            let exact: Vec<&&Definition> = many
+                .iter()
+                .filter(|d| d.path.len() == query.len())
+                .collect();
+            match exact.as_slice() {
+                [only] => **only,
+                _ => {
+                    let candidates = many
+                        .iter()
+                        .take(MAX_AMBIGUOUS_CANDIDATES)
+                        .map(|d| {
+                            format!(
+                                "{} (lines {}-{})",
+                                d.qualified_name(),
+                                d.start_line,
+                                d.end_line
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    return Err(SymbolLookupError::Ambiguous {
+                        symbol: symbol.to_string(),
+                        candidates,
+                    });
+                }
+            }
+        }
+    };
+
+    Ok(SymbolSpan {
+        qualified_name: chosen.qualified_name(),
+        start_line: chosen.start_line,
+        end_line: chosen.end_line,
+    })
+}
+
+fn collect_definitions(
+    language: SymbolLanguage,
+    node: Node<'_>,
+    source: &[u8],
+    scope: &mut Vec<String>,
+    out: &mut Vec<Definition>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match classify(language, child, source) {
+            Some(NodeRole::Definition { name, span }) => {
+                scope.push(name);
+                let (start_line, end_line) = line_span(language, span);
+                out.push(Definition {
+                    path: scope.clone(),
+                    start_line,
+                    end_line,
+                });
+                collect_definitions(language, child, source, scope, out);
+                scope.pop();
+            }
+            Some(NodeRole::Scope { name }) => {
+                scope.push(name);
+                collect_definitions(language, child, source, scope, out);
+                scope.pop();
+            }
+            None => collect_definitions(language, child, source, scope, out),
+        }
+    }
+}
+
+fn classify<'tree>(
+    language: SymbolLanguage,
+    node: Node<'tree>,
+    source: &[u8],
+) -> Option<NodeRole<'tree>> {
+    match language {
+        SymbolLanguage::Rust => classify_rust(node, source),
+        SymbolLanguage::TypeScript | SymbolLanguage::Tsx => classify_typescript(node, source),
+        SymbolLanguage::Python => classify_python(node, source),
+    }
+}
+
+fn classify_rust<'tree>(node: Node<'tree>, source: &[u8]) -> Option<NodeRole<'tree>> {
+    match node.kind() {
+        "impl_item" => {
+            let name = rust_type_name(node.child_by_field_name("type")?, source)?;
+            Some(NodeRole::Scope { name })
+        }
+        "function_item"
+        | "function_signature_item"
+        | "struct_item"
+        | "enum_item"
+        | "union_item"
+        | "trait_item"
+        | "mod_item"
+        | "type_item"
+        | "const_item"
+        | "static_item"
+        | "macro_definition" => Some(NodeRole::Definition {
+            name: field_text(node, "name", source)?,
+            span: node,
+        }),
+        _ => None,
+    }
+}
+
+/// Name of the type an `impl` block targets, stripped of generics and paths.
+fn rust_type_name(node: Node<'_>, source: &[u8]) -> Option<String> {
+    match node.kind() {
+        "generic_type" => rust_type_name(node.child_by_field_name("type")?, source),
+        "scoped_type_identifier" => field_text(node, "name", source),
+        "reference_type" | "pointer_type" => {
+            rust_type_name(node.child_by_field_name("type")?, source)
+        }
+        _ => node.utf8_text(source).ok().map(str::to_string),
+    }
+}
+
+fn classify_typescript<'tree>(node: Node<'tree>, source: &[u8]) -> Option<NodeRole<'tree>> {
+    match node.kind() {
+        "function_declaration"
+        | "generator_function_declaration"
+        | "class_declaration"
+        | "abstract_class_declaration"
+        | "interface_declaration"
+        | "type_alias_declaration"
+        | "enum_declaration"
+        | "internal_module"
+        | "module"
+        | "method_definition"
+        | "method_signature"
+        | "abstract_method_signature"
+        | "public_field_definition" => Some(NodeRole::Definition {
+            name: field_text(node, "name", source)?,
+            span: node,
+        }),
+        "variable_declarator" => {
+            // `const handler = () => { ... }` is treated as a function definition.
+            let value = node.child_by_field_name("value")?;
+            if !matches!(
+                value.kind(),
+                "arrow_function" | "function_expression" | "function" | "class"
+            ) {
+                return None;
+            }
+            let span = node
+                .parent()
+                .filter(|p| matches!(p.kind(), "lexical_declaration" | "variable_declaration"))
+                .unwrap_or(node);
+            Some(NodeRole::Definition {
+                name: field_text(node, "name", source)?,
+                span,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn classify_python<'tree>(node: Node<'tree>, source: &[u8]) -> Option<NodeRole<'tree>> {
+    match node.kind() {
+        "function_definition" | "class_definition" => Some(NodeRole::Definition {
+            name: field_text(node, "name", source)?,
+            span: node,
+        }),
+        _ => None,
+    }
+}
+
+fn field_text(node: Node<'_>, field: &str, source: &[u8]) -> Option<String> {
+    node.child_by_field_name(field)?
+        .utf8_text(source)
+        .ok()
+        .map(str::to_string)
+}
+
+/// Compute the inclusive 1-indexed line span for a definition, widening it to
+/// cover wrappers (`export`, decorators) and attached attributes/doc comments.
+fn line_span(language: SymbolLanguage, node: Node<'_>) -> (usize, usize) {
+    let mut span = node;
+    while let Some(parent) = span.parent() {
+        let wraps = match language {
+            SymbolLanguage::TypeScript | SymbolLanguage::Tsx => parent.kind() == "export_statement",
+            SymbolLanguage::Python => parent.kind() == "decorated_definition",
+            SymbolLanguage::Rust => false,
+        };
+        if !wraps {
+            break;
+        }
+        span = parent;
+    }
+
+    let mut start_row = span.start_position().row;
+    let mut prev = span.prev_sibling();
+    while let Some(sibling) = prev {
+        let attached = match language {
+            SymbolLanguage::Rust => matches!(
+                sibling.kind(),
+                "attribute_item" | "line_comment" | "block_comment"
+            ),
+            SymbolLanguage::TypeScript | SymbolLanguage::Tsx => sibling.kind() == "comment",
+            SymbolLanguage::Python => false,
+        };
+        if !attached || sibling.end_position().row + 1 < start_row {
+            break;
+        }
+        start_row = sibling.start_position().row;
+        prev = sibling.prev_sibling();
+    }
+
+    let end = span.end_position();
+    let end_row = if end.column == 0 && end.row > start_row {
+        end.row - 1
+    } else {
+        end.row
+    };
+    (start_row + 1, end_row + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const RUST_SOURCE: &str = r#"use std::fmt;
+
+/// A session.
+#[derive(Debug)]
+pub struct Session {
+    id: u64,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self { id: 0 }
+    }
+
+    /// Sends an event.
+    pub async fn send_event(&self) {
+        println!("sent");
+    }
+}
+
+impl<T> fmt::Display for Wrapper<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Ok(())
+    }
+}
+
+fn new() {}
+"#;
+
+    fn span(path: &str, source: &str, symbol: &str) -> Result<(usize, usize), SymbolLookupError> {
+        resolve_symbol(Path::new(path), source, symbol).map(|s| (s.start_line, s.end_line))
+    }
+
+    #[test]
+    fn resolves_rust_items_and_methods() {
+        assert_eq!(span("lib.rs", RUST_SOURCE, "Session"), Ok((3, 7)));
+        assert_eq!(
+            span("lib.rs", RUST_SOURCE, "Session::send_event"),
+            Ok((14, 17))
+        );
+        assert_eq!(span("lib.rs", RUST_SOURCE, "send_event"), Ok((14, 17)));
+        assert_eq!(span("lib.rs", RUST_SOURCE, "Wrapper::fmt"), Ok((21, 23)));
+        // The top-level `new` is an exact match and wins over `Session::new`.
+        assert_eq!(span("lib.rs", RUST_SOURCE, "new"), Ok((26, 26)));
+    }
+
+    #[test]
+    fn reports_missing_and_ambiguous_symbols() {
+        let source = "struct A;\nimpl A { fn go() {} }\nstruct B;\nimpl B { fn go() {} }\n";
+        assert_eq!(
+            span("lib.rs", source, "missing"),
+            Err(SymbolLookupError::NotFound("missing".to_string()))
+        );
+        let Err(SymbolLookupError::Ambiguous { candidates, .. }) = span("lib.rs", source, "go")
+        else {
+            panic!("expected an ambiguous lookup");
+        };
+        assert_eq!(candidates, "A::go (lines 2-2), B::go (lines 4-4)");
+    }
+
+    #[test]
+    fn resolves_typescript_symbols() {
+        let source = r#"/** Widget docs. */
+export class Widget {
+  render(): string {
+    return "w";
+  }
+}
+
+export const handler = async () => {
+  return 1;
+};
+
+interface Props {
+  name: string;
+}
+"#;
+        assert_eq!(span("widget.ts", source, "Widget"), Ok((1, 6)));
+        assert_eq!(span("widget.ts", source, "Widget.render"), Ok((3, 5)));
+        assert_eq!(span("widget.ts", source, "handler"), Ok((8, 10)));
+        assert_eq!(span("widget.tsx", source, "Props"), Ok((12, 14)));
+    }
+
+    #[test]
+    fn resolves_python_symbols() {
+        let source = r#"import os
+
+
+class Runner:
+    @staticmethod
+    def run(cmd):
+        return os.system(cmd)
+
+
+def main():
+    Runner.run("ls")
+"#;
+        assert_eq!(span("tool.py", source, "Runner"), Ok((4, 7)));
+        assert_eq!(span("tool.py", source, "Runner.run"), Ok((5, 7)));
+        assert_eq!(span("tool.py", source, "main"), Ok((10, 11)));
+    }
+
+    #[test]
+    fn rejects_unsupported_languages() {
+        assert_eq!(
+            span("notes.md", "# title\n", "title"),
+            Err(SymbolLookupError::UnsupportedLanguage(
+                "notes.md".to_string()
+            ))
+        );
+        assert_eq!(
+            span("lib.rs", RUST_SOURCE, " :: "),
+            Err(SymbolLookupError::EmptySymbol)
+        );
+    }
+}
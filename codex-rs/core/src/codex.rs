@@ -1,5 +1,6 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::path::Path;
 use std::path::PathBuf;
@@ -48,12 +49,21 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::background_jobs::BACKGROUND_JOB_TOOL_NAME;
+use crate::background_jobs::BackgroundJobArgs;
+use crate::background_jobs::BackgroundJobManager;
+use crate::background_jobs::JOB_LOGS_SUBDIR;
+use crate::build_log_digest::extract_failure_digest;
+use crate::build_log_digest::format_failure_digest;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::ContextPolicy;
+use crate::config_types::OutputRule;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
+use crate::env_snapshot::EnvSnapshotCache;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -65,14 +75,20 @@ use crate::exec::SandboxType;
 use crate::exec::StdoutStream;
 use crate::exec::StreamOutput;
 use crate::exec::{
-    BUILD_LOG_TAIL_LINES, BUILD_LOG_TAIL_NOTICE, process_exec_tool_call, should_tail_build_output,
+    BUILD_LOG_TAIL_MAX_BYTES, ExecOutputCaps, build_log_tail_notice, build_tool_for_command,
+    command_invokes_ripgrep, exec_truncation_notice, process_exec_tool_call_with_caps,
+    rg_truncation_notice, spawn_exec_child,
 };
 use crate::exec_command::EXEC_COMMAND_TOOL_NAME;
 use crate::exec_command::ExecCommandParams;
 use crate::exec_command::ExecSessionManager;
+use crate::exec_command::PtyCommand;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::exec_env::create_env_with_snapshot;
+use crate::exec_policy::ExecPolicy;
+use crate::git_info::head_commit_oid;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::model_family::find_family_for_model;
@@ -81,6 +97,11 @@ use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
+use crate::output_rules::output_rule_for;
+use crate::output_rules::shape_output;
+use crate::output_spill::OUTPUT_ARTIFACTS_SUBDIR;
+use crate::output_spill::output_spill_notice;
+use crate::output_spill::spill_output;
 use crate::parse_command::parse_command;
 use crate::plan_tool::handle_update_plan;
 use crate::project_doc::get_user_instructions;
@@ -91,6 +112,7 @@ use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::ContextMetrics;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -104,6 +126,8 @@ use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
 use crate::protocol::RateLimitSnapshot;
+use crate::protocol::RelaxGrantedEvent;
+use crate::protocol::RelaxSource;
 use crate::protocol::ReviewDecision;
 use crate::protocol::ReviewOutputEvent;
 use crate::protocol::SandboxPolicy;
@@ -113,20 +137,30 @@ use crate::protocol::Submission;
 use crate::protocol::TokenCountEvent;
 use crate::protocol::TokenUsage;
 use crate::protocol::TurnDiffEvent;
+use crate::protocol::TurnMetricsEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::rg_compact::compact_rg_output;
+use crate::rg_compact::rg_output_format;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::safety::get_platform_sandbox;
 use crate::shell;
+use crate::spawn::ResourceLimits;
+use crate::spawn::StdioPolicy;
+use crate::state::CHUNK_CACHE_SUBDIR;
+use crate::state::ChunkLoad;
+use crate::state::ChunkStore;
+use crate::state::ReadPartition;
 use crate::state::{
-    ActiveTurn, RepeatCommandBlock, SessionServices, TURN_OUTPUT_TRUNCATION_NOTICE,
-    ToolBudgetDecision, TurnMetrics, TurnState,
+    ActiveTurn, RepeatCommandBlock, SessionServices, ToolBudgetDecision, TurnMetrics, TurnState,
 };
 use crate::tasks::CompactTask;
 use crate::tasks::RegularTask;
 use crate::tasks::ReviewTask;
+use crate::tool_plan_reads::handle_plan_reads_tool_call;
 use crate::tool_read_code::handle_read_code_tool_call;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::unified_exec::UnifiedExecSessionManager;
@@ -290,9 +324,13 @@ pub(crate) struct TurnContext {
     pub(crate) approval_policy: AskForApproval,
     pub(crate) sandbox_policy: SandboxPolicy,
     pub(crate) shell_environment_policy: ShellEnvironmentPolicy,
+    /// Resource limits applied to commands the model runs.
+    pub(crate) sandbox_resources: ResourceLimits,
     pub(crate) tools_config: ToolsConfig,
     pub(crate) is_review_mode: bool,
     pub(crate) final_output_json_schema: Option<Value>,
+    /// Effective context budgets for tool output in this turn.
+    pub(crate) context_policy: ContextPolicy,
 }
 
 impl TurnContext {
@@ -404,7 +442,12 @@ impl Session {
         })?;
         let rollout_path = rollout_recorder.rollout_path.clone();
         // Create the mutable state for the Session.
-        let state = SessionState::new();
+        let mut state = SessionState::new();
+        state.configure_repeat_command_breaker(
+            config.context_policy.repeat_command_repeats,
+            config.context_policy.repeat_command_window,
+        );
+        state.command_rules = config.command_rules.clone();
 
         // Handle MCP manager result and record any startup failures.
         let (mcp_connection_manager, failed_clients) = match mcp_res {
@@ -432,6 +475,15 @@ impl Session {
             }
         }
 
+        let (exec_policy, exec_policy_errors) = ExecPolicy::load(&config.exec_policy_files);
+        for message in exec_policy_errors {
+            error!("{message}");
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            });
+        }
+
         let otel_event_manager = OtelEventManager::new(
             conversation_id,
             config.model.as_str(),
@@ -482,19 +534,35 @@ impl Session {
             approval_policy,
             sandbox_policy,
             shell_environment_policy: config.shell_environment_policy.clone(),
+            sandbox_resources: config.sandbox_resources,
             cwd,
             is_review_mode: false,
             final_output_json_schema: None,
+            context_policy: config.context_policy.clone(),
         };
         let services = SessionServices {
             mcp_connection_manager,
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            background_jobs: BackgroundJobManager::new(
+                config
+                    .codex_home
+                    .join(JOB_LOGS_SUBDIR)
+                    .join(conversation_id.to_string()),
+                tx_event.clone(),
+            ),
             notifier: notify,
             rollout: Mutex::new(Some(rollout_recorder)),
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
+            env_snapshot: EnvSnapshotCache::new(default_shell.clone()),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            output_artifacts_dir: config
+                .codex_home
+                .join(OUTPUT_ARTIFACTS_SUBDIR)
+                .join(conversation_id.to_string()),
+            chunk_store: ChunkStore::new(config.codex_home.join(CHUNK_CACHE_SUBDIR)),
+            exec_policy,
         };
 
         let sess = Arc::new(Session {
@@ -594,6 +662,13 @@ impl Session {
         Some(guard.reserve_tool_output(desired_bytes, notice_len))
     }
 
+    pub(crate) async fn turn_output_truncation_notice(&self) -> String {
+        match self.current_turn_state().await {
+            Some(turn_state) => turn_state.lock().await.tool_output_truncation_notice(),
+            None => String::new(),
+        }
+    }
+
     pub(crate) async fn compute_unserved_code_ranges(
         &self,
         path: &str,
@@ -606,10 +681,187 @@ impl Session {
         guard.compute_unserved_code_ranges(path, ranges)
     }
 
-    pub(crate) async fn record_served_code_ranges(&self, path: &str, ranges: &[(usize, usize)]) {
+    /// Split `ranges` of `path` into slices not yet served for the current
+    /// contents and slices already shown in an earlier turn of this session.
+    pub(crate) async fn partition_session_code_ranges(
+        &self,
+        path: &Path,
+        blob_oid: &str,
+        ranges: &[(usize, usize)],
+    ) -> ReadPartition {
+        let mut state = self.state.lock().await;
+        state.partition_code_reads(path, blob_oid, ranges)
+    }
+
+    pub(crate) async fn record_served_code_ranges(
+        &self,
+        call_id: &str,
+        rel_path: &str,
+        abs_path: &Path,
+        blob_oid: &str,
+        ranges: &[(usize, usize)],
+    ) {
+        let turn_number = match self.current_turn_state().await {
+            Some(turn_state) => {
+                let mut guard = turn_state.lock().await;
+                guard.record_served_code_ranges(rel_path, ranges);
+                guard.turn_number()
+            }
+            None => 0,
+        };
+        let mut state = self.state.lock().await;
+        state.record_code_reads(abs_path, blob_oid, ranges, turn_number);
+        state.tool_outputs_mut().record_code_read(
+            call_id,
+            abs_path.to_path_buf(),
+            rel_path,
+            ranges,
+            blob_oid,
+        );
+    }
+
+    /// Directory holding this session's spilled command output.
+    pub(crate) fn output_artifacts_dir(&self) -> &Path {
+        &self.services.output_artifacts_dir
+    }
+
+    /// Load `path` through the session chunk cache, then the chunk store
+    /// shared across sessions, reading it from disk only when neither holds
+    /// chunks whose `(size, mtime)` stamp still matches.
+    pub(crate) async fn load_code_chunks(
+        &self,
+        workspace_root: &Path,
+        path: &Path,
+        metadata: &std::fs::Metadata,
+    ) -> std::io::Result<ChunkLoad> {
+        let head_oid = self.turn_head_oid(workspace_root).await;
+        let size = metadata.len();
+        let mtime = metadata.modified().ok();
+        if let Some(file) =
+            self.state
+                .lock()
+                .await
+                .cached_code_chunks(head_oid.as_deref(), path, size, mtime)
+        {
+            return Ok(ChunkLoad {
+                file,
+                cache_hit: true,
+            });
+        }
+        let chunk_store = &self.services.chunk_store;
+        if let Some(head_oid) = head_oid.as_deref()
+            && let Some(file) = chunk_store.load(head_oid, path).await
+            && file.stat_matches(size, mtime)
+        {
+            let mut state = self.state.lock().await;
+            return Ok(state.adopt_code_chunks(head_oid, path, file));
+        }
+
+        let contents = tokio::fs::read_to_string(path).await?;
+        let load = self.state.lock().await.cache_code_chunks(
+            head_oid.as_deref(),
+            path,
+            contents,
+            size,
+            mtime,
+        );
+        if let Some(head_oid) = head_oid.as_deref()
+            && let Err(err) = chunk_store.save(head_oid, path, &load.file).await
+        {
+            warn!("failed to store chunks of {}: {err}", path.display());
+        }
+        Ok(load)
+    }
+
+    /// `HEAD` oid of `workspace_root`, running `git` at most once per turn.
+    /// A commit made during the turn is picked up by the next one; until then
+    /// the `(size, mtime, hash)` stamps still catch changed files.
+    async fn turn_head_oid(&self, workspace_root: &Path) -> Option<String> {
+        let Some(turn_state) = self.current_turn_state().await else {
+            return head_commit_oid(workspace_root).await;
+        };
+        if let Some(head_oid) = turn_state.lock().await.head_oid(workspace_root) {
+            return head_oid;
+        }
+        let head_oid = head_commit_oid(workspace_root).await;
+        turn_state
+            .lock()
+            .await
+            .set_head_oid(workspace_root, head_oid.clone());
+        head_oid
+    }
+
+    pub(crate) async fn record_chunk_cache_access(&self, chunks: usize, cache_hit: bool) {
+        if let Some(turn_state) = self.current_turn_state().await {
+            let mut guard = turn_state.lock().await;
+            guard.record_chunk_cache_access(chunks, cache_hit);
+        }
+    }
+
+    /// Bytes left in the current turn's tool output budget, if a turn is active.
+    pub(crate) async fn remaining_tool_output_budget(&self) -> Option<usize> {
+        let turn_state = self.current_turn_state().await?;
+        let guard = turn_state.lock().await;
+        Some(guard.remaining_tool_output_budget())
+    }
+
+    pub(crate) async fn accept_read_plan(&self, files: HashSet<String>, estimated_bytes: usize) {
+        if let Some(turn_state) = self.current_turn_state().await {
+            let mut guard = turn_state.lock().await;
+            guard.accept_read_plan(files, estimated_bytes);
+        }
+    }
+
+    pub(crate) async fn record_read_plan_rejected(&self) {
+        if let Some(turn_state) = self.current_turn_state().await {
+            let mut guard = turn_state.lock().await;
+            guard.record_read_plan_rejected();
+        }
+    }
+
+    pub(crate) async fn record_read_code_output(&self, rel_path: &str, bytes: usize) {
+        if let Some(turn_state) = self.current_turn_state().await {
+            let mut guard = turn_state.lock().await;
+            guard.record_read_code_output(rel_path, bytes);
+        }
+    }
+
+    /// Forget served slices for files modified by `apply_patch` so they are
+    /// served again on the next read, in this turn or a later one.
+    async fn invalidate_code_reads(
+        &self,
+        call_id: &str,
+        cwd: &Path,
+        changes: &HashMap<PathBuf, FileChange>,
+    ) {
+        let workspace_root = std::fs::canonicalize(cwd).unwrap_or_else(|_| cwd.to_path_buf());
+        let mut paths: Vec<&PathBuf> = changes.keys().collect();
+        paths.extend(changes.values().filter_map(|change| match change {
+            FileChange::Update { move_path, .. } => move_path.as_ref(),
+            FileChange::Add { .. } | FileChange::Delete { .. } => None,
+        }));
+
+        let canonical_paths: Vec<PathBuf> = paths
+            .into_iter()
+            .map(|path| std::fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+            .collect();
+
+        {
+            let mut state = self.state.lock().await;
+            for path in &canonical_paths {
+                state.invalidate_code_reads(path);
+            }
+            state
+                .tool_outputs_mut()
+                .record_edit(call_id, canonical_paths.clone());
+        }
+
         if let Some(turn_state) = self.current_turn_state().await {
             let mut guard = turn_state.lock().await;
-            guard.record_served_code_ranges(path, ranges);
+            for path in &canonical_paths {
+                let rel_path = path.strip_prefix(&workspace_root).unwrap_or(path);
+                guard.forget_code_ranges(&rel_path.display().to_string());
+            }
         }
     }
 
@@ -619,7 +871,8 @@ impl Session {
             return;
         }
 
-        let notice_len = TURN_OUTPUT_TRUNCATION_NOTICE.len();
+        let notice_template = self.turn_output_truncation_notice().await;
+        let notice_len = notice_template.len();
         let Some(decision) = self
             .reserve_tool_output_budget(desired_bytes, notice_len)
             .await
@@ -636,7 +889,7 @@ impl Session {
             decision.allowed_content_bytes,
         );
 
-        let notice = truncated_notice(TURN_OUTPUT_TRUNCATION_NOTICE, decision.notice_bytes);
+        let notice = truncated_notice(&notice_template, decision.notice_bytes);
         if !notice.is_empty() {
             if !output.aggregated_output.text.is_empty()
                 && !output.aggregated_output.text.ends_with('\n')
@@ -649,29 +902,272 @@ impl Session {
         output.aggregated_output.truncated_by_bytes = true;
     }
 
-    async fn apply_build_log_tail(&self, command: &[String], output: &mut ExecToolCallOutput) {
-        if !should_tail_build_output(command) {
+    /// Cut command output down for the model: by the matching output rule
+    /// when there is one, otherwise by the build log, rg and generic handling.
+    async fn shape_exec_output(
+        &self,
+        call_id: &str,
+        command: &[String],
+        cwd: &Path,
+        output: &mut ExecToolCallOutput,
+        caps: &ExecOutputCaps,
+        rule: Option<&OutputRule>,
+    ) {
+        if let Some(rule) = rule {
+            self.apply_output_rule(call_id, rule, output).await;
+            return;
+        }
+        self.apply_build_log_tail(call_id, command, output, caps)
+            .await;
+        self.apply_rg_output(call_id, command, cwd, output, caps)
+            .await;
+        self.apply_output_cap(call_id, command, output, caps).await;
+    }
+
+    /// Shape output with a configured rule, spilling the untrimmed text when
+    /// anything is dropped.
+    async fn apply_output_rule(
+        &self,
+        call_id: &str,
+        rule: &OutputRule,
+        output: &mut ExecToolCallOutput,
+    ) {
+        let Some(mut shaped) = shape_output(rule, &output.aggregated_output.text) else {
             return;
+        };
+        if let Some(notice) = self
+            .spill_full_output(call_id, &output.aggregated_output.text)
+            .await
+        {
+            shaped.push('\n');
+            shaped.push_str(&notice);
         }
+        output.aggregated_output.text = shaped;
+        output.aggregated_output.truncated_by_bytes = true;
+    }
+
+    /// Trim a build or test log to its tail. When anything is dropped, the
+    /// untrimmed log is spilled to disk and a digest of the first errors it
+    /// reports is placed above the tail.
+    async fn apply_build_log_tail(
+        &self,
+        call_id: &str,
+        command: &[String],
+        output: &mut ExecToolCallOutput,
+        caps: &ExecOutputCaps,
+    ) {
+        let Some(tool) = build_tool_for_command(command) else {
+            return;
+        };
 
-        if !tail_string_to_last_lines(&mut output.aggregated_output.text, BUILD_LOG_TAIL_LINES) {
+        let tail_lines = caps.build_log_tail_lines;
+        let mut tail = output.aggregated_output.text.clone();
+        let trimmed_lines = tail_string_to_last_lines(&mut tail, tail_lines);
+        let trimmed_bytes = tail_string_to_last_bytes(&mut tail, BUILD_LOG_TAIL_MAX_BYTES);
+        if !trimmed_lines && !trimmed_bytes {
             return;
         }
 
-        if !output.aggregated_output.text.is_empty()
-            && !output.aggregated_output.text.ends_with('\n')
-        {
-            output.aggregated_output.text.push('\n');
+        let full_log = std::mem::take(&mut output.aggregated_output.text);
+        let mut text = String::new();
+        let digest = extract_failure_digest(tool, &full_log, caps.build_log_digest_errors);
+        if !digest.is_empty() {
+            text.push_str(&format_failure_digest(&digest));
         }
-        output
-            .aggregated_output
-            .text
-            .push_str(BUILD_LOG_TAIL_NOTICE);
-        output.aggregated_output.truncated_after_lines = Some(BUILD_LOG_TAIL_LINES as u32);
+        if let Some(notice) = self.spill_full_output(call_id, &full_log).await {
+            text.push_str(&notice);
+            text.push('\n');
+        }
+        text.push_str(&tail);
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&build_log_tail_notice(tail_lines));
+
+        output.aggregated_output.text = text;
+        output.aggregated_output.truncated_after_lines = Some(tail_lines as u32);
         output.aggregated_output.truncated_by_bytes = true;
         self.record_turn_log_tail().await;
     }
 
+    /// Group `rg` search results by file into the compact JSON form, leaving
+    /// out matches already shown this turn. Output that cannot be grouped is
+    /// cut to the rg cap instead.
+    async fn apply_rg_output(
+        &self,
+        call_id: &str,
+        command: &[String],
+        cwd: &Path,
+        output: &mut ExecToolCallOutput,
+        caps: &ExecOutputCaps,
+    ) {
+        if !command_invokes_ripgrep(command) {
+            return;
+        }
+
+        let compact = match (rg_output_format(command), self.current_turn_state().await) {
+            (Some(format), Some(turn_state)) => {
+                let mut guard = turn_state.lock().await;
+                compact_rg_output(
+                    format,
+                    &output.aggregated_output.text,
+                    cwd,
+                    guard.seen_search_matches_mut(),
+                    caps.rg_max_bytes,
+                )
+            }
+            _ => None,
+        };
+        if let Some(compact) = compact {
+            output.aggregated_output.text = compact;
+            return;
+        }
+
+        self.truncate_and_spill(
+            call_id,
+            output,
+            caps.rg_max_bytes,
+            &rg_truncation_notice(caps.rg_max_bytes),
+        )
+        .await;
+    }
+
+    /// Cut output that neither the build log nor the rg handling covers to
+    /// the exec output cap.
+    async fn apply_output_cap(
+        &self,
+        call_id: &str,
+        command: &[String],
+        output: &mut ExecToolCallOutput,
+        caps: &ExecOutputCaps,
+    ) {
+        if command_invokes_ripgrep(command) || build_tool_for_command(command).is_some() {
+            return;
+        }
+        self.truncate_and_spill(
+            call_id,
+            output,
+            caps.exec_output_max_bytes,
+            &exec_truncation_notice(caps.exec_output_max_bytes),
+        )
+        .await;
+    }
+
+    /// Cut `output` to `max_bytes` and follow it with `notice`. The untrimmed
+    /// text is spilled first so a second notice can name its handle.
+    async fn truncate_and_spill(
+        &self,
+        call_id: &str,
+        output: &mut ExecToolCallOutput,
+        max_bytes: usize,
+        notice: &str,
+    ) {
+        if output.aggregated_output.text.len() <= max_bytes {
+            return;
+        }
+        let spill_notice = self
+            .spill_full_output(call_id, &output.aggregated_output.text)
+            .await;
+
+        let text = &mut output.aggregated_output.text;
+        truncate_string_to_bytes(text, max_bytes);
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(notice);
+        if let Some(spill_notice) = spill_notice {
+            text.push('\n');
+            text.push_str(&spill_notice);
+        }
+        output.aggregated_output.truncated_by_bytes = true;
+    }
+
+    /// Rebuild `params.env` on top of the login-shell snapshot when the
+    /// environment policy asks for one. Leaves it alone if no snapshot is
+    /// available.
+    async fn apply_env_snapshot(&self, turn_context: &TurnContext, params: &mut ExecParams) {
+        let policy = &turn_context.shell_environment_policy;
+        if !policy.profile_snapshot {
+            return;
+        }
+        if let Some(snapshot) = self.services.env_snapshot.vars(&params.cwd).await {
+            params.env = create_env_with_snapshot(policy, &snapshot);
+        }
+    }
+
+    /// Write `output` to this session's artifacts directory and return the
+    /// notice naming its handle.
+    async fn spill_full_output(&self, call_id: &str, output: &str) -> Option<String> {
+        match spill_output(&self.services.output_artifacts_dir, call_id, output).await {
+            Ok(handle) => Some(output_spill_notice(&handle, count_lines(output))),
+            Err(err) => {
+                warn!("failed to spill output for {call_id}: {err}");
+                None
+            }
+        }
+    }
+
+    /// Relax the context budgets for the running turn, or for the next turn
+    /// when the agent is idle, and announce the grant to clients.
+    pub(crate) async fn grant_relax(
+        &self,
+        sub_id: &str,
+        source: RelaxSource,
+        reason: Option<String>,
+        turn_output_max_bytes: usize,
+    ) -> bool {
+        let deferred = match self.current_turn_state().await {
+            Some(turn_state) => {
+                let mut guard = turn_state.lock().await;
+                if !guard.grant_relax(turn_output_max_bytes) {
+                    return false;
+                }
+                false
+            }
+            None => {
+                let mut state = self.state.lock().await;
+                state.defer_relax();
+                true
+            }
+        };
+
+        let event = Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::RelaxGranted(RelaxGrantedEvent {
+                source,
+                turn_output_max_bytes: turn_output_max_bytes as u64,
+                deferred,
+                reason,
+            }),
+        };
+        self.send_event(event).await;
+        true
+    }
+
+    pub(crate) async fn begin_turn(&self) -> u64 {
+        let mut state = self.state.lock().await;
+        state.begin_turn()
+    }
+
+    pub(crate) async fn take_pending_relax(&self) -> bool {
+        let mut state = self.state.lock().await;
+        state.take_pending_relax()
+    }
+
+    pub(crate) async fn is_turn_relaxed(&self) -> bool {
+        match self.current_turn_state().await {
+            Some(turn_state) => turn_state.lock().await.is_relaxed(),
+            None => false,
+        }
+    }
+
+    async fn take_turn_breaker_bypass(&self) -> bool {
+        match self.current_turn_state().await {
+            Some(turn_state) => turn_state.lock().await.take_breaker_bypass(),
+            None => false,
+        }
+    }
+
     async fn record_turn_command_blocked(&self) {
         if let Some(turn_state) = self.current_turn_state().await {
             let mut guard = turn_state.lock().await;
@@ -686,7 +1182,9 @@ impl Session {
         }
     }
 
-    pub(crate) async fn log_turn_metrics(&self, turn_id: &str, metrics: TurnMetrics) {
+    /// Log the counters for a finished turn and report them, together with
+    /// the running session totals, as an `EventMsg::TurnMetrics`.
+    pub(crate) async fn emit_turn_metrics(&self, turn_id: &str, metrics: TurnMetrics) {
         if metrics.is_empty() {
             return;
         }
@@ -698,8 +1196,31 @@ impl Session {
             outputs_truncated = metrics.outputs_truncated,
             commands_blocked = metrics.commands_blocked,
             log_tail_invocations = metrics.log_tail_invocations,
+            relax_grants = metrics.relax_grants,
+            breaker_bypasses = metrics.breaker_bypasses,
+            chunk_cache_hits = metrics.chunk_cache_hits,
+            chunk_cache_misses = metrics.chunk_cache_misses,
+            read_plans_accepted = metrics.read_plans_accepted,
+            read_plans_rejected = metrics.read_plans_rejected,
+            planned_read_bytes = metrics.planned_read_bytes,
+            read_code_bytes = metrics.read_code_bytes,
+            unplanned_reads = metrics.unplanned_reads,
             "turn_metrics"
         );
+
+        let turn = ContextMetrics::from(&metrics);
+        let session_total = {
+            let mut state = self.state.lock().await;
+            state.add_turn_metrics(&turn)
+        };
+        let event = Event {
+            id: turn_id.to_string(),
+            msg: EventMsg::TurnMetrics(TurnMetricsEvent {
+                turn,
+                session_total,
+            }),
+        };
+        self.send_event(event).await;
     }
 
     pub async fn request_command_approval(
@@ -954,12 +1475,14 @@ impl Session {
         &self,
         turn_diff_tracker: &mut TurnDiffTracker,
         exec_command_context: ExecCommandContext,
+        sandbox_type: SandboxType,
     ) {
         let ExecCommandContext {
             sub_id,
             call_id,
             command_for_display,
             cwd,
+            timeout_ms,
             apply_patch,
         } = exec_command_context;
         let msg = match apply_patch {
@@ -983,6 +1506,8 @@ impl Session {
                     .into_iter()
                     .map(Into::into)
                     .collect(),
+                timeout_ms,
+                sandbox_type,
             }),
         };
         let event = Event {
@@ -1067,27 +1592,59 @@ impl Session {
         let sub_id = begin_ctx.sub_id.clone();
         let call_id = begin_ctx.call_id.clone();
 
-        self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
+        self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone(), exec_args.sandbox_type)
             .await;
 
-        let mut result = process_exec_tool_call(
+        let output_rule = output_rule_for(exec_args.output_rules, &begin_ctx.command_for_display);
+        // Output past the caps is spilled to disk below, so keep it whole.
+        let output_caps = ExecOutputCaps {
+            keep_full_output: true,
+            ..exec_args.output_caps
+        };
+        let mut result = process_exec_tool_call_with_caps(
             exec_args.params,
             exec_args.sandbox_type,
             exec_args.sandbox_policy,
             exec_args.sandbox_cwd,
             exec_args.codex_linux_sandbox_exe,
             exec_args.stdout_stream,
+            output_caps,
         )
         .await;
 
         match &mut result {
             Ok(output) => {
-                self.apply_build_log_tail(&begin_ctx.command_for_display, output)
-                    .await;
+                self.shape_exec_output(
+                    &call_id,
+                    &begin_ctx.command_for_display,
+                    &begin_ctx.cwd,
+                    output,
+                    &output_caps,
+                    output_rule,
+                )
+                .await;
             }
             Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => {
-                self.apply_build_log_tail(&begin_ctx.command_for_display, output)
-                    .await;
+                self.shape_exec_output(
+                    &call_id,
+                    &begin_ctx.command_for_display,
+                    &begin_ctx.cwd,
+                    output,
+                    &output_caps,
+                    output_rule,
+                )
+                .await;
+            }
+            Err(CodexErr::Sandbox(SandboxErr::ResourceLimit { output, .. })) => {
+                self.shape_exec_output(
+                    &call_id,
+                    &begin_ctx.command_for_display,
+                    &begin_ctx.cwd,
+                    output,
+                    &output_caps,
+                    output_rule,
+                )
+                .await;
             }
             _ => {}
         }
@@ -1099,6 +1656,9 @@ impl Session {
             Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => {
                 self.apply_turn_output_budget(output).await;
             }
+            Err(CodexErr::Sandbox(SandboxErr::ResourceLimit { output, .. })) => {
+                self.apply_turn_output_budget(output).await;
+            }
             _ => {}
         }
 
@@ -1106,6 +1666,7 @@ impl Session {
         let borrowed: &ExecToolCallOutput = match &result {
             Ok(output) => output,
             Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => output,
+            Err(CodexErr::Sandbox(SandboxErr::ResourceLimit { output, .. })) => output,
             Err(e) => {
                 output_stderr = ExecToolCallOutput {
                     exit_code: -1,
@@ -1170,6 +1731,9 @@ impl Session {
             state.check_repeat_command(command, now)
         };
         if blocked.is_some() {
+            if self.take_turn_breaker_bypass().await {
+                return None;
+            }
             self.record_turn_command_blocked().await;
         }
         blocked
@@ -1185,11 +1749,24 @@ impl Session {
         state.record_repeat_command(command, &aggregated, Instant::now());
     }
 
+    /// Remember a shell command so its output can be compacted once a later
+    /// run of the same build or test command supersedes it.
+    async fn record_command_output(&self, call_id: &str, command: &[String]) {
+        let mut state = self.state.lock().await;
+        state.tool_outputs_mut().record_command(call_id, command);
+    }
+
     /// Build the full turn input by concatenating the current conversation
-    /// history with additional items for this turn.
+    /// history with additional items for this turn. Stale tool output is
+    /// compacted in the stored history first so the rewrite is kept for every
+    /// later request.
     pub async fn turn_input_with_history(&self, extra: Vec<ResponseItem>) -> Vec<ResponseItem> {
         let history = {
-            let state = self.state.lock().await;
+            let mut state = self.state.lock().await;
+            let compacted = state.compact_stale_tool_outputs();
+            if compacted > 0 {
+                debug!(compacted, "compacted stale tool outputs");
+            }
             state.history_snapshot()
         };
         [history, extra].concat()
@@ -1274,6 +1851,7 @@ pub(crate) struct ExecCommandContext {
     pub(crate) call_id: String,
     pub(crate) command_for_display: Vec<String>,
     pub(crate) cwd: PathBuf,
+    pub(crate) timeout_ms: Option<u64>,
     pub(crate) apply_patch: Option<ApplyPatchCommandContext>,
 }
 
@@ -1372,9 +1950,11 @@ async fn submission_loop(
                     approval_policy: new_approval_policy,
                     sandbox_policy: new_sandbox_policy.clone(),
                     shell_environment_policy: prev.shell_environment_policy.clone(),
+                    sandbox_resources: prev.sandbox_resources,
                     cwd: new_cwd.clone(),
                     is_review_mode: false,
                     final_output_json_schema: None,
+                    context_policy: prev.context_policy.clone(),
                 };
 
                 // Install the new persistent context for subsequent tasks/turns.
@@ -1472,9 +2052,11 @@ async fn submission_loop(
                         approval_policy,
                         sandbox_policy,
                         shell_environment_policy: turn_context.shell_environment_policy.clone(),
+                        sandbox_resources: turn_context.sandbox_resources,
                         cwd,
                         is_review_mode: false,
                         final_output_json_schema,
+                        context_policy: turn_context.context_policy.clone(),
                     };
 
                     // if the environment context has changed, record it in the conversation history
@@ -1505,6 +2087,9 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other).await,
             },
+            Op::AddCommandRule { rule } => {
+                sess.state.lock().await.add_command_rule(rule);
+            }
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1594,6 +2179,7 @@ async fn submission_loop(
             }
             Op::Shutdown => {
                 sess.abort_all_tasks(TurnAbortReason::Interrupted).await;
+                sess.services.background_jobs.kill_all().await;
                 info!("Shutting down Codex instance");
 
                 // Gracefully flush and shutdown rollout recorder on session end so tests
@@ -1659,6 +2245,23 @@ async fn submission_loop(
                 )
                 .await;
             }
+            Op::Relax => {
+                if !sess
+                    .grant_relax(
+                        &sub.id,
+                        RelaxSource::User,
+                        None,
+                        turn_context.context_policy.relax_per_turn_max_bytes,
+                    )
+                    .await
+                {
+                    sess.notify_background_event(
+                        &sub.id,
+                        "Context budgets are already relaxed for this turn.",
+                    )
+                    .await;
+                }
+            }
             _ => {
                 // Ignore unknown ops; enum is non_exhaustive to allow extensions.
             }
@@ -1731,9 +2334,11 @@ async fn spawn_review_thread(
         approval_policy: parent_turn_context.approval_policy,
         sandbox_policy: parent_turn_context.sandbox_policy.clone(),
         shell_environment_policy: parent_turn_context.shell_environment_policy.clone(),
+        sandbox_resources: parent_turn_context.sandbox_resources,
         cwd: parent_turn_context.cwd.clone(),
         is_review_mode: true,
         final_output_json_schema: None,
+        context_policy: parent_turn_context.context_policy.clone(),
     };
 
     // Seed the child task with the review prompt as the initial user message.
@@ -2550,11 +3155,17 @@ async fn handle_response_item(
     Ok(output)
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_unified_exec_tool_call(
     sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    call_id: &str,
     session_id: Option<String>,
     arguments: Vec<String>,
     timeout_ms: Option<u64>,
+    with_escalated_permissions: bool,
+    justification: Option<String>,
 ) -> Result<String, FunctionCallError> {
     let parsed_session_id = if let Some(session_id) = session_id {
         match session_id.parse::<i32>() {
@@ -2569,9 +3180,48 @@ async fn handle_unified_exec_tool_call(
         None
     };
 
+    // Input to a running session goes to its stdin; it only needs approval
+    // when the session runs outside the sandbox.
+    let command = if let Some(session_id) = parsed_session_id {
+        if sess
+            .services
+            .unified_exec_manager
+            .is_escalated(session_id)
+            .await
+        {
+            approve_pty_input(
+                sess,
+                turn_context,
+                "unified_exec",
+                sub_id,
+                call_id,
+                &arguments.join(" "),
+            )
+            .await?;
+        }
+        None
+    } else if !arguments.is_empty() {
+        Some(
+            prepare_pty_command(
+                sess,
+                turn_context,
+                "unified_exec",
+                sub_id,
+                call_id,
+                arguments.clone(),
+                with_escalated_permissions,
+                justification,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
     let request = crate::unified_exec::UnifiedExecRequest {
         session_id: parsed_session_id,
         input_chunks: &arguments,
+        command,
         timeout_ms,
     };
 
@@ -2632,6 +3282,10 @@ async fn handle_function_call(
                 session_id: Option<String>,
                 #[serde(default)]
                 timeout_ms: Option<u64>,
+                #[serde(default)]
+                with_escalated_permissions: Option<bool>,
+                #[serde(default)]
+                justification: Option<String>,
             }
 
             let args: UnifiedExecArgs = serde_json::from_str(&arguments).map_err(|err| {
@@ -2640,7 +3294,18 @@ async fn handle_function_call(
                 ))
             })?;
 
-            handle_unified_exec_tool_call(sess, args.session_id, args.input, args.timeout_ms).await
+            handle_unified_exec_tool_call(
+                sess,
+                turn_context,
+                &sub_id,
+                &call_id,
+                args.session_id,
+                args.input,
+                args.timeout_ms,
+                args.with_escalated_permissions.unwrap_or(false),
+                args.justification,
+            )
+            .await
         }
         "view_image" => {
             #[derive(serde::Deserialize)]
@@ -2676,6 +3341,7 @@ async fn handle_function_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                resource_limits: turn_context.sandbox_resources,
             };
             handle_container_exec_with_params(
                 name.as_str(),
@@ -2688,19 +3354,62 @@ async fn handle_function_call(
             )
             .await
         }
-        "read_code" => handle_read_code_tool_call(sess, turn_context, arguments).await,
+        "read_code" => handle_read_code_tool_call(sess, turn_context, &call_id, arguments).await,
+        "plan_reads" => handle_plan_reads_tool_call(sess, turn_context, arguments).await,
+        "request_relax" => {
+            #[derive(serde::Deserialize)]
+            struct RequestRelaxArgs {
+                reason: String,
+            }
+            let args: RequestRelaxArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            let granted = sess
+                .grant_relax(
+                    &sub_id,
+                    RelaxSource::Model,
+                    Some(args.reason),
+                    turn_context.context_policy.relax_per_turn_max_bytes,
+                )
+                .await;
+            if granted {
+                Ok(format!(
+                    "context budgets relaxed for this turn (tool output budget {} bytes)",
+                    turn_context.context_policy.relax_per_turn_max_bytes
+                ))
+            } else {
+                Err(FunctionCallError::RespondToModel(
+                    "context budgets are already relaxed for this turn".to_string(),
+                ))
+            }
+        }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        BACKGROUND_JOB_TOOL_NAME => {
+            handle_background_job_tool_call(sess, turn_context, &sub_id, &call_id, arguments).await
+        }
         EXEC_COMMAND_TOOL_NAME => {
-            // TODO(mbolin): Sandbox check.
             let exec_params: ExecCommandParams = serde_json::from_str(&arguments).map_err(|e| {
                 FunctionCallError::RespondToModel(format!(
                     "failed to parse function arguments: {e:?}"
                 ))
             })?;
+            let command = prepare_pty_command(
+                sess,
+                turn_context,
+                EXEC_COMMAND_TOOL_NAME,
+                &sub_id,
+                &call_id,
+                exec_params.shell_command(),
+                exec_params.with_escalated_permissions.unwrap_or(false),
+                exec_params.justification.clone(),
+            )
+            .await?;
             let result = sess
                 .services
                 .session_manager
-                .handle_exec_command_request(exec_params)
+                .handle_exec_command_request(exec_params, command)
                 .await;
             match result {
                 Ok(output) => Ok(output.to_text_output()),
@@ -2714,6 +3423,22 @@ async fn handle_function_call(
                         "failed to parse function arguments: {e:?}"
                     ))
                 })?;
+            if sess
+                .services
+                .session_manager
+                .is_escalated(write_stdin_params.session_id)
+                .await
+            {
+                approve_pty_input(
+                    sess,
+                    turn_context,
+                    WRITE_STDIN_TOOL_NAME,
+                    &sub_id,
+                    &call_id,
+                    &write_stdin_params.chars,
+                )
+                .await?;
+            }
 
             let result = sess
                 .services
@@ -2749,6 +3474,7 @@ async fn handle_custom_tool_call(
                 env: HashMap::new(),
                 with_escalated_permissions: None,
                 justification: None,
+                resource_limits: turn_context.sandbox_resources,
             };
 
             handle_container_exec_with_params(
@@ -2779,6 +3505,7 @@ fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> Ex
         env: create_env(&turn_context.shell_environment_policy),
         with_escalated_permissions: params.with_escalated_permissions,
         justification: params.justification,
+        resource_limits: turn_context.sandbox_resources,
     }
 }
 
@@ -2801,6 +3528,8 @@ pub struct ExecInvokeArgs<'a> {
     pub sandbox_cwd: &'a Path,
     pub codex_linux_sandbox_exe: &'a Option<PathBuf>,
     pub stdout_stream: Option<StdoutStream>,
+    pub output_caps: ExecOutputCaps,
+    pub output_rules: &'a [OutputRule],
 }
 
 fn maybe_translate_shell_command(
@@ -2891,6 +3620,7 @@ async fn handle_container_exec_with_params(
                 env: HashMap::new(),
                 with_escalated_permissions: params.with_escalated_permissions,
                 justification: params.justification.clone(),
+                resource_limits: params.resource_limits,
             };
             let safety = if *user_explicitly_approved_this_action {
                 SafetyCheck::AutoApprove {
@@ -2911,13 +3641,18 @@ async fn handle_container_exec_with_params(
             )
         }
         None => {
+            let mut params = params;
+            sess.apply_env_snapshot(turn_context, &mut params).await;
             let safety = {
                 let state = sess.state.lock().await;
                 assess_command_safety(
                     &params.command,
+                    &params.cwd,
                     turn_context.approval_policy,
                     &turn_context.sandbox_policy,
                     state.approved_commands_ref(),
+                    &state.command_rules,
+                    &sess.services.exec_policy,
                     params.with_escalated_permissions.unwrap_or(false),
                 )
             };
@@ -2926,39 +3661,327 @@ async fn handle_container_exec_with_params(
         }
     };
 
-    if apply_patch_exec.is_none() {
-        if let Some(block) = sess
+    if apply_patch_exec.is_none()
+        && let Some(block) = sess
             .should_block_repeated_command(&command_for_display)
             .await
-        {
-            let command_str = if command_for_display.is_empty() {
-                "<empty command>".to_string()
+    {
+        let command_str = if command_for_display.is_empty() {
+            "<empty command>".to_string()
+        } else {
+            command_for_display.join(" ")
+        };
+        let repeat_count = block.repeat_count;
+        let plural = if repeat_count == 1 { "" } else { "s" };
+        let key = &block.key;
+        let mut message = format!(
+            "repeat-command breaker: `{command_str}` (normalized: `{key}`) already ran {repeat_count} time{plural} in the last {} seconds without producing new output; refine the command (e.g., narrow its scope) or request /relax to override.",
+            block.window.as_secs()
+        );
+        if let Some(excerpt) = block.last_excerpt.as_deref() {
+            let indented = excerpt.replace('\n', "\n  ");
+            message.push_str(&format!("\nLast output sample:\n  {indented}"));
+        }
+        sess.notify_background_event(&sub_id, message.clone()).await;
+        return Err(FunctionCallError::RespondToModel(message));
+    }
+
+    let sandbox_type = resolve_command_safety(
+        sess,
+        &otel_event_manager,
+        tool_name,
+        &sub_id,
+        &call_id,
+        &params.command,
+        &params.cwd,
+        params.justification.clone(),
+        safety,
+    )
+    .await?;
+
+    let exec_command_context = ExecCommandContext {
+        sub_id: sub_id.clone(),
+        call_id: call_id.clone(),
+        command_for_display: command_for_display.clone(),
+        cwd: params.cwd.clone(),
+        timeout_ms: params.timeout_ms,
+        apply_patch: apply_patch_exec.map(
+            |ApplyPatchExec {
+                 action,
+                 user_explicitly_approved_this_action,
+             }| ApplyPatchCommandContext {
+                user_explicitly_approved_this_action,
+                changes: convert_apply_patch_to_protocol(&action),
+            },
+        ),
+    };
+
+    let params = maybe_translate_shell_command(params, sess, turn_context);
+    let output_result = sess
+        .run_exec_with_events(
+            turn_diff_tracker,
+            exec_command_context.clone(),
+            ExecInvokeArgs {
+                params: params.clone(),
+                sandbox_type,
+                sandbox_policy: &turn_context.sandbox_policy,
+                sandbox_cwd: &turn_context.cwd,
+                codex_linux_sandbox_exe: &sess.services.codex_linux_sandbox_exe,
+                stdout_stream: if exec_command_context.apply_patch.is_some() {
+                    None
+                } else {
+                    Some(StdoutStream {
+                        sub_id: sub_id.clone(),
+                        call_id: call_id.clone(),
+                        tx_event: sess.tx_event.clone(),
+                    })
+                },
+                output_caps: ExecOutputCaps::from(&turn_context.context_policy),
+                output_rules: &turn_context.context_policy.output_rules,
+            },
+        )
+        .await;
+
+    if let Some(apply_patch) = &exec_command_context.apply_patch {
+        sess.invalidate_code_reads(&call_id, &params.cwd, &apply_patch.changes)
+            .await;
+    } else {
+        sess.record_command_output(&call_id, &params.command).await;
+    }
+
+    match output_result {
+        Ok(output) => {
+            let ExecToolCallOutput { exit_code, .. } = &output;
+            let content = format_exec_output(&output);
+            if *exit_code == 0 {
+                Ok(content)
             } else {
-                command_for_display.join(" ")
-            };
-            let repeat_count = block.repeat_count;
-            let plural = if repeat_count == 1 { "" } else { "s" };
-            let mut message = format!(
-                "repeat-command breaker: `{command_str}` already ran {repeat_count} time{plural} in the last {} seconds without producing new output; refine the command (e.g., narrow its scope) or request /relax to override.",
-                block.window.as_secs()
-            );
-            if let Some(excerpt) = block.last_excerpt.as_deref() {
-                let indented = excerpt.replace('\n', "\n  ");
-                message.push_str(&format!("\nLast output sample:\n  {indented}"));
+                Err(FunctionCallError::RespondToModel(content))
             }
-            sess.notify_background_event(&sub_id, message.clone()).await;
-            return Err(FunctionCallError::RespondToModel(message));
         }
+        Err(CodexErr::Sandbox(error)) => {
+            handle_sandbox_error(
+                tool_name,
+                turn_diff_tracker,
+                params,
+                exec_command_context,
+                error,
+                sandbox_type,
+                sess,
+                turn_context,
+                &otel_event_manager,
+            )
+            .await
+        }
+        Err(e) => Err(FunctionCallError::RespondToModel(format!(
+            "execution error: {e:?}"
+        ))),
+    }
+}
+
+async fn handle_background_job_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    call_id: &str,
+    arguments: String,
+) -> Result<String, FunctionCallError> {
+    let args: BackgroundJobArgs = serde_json::from_str(&arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let jobs = &sess.services.background_jobs;
+    let result = match args {
+        BackgroundJobArgs::Start { command, workdir } => {
+            jobs.ensure_capacity()
+                .map_err(FunctionCallError::RespondToModel)?;
+            let mut params = ExecParams {
+                command,
+                cwd: turn_context.resolve_path(workdir),
+                timeout_ms: None,
+                env: create_env(&turn_context.shell_environment_policy),
+                with_escalated_permissions: None,
+                justification: None,
+                resource_limits: turn_context.sandbox_resources,
+            };
+            sess.apply_env_snapshot(turn_context, &mut params).await;
+            let safety = {
+                let state = sess.state.lock().await;
+                assess_command_safety(
+                    &params.command,
+                    &params.cwd,
+                    turn_context.approval_policy,
+                    &turn_context.sandbox_policy,
+                    state.approved_commands_ref(),
+                    &state.command_rules,
+                    &sess.services.exec_policy,
+                    false,
+                )
+            };
+            let sandbox_type = resolve_command_safety(
+                sess,
+                &turn_context.client.get_otel_event_manager(),
+                BACKGROUND_JOB_TOOL_NAME,
+                sub_id,
+                call_id,
+                &params.command,
+                &params.cwd,
+                None,
+                safety,
+            )
+            .await?;
+            let command = params.command.clone();
+            let child = spawn_exec_child(
+                params,
+                sandbox_type,
+                &turn_context.sandbox_policy,
+                &turn_context.cwd,
+                &sess.services.codex_linux_sandbox_exe,
+                StdioPolicy::RedirectForBackgroundJob,
+            )
+            .await
+            .map_err(|err| {
+                FunctionCallError::RespondToModel(format!("failed to start job: {err}"))
+            })?;
+            jobs.start(sub_id, &command, child).await
+        }
+        BackgroundJobArgs::Status { job_id } => jobs.status(job_id),
+        BackgroundJobArgs::Tail { job_id, lines } => jobs.tail(job_id, lines).await,
+        BackgroundJobArgs::Grep { job_id, pattern } => jobs.grep(job_id, &pattern).await,
+        BackgroundJobArgs::Kill { job_id } => jobs.kill(job_id).await,
+    };
+    result.map_err(FunctionCallError::RespondToModel)
+}
+
+/// Approve `command` for an interactive PTY session the same way `shell`
+/// commands are approved, and wrap it for the sandbox it will run under.
+///
+/// Approval covers only the launch, while the session goes on to run whatever
+/// is written to its stdin, so the session keeps the platform sandbox unless
+/// the model asked for escalated permissions. Input to an escalated session
+/// is approved write by write in [`approve_pty_input`].
+#[allow(clippy::too_many_arguments)]
+async fn prepare_pty_command(
+    sess: &Session,
+    turn_context: &TurnContext,
+    tool_name: &str,
+    sub_id: &str,
+    call_id: &str,
+    command: Vec<String>,
+    with_escalated_permissions: bool,
+    justification: Option<String>,
+) -> Result<PtyCommand, FunctionCallError> {
+    let safety = {
+        let state = sess.state.lock().await;
+        assess_command_safety(
+            &command,
+            &turn_context.cwd,
+            turn_context.approval_policy,
+            &turn_context.sandbox_policy,
+            state.approved_commands_ref(),
+            &state.command_rules,
+            &sess.services.exec_policy,
+            with_escalated_permissions,
+        )
+    };
+    let approved_sandbox_type = resolve_command_safety(
+        sess,
+        &turn_context.client.get_otel_event_manager(),
+        tool_name,
+        sub_id,
+        call_id,
+        &command,
+        &turn_context.cwd,
+        justification,
+        safety,
+    )
+    .await?;
+    let sandbox_type = match &turn_context.sandbox_policy {
+        _ if with_escalated_permissions => approved_sandbox_type,
+        SandboxPolicy::DangerFullAccess => SandboxType::None,
+        _ => get_platform_sandbox().unwrap_or(SandboxType::None),
+    };
+
+    PtyCommand::new(
+        command,
+        turn_context.cwd.clone(),
+        sandbox_type,
+        &turn_context.sandbox_policy,
+        &turn_context.cwd,
+        &sess.services.codex_linux_sandbox_exe,
+    )
+    .map_err(|err| FunctionCallError::RespondToModel(format!("failed to sandbox command: {err}")))
+}
+
+/// Ask the user before writing `input` to a PTY session that runs outside
+/// the sandbox. Empty input only polls for output and needs no approval.
+async fn approve_pty_input(
+    sess: &Session,
+    turn_context: &TurnContext,
+    tool_name: &str,
+    sub_id: &str,
+    call_id: &str,
+    input: &str,
+) -> Result<(), FunctionCallError> {
+    if input.is_empty() {
+        return Ok(());
+    }
+    let otel_event_manager = turn_context.client.get_otel_event_manager();
+    if turn_context.approval_policy == AskForApproval::Never {
+        otel_event_manager.tool_decision(
+            tool_name,
+            call_id,
+            ReviewDecision::Denied,
+            ToolDecisionSource::Config,
+        );
+        return Err(FunctionCallError::RespondToModel(
+            "input to a session running outside the sandbox rejected by user approval settings"
+                .to_string(),
+        ));
     }
 
-    let sandbox_type = match safety {
+    let decision = sess
+        .request_command_approval(
+            sub_id.to_string(),
+            call_id.to_string(),
+            vec![tool_name.to_string(), input.to_string()],
+            turn_context.cwd.clone(),
+            Some("writes to the stdin of a session running outside the sandbox".to_string()),
+        )
+        .await;
+    otel_event_manager.tool_decision(tool_name, call_id, decision, ToolDecisionSource::User);
+    match decision {
+        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => Ok(()),
+        ReviewDecision::Denied => Err(FunctionCallError::RespondToModel(
+            "input rejected by user".to_string(),
+        )),
+        ReviewDecision::Abort => Err(FunctionCallError::RespondToModel(
+            "input aborted by user".to_string(),
+        )),
+    }
+}
+
+/// Turn the safety assessment of a command into the sandbox it runs under,
+/// asking the user first when the assessment requires it.
+#[allow(clippy::too_many_arguments)]
+async fn resolve_command_safety(
+    sess: &Session,
+    otel_event_manager: &OtelEventManager,
+    tool_name: &str,
+    sub_id: &str,
+    call_id: &str,
+    command: &[String],
+    cwd: &Path,
+    justification: Option<String>,
+    safety: SafetyCheck,
+) -> Result<SandboxType, FunctionCallError> {
+    match safety {
         SafetyCheck::AutoApprove {
             sandbox_type,
             user_explicitly_approved,
         } => {
             otel_event_manager.tool_decision(
                 tool_name,
-                call_id.as_str(),
+                call_id,
                 ReviewDecision::Approved,
                 if user_explicitly_approved {
                     ToolDecisionSource::User
@@ -2967,23 +3990,27 @@ async fn handle_container_exec_with_params(
                 },
             );
 
-            sandbox_type
+            Ok(sandbox_type)
         }
-        SafetyCheck::AskUser => {
+        SafetyCheck::AskUser { reason } => {
+            let reason = match (justification, reason) {
+                (Some(justification), Some(reason)) => Some(format!("{justification}\n{reason}")),
+                (justification, reason) => justification.or(reason),
+            };
             let decision = sess
                 .request_command_approval(
-                    sub_id.clone(),
-                    call_id.clone(),
-                    params.command.clone(),
-                    params.cwd.clone(),
-                    params.justification.clone(),
+                    sub_id.to_string(),
+                    call_id.to_string(),
+                    command.to_vec(),
+                    cwd.to_path_buf(),
+                    reason,
                 )
                 .await;
             match decision {
                 ReviewDecision::Approved => {
                     otel_event_manager.tool_decision(
                         tool_name,
-                        call_id.as_str(),
+                        call_id,
                         ReviewDecision::Approved,
                         ToolDecisionSource::User,
                     );
@@ -2991,16 +4018,16 @@ async fn handle_container_exec_with_params(
                 ReviewDecision::ApprovedForSession => {
                     otel_event_manager.tool_decision(
                         tool_name,
-                        call_id.as_str(),
+                        call_id,
                         ReviewDecision::ApprovedForSession,
                         ToolDecisionSource::User,
                     );
-                    sess.add_approved_command(params.command.clone()).await;
+                    sess.add_approved_command(command.to_vec()).await;
                 }
                 ReviewDecision::Denied => {
                     otel_event_manager.tool_decision(
                         tool_name,
-                        call_id.as_str(),
+                        call_id,
                         ReviewDecision::Denied,
                         ToolDecisionSource::User,
                     );
@@ -3011,7 +4038,7 @@ async fn handle_container_exec_with_params(
                 ReviewDecision::Abort => {
                     otel_event_manager.tool_decision(
                         tool_name,
-                        call_id.as_str(),
+                        call_id,
                         ReviewDecision::Abort,
                         ToolDecisionSource::User,
                     );
@@ -3024,88 +4051,19 @@ async fn handle_container_exec_with_params(
             // explicit approval. Often, we end up in this case because
             // the command cannot be run in a sandbox, such as
             // installing a new dependency that requires network access.
-            SandboxType::None
+            Ok(SandboxType::None)
         }
         SafetyCheck::Reject { reason } => {
             otel_event_manager.tool_decision(
                 tool_name,
-                call_id.as_str(),
+                call_id,
                 ReviewDecision::Denied,
                 ToolDecisionSource::Config,
             );
-            return Err(FunctionCallError::RespondToModel(format!(
+            Err(FunctionCallError::RespondToModel(format!(
                 "exec command rejected: {reason:?}"
-            )));
-        }
-    };
-
-    let exec_command_context = ExecCommandContext {
-        sub_id: sub_id.clone(),
-        call_id: call_id.clone(),
-        command_for_display: command_for_display.clone(),
-        cwd: params.cwd.clone(),
-        apply_patch: apply_patch_exec.map(
-            |ApplyPatchExec {
-                 action,
-                 user_explicitly_approved_this_action,
-             }| ApplyPatchCommandContext {
-                user_explicitly_approved_this_action,
-                changes: convert_apply_patch_to_protocol(&action),
-            },
-        ),
-    };
-
-    let params = maybe_translate_shell_command(params, sess, turn_context);
-    let output_result = sess
-        .run_exec_with_events(
-            turn_diff_tracker,
-            exec_command_context.clone(),
-            ExecInvokeArgs {
-                params: params.clone(),
-                sandbox_type,
-                sandbox_policy: &turn_context.sandbox_policy,
-                sandbox_cwd: &turn_context.cwd,
-                codex_linux_sandbox_exe: &sess.services.codex_linux_sandbox_exe,
-                stdout_stream: if exec_command_context.apply_patch.is_some() {
-                    None
-                } else {
-                    Some(StdoutStream {
-                        sub_id: sub_id.clone(),
-                        call_id: call_id.clone(),
-                        tx_event: sess.tx_event.clone(),
-                    })
-                },
-            },
-        )
-        .await;
-
-    match output_result {
-        Ok(output) => {
-            let ExecToolCallOutput { exit_code, .. } = &output;
-            let content = format_exec_output(&output);
-            if *exit_code == 0 {
-                Ok(content)
-            } else {
-                Err(FunctionCallError::RespondToModel(content))
-            }
-        }
-        Err(CodexErr::Sandbox(error)) => {
-            handle_sandbox_error(
-                tool_name,
-                turn_diff_tracker,
-                params,
-                exec_command_context,
-                error,
-                sandbox_type,
-                sess,
-                turn_context,
-                &otel_event_manager,
-            )
-            .await
+            )))
         }
-        Err(e) => Err(FunctionCallError::RespondToModel(format!(
-            "execution error: {e:?}"
-        ))),
     }
 }
 
@@ -3130,6 +4088,13 @@ async fn handle_sandbox_error(
         return Err(FunctionCallError::RespondToModel(content));
     }
 
+    // Retrying without the sandbox would hit the same limits, so report the
+    // violation to the model instead of asking to escalate.
+    if let SandboxErr::ResourceLimit { output, .. } = &error {
+        let content = format_exec_output(output);
+        return Err(FunctionCallError::RespondToModel(content));
+    }
+
     // Early out if either the user never wants to be asked for approval, or
     // we're letting the model manage escalation requests. Otherwise, continue
     match turn_context.approval_policy {
@@ -3202,6 +4167,8 @@ async fn handle_sandbox_error(
                                 tx_event: sess.tx_event.clone(),
                             })
                         },
+                        output_caps: ExecOutputCaps::from(&turn_context.context_policy),
+                        output_rules: &turn_context.context_policy.output_rules,
                     },
                 )
                 .await;
@@ -3364,6 +4331,20 @@ fn tail_string_to_last_lines(text: &mut String, max_lines: usize) -> bool {
     true
 }
 
+/// Keep at most the last `max_bytes` of `text`, starting on a char boundary.
+fn tail_string_to_last_bytes(text: &mut String, max_bytes: usize) -> bool {
+    if text.len() <= max_bytes {
+        return false;
+    }
+
+    let mut start = text.len() - max_bytes;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    text.drain(..start);
+    true
+}
+
 fn count_lines(text: &str) -> usize {
     if text.is_empty() {
         return 0;
@@ -3572,12 +4553,15 @@ pub(crate) async fn exit_review_mode(
 
 #[cfg(test)]
 pub(crate) use tests::make_session_and_context;
+#[cfg(test)]
+pub(crate) use tests::make_session_and_context_in;
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
+    use crate::config_types::OutputStrategy;
 
     use crate::protocol::CompactedItem;
     use crate::protocol::InitialHistory;
@@ -3836,12 +4820,18 @@ mod tests {
     }
 
     pub(crate) fn make_session_and_context() -> (Session, TurnContext) {
-        let (tx_event, _rx_event) = async_channel::unbounded();
         let codex_home = tempfile::tempdir().expect("create temp dir");
+        make_session_and_context_in(codex_home.path())
+    }
+
+    /// Like make_session_and_context, but keeps the session's files under
+    /// `codex_home` so several sessions can share them.
+    pub(crate) fn make_session_and_context_in(codex_home: &Path) -> (Session, TurnContext) {
+        let (tx_event, _rx_event) = async_channel::unbounded();
         let config = Config::load_from_base_config_with_overrides(
             ConfigToml::default(),
             ConfigOverrides::default(),
-            codex_home.path().to_path_buf(),
+            codex_home.to_path_buf(),
         )
         .expect("load default test config");
         let config = Arc::new(config);
@@ -3873,19 +4863,29 @@ mod tests {
             approval_policy: config.approval_policy,
             sandbox_policy: config.sandbox_policy.clone(),
             shell_environment_policy: config.shell_environment_policy.clone(),
+            sandbox_resources: config.sandbox_resources,
             tools_config,
             is_review_mode: false,
             final_output_json_schema: None,
+            context_policy: config.context_policy.clone(),
         };
         let services = SessionServices {
             mcp_connection_manager: McpConnectionManager::default(),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            background_jobs: BackgroundJobManager::new(
+                codex_home.join(JOB_LOGS_SUBDIR),
+                tx_event.clone(),
+            ),
             notifier: UserNotifier::default(),
             rollout: Mutex::new(None),
             codex_linux_sandbox_exe: None,
+            env_snapshot: EnvSnapshotCache::new(shell::Shell::Unknown),
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            output_artifacts_dir: codex_home.join(OUTPUT_ARTIFACTS_SUBDIR),
+            chunk_store: ChunkStore::new(codex_home.join(CHUNK_CACHE_SUBDIR)),
+            exec_policy: ExecPolicy::default(),
         };
         let session = Session {
             conversation_id,
@@ -3942,19 +4942,29 @@ mod tests {
             approval_policy: config.approval_policy,
             sandbox_policy: config.sandbox_policy.clone(),
             shell_environment_policy: config.shell_environment_policy.clone(),
+            sandbox_resources: config.sandbox_resources,
             tools_config,
             is_review_mode: false,
             final_output_json_schema: None,
+            context_policy: config.context_policy.clone(),
         });
         let services = SessionServices {
             mcp_connection_manager: McpConnectionManager::default(),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            background_jobs: BackgroundJobManager::new(
+                codex_home.path().join(JOB_LOGS_SUBDIR),
+                tx_event.clone(),
+            ),
             notifier: UserNotifier::default(),
             rollout: Mutex::new(None),
             codex_linux_sandbox_exe: None,
+            env_snapshot: EnvSnapshotCache::new(shell::Shell::Unknown),
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            output_artifacts_dir: codex_home.path().join(OUTPUT_ARTIFACTS_SUBDIR),
+            chunk_store: ChunkStore::new(codex_home.path().join(CHUNK_CACHE_SUBDIR)),
+            exec_policy: ExecPolicy::default(),
         };
         let session = Arc::new(Session {
             conversation_id,
@@ -3995,6 +5005,151 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn build_log_tail_adds_digest_and_spills_full_log() {
+        let (mut session, _turn_context) = make_session_and_context();
+        let log_dir = tempfile::tempdir().expect("log dir");
+        session.services.output_artifacts_dir = log_dir.path().to_path_buf();
+
+        let mut log = String::from("error[E0425]: cannot find value `x`\n --> src/lib.rs:7:9\n");
+        for n in 0..50 {
+            log.push_str(&format!("   Compiling dep{n}\n"));
+        }
+        log.push_str("error: could not compile `demo`\n");
+        let mut output = ExecToolCallOutput {
+            exit_code: 101,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(log.clone()),
+            duration: Duration::ZERO,
+            timed_out: false,
+        };
+        let caps = ExecOutputCaps {
+            build_log_tail_lines: 5,
+            ..ExecOutputCaps::default()
+        };
+
+        session
+            .apply_build_log_tail(
+                "call-1",
+                &["cargo".to_string(), "build".to_string()],
+                &mut output,
+                &caps,
+            )
+            .await;
+
+        let spilled = log_dir.path().join("call-1.log");
+        assert_eq!(std::fs::read_to_string(&spilled).expect("spilled log"), log);
+        let text = &output.aggregated_output.text;
+        assert!(
+            text.starts_with(
+                "[failure digest: first 1 error]\nsrc/lib.rs:7:9: error[E0425]: cannot find value `x`\n"
+            ),
+            "unexpected output: {text}"
+        );
+        assert!(text.contains(&output_spill_notice("output:call-1", 53)));
+        assert!(text.contains("error: could not compile `demo`\n"));
+        assert!(text.ends_with(&build_log_tail_notice(5)));
+        assert!(!text.contains("Compiling dep0\n"));
+    }
+
+    #[tokio::test]
+    async fn output_cap_spills_untrimmed_output() {
+        let (mut session, _turn_context) = make_session_and_context();
+        let artifacts_dir = tempfile::tempdir().expect("artifacts dir");
+        session.services.output_artifacts_dir = artifacts_dir.path().to_path_buf();
+
+        let full: String = (1..=100).map(|n| format!("line {n:04}\n")).collect();
+        let mut output = ExecToolCallOutput {
+            exit_code: 0,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(full.clone()),
+            duration: Duration::ZERO,
+            timed_out: false,
+        };
+        let caps = ExecOutputCaps {
+            exec_output_max_bytes: 100,
+            ..ExecOutputCaps::default()
+        };
+
+        session
+            .apply_output_cap(
+                "call-7",
+                &["python".to_string(), "gen.py".to_string()],
+                &mut output,
+                &caps,
+            )
+            .await;
+
+        let spilled = artifacts_dir.path().join("call-7.log");
+        assert_eq!(
+            std::fs::read_to_string(&spilled).expect("spilled output"),
+            full
+        );
+        let expected = format!(
+            "{}{}\n{}",
+            &full[..100],
+            exec_truncation_notice(100),
+            output_spill_notice("output:call-7", 100)
+        );
+        assert_eq!(output.aggregated_output.text, expected);
+        assert!(output.aggregated_output.truncated_by_bytes);
+    }
+
+    #[tokio::test]
+    async fn output_rule_replaces_build_log_tail() {
+        let (mut session, _turn_context) = make_session_and_context();
+        let artifacts_dir = tempfile::tempdir().expect("artifacts dir");
+        session.services.output_artifacts_dir = artifacts_dir.path().to_path_buf();
+
+        let mut full: String = (1..=200).map(|n| format!("test t{n} ... ok\n")).collect();
+        full.push_str("test parse ... FAILED\n");
+        let mut output = ExecToolCallOutput {
+            exit_code: 101,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(full.clone()),
+            duration: Duration::ZERO,
+            timed_out: false,
+        };
+        let rule = OutputRule {
+            program: Some("cargo".to_string()),
+            subcommand: Some("test".to_string()),
+            kind: None,
+            strategy: OutputStrategy::ErrorsOnly {
+                pattern: "FAILED".to_string(),
+            },
+            max_lines: 20,
+            max_bytes: 1024,
+        };
+        let command = vec!["cargo".to_string(), "test".to_string()];
+
+        session
+            .shape_exec_output(
+                "call-8",
+                &command,
+                Path::new("/"),
+                &mut output,
+                &ExecOutputCaps::default(),
+                output_rule_for(std::slice::from_ref(&rule), &command),
+            )
+            .await;
+
+        assert_eq!(
+            output.aggregated_output.text,
+            format!(
+                "test parse ... FAILED\n[output shaped by errors_only rule: kept 1 of 201 lines]\n{}",
+                output_spill_notice("output:call-8", 201)
+            )
+        );
+        assert_eq!(
+            std::fs::read_to_string(artifacts_dir.path().join("call-8.log"))
+                .expect("spilled output"),
+            full
+        );
+    }
+
     #[tokio::test]
     async fn abort_regular_task_emits_turn_aborted_only() {
         let (sess, tc, rx) = make_session_and_context_with_rx();
@@ -4201,6 +5356,7 @@ mod tests {
             env: HashMap::new(),
             with_escalated_permissions: Some(true),
             justification: Some("test".to_string()),
+            resource_limits: ResourceLimits::default(),
         };
 
         let params2 = ExecParams {
@@ -0,0 +1,332 @@
+//! Persistent, pattern-based approval rules configured per project:
+//!
+//! ```toml
+//! [projects."/home/me/code/app"]
+//! command_rules = [
+//!     { program = "cargo", args = ["test", "**"], decision = "allow" },
+//!     { program = "git", args = ["push", "**"], decision = "ask" },
+//!     { program = "pnpm", args = ["publish", "**"], decision = "deny" },
+//! ]
+//! ```
+//!
+//! `program` and each entry of `args` are globs matched against one argv
+//! element, except `**`, which matches any number of arguments. `cwd`
+//! restricts a rule to commands run in a directory matching it, or beneath
+//! one.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::config_types::CommandRule;
+use codex_protocol::config_types::CommandRuleDecision;
+use wildmatch::WildMatchPattern;
+
+use crate::bash::parse_bash_lc_plain_commands;
+
+/// Matches any number of arguments, including none.
+const ANY_ARGS: &str = "**";
+
+/// Longest run of leading subcommand words kept by [`rule_for_similar_commands`].
+const MAX_SUBCOMMAND_WORDS: usize = 2;
+
+/// The command rules of the project a session runs in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandRules {
+    /// The `[projects]` key the rules come from, or where new rules for the
+    /// session's cwd belong when there is no entry yet.
+    pub project_root: PathBuf,
+    pub rules: Vec<CommandRule>,
+}
+
+/// The decision for a command, with the rule that made it.
+#[derive(Debug, PartialEq)]
+pub(crate) struct CommandRuleMatch<'a> {
+    pub(crate) decision: CommandRuleDecision,
+    pub(crate) rule: &'a CommandRule,
+}
+
+impl CommandRules {
+    /// The strictest rule matching `command` when run in `cwd`. A `bash -lc`
+    /// script of plain commands is allowed only if every part is, and is
+    /// denied or asked about if any part is.
+    pub(crate) fn evaluate(&self, command: &[String], cwd: &Path) -> Option<CommandRuleMatch<'_>> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let commands =
+            parse_bash_lc_plain_commands(command).unwrap_or_else(|| vec![command.to_vec()]);
+        let matches: Vec<Option<CommandRuleMatch<'_>>> = commands
+            .iter()
+            .map(|command| self.evaluate_one(command, cwd))
+            .collect();
+
+        let strictest = matches
+            .iter()
+            .flatten()
+            .max_by_key(|matched| matched.decision)?;
+        if strictest.decision == CommandRuleDecision::Allow && matches.iter().any(Option::is_none) {
+            return None;
+        }
+        Some(CommandRuleMatch {
+            decision: strictest.decision,
+            rule: strictest.rule,
+        })
+    }
+
+    fn evaluate_one(&self, command: &[String], cwd: &Path) -> Option<CommandRuleMatch<'_>> {
+        self.rules
+            .iter()
+            .filter(|rule| self.rule_matches(rule, command, cwd))
+            .max_by_key(|rule| rule.decision)
+            .map(|rule| CommandRuleMatch {
+                decision: rule.decision,
+                rule,
+            })
+    }
+
+    fn rule_matches(&self, rule: &CommandRule, command: &[String], cwd: &Path) -> bool {
+        let Some((program, args)) = command.split_first() else {
+            return false;
+        };
+        if !glob_matches(&rule.program, program) || !args_match(&rule.args, args) {
+            return false;
+        }
+        let Some(cwd_pattern) = &rule.cwd else {
+            return true;
+        };
+        let cwd_pattern = self.project_root.join(cwd_pattern);
+        let cwd_pattern = cwd_pattern.to_string_lossy();
+        cwd.ancestors()
+            .any(|dir| glob_matches(&cwd_pattern, &dir.to_string_lossy()))
+    }
+}
+
+/// Renders `rule` as it would appear on a command line, e.g. `cargo test **`.
+pub fn describe_rule(rule: &CommandRule) -> String {
+    let mut description = std::iter::once(&rule.program)
+        .chain(&rule.args)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if let Some(cwd) = &rule.cwd {
+        description.push_str(&format!(" (in {cwd})"));
+    }
+    description
+}
+
+/// An `allow` rule for exactly `command`, so `cargo test -p foo` yields
+/// `cargo test -p foo`. Returns `None` for scripts of several commands and
+/// for arguments that contain glob characters.
+pub fn rule_for_command(command: &[String]) -> Option<CommandRule> {
+    let command = single_command(command)?;
+    let (program, args) = command.split_first()?;
+    Some(allow_rule(program, args.to_vec()))
+}
+
+/// A wider `allow` rule for commands like `command`: the same program and
+/// leading subcommand words with any further arguments, so `cargo test -p
+/// foo` yields `cargo test **`. Returns `None` where [`rule_for_command`]
+/// does, and for commands without subcommand words, such as `python -c ...`.
+pub fn rule_for_similar_commands(command: &[String]) -> Option<CommandRule> {
+    let command = single_command(command)?;
+    let (program, args) = command.split_first()?;
+    let subcommand: Vec<String> = args
+        .iter()
+        .take(MAX_SUBCOMMAND_WORDS)
+        .take_while(|arg| is_subcommand_word(arg))
+        .cloned()
+        .collect();
+    if subcommand.is_empty() {
+        return None;
+    }
+    let args = subcommand
+        .into_iter()
+        .chain(std::iter::once(ANY_ARGS.to_string()))
+        .collect();
+    Some(allow_rule(program, args))
+}
+
+/// The one plain command `command` runs, unwrapping a `bash -lc` script,
+/// unless one of its arguments would be read as a glob.
+fn single_command(command: &[String]) -> Option<Vec<String>> {
+    let command = match parse_bash_lc_plain_commands(command) {
+        Some(mut commands) if commands.len() == 1 => commands.pop()?,
+        Some(_) => return None,
+        None => command.to_vec(),
+    };
+    if command.iter().any(|arg| arg.contains(['*', '?'])) {
+        return None;
+    }
+    Some(command)
+}
+
+fn allow_rule(program: &str, args: Vec<String>) -> CommandRule {
+    CommandRule {
+        program: program.to_string(),
+        args,
+        cwd: None,
+        decision: CommandRuleDecision::Allow,
+    }
+}
+
+fn is_subcommand_word(arg: &str) -> bool {
+    arg.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'))
+}
+
+fn args_match(patterns: &[String], args: &[String]) -> bool {
+    match patterns.split_first() {
+        None => args.is_empty(),
+        Some((pattern, rest)) if pattern == ANY_ARGS => {
+            (0..=args.len()).any(|skip| args_match(rest, &args[skip..]))
+        }
+        Some((pattern, rest)) => args
+            .split_first()
+            .is_some_and(|(arg, args)| glob_matches(pattern, arg) && args_match(rest, args)),
+    }
+}
+
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    WildMatchPattern::<'*', '?'>::new(pattern).matches(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    fn rule(program: &str, args: &[&str], decision: CommandRuleDecision) -> CommandRule {
+        CommandRule {
+            program: program.to_string(),
+            args: argv(args),
+            cwd: None,
+            decision,
+        }
+    }
+
+    fn decision(rules: &CommandRules, command: &[&str], cwd: &str) -> Option<CommandRuleDecision> {
+        rules
+            .evaluate(&argv(command), Path::new(cwd))
+            .map(|matched| matched.decision)
+    }
+
+    #[test]
+    fn strictest_matching_rule_decides() {
+        let rules = CommandRules {
+            project_root: PathBuf::from("/repo"),
+            rules: vec![
+                rule("cargo", &["test", "**"], CommandRuleDecision::Allow),
+                rule(
+                    "cargo",
+                    &["test", "-p", "secret*", "**"],
+                    CommandRuleDecision::Ask,
+                ),
+                rule("pnpm", &["lint"], CommandRuleDecision::Allow),
+                rule("git", &["push", "**"], CommandRuleDecision::Deny),
+                CommandRule {
+                    cwd: Some("crates/*".to_string()),
+                    ..rule("make", &["**"], CommandRuleDecision::Allow)
+                },
+            ],
+        };
+
+        assert_eq!(
+            decision(&rules, &["cargo", "test", "-p", "foo"], "/repo"),
+            Some(CommandRuleDecision::Allow)
+        );
+        assert_eq!(
+            decision(
+                &rules,
+                &["cargo", "test", "-p", "secrets", "--", "x"],
+                "/repo"
+            ),
+            Some(CommandRuleDecision::Ask)
+        );
+        assert_eq!(decision(&rules, &["pnpm", "lint", "--fix"], "/repo"), None);
+        assert_eq!(
+            decision(&rules, &["make"], "/repo/crates/core/src"),
+            Some(CommandRuleDecision::Allow)
+        );
+        assert_eq!(decision(&rules, &["make"], "/repo"), None);
+
+        let script = |script: &str| ["bash", "-lc", script].map(str::to_string);
+        assert_eq!(
+            rules
+                .evaluate(&script("pnpm lint && cargo test"), Path::new("/repo"))
+                .map(|matched| matched.decision),
+            Some(CommandRuleDecision::Allow)
+        );
+        assert_eq!(
+            rules
+                .evaluate(&script("pnpm lint && cargo build"), Path::new("/repo"))
+                .map(|matched| matched.decision),
+            None
+        );
+        assert_eq!(
+            rules
+                .evaluate(
+                    &script("cargo build && git push origin"),
+                    Path::new("/repo")
+                )
+                .map(|matched| matched.decision),
+            Some(CommandRuleDecision::Deny)
+        );
+    }
+
+    #[test]
+    fn derives_rules_for_the_exact_command_and_similar_ones() {
+        assert_eq!(
+            rule_for_command(&argv(&["cargo", "test", "-p", "foo"])),
+            Some(rule(
+                "cargo",
+                &["test", "-p", "foo"],
+                CommandRuleDecision::Allow
+            ))
+        );
+        assert_eq!(
+            rule_for_similar_commands(&argv(&["cargo", "test", "-p", "foo"])),
+            Some(rule("cargo", &["test", "**"], CommandRuleDecision::Allow))
+        );
+        assert_eq!(
+            rule_for_command(&argv(&["bash", "-lc", "pnpm run lint:fix src"])),
+            Some(rule(
+                "pnpm",
+                &["run", "lint:fix", "src"],
+                CommandRuleDecision::Allow
+            ))
+        );
+        assert_eq!(
+            rule_for_similar_commands(&argv(&["bash", "-lc", "pnpm run lint:fix src"])),
+            Some(rule(
+                "pnpm",
+                &["run", "lint:fix", "**"],
+                CommandRuleDecision::Allow
+            ))
+        );
+        assert_eq!(
+            rule_for_command(&argv(&["python3", "-c", "print(1)"])),
+            Some(rule(
+                "python3",
+                &["-c", "print(1)"],
+                CommandRuleDecision::Allow
+            ))
+        );
+        assert_eq!(
+            rule_for_similar_commands(&argv(&["python3", "-c", "print(1)"])),
+            None
+        );
+        assert_eq!(rule_for_command(&argv(&["bash", "-lc", "ls && pwd"])), None);
+        assert_eq!(rule_for_command(&argv(&["rm", "*.log"])), None);
+        assert_eq!(rule_for_similar_commands(&argv(&["rm", "*.log"])), None);
+        assert_eq!(
+            describe_rule(&rule("cargo", &["test", "**"], CommandRuleDecision::Allow)),
+            "cargo test **"
+        );
+    }
+}
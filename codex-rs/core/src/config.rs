@@ -1,4 +1,7 @@
+use crate::command_rules::CommandRules;
 use crate::config_profile::ConfigProfile;
+use crate::config_types::ContextPolicy;
+use crate::config_types::ContextPolicyToml;
 use crate::config_types::DEFAULT_OTEL_ENVIRONMENT;
 use crate::config_types::History;
 use crate::config_types::McpServerConfig;
@@ -12,7 +15,10 @@ use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::config_types::Tui;
+use crate::config_types::TurnBudgetToml;
 use crate::config_types::UriBasedFileOpener;
+use crate::exec_policy::EXEC_POLICY_FILENAME;
+use crate::git_info::get_git_repo_root;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::derive_default_model_family;
@@ -22,7 +28,9 @@ use crate::model_provider_info::built_in_model_providers;
 use crate::openai_model_info::get_model_info;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use crate::spawn::ResourceLimits;
 use anyhow::Context;
+use codex_protocol::config_types::CommandRule;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::SandboxMode;
@@ -86,6 +94,21 @@ pub struct Config {
 
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
+    /// Resource limits applied to commands the model runs.
+    pub sandbox_resources: ResourceLimits,
+
+    /// `.policy` files consulted when deciding whether a command needs
+    /// approval, from least to most specific. Files that do not exist are
+    /// ignored.
+    pub exec_policy_files: Vec<PathBuf>,
+
+    /// Approval rules of the project the session runs in.
+    pub command_rules: CommandRules,
+
+    /// Whether rollout files record each exec call's begin and end events,
+    /// output included, so `codex debug replay` can re-run it.
+    pub record_exec_results: bool,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -141,6 +164,10 @@ pub struct Config {
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: usize,
 
+    /// Effective `[context_policy]` after layering project and profile
+    /// overrides.
+    pub context_policy: ContextPolicy,
+
     /// Directory containing all Codex state (defaults to `~/.codex` but can be
     /// overridden by the `CODEX_HOME` environment variable).
     pub codex_home: PathBuf,
@@ -646,6 +673,14 @@ pub struct ConfigToml {
     #[serde(default)]
     pub shell_environment_policy: ShellEnvironmentPolicyToml,
 
+    /// Resource limits (`[sandbox_resources]`) applied to commands the model
+    /// runs, with or without a sandbox.
+    pub sandbox_resources: Option<ResourceLimits>,
+
+    /// Record exec calls, output included, in rollout files so they can be
+    /// replayed with `codex debug replay`. Defaults to `false`.
+    pub record_exec_results: Option<bool>,
+
     /// Sandbox mode to use.
     pub sandbox_mode: Option<SandboxMode>,
 
@@ -670,6 +705,14 @@ pub struct ConfigToml {
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: Option<usize>,
 
+    /// Per-turn tool output budget (in bytes) applied after `/relax`. Kept
+    /// for existing configs; `context_policy.per_turn.relax_max_bytes` takes
+    /// precedence.
+    pub relax_turn_output_max_bytes: Option<usize>,
+
+    /// Budgets for tool output sent to the model.
+    pub context_policy: Option<ContextPolicyToml>,
+
     /// Profile to use from the `profiles` map.
     pub profile: Option<String>,
 
@@ -757,6 +800,27 @@ impl From<ConfigToml> for UserSavedConfig {
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ProjectConfig {
     pub trust_level: Option<String>,
+    /// Overrides for the top-level `[context_policy]` in this project.
+    pub context_policy: Option<ContextPolicyToml>,
+    /// Persistent allow/deny/ask rules for commands run in this project.
+    #[serde(default)]
+    pub command_rules: Vec<CommandRule>,
+}
+
+/// The `[projects]` entry for `resolved_cwd` and its key, falling back to
+/// the root git project so worktrees share the settings of their main
+/// checkout.
+fn project_for_cwd<'a>(
+    projects: &'a HashMap<String, ProjectConfig>,
+    resolved_cwd: &Path,
+) -> Option<(PathBuf, &'a ProjectConfig)> {
+    let lookup = |path: &Path| {
+        projects
+            .get(path.to_string_lossy().as_ref())
+            .map(|project| (path.to_path_buf(), project))
+    };
+    lookup(resolved_cwd)
+        .or_else(|| resolve_root_git_project_for_trust(resolved_cwd).and_then(|root| lookup(&root)))
 }
 
 #[derive(Deserialize, Debug, Clone, Default, PartialEq)]
@@ -789,12 +853,18 @@ impl ConfigToml {
             SandboxMode::WorkspaceWrite => match self.sandbox_workspace_write.as_ref() {
                 Some(SandboxWorkspaceWrite {
                     writable_roots,
+                    read_only_subpaths,
+                    deny_read_paths,
                     network_access,
+                    network_allowlist,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
                 }) => SandboxPolicy::WorkspaceWrite {
                     writable_roots: writable_roots.clone(),
+                    read_only_subpaths: read_only_subpaths.clone(),
+                    deny_read_paths: deny_read_paths.clone(),
                     network_access: *network_access,
+                    network_allowlist: network_allowlist.clone(),
                     exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
                     exclude_slash_tmp: *exclude_slash_tmp,
                 },
@@ -960,6 +1030,64 @@ impl Config {
             }
         };
 
+        // Context budgets layer as: built-in defaults, the top-level
+        // `relax_turn_output_max_bytes`, top-level `[context_policy]`, the
+        // entry for the current project, then the active profile.
+        let context_policy = {
+            let relax_key = ContextPolicyToml {
+                per_turn: cfg
+                    .relax_turn_output_max_bytes
+                    .map(|relax_max_bytes| TurnBudgetToml {
+                        max_bytes: None,
+                        relax_max_bytes: Some(relax_max_bytes),
+                    }),
+                ..Default::default()
+            };
+            let project_policy = cfg
+                .projects
+                .as_ref()
+                .and_then(|projects| project_for_cwd(projects, &resolved_cwd))
+                .and_then(|(_, project)| project.context_policy.clone());
+            let policy = [
+                cfg.context_policy.clone(),
+                project_policy,
+                config_profile.context_policy,
+            ]
+            .into_iter()
+            .flatten()
+            .fold(relax_key, ContextPolicyToml::merge);
+            ContextPolicy::from(policy)
+        };
+
+        // The project's policy can auto-approve commands, so it is only
+        // honoured once the project is trusted.
+        let mut exec_policy_files = vec![codex_home.join(EXEC_POLICY_FILENAME)];
+        let project = cfg
+            .projects
+            .as_ref()
+            .and_then(|projects| project_for_cwd(projects, &resolved_cwd));
+        let cwd_trusted = project
+            .as_ref()
+            .is_some_and(|(_, project)| project.trust_level.as_deref() == Some("trusted"));
+        if cwd_trusted {
+            let project_root =
+                get_git_repo_root(&resolved_cwd).unwrap_or_else(|| resolved_cwd.clone());
+            exec_policy_files.push(project_root.join(".codex").join(EXEC_POLICY_FILENAME));
+        }
+
+        // New rules go where the trust onboarding would put the project.
+        let command_rules = match project {
+            Some((project_root, project)) => CommandRules {
+                project_root,
+                rules: project.command_rules.clone(),
+            },
+            None => CommandRules {
+                project_root: resolve_root_git_project_for_trust(&resolved_cwd)
+                    .unwrap_or_else(|| resolved_cwd.clone()),
+                rules: Vec::new(),
+            },
+        };
+
         let history = cfg.history.unwrap_or_default();
 
         let tools_web_search_request = override_tools_web_search_request
@@ -1032,12 +1160,17 @@ impl Config {
                 .unwrap_or_else(AskForApproval::default),
             sandbox_policy,
             shell_environment_policy,
+            sandbox_resources: cfg.sandbox_resources.unwrap_or_default(),
+            exec_policy_files,
+            command_rules,
+            record_exec_results: cfg.record_exec_results.unwrap_or(false),
             notify: cfg.notify,
             user_instructions,
             base_instructions,
             mcp_servers: cfg.mcp_servers,
             model_providers,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
+            context_policy,
             codex_home,
             history,
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
@@ -1200,10 +1333,15 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
 
 #[cfg(test)]
 mod tests {
+    use crate::config_types::DEFAULT_ERROR_PATTERN;
     use crate::config_types::HistoryPersistence;
     use crate::config_types::Notifications;
+    use crate::config_types::OutputRule;
+    use crate::config_types::OutputStrategy;
+    use crate::config_types::ReadCaps;
 
     use super::*;
+    use codex_protocol::config_types::CommandRuleDecision;
     use pretty_assertions::assert_eq;
 
     use std::time::Duration;
@@ -1302,7 +1440,10 @@ exclude_slash_tmp = true
         assert_eq!(
             SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![PathBuf::from("/my/workspace")],
+                read_only_subpaths: vec![],
+                deny_read_paths: vec![],
                 network_access: false,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             },
@@ -1310,6 +1451,31 @@ exclude_slash_tmp = true
         );
     }
 
+    #[test]
+    fn network_allowlist_entries_are_validated() {
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+sandbox_mode = "workspace-write"
+
+[sandbox_workspace_write]
+network_allowlist = ["pypi.internal:443", "localhost:5432", "[::1]:6379"]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        assert_eq!(
+            cfg.derive_sandbox_policy(None).network_allowlist(),
+            ["pypi.internal:443", "localhost:5432", "[::1]:6379"]
+        );
+
+        for entry in ["pypi.internal", "pypi.internal:0", "::1:80", ":443"] {
+            let toml = format!("[sandbox_workspace_write]\nnetwork_allowlist = [\"{entry}\"]\n");
+            assert!(
+                toml::from_str::<ConfigToml>(&toml).is_err(),
+                "{entry} should be rejected"
+            );
+        }
+    }
+
     #[test]
     fn load_global_mcp_servers_returns_empty_if_missing() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1805,12 +1971,20 @@ model_verbosity = "high"
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
+                sandbox_resources: ResourceLimits::default(),
+                exec_policy_files: vec![fixture.codex_home().join(EXEC_POLICY_FILENAME)],
+                command_rules: CommandRules {
+                    project_root: fixture.cwd(),
+                    rules: Vec::new(),
+                },
+                record_exec_results: false,
                 user_instructions: None,
                 notify: None,
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
                 model_providers: fixture.model_provider_map.clone(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+                context_policy: ContextPolicy::default(),
                 codex_home: fixture.codex_home(),
                 history: History::default(),
                 file_opener: UriBasedFileOpener::VsCode,
@@ -1865,12 +2039,20 @@ model_verbosity = "high"
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            sandbox_resources: ResourceLimits::default(),
+            exec_policy_files: vec![fixture.codex_home().join(EXEC_POLICY_FILENAME)],
+            command_rules: CommandRules {
+                project_root: fixture.cwd(),
+                rules: Vec::new(),
+            },
+            record_exec_results: false,
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            context_policy: ContextPolicy::default(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
@@ -1940,12 +2122,20 @@ model_verbosity = "high"
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            sandbox_resources: ResourceLimits::default(),
+            exec_policy_files: vec![fixture.codex_home().join(EXEC_POLICY_FILENAME)],
+            command_rules: CommandRules {
+                project_root: fixture.cwd(),
+                rules: Vec::new(),
+            },
+            record_exec_results: false,
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            context_policy: ContextPolicy::default(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
@@ -2001,12 +2191,20 @@ model_verbosity = "high"
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            sandbox_resources: ResourceLimits::default(),
+            exec_policy_files: vec![fixture.codex_home().join(EXEC_POLICY_FILENAME)],
+            command_rules: CommandRules {
+                project_root: fixture.cwd(),
+                rules: Vec::new(),
+            },
+            record_exec_results: false,
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
+            context_policy: ContextPolicy::default(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
@@ -2131,6 +2329,199 @@ trust_level = "trusted"
 
         Ok(())
     }
+
+    #[test]
+    fn context_policy_layers_project_and_profile() -> std::io::Result<()> {
+        let cwd = TempDir::new()?;
+        let codex_home = TempDir::new()?;
+        let toml = format!(
+            r#"
+profile = "mono"
+
+[context_policy]
+exec_output_max_bytes = 4096
+per_call = {{ max_lines = 200 }}
+per_extension_caps = {{ md = {{ max_lines = 80 }} }}
+
+[projects.'{cwd}'.context_policy]
+exec_output_max_bytes = 12288
+per_turn = {{ max_bytes = 65536 }}
+
+[profiles.mono.context_policy]
+per_call = {{ max_bytes = 12288 }}
+per_extension_caps = {{ ".RS" = {{ max_lines = 300 }} }}
+"#,
+            cwd = cwd.path().display()
+        );
+        let cfg: ConfigToml = toml::from_str(&toml).expect("TOML deserialization should succeed");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(cwd.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+        let policy = &config.context_policy;
+
+        assert_eq!(policy.exec_output_max_bytes, 12288);
+        assert_eq!(policy.per_turn_max_bytes, 65536);
+        // Relaxed budgets never shrink below the regular one.
+        assert_eq!(policy.relax_per_turn_max_bytes, 65536);
+
+        let cfg: ConfigToml = toml::from_str("relax_turn_output_max_bytes = 40960")
+            .expect("TOML deserialization should succeed");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(cwd.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(config.context_policy.relax_per_turn_max_bytes, 40960);
+        assert_eq!(
+            policy.per_call,
+            ReadCaps {
+                max_lines: 200,
+                max_bytes: 12288,
+            }
+        );
+        assert_eq!(
+            policy.read_caps_for(Path::new("src/lib.rs")),
+            ReadCaps {
+                max_lines: 300,
+                max_bytes: 12288,
+            }
+        );
+        assert_eq!(policy.read_caps_for(Path::new("README.md")).max_lines, 80);
+        assert_eq!(policy.rg_max_bytes, ContextPolicy::default().rg_max_bytes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn command_rules_come_from_the_project_entry() -> std::io::Result<()> {
+        let cwd = TempDir::new()?;
+        let codex_home = TempDir::new()?;
+        let toml = format!(
+            r#"
+[projects.'{cwd}']
+command_rules = [
+    {{ program = "cargo", args = ["test", "**"], decision = "allow" }},
+    {{ program = "git", args = ["push", "**"], cwd = "web", decision = "deny" }},
+]
+"#,
+            cwd = cwd.path().display()
+        );
+        let cfg: ConfigToml = toml::from_str(&toml).expect("TOML deserialization should succeed");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(cwd.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config.command_rules,
+            CommandRules {
+                project_root: cwd.path().to_path_buf(),
+                rules: vec![
+                    CommandRule {
+                        program: "cargo".to_string(),
+                        args: vec!["test".to_string(), "**".to_string()],
+                        cwd: None,
+                        decision: CommandRuleDecision::Allow,
+                    },
+                    CommandRule {
+                        program: "git".to_string(),
+                        args: vec!["push".to_string(), "**".to_string()],
+                        cwd: Some("web".to_string()),
+                        decision: CommandRuleDecision::Deny,
+                    },
+                ],
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn context_policy_output_rules_layer_before_defaults() -> std::io::Result<()> {
+        let cwd = TempDir::new()?;
+        let codex_home = TempDir::new()?;
+        let toml = format!(
+            r#"
+[context_policy]
+exec_output_max_bytes = 4096
+
+[[context_policy.output_rules]]
+program = "pytest"
+strategy = "errors_only"
+
+[[projects.'{cwd}'.context_policy.output_rules]]
+program = "cargo"
+subcommand = "test"
+strategy = "head_tail"
+max_lines = 60
+"#,
+            cwd = cwd.path().display()
+        );
+        let cfg: ConfigToml = toml::from_str(&toml).expect("TOML deserialization should succeed");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(cwd.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+        let rules = &config.context_policy.output_rules;
+
+        assert_eq!(
+            rules[..2],
+            [
+                OutputRule {
+                    program: Some("cargo".to_string()),
+                    subcommand: Some("test".to_string()),
+                    kind: None,
+                    strategy: OutputStrategy::HeadTail,
+                    max_lines: 60,
+                    max_bytes: 4096,
+                },
+                OutputRule {
+                    program: Some("pytest".to_string()),
+                    subcommand: None,
+                    kind: None,
+                    strategy: OutputStrategy::ErrorsOnly {
+                        pattern: DEFAULT_ERROR_PATTERN.to_string(),
+                    },
+                    max_lines: ContextPolicy::default().build_log_tail_lines,
+                    max_bytes: 4096,
+                },
+            ]
+        );
+        // The built-in rules follow the configured ones.
+        assert_eq!(rules[2].program.as_deref(), Some("go"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn output_rule_with_invalid_pattern_is_rejected() {
+        let toml = r#"
+[[context_policy.output_rules]]
+program = "make"
+strategy = "errors_only"
+pattern = "(unclosed"
+"#;
+        assert!(toml::from_str::<ConfigToml>(toml).is_err());
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,6 @@
 use crate::config::CONFIG_TOML_FILE;
 use anyhow::Result;
+use codex_protocol::config_types::CommandRule;
 use std::path::Path;
 use tempfile::NamedTempFile;
 use toml_edit::DocumentMut;
@@ -186,6 +187,94 @@ async fn persist_overrides_with_behavior(
     Ok(())
 }
 
+/// Append `rule` to the `command_rules` of the `[projects]` entry for
+/// `project`, creating the entry if needed. A rule that is already present is
+/// not added twice.
+pub async fn persist_command_rule(
+    codex_home: &Path,
+    project: &Path,
+    rule: &CommandRule,
+) -> Result<()> {
+    use toml_edit::Item;
+
+    let config_path = codex_home.join(CONFIG_TOML_FILE);
+    let mut doc = match tokio::fs::read_to_string(&config_path).await {
+        Ok(contents) => contents.parse::<DocumentMut>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            tokio::fs::create_dir_all(codex_home).await?;
+            DocumentMut::new()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let projects = table_entry(doc.as_table_mut(), "projects", true)?;
+    let project_table = table_entry(projects, &project.to_string_lossy(), false)?;
+    let new_rule = command_rule_table(rule);
+    match project_table
+        .entry("command_rules")
+        .or_insert_with(|| toml_edit::value(toml_edit::Array::new()))
+    {
+        Item::Value(toml_edit::Value::Array(rules)) => {
+            let exists = rules.iter().any(|existing| {
+                existing.as_inline_table().is_some_and(|existing| {
+                    toml::from_str::<CommandRule>(&existing.clone().into_table().to_string())
+                        .is_ok_and(|existing| &existing == rule)
+                })
+            });
+            if exists {
+                return Ok(());
+            }
+            rules.push(new_rule);
+        }
+        Item::ArrayOfTables(rules) => rules.push(new_rule.into_table()),
+        _ => anyhow::bail!(
+            "`command_rules` of project {} is not an array",
+            project.display()
+        ),
+    }
+
+    let tmp_file = NamedTempFile::new_in(codex_home)?;
+    tokio::fs::write(tmp_file.path(), doc.to_string()).await?;
+    tmp_file.persist(config_path)?;
+
+    Ok(())
+}
+
+/// The table at `key` in `table`, created when missing and converted from an
+/// inline table when needed so its existing entries are kept.
+fn table_entry<'a>(
+    table: &'a mut toml_edit::Table,
+    key: &str,
+    implicit: bool,
+) -> Result<&'a mut toml_edit::Table> {
+    use toml_edit::Item;
+
+    let item = table.entry(key).or_insert_with(|| {
+        let mut new_table = toml_edit::Table::new();
+        new_table.set_implicit(implicit);
+        Item::Table(new_table)
+    });
+    if let Some(inline) = item.as_inline_table() {
+        *item = Item::Table(inline.clone().into_table());
+    }
+    item.as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("`{key}` in config.toml is not a table"))
+}
+
+fn command_rule_table(rule: &CommandRule) -> toml_edit::InlineTable {
+    let mut table = toml_edit::InlineTable::new();
+    table.insert("program", rule.program.as_str().into());
+    table.insert(
+        "args",
+        toml_edit::Value::Array(rule.args.iter().map(String::as_str).collect()),
+    );
+    if let Some(cwd) = &rule.cwd {
+        table.insert("cwd", cwd.as_str().into());
+    }
+    table.insert("decision", rule.decision.to_string().into());
+    table
+}
+
 fn remove_toml_edit_segments(doc: &mut DocumentMut, segments: &[&str]) -> bool {
     use toml_edit::Item;
 
@@ -215,6 +304,7 @@ fn remove_toml_edit_segments(doc: &mut DocumentMut, segments: &[&str]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_protocol::config_types::CommandRuleDecision;
     use pretty_assertions::assert_eq;
     use tempfile::tempdir;
 
@@ -745,4 +835,52 @@ model_reasoning_effort = "high"
         let p = codex_home.join(CONFIG_TOML_FILE);
         tokio::fs::read_to_string(p).await.unwrap_or_default()
     }
+
+    /// Verifies command rules are appended to the project's entry once.
+    #[tokio::test]
+    async fn persist_command_rule_appends_to_project_entry() {
+        let tmpdir = tempdir().expect("tmp");
+        let codex_home = tmpdir.path();
+        let seed = r#"model = "o3"
+
+[projects."/repo"]
+trust_level = "trusted"
+"#;
+        tokio::fs::write(codex_home.join(CONFIG_TOML_FILE), seed)
+            .await
+            .expect("seed write");
+
+        let cargo_test = CommandRule {
+            program: "cargo".to_string(),
+            args: vec!["test".to_string(), "**".to_string()],
+            cwd: None,
+            decision: CommandRuleDecision::Allow,
+        };
+        let git_push = CommandRule {
+            program: "git".to_string(),
+            args: vec!["push".to_string(), "**".to_string()],
+            cwd: Some("web".to_string()),
+            decision: CommandRuleDecision::Deny,
+        };
+        for rule in [&cargo_test, &git_push, &cargo_test] {
+            persist_command_rule(codex_home, Path::new("/repo"), rule)
+                .await
+                .expect("persist");
+        }
+        persist_command_rule(codex_home, Path::new("/other"), &cargo_test)
+            .await
+            .expect("persist");
+
+        let contents = read_config(codex_home).await;
+        let expected = r#"model = "o3"
+
+[projects."/repo"]
+trust_level = "trusted"
+command_rules = [{ program = "cargo", args = ["test", "**"], decision = "allow" }, { program = "git", args = ["push", "**"], cwd = "web", decision = "deny" }]
+
+[projects."/other"]
+command_rules = [{ program = "cargo", args = ["test", "**"], decision = "allow" }]
+"#;
+        assert_eq!(contents, expected);
+    }
 }
@@ -5,7 +5,9 @@
 
 use serde::Deserializer;
 use std::collections::HashMap;
+use std::path::Path;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 use wildmatch::WildMatchPattern;
 
@@ -305,8 +307,17 @@ pub struct Tui {
 pub struct SandboxWorkspaceWrite {
     #[serde(default)]
     pub writable_roots: Vec<PathBuf>,
+    /// Paths under the writable roots that stay read-only.
+    #[serde(default)]
+    pub read_only_subpaths: Vec<String>,
+    /// Paths whose contents commands may neither read nor modify.
+    #[serde(default)]
+    pub deny_read_paths: Vec<String>,
     #[serde(default)]
     pub network_access: bool,
+    /// `host:port` destinations reachable while `network_access` is off.
+    #[serde(default, deserialize_with = "deserialize_network_allowlist")]
+    pub network_allowlist: Vec<String>,
     #[serde(default)]
     pub exclude_tmpdir_env_var: bool,
     #[serde(default)]
@@ -317,13 +328,81 @@ impl From<SandboxWorkspaceWrite> for codex_protocol::mcp_protocol::SandboxSettin
     fn from(sandbox_workspace_write: SandboxWorkspaceWrite) -> Self {
         Self {
             writable_roots: sandbox_workspace_write.writable_roots,
+            read_only_subpaths: sandbox_workspace_write.read_only_subpaths,
+            deny_read_paths: sandbox_workspace_write.deny_read_paths,
             network_access: Some(sandbox_workspace_write.network_access),
+            network_allowlist: sandbox_workspace_write.network_allowlist,
             exclude_tmpdir_env_var: Some(sandbox_workspace_write.exclude_tmpdir_env_var),
             exclude_slash_tmp: Some(sandbox_workspace_write.exclude_slash_tmp),
         }
     }
 }
 
+/// One `host:port` entry of `sandbox_workspace_write.network_allowlist`.
+/// IPv6 hosts are written in brackets, as in `[::1]:5432`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkDestination {
+    pub host: String,
+    pub port: u16,
+}
+
+impl NetworkDestination {
+    /// Whether the destination names this machine's loopback interface.
+    pub fn is_loopback(&self) -> bool {
+        self.host.eq_ignore_ascii_case("localhost")
+            || self
+                .host
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    }
+}
+
+impl FromStr for NetworkDestination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, port) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("expected `host:port`, got `{s}`"))?;
+        let host = match host.strip_prefix('[') {
+            Some(rest) => rest
+                .strip_suffix(']')
+                .ok_or_else(|| format!("unterminated `[` in `{s}`"))?,
+            None if host.contains(':') => {
+                return Err(format!(
+                    "IPv6 hosts must be bracketed, as in `[::1]:80`: `{s}`"
+                ));
+            }
+            None => host,
+        };
+        if host.is_empty() || host.contains(char::is_whitespace) {
+            return Err(format!("invalid host in `{s}`"));
+        }
+        let port = port
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port != 0)
+            .ok_or_else(|| format!("invalid port in `{s}`"))?;
+        Ok(Self {
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+fn deserialize_network_allowlist<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let allowlist = Vec::<String>::deserialize(deserializer)?;
+    for entry in &allowlist {
+        entry
+            .parse::<NetworkDestination>()
+            .map_err(SerdeError::custom)?;
+    }
+    Ok(allowlist)
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum ShellEnvironmentPolicyInherit {
@@ -356,6 +435,8 @@ pub struct ShellEnvironmentPolicyToml {
     pub include_only: Option<Vec<String>>,
 
     pub experimental_use_profile: Option<bool>,
+
+    pub experimental_profile_snapshot: Option<bool>,
 }
 
 pub type EnvironmentVariablePattern = WildMatchPattern<'*', '?'>;
@@ -387,6 +468,11 @@ pub struct ShellEnvironmentPolicy {
 
     /// If true, the shell profile will be used to run the command.
     pub use_profile: bool,
+
+    /// If true, the environment of a login shell with the user's rc file
+    /// sourced is captured once and used as the starting point for every
+    /// command.
+    pub profile_snapshot: bool,
 }
 
 impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
@@ -408,6 +494,7 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
             .map(|s| EnvironmentVariablePattern::new_case_insensitive(&s))
             .collect();
         let use_profile = toml.experimental_use_profile.unwrap_or(false);
+        let profile_snapshot = toml.experimental_profile_snapshot.unwrap_or(false);
 
         Self {
             inherit,
@@ -416,6 +503,382 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
             r#set,
             include_only,
             use_profile,
+            profile_snapshot,
+        }
+    }
+}
+
+/// Line and byte caps for a single `read_code` call.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadCapsToml {
+    pub max_lines: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+impl ReadCapsToml {
+    fn merge(self, overlay: ReadCapsToml) -> Self {
+        Self {
+            max_lines: overlay.max_lines.or(self.max_lines),
+            max_bytes: overlay.max_bytes.or(self.max_bytes),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TurnBudgetToml {
+    /// Tool output budget for a turn.
+    pub max_bytes: Option<usize>,
+    /// Tool output budget once `/relax` is granted.
+    pub relax_max_bytes: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RepeatCommandBreakerToml {
+    /// Identical runs with unchanged output allowed before the command is
+    /// blocked. Values below 2 disable the breaker.
+    pub repeats: Option<usize>,
+    pub window_seconds: Option<u64>,
+}
+
+/// Classification of a command as reported by `parse_command`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandKind {
+    Read,
+    ListFiles,
+    Search,
+    Unknown,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStrategyKind {
+    Head,
+    Tail,
+    HeadTail,
+    ErrorsOnly,
+    Dedupe,
+}
+
+/// One `[[context_policy.output_rules]]` entry. Every matcher that is set
+/// must match; a rule without matchers applies to every command.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OutputRuleToml {
+    /// Program name, compared with the file name of the first word.
+    pub program: Option<String>,
+    /// First argument after the program that is not a flag, such as `test`
+    /// in `cargo test --all`.
+    pub subcommand: Option<String>,
+    pub kind: Option<CommandKind>,
+    pub strategy: OutputStrategyKind,
+    pub max_lines: Option<usize>,
+    pub max_bytes: Option<usize>,
+    /// Lines kept by `errors_only`.
+    #[serde(default, deserialize_with = "deserialize_output_pattern")]
+    pub pattern: Option<String>,
+}
+
+fn deserialize_output_pattern<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = Option::<String>::deserialize(deserializer)?;
+    if let Some(pattern) = &pattern {
+        regex_lite::Regex::new(pattern).map_err(SerdeError::custom)?;
+    }
+    Ok(pattern)
+}
+
+/// Budgets for how much tool output reaches the model, as written in
+/// `[context_policy]`. The same table can appear under
+/// `[projects."<path>".context_policy]` and `[profiles.<name>.context_policy]`;
+/// fields set there override the top-level values.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ContextPolicyToml {
+    pub per_call: Option<ReadCapsToml>,
+    pub per_turn: Option<TurnBudgetToml>,
+    pub rg_max_bytes: Option<usize>,
+    pub exec_output_max_bytes: Option<usize>,
+    pub build_log_tail_lines: Option<usize>,
+    /// Distinct errors listed in the failure digest above a trimmed build log.
+    pub build_log_digest_errors: Option<usize>,
+    /// Skip lines already served earlier in the turn or session.
+    pub overlap_suppression: Option<bool>,
+    pub repeat_command_breaker: Option<RepeatCommandBreakerToml>,
+    pub allow_large_slice_if_small_file: Option<bool>,
+    pub large_slice_lines: Option<usize>,
+    pub large_slice_bytes: Option<usize>,
+    /// Per-call caps keyed by file extension (`"rs"`, `"md"`, ...). Replaces
+    /// `per_call` for matching files.
+    pub per_extension_caps: Option<HashMap<String, ReadCapsToml>>,
+    /// Output shaping for matching commands, checked in order. Rules from an
+    /// overriding layer are checked before the ones they override.
+    pub output_rules: Option<Vec<OutputRuleToml>>,
+}
+
+impl ContextPolicyToml {
+    /// Layer `overlay` on top of `self`, field by field.
+    pub fn merge(self, overlay: ContextPolicyToml) -> Self {
+        let per_extension_caps = merge_nested(
+            self.per_extension_caps,
+            overlay.per_extension_caps,
+            |mut base, overlay| {
+                for (ext, caps) in overlay {
+                    let merged = base.get(&ext).copied().unwrap_or_default().merge(caps);
+                    base.insert(ext, merged);
+                }
+                base
+            },
+        );
+        Self {
+            per_call: merge_nested(self.per_call, overlay.per_call, ReadCapsToml::merge),
+            per_turn: merge_nested(self.per_turn, overlay.per_turn, |base, overlay| {
+                TurnBudgetToml {
+                    max_bytes: overlay.max_bytes.or(base.max_bytes),
+                    relax_max_bytes: overlay.relax_max_bytes.or(base.relax_max_bytes),
+                }
+            }),
+            rg_max_bytes: overlay.rg_max_bytes.or(self.rg_max_bytes),
+            exec_output_max_bytes: overlay.exec_output_max_bytes.or(self.exec_output_max_bytes),
+            build_log_tail_lines: overlay.build_log_tail_lines.or(self.build_log_tail_lines),
+            build_log_digest_errors: overlay
+                .build_log_digest_errors
+                .or(self.build_log_digest_errors),
+            overlap_suppression: overlay.overlap_suppression.or(self.overlap_suppression),
+            repeat_command_breaker: merge_nested(
+                self.repeat_command_breaker,
+                overlay.repeat_command_breaker,
+                |base, overlay| RepeatCommandBreakerToml {
+                    repeats: overlay.repeats.or(base.repeats),
+                    window_seconds: overlay.window_seconds.or(base.window_seconds),
+                },
+            ),
+            allow_large_slice_if_small_file: overlay
+                .allow_large_slice_if_small_file
+                .or(self.allow_large_slice_if_small_file),
+            large_slice_lines: overlay.large_slice_lines.or(self.large_slice_lines),
+            large_slice_bytes: overlay.large_slice_bytes.or(self.large_slice_bytes),
+            per_extension_caps,
+            output_rules: merge_nested(self.output_rules, overlay.output_rules, |base, overlay| {
+                overlay.into_iter().chain(base).collect()
+            }),
+        }
+    }
+}
+
+fn merge_nested<T>(
+    base: Option<T>,
+    overlay: Option<T>,
+    merge: impl FnOnce(T, T) -> T,
+) -> Option<T> {
+    match (base, overlay) {
+        (Some(base), Some(overlay)) => Some(merge(base, overlay)),
+        (base, overlay) => overlay.or(base),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadCaps {
+    pub max_lines: usize,
+    pub max_bytes: usize,
+}
+
+/// How output from commands matched by an [`OutputRule`] is cut down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputStrategy {
+    /// Keep the first lines.
+    Head,
+    /// Keep the last lines.
+    Tail,
+    /// Keep the first and last lines and drop the middle.
+    HeadTail,
+    /// Keep only lines matching `pattern`, or the tail when none do.
+    ErrorsOnly { pattern: String },
+    /// Collapse runs of identical lines, then keep the first lines.
+    Dedupe,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputRule {
+    pub program: Option<String>,
+    pub subcommand: Option<String>,
+    pub kind: Option<CommandKind>,
+    pub strategy: OutputStrategy,
+    pub max_lines: usize,
+    pub max_bytes: usize,
+}
+
+/// Used by `errors_only` rules that do not set a pattern.
+pub const DEFAULT_ERROR_PATTERN: &str =
+    r"(?i)\b(error|errors|fail|failed|failure|panic|panicked|fatal|exception|traceback)\b";
+
+/// Effective context budgets after defaults are applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextPolicy {
+    /// Default caps for a single `read_code` call.
+    pub per_call: ReadCaps,
+    pub per_turn_max_bytes: usize,
+    pub relax_per_turn_max_bytes: usize,
+    pub rg_max_bytes: usize,
+    pub exec_output_max_bytes: usize,
+    pub build_log_tail_lines: usize,
+    pub build_log_digest_errors: usize,
+    pub overlap_suppression: bool,
+    pub repeat_command_repeats: usize,
+    pub repeat_command_window: Duration,
+    /// Whether files no larger than `large_slice` may be read in one call.
+    pub allow_large_slice_if_small_file: bool,
+    /// Caps used for small files and for relaxed turns.
+    pub large_slice: ReadCaps,
+    /// Keys are lowercase extensions without the leading dot.
+    pub per_extension_caps: HashMap<String, ReadCaps>,
+    /// Configured rules first, then the built-in ones. These take precedence
+    /// over the rg and build log handling.
+    pub output_rules: Vec<OutputRule>,
+}
+
+impl Default for ContextPolicy {
+    fn default() -> Self {
+        Self {
+            per_call: ReadCaps {
+                max_lines: 160,
+                max_bytes: 8 * 1024,
+            },
+            per_turn_max_bytes: 24 * 1024,
+            relax_per_turn_max_bytes: 32 * 1024,
+            rg_max_bytes: 8 * 1024,
+            exec_output_max_bytes: 6 * 1024,
+            build_log_tail_lines: 120,
+            build_log_digest_errors: 8,
+            overlap_suppression: true,
+            repeat_command_repeats: 3,
+            repeat_command_window: Duration::from_secs(120),
+            allow_large_slice_if_small_file: true,
+            large_slice: ReadCaps {
+                max_lines: 400,
+                max_bytes: 16 * 1024,
+            },
+            per_extension_caps: HashMap::new(),
+            output_rules: default_output_rules(6 * 1024, 120),
+        }
+    }
+}
+
+/// Shaping for test runners the build log handling does not cover.
+fn default_output_rules(max_bytes: usize, max_lines: usize) -> Vec<OutputRule> {
+    vec![OutputRule {
+        program: Some("go".to_string()),
+        subcommand: Some("test".to_string()),
+        kind: None,
+        strategy: OutputStrategy::ErrorsOnly {
+            pattern: r"^(\s*--- FAIL|FAIL|ok\s|panic:|\s+\S+_test\.go:\d+:)".to_string(),
+        },
+        max_lines,
+        max_bytes,
+    }]
+}
+
+impl ContextPolicy {
+    /// Per-call caps for `path`, honoring `per_extension_caps`.
+    pub fn read_caps_for(&self, path: &Path) -> ReadCaps {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.per_extension_caps.get(&ext.to_ascii_lowercase()))
+            .copied()
+            .unwrap_or(self.per_call)
+    }
+}
+
+impl From<ContextPolicyToml> for ContextPolicy {
+    fn from(toml: ContextPolicyToml) -> Self {
+        let defaults = ContextPolicy::default();
+        // Zero caps would make every read fail; treat them as "one unit".
+        let resolve_caps = |caps: ReadCapsToml, fallback: ReadCaps| ReadCaps {
+            max_lines: caps.max_lines.unwrap_or(fallback.max_lines).max(1),
+            max_bytes: caps.max_bytes.unwrap_or(fallback.max_bytes).max(1),
+        };
+
+        let per_call = resolve_caps(toml.per_call.unwrap_or_default(), defaults.per_call);
+        let per_turn = toml.per_turn.unwrap_or_default();
+        let per_turn_max_bytes = per_turn.max_bytes.unwrap_or(defaults.per_turn_max_bytes);
+        let relax_per_turn_max_bytes = per_turn
+            .relax_max_bytes
+            .unwrap_or(defaults.relax_per_turn_max_bytes)
+            .max(per_turn_max_bytes);
+        let breaker = toml.repeat_command_breaker.unwrap_or_default();
+        let per_extension_caps = toml
+            .per_extension_caps
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(ext, caps)| {
+                let ext = ext.trim_start_matches('.').to_ascii_lowercase();
+                (ext, resolve_caps(caps, per_call))
+            })
+            .collect();
+        let exec_output_max_bytes = toml
+            .exec_output_max_bytes
+            .unwrap_or(defaults.exec_output_max_bytes);
+        let build_log_tail_lines = toml
+            .build_log_tail_lines
+            .unwrap_or(defaults.build_log_tail_lines);
+        let output_rules = toml
+            .output_rules
+            .unwrap_or_default()
+            .into_iter()
+            .map(|rule| OutputRule {
+                program: rule.program,
+                subcommand: rule.subcommand,
+                kind: rule.kind,
+                strategy: match rule.strategy {
+                    OutputStrategyKind::Head => OutputStrategy::Head,
+                    OutputStrategyKind::Tail => OutputStrategy::Tail,
+                    OutputStrategyKind::HeadTail => OutputStrategy::HeadTail,
+                    OutputStrategyKind::ErrorsOnly => OutputStrategy::ErrorsOnly {
+                        pattern: rule
+                            .pattern
+                            .unwrap_or_else(|| DEFAULT_ERROR_PATTERN.to_string()),
+                    },
+                    OutputStrategyKind::Dedupe => OutputStrategy::Dedupe,
+                },
+                max_lines: rule.max_lines.unwrap_or(build_log_tail_lines).max(1),
+                max_bytes: rule.max_bytes.unwrap_or(exec_output_max_bytes).max(1),
+            })
+            .chain(default_output_rules(
+                exec_output_max_bytes,
+                build_log_tail_lines.max(1),
+            ))
+            .collect();
+
+        Self {
+            per_call,
+            per_turn_max_bytes,
+            relax_per_turn_max_bytes,
+            rg_max_bytes: toml.rg_max_bytes.unwrap_or(defaults.rg_max_bytes),
+            exec_output_max_bytes,
+            build_log_tail_lines,
+            build_log_digest_errors: toml
+                .build_log_digest_errors
+                .unwrap_or(defaults.build_log_digest_errors),
+            overlap_suppression: toml
+                .overlap_suppression
+                .unwrap_or(defaults.overlap_suppression),
+            repeat_command_repeats: breaker.repeats.unwrap_or(defaults.repeat_command_repeats),
+            repeat_command_window: breaker
+                .window_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.repeat_command_window),
+            allow_large_slice_if_small_file: toml
+                .allow_large_slice_if_small_file
+                .unwrap_or(defaults.allow_large_slice_if_small_file),
+            large_slice: resolve_caps(
+                ReadCapsToml {
+                    max_lines: toml.large_slice_lines,
+                    max_bytes: toml.large_slice_bytes,
+                },
+                defaults.large_slice,
+            ),
+            per_extension_caps,
+            output_rules,
         }
     }
 }
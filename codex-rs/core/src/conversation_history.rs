@@ -1,5 +1,8 @@
 use codex_protocol::models::ResponseItem;
 
+use crate::transcript_compaction::ToolOutputLedger;
+use crate::transcript_compaction::compact_stale_tool_outputs;
+
 /// Transcript of conversation history
 #[derive(Debug, Clone, Default)]
 pub(crate) struct ConversationHistory {
@@ -35,6 +38,12 @@ impl ConversationHistory {
     pub(crate) fn replace(&mut self, items: Vec<ResponseItem>) {
         self.items = items;
     }
+
+    /// Rewrite tool outputs superseded by later items in place. Returns the
+    /// number of outputs that were compacted.
+    pub(crate) fn compact_stale_tool_outputs(&mut self, ledger: &mut ToolOutputLedger) -> usize {
+        compact_stale_tool_outputs(&mut self.items, ledger)
+    }
 }
 
 /// Anything that is not a system message or "reasoning" message is considered
@@ -0,0 +1,239 @@
+//! Environment captured from the user's login shell.
+//!
+//! With `shell_environment_policy.experimental_profile_snapshot` set, the
+//! user's shell is started once with their rc file sourced and its
+//! environment is recorded. Commands are then spawned with that environment
+//! (`PATH` entries from nvm, pyenv, rustup, direnv and the like) without
+//! paying for shell startup on every call. The snapshot is taken again when
+//! one of the rc files, or the `.envrc` that applies to the working
+//! directory, changes.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::shell::Shell;
+
+/// How long the login shell may take to print its environment.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Printed between anything the rc files write to stdout and the
+/// environment dump.
+const ENV_MARKER: &str = "__CODEX_ENV_SNAPSHOT__";
+
+/// Variables that describe the capturing shell rather than the user's setup.
+const SHELL_LOCAL_VARS: &[&str] = &["_", "OLDPWD", "PWD", "SHLVL"];
+
+const BASH_STARTUP_FILES: &[&str] = &[".bash_profile", ".bash_login", ".profile", ".bashrc"];
+const ZSH_STARTUP_FILES: &[&str] = &[".zshenv", ".zprofile", ".zshrc", ".zlogin"];
+
+pub(crate) struct EnvSnapshotCache {
+    shell: Shell,
+    snapshot: Mutex<Option<EnvSnapshot>>,
+}
+
+struct EnvSnapshot {
+    fingerprint: u64,
+    /// `None` when capture failed; kept so a broken rc file is not retried
+    /// on every command.
+    vars: Option<HashMap<String, String>>,
+}
+
+/// How to start the user's shell and which files shape its environment.
+struct ShellSetup<'a> {
+    shell_path: &'a str,
+    rc_path: &'a str,
+    direnv_shell: &'static str,
+    startup_files: Vec<PathBuf>,
+}
+
+impl EnvSnapshotCache {
+    pub(crate) fn new(shell: Shell) -> Self {
+        Self {
+            shell,
+            snapshot: Mutex::new(None),
+        }
+    }
+
+    /// The login-shell environment for commands run in `cwd`. Returns `None`
+    /// for shells other than bash and zsh, or when capture fails.
+    pub(crate) async fn vars(&self, cwd: &Path) -> Option<HashMap<String, String>> {
+        let setup = self.shell_setup()?;
+        let envrc = find_envrc(cwd);
+        let fingerprint = fingerprint_files(setup.startup_files.iter().chain(envrc.as_ref()));
+
+        let mut guard = self.snapshot.lock().await;
+        if let Some(snapshot) = guard.as_ref()
+            && snapshot.fingerprint == fingerprint
+        {
+            return snapshot.vars.clone();
+        }
+
+        let vars = match capture_env(&setup, cwd).await {
+            Ok(vars) => Some(vars),
+            Err(err) => {
+                warn!("failed to capture login shell environment: {err}");
+                None
+            }
+        };
+        *guard = Some(EnvSnapshot {
+            fingerprint,
+            vars: vars.clone(),
+        });
+        vars
+    }
+
+    fn shell_setup(&self) -> Option<ShellSetup<'_>> {
+        let (shell_path, rc_path, direnv_shell, names) = match &self.shell {
+            Shell::Bash(bash) => (
+                &bash.shell_path,
+                &bash.bashrc_path,
+                "bash",
+                BASH_STARTUP_FILES,
+            ),
+            Shell::Zsh(zsh) => (&zsh.shell_path, &zsh.zshrc_path, "zsh", ZSH_STARTUP_FILES),
+            Shell::PowerShell(_) | Shell::Unknown => return None,
+        };
+        let home = Path::new(rc_path).parent()?;
+        Some(ShellSetup {
+            shell_path,
+            rc_path,
+            direnv_shell,
+            startup_files: names.iter().map(|name| home.join(name)).collect(),
+        })
+    }
+}
+
+/// The `.envrc` direnv would load for `cwd`: the nearest one in `cwd` or an
+/// ancestor.
+fn find_envrc(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(".envrc"))
+        .find(|path| path.is_file())
+}
+
+/// Hash of the paths and contents of `files`; missing files hash as absent.
+fn fingerprint_files<'a>(files: impl Iterator<Item = &'a PathBuf>) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for path in files {
+        path.hash(&mut hasher);
+        std::fs::read(path).ok().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+async fn capture_env(
+    setup: &ShellSetup<'_>,
+    cwd: &Path,
+) -> std::io::Result<HashMap<String, String>> {
+    let rc = shlex::try_quote(setup.rc_path)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    let direnv_shell = setup.direnv_shell;
+    let script = format!(
+        "[ -f {rc} ] && source {rc} >/dev/null 2>&1; \
+         command -v direnv >/dev/null 2>&1 && eval \"$(direnv export {direnv_shell} 2>/dev/null)\"; \
+         printf '%s\\0' {ENV_MARKER}; env -0"
+    );
+
+    let child = Command::new(setup.shell_path)
+        .args(["-lc", &script])
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    let output = tokio::time::timeout(CAPTURE_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::TimedOut, "login shell timed out")
+        })??;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "login shell exited with {}",
+            output.status
+        )));
+    }
+
+    parse_env_dump(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "login shell did not print its environment",
+        )
+    })
+}
+
+/// Parse the NUL-separated `env -0` output that follows [`ENV_MARKER`].
+fn parse_env_dump(stdout: &str) -> Option<HashMap<String, String>> {
+    let (_, dump) = stdout.split_once(&format!("{ENV_MARKER}\0"))?;
+    Some(
+        dump.split('\0')
+            .filter_map(|entry| entry.split_once('='))
+            .filter(|(name, _)| !name.is_empty() && !SHELL_LOCAL_VARS.contains(name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell::BashShell;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_env_dump_after_marker() {
+        let stdout = format!("welcome!\n{ENV_MARKER}\0PATH=/a:/b\0SHLVL=2\0EQ=x=y\0");
+        let vars = parse_env_dump(&stdout).expect("dump");
+        assert_eq!(
+            vars,
+            HashMap::from([
+                ("PATH".to_string(), "/a:/b".to_string()),
+                ("EQ".to_string(), "x=y".to_string()),
+            ])
+        );
+        assert_eq!(parse_env_dump("no marker"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn captures_rc_exports_and_recaptures_after_edit() {
+        if !Path::new("/bin/bash").exists() {
+            return;
+        }
+        let home = tempfile::tempdir().expect("home");
+        let bashrc = home.path().join(".bashrc");
+        std::fs::write(&bashrc, "export CODEX_SNAPSHOT_TEST=one\n").expect("write bashrc");
+        let cache = EnvSnapshotCache::new(Shell::Bash(BashShell {
+            shell_path: "/bin/bash".to_string(),
+            bashrc_path: bashrc.to_string_lossy().to_string(),
+        }));
+
+        let vars = cache.vars(home.path()).await.expect("snapshot");
+        assert_eq!(
+            vars.get("CODEX_SNAPSHOT_TEST").map(String::as_str),
+            Some("one")
+        );
+
+        std::fs::write(&bashrc, "export CODEX_SNAPSHOT_TEST=two\n").expect("rewrite bashrc");
+        let vars = cache.vars(home.path()).await.expect("snapshot");
+        assert_eq!(
+            vars.get("CODEX_SNAPSHOT_TEST").map(String::as_str),
+            Some("two")
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_shells_have_no_snapshot() {
+        let cache = EnvSnapshotCache::new(Shell::Unknown);
+        assert_eq!(cache.vars(Path::new("/")).await, None);
+    }
+}
@@ -27,6 +27,7 @@ pub(crate) struct EnvironmentContext {
     pub approval_policy: Option<AskForApproval>,
     pub sandbox_mode: Option<SandboxMode>,
     pub network_access: Option<NetworkAccess>,
+    pub network_allowlist: Option<Vec<String>>,
     pub writable_roots: Option<Vec<PathBuf>>,
     pub shell: Option<Shell>,
 }
@@ -59,6 +60,11 @@ impl EnvironmentContext {
                 }
                 None => None,
             },
+            network_allowlist: sandbox_policy
+                .as_ref()
+                .map(SandboxPolicy::network_allowlist)
+                .filter(|allowlist| !allowlist.is_empty())
+                .map(<[String]>::to_vec),
             writable_roots: match sandbox_policy {
                 Some(SandboxPolicy::WorkspaceWrite { writable_roots, .. }) => {
                     if writable_roots.is_empty() {
@@ -82,6 +88,7 @@ impl EnvironmentContext {
             approval_policy,
             sandbox_mode,
             network_access,
+            network_allowlist,
             writable_roots,
             // should compare all fields except shell
             shell: _,
@@ -91,6 +98,7 @@ impl EnvironmentContext {
             && self.approval_policy == *approval_policy
             && self.sandbox_mode == *sandbox_mode
             && self.network_access == *network_access
+            && self.network_allowlist == *network_allowlist
             && self.writable_roots == *writable_roots
     }
 }
@@ -119,6 +127,7 @@ impl EnvironmentContext {
     ///   <sandbox_mode>...</sandbox_mode>
     ///   <writable_roots>...</writable_roots>
     ///   <network_access>...</network_access>
+    ///   <network_allowlist>...</network_allowlist>
     ///   <shell>...</shell>
     /// </environment_context>
     /// ```
@@ -140,6 +149,13 @@ impl EnvironmentContext {
                 "  <network_access>{network_access}</network_access>"
             ));
         }
+        if let Some(network_allowlist) = self.network_allowlist {
+            lines.push("  <network_allowlist>".to_string());
+            for destination in network_allowlist {
+                lines.push(format!("    <destination>{destination}</destination>"));
+            }
+            lines.push("  </network_allowlist>".to_string());
+        }
         if let Some(writable_roots) = self.writable_roots {
             lines.push("  <writable_roots>".to_string());
             for writable_root in writable_roots {
@@ -183,7 +199,10 @@ mod tests {
     fn workspace_write_policy(writable_roots: Vec<&str>, network_access: bool) -> SandboxPolicy {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: writable_roots.into_iter().map(PathBuf::from).collect(),
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![],
             network_access,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         }
@@ -212,6 +231,30 @@ mod tests {
         assert_eq!(context.serialize_to_xml(), expected);
     }
 
+    #[test]
+    fn serialize_network_allowlist_environment_context() {
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![],
+            network_access: false,
+            network_allowlist: vec!["localhost:5432".to_string()],
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+        };
+        let context = EnvironmentContext::new(None, None, Some(policy), None);
+
+        let expected = r#"<environment_context>
+  <sandbox_mode>workspace-write</sandbox_mode>
+  <network_access>restricted</network_access>
+  <network_allowlist>
+    <destination>localhost:5432</destination>
+  </network_allowlist>
+</environment_context>"#;
+
+        assert_eq!(context.serialize_to_xml(), expected);
+    }
+
     #[test]
     fn serialize_read_only_environment_context() {
         let context = EnvironmentContext::new(
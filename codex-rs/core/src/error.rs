@@ -1,4 +1,5 @@
 use crate::exec::ExecToolCallOutput;
+use crate::spawn::ResourceLimitKind;
 use crate::token_data::KnownPlan;
 use crate::token_data::PlanType;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -35,6 +36,13 @@ pub enum SandboxErr {
     #[error("command timed out")]
     Timeout { output: Box<ExecToolCallOutput> },
 
+    /// Command exceeded one of the `[sandbox_resources]` limits
+    #[error("command exceeded the {limit}")]
+    ResourceLimit {
+        limit: ResourceLimitKind,
+        output: Box<ExecToolCallOutput>,
+    },
+
     /// Command was killed by a signal
     #[error("command was killed by a signal")]
     Signal(i32),
@@ -260,6 +268,9 @@ pub fn get_error_message_ui(e: &CodexErr) -> String {
             "error: command timed out after {} ms",
             output.duration.as_millis()
         ),
+        CodexErr::Sandbox(SandboxErr::ResourceLimit { limit, .. }) => {
+            format!("error: command exceeded the {limit}")
+        }
         _ => e.to_string(),
     }
 }
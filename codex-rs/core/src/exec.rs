@@ -16,6 +16,7 @@ use tokio::io::BufReader;
 use tokio::process::Child;
 
 use crate::bash::parse_bash_lc_plain_commands;
+use crate::config_types::ContextPolicy;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
@@ -25,9 +26,13 @@ use crate::protocol::EventMsg;
 use crate::protocol::ExecCommandOutputDeltaEvent;
 use crate::protocol::ExecOutputStream;
 use crate::protocol::SandboxPolicy;
+pub use crate::protocol::SandboxType;
 use crate::seatbelt::spawn_command_under_seatbelt;
+use crate::spawn::ResourceLimitKind;
+use crate::spawn::ResourceLimits;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
+use crate::truncate::format_byte_limit;
 
 const DEFAULT_TIMEOUT_MS: u64 = 10_000;
 
@@ -42,17 +47,67 @@ const EXEC_TIMEOUT_EXIT_CODE: i32 = 124; // conventional timeout exit code
 const READ_CHUNK_SIZE: usize = 8192; // bytes per read
 const AGGREGATE_BUFFER_INITIAL_CAPACITY: usize = 8 * 1024; // 8 KiB
 
-const GENERIC_EXEC_OUTPUT_MAX_BYTES: usize = 6 * 1024; // 6 KiB budget for most commands
-const RG_EXEC_OUTPUT_MAX_BYTES: usize = 8 * 1024; // 8 KiB budget for ripgrep
-const BUILD_LOG_TAIL_MAX_BYTES: usize = 16 * 1024; // retain up to 16 KiB before tailing lines
+pub(crate) const BUILD_LOG_TAIL_MAX_BYTES: usize = 16 * 1024; // cap on the tail shown to the model
+/// Output that is spilled to disk before being trimmed is kept whole up to
+/// this size. Build logs always are; other output only with
+/// [`ExecOutputCaps::keep_full_output`].
+const OUTPUT_SPILL_MAX_BYTES: usize = 4 * 1024 * 1024;
+/// `rg` output is kept up to this size so it can be grouped into the compact
+/// form before being cut to the rg cap.
+const RG_COMPACT_INPUT_MAX_BYTES: usize = 1024 * 1024;
 
-pub(crate) const BUILD_LOG_TAIL_LINES: usize = 120;
-pub(crate) const BUILD_LOG_TAIL_NOTICE: &str = "[build log trimmed to last 120 lines; refine the command or request /relax to inspect the full log]";
+/// Output caps for a single exec call, taken from the context policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecOutputCaps {
+    pub exec_output_max_bytes: usize,
+    pub rg_max_bytes: usize,
+    /// Lines of a build or test log kept once the command finishes.
+    pub build_log_tail_lines: usize,
+    /// Distinct errors listed in the failure digest above a trimmed log.
+    pub build_log_digest_errors: usize,
+    /// Keep up to [`OUTPUT_SPILL_MAX_BYTES`] of combined output instead of
+    /// `exec_output_max_bytes`, leaving the caller to spill it and cut
+    /// `aggregated_output` down itself.
+    pub keep_full_output: bool,
+}
+
+impl From<&ContextPolicy> for ExecOutputCaps {
+    fn from(policy: &ContextPolicy) -> Self {
+        Self {
+            exec_output_max_bytes: policy.exec_output_max_bytes,
+            rg_max_bytes: policy.rg_max_bytes,
+            build_log_tail_lines: policy.build_log_tail_lines,
+            build_log_digest_errors: policy.build_log_digest_errors,
+            keep_full_output: false,
+        }
+    }
+}
+
+impl Default for ExecOutputCaps {
+    fn default() -> Self {
+        Self::from(&ContextPolicy::default())
+    }
+}
+
+pub(crate) fn rg_truncation_notice(max_bytes: usize) -> String {
+    let limit = format_byte_limit(max_bytes);
+    format!(
+        "[rg output truncated to {limit}; narrow the search (e.g., add filters) or request /relax]"
+    )
+}
+
+pub(crate) fn exec_truncation_notice(max_bytes: usize) -> String {
+    let limit = format_byte_limit(max_bytes);
+    format!(
+        "[output truncated to {limit}; refine the command or request /relax for a temporary increase]"
+    )
+}
 
-const GENERIC_EXEC_TRUNCATION_NOTICE: &str =
-    "[output truncated to 6 KiB; refine the command or request /relax for a temporary increase]";
-const RG_EXEC_TRUNCATION_NOTICE: &str =
-    "[rg output truncated to 8 KiB; narrow the search (e.g., add filters) or request /relax]";
+pub(crate) fn build_log_tail_notice(tail_lines: usize) -> String {
+    format!(
+        "[build log trimmed to last {tail_lines} lines; refine the command or request /relax to inspect the full log]"
+    )
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum AggregatedOutputMode {
@@ -60,54 +115,71 @@ enum AggregatedOutputMode {
     Tail,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TruncationNotice {
+    Generic,
+    Ripgrep,
+}
+
 #[derive(Clone, Copy, Debug)]
 struct ExecOutputLimit {
     stream_max_bytes: usize,
     aggregated_max_bytes: usize,
-    truncation_notice: &'static str,
+    notice: TruncationNotice,
     aggregated_mode: AggregatedOutputMode,
 }
 
 impl ExecOutputLimit {
-    const fn generic() -> Self {
+    const fn generic(caps: &ExecOutputCaps) -> Self {
         Self {
-            stream_max_bytes: GENERIC_EXEC_OUTPUT_MAX_BYTES,
-            aggregated_max_bytes: GENERIC_EXEC_OUTPUT_MAX_BYTES,
-            truncation_notice: GENERIC_EXEC_TRUNCATION_NOTICE,
+            stream_max_bytes: caps.exec_output_max_bytes,
+            aggregated_max_bytes: if caps.keep_full_output {
+                OUTPUT_SPILL_MAX_BYTES
+            } else {
+                caps.exec_output_max_bytes
+            },
+            notice: TruncationNotice::Generic,
             aggregated_mode: AggregatedOutputMode::Head,
         }
     }
 
-    const fn ripgrep() -> Self {
+    const fn ripgrep(caps: &ExecOutputCaps) -> Self {
         Self {
-            stream_max_bytes: RG_EXEC_OUTPUT_MAX_BYTES,
-            aggregated_max_bytes: RG_EXEC_OUTPUT_MAX_BYTES,
-            truncation_notice: RG_EXEC_TRUNCATION_NOTICE,
+            stream_max_bytes: caps.rg_max_bytes,
+            aggregated_max_bytes: RG_COMPACT_INPUT_MAX_BYTES,
+            notice: TruncationNotice::Ripgrep,
             aggregated_mode: AggregatedOutputMode::Head,
         }
     }
 
-    const fn build() -> Self {
+    const fn build(caps: &ExecOutputCaps) -> Self {
         Self {
-            stream_max_bytes: GENERIC_EXEC_OUTPUT_MAX_BYTES,
-            aggregated_max_bytes: BUILD_LOG_TAIL_MAX_BYTES,
-            truncation_notice: GENERIC_EXEC_TRUNCATION_NOTICE,
+            stream_max_bytes: caps.exec_output_max_bytes,
+            aggregated_max_bytes: OUTPUT_SPILL_MAX_BYTES,
+            notice: TruncationNotice::Generic,
             aggregated_mode: AggregatedOutputMode::Tail,
         }
     }
+
+    fn truncation_notice(&self) -> String {
+        match self.notice {
+            TruncationNotice::Generic => exec_truncation_notice(self.stream_max_bytes),
+            TruncationNotice::Ripgrep => rg_truncation_notice(self.stream_max_bytes),
+        }
+    }
 }
 
-fn exec_output_limit_for_command(command: &[String]) -> ExecOutputLimit {
+fn exec_output_limit_for_command(command: &[String], caps: &ExecOutputCaps) -> ExecOutputLimit {
     if command_invokes_ripgrep(command) {
-        ExecOutputLimit::ripgrep()
+        ExecOutputLimit::ripgrep(caps)
     } else if should_tail_build_output(command) {
-        ExecOutputLimit::build()
+        ExecOutputLimit::build(caps)
     } else {
-        ExecOutputLimit::generic()
+        ExecOutputLimit::generic(caps)
     }
 }
 
-fn command_invokes_ripgrep(command: &[String]) -> bool {
+pub(crate) fn command_invokes_ripgrep(command: &[String]) -> bool {
     fn is_rg_program(program: &str) -> bool {
         Path::new(program)
             .file_name()
@@ -133,37 +205,48 @@ fn command_invokes_ripgrep(command: &[String]) -> bool {
     }
 }
 
+/// Toolchain family of a build or test command, used to pick the failure
+/// digest extractors for its log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BuildTool {
+    Cargo,
+    /// npm, pnpm, yarn, tsc, jest and vitest.
+    Node,
+    Pytest,
+    /// Gradle and Maven.
+    Jvm,
+}
+
 pub(crate) fn should_tail_build_output(command: &[String]) -> bool {
+    build_tool_for_command(command).is_some()
+}
+
+/// The build tool `command` runs, if it looks like a build or test run whose
+/// log should be trimmed to its tail.
+pub(crate) fn build_tool_for_command(command: &[String]) -> Option<BuildTool> {
     if let Some(all_commands) = parse_bash_lc_plain_commands(command) {
         if all_commands.len() != 1 {
-            return false;
+            return None;
         }
-        all_commands
-            .first()
-            .is_some_and(|cmd| command_is_build_tool(cmd))
+        all_commands.first().and_then(|cmd| command_build_tool(cmd))
     } else {
-        command_is_build_tool(command)
+        command_build_tool(command)
     }
 }
 
-fn command_is_build_tool(command: &[String]) -> bool {
-    let program = match command.first() {
-        Some(p) => p,
-        None => return false,
-    };
-    let name = match canonical_tool_name(program) {
-        Some(name) => name,
-        None => return false,
-    };
+fn command_build_tool(command: &[String]) -> Option<BuildTool> {
+    let name = canonical_tool_name(command.first()?)?;
 
     match name.as_str() {
-        "npm" => is_npm_build_command(command),
-        "pnpm" => is_pnpm_build_command(command),
-        "yarn" | "yarnpkg" => is_yarn_build_command(command),
-        "cargo" => is_cargo_build_command(command),
-        "gradle" | "gradlew" => true,
-        "mvn" | "mvnw" => true,
-        _ => false,
+        "npm" => is_npm_build_command(command).then_some(BuildTool::Node),
+        "pnpm" => is_pnpm_build_command(command).then_some(BuildTool::Node),
+        "yarn" | "yarnpkg" => is_yarn_build_command(command).then_some(BuildTool::Node),
+        "tsc" | "jest" | "vitest" => Some(BuildTool::Node),
+        "cargo" => is_cargo_build_command(command).then_some(BuildTool::Cargo),
+        "pytest" | "py.test" => Some(BuildTool::Pytest),
+        "gradle" | "gradlew" => Some(BuildTool::Jvm),
+        "mvn" | "mvnw" => Some(BuildTool::Jvm),
+        _ => None,
     }
 }
 
@@ -196,14 +279,14 @@ fn is_npm_build_command(command: &[String]) -> bool {
             "run" | "run-script" => {
                 return command
                     .get(i + 1)
-                    .map_or(false, |script| is_buildish_script(script));
+                    .is_some_and(|script| is_buildish_script(script));
             }
             "test" | "build" => return true,
             _ if arg.starts_with('-') => {
-                if let Some(next) = command.get(i + 1) {
-                    if !next.starts_with('-') {
-                        i += 1;
-                    }
+                if let Some(next) = command.get(i + 1)
+                    && !next.starts_with('-')
+                {
+                    i += 1;
                 }
             }
             _ => break,
@@ -221,14 +304,14 @@ fn is_pnpm_build_command(command: &[String]) -> bool {
             "run" | "run-script" => {
                 return command
                     .get(i + 1)
-                    .map_or(false, |script| is_buildish_script(script));
+                    .is_some_and(|script| is_buildish_script(script));
             }
             _ if is_buildish_script(arg) => return true,
             _ if arg.starts_with('-') => {
-                if let Some(next) = command.get(i + 1) {
-                    if !next.starts_with('-') {
-                        i += 1;
-                    }
+                if let Some(next) = command.get(i + 1)
+                    && !next.starts_with('-')
+                {
+                    i += 1;
                 }
             }
             _ => break,
@@ -246,7 +329,7 @@ fn is_yarn_build_command(command: &[String]) -> bool {
             "run" | "run-script" => {
                 return command
                     .get(i + 1)
-                    .map_or(false, |script| is_buildish_script(script));
+                    .is_some_and(|script| is_buildish_script(script));
             }
             "workspace" => {
                 // Skip workspace name and continue scanning for a run command or script.
@@ -255,18 +338,18 @@ fn is_yarn_build_command(command: &[String]) -> bool {
                 }
             }
             "workspaces" => {
-                if command.get(i + 1).map(|s| s.as_str()) == Some("run") {
+                if command.get(i + 1).map(String::as_str) == Some("run") {
                     return command
                         .get(i + 2)
-                        .map_or(false, |script| is_buildish_script(script));
+                        .is_some_and(|script| is_buildish_script(script));
                 }
             }
             _ if is_buildish_script(arg) => return true,
             _ if arg.starts_with('-') => {
-                if let Some(next) = command.get(i + 1) {
-                    if !next.starts_with('-') {
-                        i += 1;
-                    }
+                if let Some(next) = command.get(i + 1)
+                    && !next.starts_with('-')
+                {
+                    i += 1;
                 }
             }
             _ => break,
@@ -299,23 +382,65 @@ pub struct ExecParams {
     pub env: HashMap<String, String>,
     pub with_escalated_permissions: Option<bool>,
     pub justification: Option<String>,
+    pub resource_limits: ResourceLimits,
 }
 
 impl ExecParams {
+    /// The requested timeout, capped by the wall-clock resource limit.
     pub fn timeout_duration(&self) -> Duration {
+        let requested = self.requested_timeout();
+        match self.resource_limits.wall_clock() {
+            Some(limit) => requested.min(limit),
+            None => requested,
+        }
+    }
+
+    fn requested_timeout(&self) -> Duration {
         Duration::from_millis(self.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS))
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum SandboxType {
-    None,
-
-    /// Only available on macOS.
-    MacosSeatbelt,
+/// The resource limit that explains how a command ended, if any.
+/// `wall_clock_capped` is set when the wall-clock limit shortened the
+/// requested timeout. Only the signal a limit delivers counts as evidence;
+/// a SIGKILL could come from anywhere, and the hard CPU limit that sends one
+/// is only reached by commands that ignored SIGXCPU.
+fn exceeded_resource_limit(
+    limits: &ResourceLimits,
+    wall_clock_capped: bool,
+    signal: Option<i32>,
+    timed_out: bool,
+    exit_code: i32,
+) -> Option<ResourceLimitKind> {
+    if timed_out {
+        return limits
+            .wall_clock_seconds
+            .filter(|_| wall_clock_capped)
+            .map(|seconds| ResourceLimitKind::WallClock { seconds });
+    }
+    // Shells report a child killed by a signal as exit status 128 + signal.
+    let signal = signal.or_else(|| (exit_code > 128).then(|| exit_code - 128));
+    #[cfg(unix)]
+    match signal {
+        Some(libc::SIGXCPU) => limits
+            .cpu_seconds
+            .map(|seconds| ResourceLimitKind::CpuTime { seconds }),
+        Some(libc::SIGXFSZ) => limits
+            .max_file_size_mb
+            .map(|max_mb| ResourceLimitKind::FileSize { max_mb }),
+        _ => None,
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = signal;
+        None
+    }
+}
 
-    /// Only available on Linux.
-    LinuxSeccomp,
+fn resource_limit_notice(limit: ResourceLimitKind) -> String {
+    format!(
+        "[command stopped by the {limit} in [sandbox_resources]; reduce its parallelism or scope instead of retrying it unchanged]"
+    )
 }
 
 #[derive(Clone)]
@@ -332,11 +457,36 @@ pub async fn process_exec_tool_call(
     sandbox_cwd: &Path,
     codex_linux_sandbox_exe: &Option<PathBuf>,
     stdout_stream: Option<StdoutStream>,
+) -> Result<ExecToolCallOutput> {
+    process_exec_tool_call_with_caps(
+        params,
+        sandbox_type,
+        sandbox_policy,
+        sandbox_cwd,
+        codex_linux_sandbox_exe,
+        stdout_stream,
+        ExecOutputCaps::default(),
+    )
+    .await
+}
+
+/// Like [`process_exec_tool_call`], but truncates output according to `caps`
+/// instead of the default context policy.
+pub async fn process_exec_tool_call_with_caps(
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_cwd: &Path,
+    codex_linux_sandbox_exe: &Option<PathBuf>,
+    stdout_stream: Option<StdoutStream>,
+    caps: ExecOutputCaps,
 ) -> Result<ExecToolCallOutput> {
     let start = Instant::now();
 
     let timeout_duration = params.timeout_duration();
-    let output_limit = exec_output_limit_for_command(&params.command);
+    let resource_limits = params.resource_limits;
+    let wall_clock_capped = timeout_duration < params.requested_timeout();
+    let output_limit = exec_output_limit_for_command(&params.command, &caps);
     let aggregated_mode = output_limit.aggregated_mode;
 
     let raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr> = match sandbox_type
@@ -344,63 +494,35 @@ pub async fn process_exec_tool_call(
         SandboxType::None => {
             exec(params, sandbox_policy, stdout_stream.clone(), output_limit).await
         }
-        SandboxType::MacosSeatbelt => {
-            let ExecParams {
-                command,
-                cwd: command_cwd,
-                env,
-                ..
-            } = params;
-            let child = spawn_command_under_seatbelt(
-                command,
-                command_cwd,
+        SandboxType::MacosSeatbelt | SandboxType::LinuxSeccomp => {
+            let child = spawn_exec_child(
+                params,
+                sandbox_type,
                 sandbox_policy,
                 sandbox_cwd,
+                codex_linux_sandbox_exe,
                 StdioPolicy::RedirectForShellTool,
-                env,
             )
             .await?;
             consume_truncated_output(child, timeout_duration, stdout_stream.clone(), output_limit)
                 .await
         }
-        SandboxType::LinuxSeccomp => {
-            let ExecParams {
-                command,
-                cwd: command_cwd,
-                env,
-                ..
-            } = params;
-
-            let codex_linux_sandbox_exe = codex_linux_sandbox_exe
-                .as_ref()
-                .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
-            let child = spawn_command_under_linux_sandbox(
-                codex_linux_sandbox_exe,
-                command,
-                command_cwd,
-                sandbox_policy,
-                sandbox_cwd,
-                StdioPolicy::RedirectForShellTool,
-                env,
-            )
-            .await?;
-
-            consume_truncated_output(child, timeout_duration, stdout_stream, output_limit).await
-        }
     };
     let duration = start.elapsed();
     match raw_output_result {
         Ok(raw_output) => {
             #[allow(unused_mut)]
             let mut timed_out = raw_output.timed_out;
+            #[allow(unused_mut)]
+            let mut signal = None;
 
             #[cfg(target_family = "unix")]
             {
-                if let Some(signal) = raw_output.exit_status.signal() {
-                    if signal == TIMEOUT_CODE {
+                if let Some(raw_signal) = raw_output.exit_status.signal() {
+                    if raw_signal == TIMEOUT_CODE {
                         timed_out = true;
                     } else {
-                        return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
+                        signal = Some(raw_signal);
                     }
                 }
             }
@@ -414,14 +536,33 @@ pub async fn process_exec_tool_call(
             let mut stderr = raw_output.stderr.from_utf8_lossy();
             let mut aggregated_output = raw_output.aggregated_output.from_utf8_lossy();
 
+            let truncation_notice = output_limit.truncation_notice();
+            append_truncation_notice(&mut stdout, &truncation_notice);
+            append_truncation_notice(&mut stderr, &truncation_notice);
+            // Only note truncation of the aggregated buffer itself; for rg, and
+            // when full output is kept, it holds more than the streams so the
+            // caller can compact or spill it afterwards.
+            if matches!(aggregated_mode, AggregatedOutputMode::Head) {
+                append_truncation_notice(&mut aggregated_output, &truncation_notice);
+            }
+
             if stdout.truncated_by_bytes || stderr.truncated_by_bytes {
                 aggregated_output.truncated_by_bytes = true;
             }
 
-            append_truncation_notice(&mut stdout, output_limit.truncation_notice);
-            append_truncation_notice(&mut stderr, output_limit.truncation_notice);
-            if matches!(aggregated_mode, AggregatedOutputMode::Head) {
-                append_truncation_notice(&mut aggregated_output, output_limit.truncation_notice);
+            let exceeded = exceeded_resource_limit(
+                &resource_limits,
+                wall_clock_capped,
+                signal,
+                timed_out,
+                exit_code,
+            );
+            if let Some(limit) = exceeded {
+                let text = &mut aggregated_output.text;
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text.push_str(&resource_limit_notice(limit));
             }
 
             let exec_output = ExecToolCallOutput {
@@ -433,6 +574,17 @@ pub async fn process_exec_tool_call(
                 timed_out,
             };
 
+            if let Some(limit) = exceeded {
+                return Err(CodexErr::Sandbox(SandboxErr::ResourceLimit {
+                    limit,
+                    output: Box::new(exec_output),
+                }));
+            }
+
+            if let Some(signal) = signal {
+                return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
+            }
+
             if timed_out {
                 return Err(CodexErr::Sandbox(SandboxErr::Timeout {
                     output: Box::new(exec_output),
@@ -531,30 +683,90 @@ async fn exec(
     output_limit: ExecOutputLimit,
 ) -> Result<RawExecToolCallOutput> {
     let timeout = params.timeout_duration();
-    let ExecParams {
-        command, cwd, env, ..
-    } = params;
-
-    let (program, args) = command.split_first().ok_or_else(|| {
-        CodexErr::Io(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "command args are empty",
-        ))
-    })?;
-    let arg0 = None;
-    let child = spawn_child_async(
-        PathBuf::from(program),
-        args.into(),
-        arg0,
-        cwd,
+    let child = spawn_exec_child(
+        params,
+        SandboxType::None,
         sandbox_policy,
+        Path::new("."),
+        &None,
         StdioPolicy::RedirectForShellTool,
-        env,
     )
     .await?;
     consume_truncated_output(child, timeout, stdout_stream, output_limit).await
 }
 
+/// Spawn `params.command` under `sandbox_type` with stdout and stderr piped,
+/// without waiting for it. `sandbox_cwd` and `codex_linux_sandbox_exe` are
+/// only consulted by the sandboxed variants.
+pub(crate) async fn spawn_exec_child(
+    params: ExecParams,
+    sandbox_type: SandboxType,
+    sandbox_policy: &SandboxPolicy,
+    sandbox_cwd: &Path,
+    codex_linux_sandbox_exe: &Option<PathBuf>,
+    stdio_policy: StdioPolicy,
+) -> Result<Child> {
+    let ExecParams {
+        command,
+        cwd: command_cwd,
+        env,
+        resource_limits,
+        ..
+    } = params;
+
+    let child = match sandbox_type {
+        SandboxType::None => {
+            let (program, args) = command.split_first().ok_or_else(|| {
+                CodexErr::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "command args are empty",
+                ))
+            })?;
+            let arg0 = None;
+            spawn_child_async(
+                PathBuf::from(program),
+                args.into(),
+                arg0,
+                command_cwd,
+                sandbox_policy,
+                stdio_policy,
+                env,
+                resource_limits,
+            )
+            .await?
+        }
+        SandboxType::MacosSeatbelt => {
+            spawn_command_under_seatbelt(
+                command,
+                command_cwd,
+                sandbox_policy,
+                sandbox_cwd,
+                stdio_policy,
+                env,
+                resource_limits,
+            )
+            .await?
+        }
+        SandboxType::LinuxSeccomp => {
+            let codex_linux_sandbox_exe = codex_linux_sandbox_exe
+                .as_ref()
+                .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+            spawn_command_under_linux_sandbox(
+                codex_linux_sandbox_exe,
+                command,
+                command_cwd,
+                sandbox_policy,
+                sandbox_cwd,
+                stdio_policy,
+                env,
+                resource_limits,
+            )
+            .await?
+        }
+    };
+    Ok(child)
+}
+
 /// Consumes the output of a child process, truncating it so it is suitable for
 /// use as the output of a `shell` tool call. Also enforces specified timeout.
 async fn consume_truncated_output(
@@ -756,6 +968,19 @@ fn append_truncation_notice(output: &mut StreamOutput<String>, notice: &str) {
     output.text.push_str(notice);
 }
 
+#[cfg(unix)]
+fn synthetic_exit_status(code: i32) -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    std::process::ExitStatus::from_raw(code)
+}
+
+#[cfg(windows)]
+fn synthetic_exit_status(code: i32) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    #[expect(clippy::unwrap_used)]
+    std::process::ExitStatus::from_raw(code.try_into().unwrap())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -763,10 +988,13 @@ mod tests {
     #[test]
     fn detects_ripgrep_plain_command() {
         let command = vec!["rg".to_string(), "needle".to_string()];
-        let limits = exec_output_limit_for_command(&command);
-        assert_eq!(limits.aggregated_max_bytes, RG_EXEC_OUTPUT_MAX_BYTES);
-        assert_eq!(limits.stream_max_bytes, RG_EXEC_OUTPUT_MAX_BYTES);
-        assert_eq!(limits.truncation_notice, RG_EXEC_TRUNCATION_NOTICE);
+        let limits = exec_output_limit_for_command(&command, &ExecOutputCaps::default());
+        assert_eq!(limits.aggregated_max_bytes, RG_COMPACT_INPUT_MAX_BYTES);
+        assert_eq!(limits.stream_max_bytes, 8 * 1024);
+        assert_eq!(
+            limits.truncation_notice(),
+            "[rg output truncated to 8 KiB; narrow the search (e.g., add filters) or request /relax]"
+        );
     }
 
     #[test]
@@ -776,24 +1004,42 @@ mod tests {
             "-lc".to_string(),
             "rg --json term".to_string(),
         ];
-        let limits = exec_output_limit_for_command(&command);
-        assert_eq!(limits.aggregated_max_bytes, RG_EXEC_OUTPUT_MAX_BYTES);
+        let limits = exec_output_limit_for_command(&command, &ExecOutputCaps::default());
+        assert_eq!(limits.aggregated_max_bytes, RG_COMPACT_INPUT_MAX_BYTES);
+        assert_eq!(limits.stream_max_bytes, 8 * 1024);
     }
 
     #[test]
     fn defaults_to_generic_for_other_commands() {
         let command = vec!["python".to_string(), "script.py".to_string()];
-        let limits = exec_output_limit_for_command(&command);
-        assert_eq!(limits.aggregated_max_bytes, GENERIC_EXEC_OUTPUT_MAX_BYTES);
-        assert_eq!(limits.truncation_notice, GENERIC_EXEC_TRUNCATION_NOTICE);
+        let limits = exec_output_limit_for_command(&command, &ExecOutputCaps::default());
+        assert_eq!(limits.aggregated_max_bytes, 6 * 1024);
+        assert_eq!(
+            limits.truncation_notice(),
+            "[output truncated to 6 KiB; refine the command or request /relax for a temporary increase]"
+        );
     }
 
     #[test]
     fn detects_pnpm_build_scripts() {
         let command = vec!["pnpm".to_string(), "run".to_string(), "build".to_string()];
-        assert!(should_tail_build_output(&command));
-        let limits = exec_output_limit_for_command(&command);
-        assert_eq!(limits.aggregated_max_bytes, BUILD_LOG_TAIL_MAX_BYTES);
+        assert_eq!(build_tool_for_command(&command), Some(BuildTool::Node));
+        let limits = exec_output_limit_for_command(&command, &ExecOutputCaps::default());
+        assert_eq!(limits.aggregated_max_bytes, OUTPUT_SPILL_MAX_BYTES);
+    }
+
+    #[test]
+    fn classifies_test_runners() {
+        let pytest = vec!["pytest".to_string(), "-x".to_string()];
+        assert_eq!(build_tool_for_command(&pytest), Some(BuildTool::Pytest));
+        let cargo = vec![
+            "bash".to_string(),
+            "-lc".to_string(),
+            "cargo test".to_string(),
+        ];
+        assert_eq!(build_tool_for_command(&cargo), Some(BuildTool::Cargo));
+        let gradle = vec!["./gradlew".to_string(), "build".to_string()];
+        assert_eq!(build_tool_for_command(&gradle), Some(BuildTool::Jvm));
     }
 
     #[test]
@@ -823,17 +1069,136 @@ mod tests {
         ];
         assert!(should_tail_build_output(&command));
     }
-}
 
-#[cfg(unix)]
-fn synthetic_exit_status(code: i32) -> ExitStatus {
-    use std::os::unix::process::ExitStatusExt;
-    std::process::ExitStatus::from_raw(code)
-}
+    #[test]
+    fn configured_caps_change_limits_and_notice() {
+        let caps = ExecOutputCaps {
+            exec_output_max_bytes: 5000,
+            rg_max_bytes: 32 * 1024,
+            build_log_tail_lines: 40,
+            build_log_digest_errors: 5,
+            keep_full_output: false,
+        };
+        let limits =
+            exec_output_limit_for_command(&["python".to_string(), "x.py".to_string()], &caps);
+        assert_eq!(limits.aggregated_max_bytes, 5000);
+        assert_eq!(
+            limits.truncation_notice(),
+            "[output truncated to 5000 bytes; refine the command or request /relax for a temporary increase]"
+        );
+        let limits = exec_output_limit_for_command(&["rg".to_string(), "x".to_string()], &caps);
+        assert_eq!(limits.stream_max_bytes, 32 * 1024);
+    }
 
-#[cfg(windows)]
-fn synthetic_exit_status(code: i32) -> ExitStatus {
-    use std::os::windows::process::ExitStatusExt;
-    #[expect(clippy::unwrap_used)]
-    std::process::ExitStatus::from_raw(code.try_into().unwrap())
+    #[test]
+    fn keeping_full_output_raises_only_the_aggregated_cap() {
+        let caps = ExecOutputCaps {
+            keep_full_output: true,
+            ..ExecOutputCaps::default()
+        };
+        let limits =
+            exec_output_limit_for_command(&["python".to_string(), "x.py".to_string()], &caps);
+        assert_eq!(limits.stream_max_bytes, 6 * 1024);
+        assert_eq!(limits.aggregated_max_bytes, OUTPUT_SPILL_MAX_BYTES);
+    }
+
+    fn sh_params(script: &str, cwd: PathBuf, resource_limits: ResourceLimits) -> ExecParams {
+        ExecParams {
+            command: vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()],
+            cwd,
+            timeout_ms: Some(10_000),
+            env: HashMap::new(),
+            with_escalated_permissions: None,
+            justification: None,
+            resource_limits,
+        }
+    }
+
+    #[test]
+    fn wall_clock_limit_caps_the_requested_timeout() {
+        let limits = ResourceLimits {
+            wall_clock_seconds: Some(2),
+            ..ResourceLimits::default()
+        };
+        let params = sh_params("true", PathBuf::from("/"), limits);
+        assert_eq!(params.timeout_duration(), Duration::from_secs(2));
+        let params = ExecParams {
+            timeout_ms: Some(500),
+            ..params
+        };
+        assert_eq!(params.timeout_duration(), Duration::from_millis(500));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn limits_are_reported_only_for_their_own_signal() {
+        let limits = ResourceLimits {
+            cpu_seconds: Some(10),
+            ..ResourceLimits::default()
+        };
+        assert_eq!(
+            exceeded_resource_limit(&limits, false, Some(libc::SIGXCPU), false, -1),
+            Some(ResourceLimitKind::CpuTime { seconds: 10 })
+        );
+        assert_eq!(
+            exceeded_resource_limit(&limits, false, None, false, 128 + libc::SIGXCPU),
+            Some(ResourceLimitKind::CpuTime { seconds: 10 })
+        );
+        assert_eq!(
+            exceeded_resource_limit(&limits, false, Some(libc::SIGKILL), false, -1),
+            None
+        );
+        assert_eq!(
+            exceeded_resource_limit(&limits, false, None, false, 137),
+            None
+        );
+        assert_eq!(
+            exceeded_resource_limit(
+                &ResourceLimits::default(),
+                false,
+                Some(libc::SIGXCPU),
+                false,
+                -1
+            ),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn file_size_limit_is_reported_as_resource_limit() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let limits = ResourceLimits {
+            max_file_size_mb: Some(1),
+            ..ResourceLimits::default()
+        };
+        let params = sh_params(
+            "head -c 2097152 /dev/zero > big.bin",
+            dir.path().to_path_buf(),
+            limits,
+        );
+        let result = process_exec_tool_call(
+            params,
+            SandboxType::None,
+            &SandboxPolicy::DangerFullAccess,
+            dir.path(),
+            &None,
+            None,
+        )
+        .await;
+        match result {
+            Err(CodexErr::Sandbox(SandboxErr::ResourceLimit { limit, output })) => {
+                assert_eq!(limit, ResourceLimitKind::FileSize { max_mb: 1 });
+                assert!(
+                    output
+                        .aggregated_output
+                        .text
+                        .contains("[command stopped by the file size limit (1 MiB)"),
+                    "{}",
+                    output.aggregated_output.text
+                );
+            }
+            other => panic!("expected a resource limit error, got {other:?}"),
+        }
+    }
 }
@@ -18,6 +18,24 @@ pub struct ExecCommandParams {
 
     #[serde(default = "default_login")]
     pub(crate) login: bool,
+
+    #[serde(default)]
+    pub(crate) with_escalated_permissions: Option<bool>,
+
+    #[serde(default)]
+    pub(crate) justification: Option<String>,
+}
+
+impl ExecCommandParams {
+    /// The shell invocation that runs `cmd`.
+    pub(crate) fn shell_command(&self) -> Vec<String> {
+        let shell_mode_opt = if self.login { "-lc" } else { "-c" };
+        vec![
+            self.shell.clone(),
+            shell_mode_opt.to_string(),
+            self.cmd.clone(),
+        ]
+    }
 }
 
 fn default_yield_time() -> u64 {
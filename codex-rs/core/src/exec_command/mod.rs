@@ -1,5 +1,6 @@
 mod exec_command_params;
 mod exec_command_session;
+mod pty_command;
 mod responses_api;
 mod session_id;
 mod session_manager;
@@ -7,6 +8,7 @@ mod session_manager;
 pub use exec_command_params::ExecCommandParams;
 pub use exec_command_params::WriteStdinParams;
 pub(crate) use exec_command_session::ExecCommandSession;
+pub(crate) use pty_command::PtyCommand;
 pub use responses_api::EXEC_COMMAND_TOOL_NAME;
 pub use responses_api::WRITE_STDIN_TOOL_NAME;
 pub use responses_api::create_exec_command_tool_for_responses_api;
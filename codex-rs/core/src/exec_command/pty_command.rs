@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use portable_pty::CommandBuilder;
+
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::exec::SandboxType;
+use crate::landlock::create_linux_sandbox_command_args;
+use crate::protocol::SandboxPolicy;
+use crate::seatbelt::MACOS_PATH_TO_SEATBELT_EXECUTABLE;
+use crate::seatbelt::create_seatbelt_command_args;
+use crate::spawn::CODEX_SANDBOX_ENV_VAR;
+use crate::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+
+/// Name the Codex executable answers to when it should act as the Linux
+/// sandbox helper.
+const LINUX_SANDBOX_ARG0: &str = "codex-linux-sandbox";
+
+/// A command to launch in a PTY, already wrapped for the sandbox it has to
+/// run under.
+///
+/// Unlike [`crate::spawn::spawn_child_async`], a PTY cannot override `argv[0]`,
+/// so the Linux helper is launched through the `codex-linux-sandbox` alias the
+/// CLI puts on `PATH` rather than through the Codex executable itself.
+#[derive(Debug, Clone)]
+pub(crate) struct PtyCommand {
+    program: String,
+    args: Vec<String>,
+    cwd: PathBuf,
+    env: HashMap<String, String>,
+    /// Whether the command runs without the sandbox its policy asks for.
+    escalated: bool,
+}
+
+impl PtyCommand {
+    pub(crate) fn new(
+        command: Vec<String>,
+        cwd: PathBuf,
+        sandbox_type: SandboxType,
+        sandbox_policy: &SandboxPolicy,
+        sandbox_policy_cwd: &Path,
+        codex_linux_sandbox_exe: &Option<PathBuf>,
+    ) -> Result<Self> {
+        let escalated = matches!(sandbox_type, SandboxType::None)
+            && !matches!(sandbox_policy, SandboxPolicy::DangerFullAccess);
+        let mut env = HashMap::new();
+        if !sandbox_policy.has_full_network_access() {
+            env.insert(
+                CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR.to_string(),
+                "1".to_string(),
+            );
+        }
+
+        let (program, args) = match sandbox_type {
+            SandboxType::None => {
+                let mut command = command.into_iter();
+                let program = command.next().ok_or_else(|| {
+                    CodexErr::Io(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "command args are empty",
+                    ))
+                })?;
+                (program, command.collect())
+            }
+            SandboxType::MacosSeatbelt => {
+                env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "seatbelt".to_string());
+                (
+                    MACOS_PATH_TO_SEATBELT_EXECUTABLE.to_string(),
+                    create_seatbelt_command_args(command, sandbox_policy, sandbox_policy_cwd),
+                )
+            }
+            SandboxType::LinuxSeccomp => {
+                let exe = codex_linux_sandbox_exe
+                    .as_ref()
+                    .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+                (
+                    linux_sandbox_program(exe)?,
+                    create_linux_sandbox_command_args(command, sandbox_policy, sandbox_policy_cwd),
+                )
+            }
+        };
+
+        Ok(Self {
+            program,
+            args,
+            cwd,
+            env,
+            escalated,
+        })
+    }
+
+    /// Whether the session escapes the sandbox of its policy, in which case
+    /// every write to its stdin needs the user's approval.
+    pub(crate) fn is_escalated(&self) -> bool {
+        self.escalated
+    }
+
+    pub(crate) fn to_command_builder(&self) -> CommandBuilder {
+        let mut builder = CommandBuilder::new(&self.program);
+        builder.args(&self.args);
+        builder.cwd(&self.cwd);
+        for (key, value) in &self.env {
+            builder.env(key, value);
+        }
+        builder
+    }
+}
+
+/// Path that launches `exe` as the Linux sandbox helper: `exe` itself when it
+/// is already named `codex-linux-sandbox`, otherwise the alias on `PATH`,
+/// provided it resolves to the same executable.
+fn linux_sandbox_program(exe: &Path) -> Result<String> {
+    let named_as_helper =
+        exe.file_name().and_then(|name| name.to_str()) == Some(LINUX_SANDBOX_ARG0);
+    let program = if named_as_helper {
+        exe.to_path_buf()
+    } else {
+        let alias = which::which(LINUX_SANDBOX_ARG0)
+            .map_err(|_| CodexErr::LandlockSandboxExecutableNotProvided)?;
+        if std::fs::canonicalize(&alias)? != std::fs::canonicalize(exe)? {
+            return Err(CodexErr::LandlockSandboxExecutableNotProvided);
+        }
+        alias
+    };
+    Ok(program.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unsandboxed_command_runs_as_given() {
+        let command = PtyCommand::new(
+            vec!["bash".to_string(), "-i".to_string()],
+            PathBuf::from("/tmp"),
+            SandboxType::None,
+            &SandboxPolicy::DangerFullAccess,
+            Path::new("/tmp"),
+            &None,
+        )
+        .expect("pty command");
+        assert_eq!(command.program, "bash");
+        assert_eq!(command.args, vec!["-i".to_string()]);
+        assert!(command.env.is_empty());
+        assert!(!command.is_escalated());
+    }
+
+    #[test]
+    fn unsandboxed_command_under_a_sandbox_policy_is_escalated() {
+        let command = PtyCommand::new(
+            vec!["bash".to_string(), "-i".to_string()],
+            PathBuf::from("/tmp"),
+            SandboxType::None,
+            &SandboxPolicy::ReadOnly,
+            Path::new("/tmp"),
+            &None,
+        )
+        .expect("pty command");
+        assert!(command.is_escalated());
+    }
+
+    #[test]
+    fn seatbelt_wraps_command_and_marks_environment() {
+        let command = PtyCommand::new(
+            vec!["bash".to_string(), "-i".to_string()],
+            PathBuf::from("/tmp"),
+            SandboxType::MacosSeatbelt,
+            &SandboxPolicy::ReadOnly,
+            Path::new("/tmp"),
+            &None,
+        )
+        .expect("pty command");
+        assert_eq!(command.program, MACOS_PATH_TO_SEATBELT_EXECUTABLE);
+        assert_eq!(
+            command.args[command.args.len() - 3..],
+            ["--".to_string(), "bash".to_string(), "-i".to_string()]
+        );
+        assert_eq!(
+            command.env.get(CODEX_SANDBOX_ENV_VAR).map(String::as_str),
+            Some("seatbelt")
+        );
+        assert!(!command.is_escalated());
+        assert_eq!(
+            command
+                .env
+                .get(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR)
+                .map(String::as_str),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn linux_sandbox_requires_helper_executable() {
+        let err = PtyCommand::new(
+            vec!["bash".to_string()],
+            PathBuf::from("/tmp"),
+            SandboxType::LinuxSeccomp,
+            &SandboxPolicy::ReadOnly,
+            Path::new("/tmp"),
+            &None,
+        )
+        .expect_err("missing helper");
+        assert!(matches!(
+            err,
+            CodexErr::LandlockSandboxExecutableNotProvided
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn linux_sandbox_launches_helper_by_its_own_name() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let helper = dir.path().join(LINUX_SANDBOX_ARG0);
+        std::fs::write(&helper, "").expect("write helper");
+
+        let command = PtyCommand::new(
+            vec!["bash".to_string()],
+            PathBuf::from("/tmp"),
+            SandboxType::LinuxSeccomp,
+            &SandboxPolicy::ReadOnly,
+            Path::new("/tmp"),
+            &Some(helper.clone()),
+        )
+        .expect("pty command");
+        assert_eq!(command.program, helper.to_string_lossy());
+        assert_eq!(
+            command.args,
+            vec![
+                "/tmp".to_string(),
+                serde_json::to_string(&SandboxPolicy::ReadOnly).expect("policy json"),
+                "--".to_string(),
+                "bash".to_string(),
+            ]
+        );
+    }
+}
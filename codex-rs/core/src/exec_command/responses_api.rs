@@ -40,6 +40,18 @@ pub fn create_exec_command_tool_for_responses_api() -> ResponsesApiTool {
             ),
         },
     );
+    properties.insert(
+        "with_escalated_permissions".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Whether to request escalated permissions. Set to true if the session needs to run without sandbox restrictions; every later write to its stdin then needs approval too.".to_string()),
+        },
+    );
+    properties.insert(
+        "justification".to_string(),
+        JsonSchema::String {
+            description: Some("Only set if with_escalated_permissions is true. 1-sentence explanation of why we want to run this command.".to_string()),
+        },
+    );
 
     ResponsesApiTool {
         name: EXEC_COMMAND_TOOL_NAME.to_owned(),
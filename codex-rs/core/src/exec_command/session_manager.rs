@@ -6,7 +6,6 @@ use std::sync::Mutex as StdMutex;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicU32;
 
-use portable_pty::CommandBuilder;
 use portable_pty::PtySize;
 use portable_pty::native_pty_system;
 use tokio::sync::Mutex;
@@ -19,6 +18,7 @@ use tokio::time::timeout;
 use crate::exec_command::exec_command_params::ExecCommandParams;
 use crate::exec_command::exec_command_params::WriteStdinParams;
 use crate::exec_command::exec_command_session::ExecCommandSession;
+use crate::exec_command::pty_command::PtyCommand;
 use crate::exec_command::session_id::SessionId;
 use crate::truncate::truncate_middle;
 
@@ -69,9 +69,13 @@ pub enum ExitStatus {
 
 impl SessionManager {
     /// Processes the request and is required to send a response via `outgoing`.
-    pub async fn handle_exec_command_request(
+    ///
+    /// `command` is `params` as a command line, wrapped for the sandbox the
+    /// session runs under.
+    pub(crate) async fn handle_exec_command_request(
         &self,
         params: ExecCommandParams,
+        command: PtyCommand,
     ) -> Result<ExecCommandOutput, String> {
         // Allocate a session id.
         let session_id = SessionId(
@@ -79,9 +83,8 @@ impl SessionManager {
                 .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
         );
 
-        let (session, mut output_rx, mut exit_rx) = create_exec_command_session(params.clone())
-            .await
-            .map_err(|err| {
+        let (session, mut output_rx, mut exit_rx) =
+            create_exec_command_session(command).await.map_err(|err| {
                 format!(
                     "failed to create exec command session for session id {}: {err}",
                     session_id.0
@@ -162,6 +165,15 @@ impl SessionManager {
     }
 
     /// Write characters to a session's stdin and collect combined output for up to `yield_time_ms`.
+    /// Whether `session_id` runs outside the sandbox of its policy.
+    pub(crate) async fn is_escalated(&self, session_id: SessionId) -> bool {
+        self.sessions
+            .lock()
+            .await
+            .get(&session_id)
+            .is_some_and(ExecCommandSession::is_escalated)
+    }
+
     pub async fn handle_write_stdin_request(
         &self,
         params: WriteStdinParams,
@@ -228,20 +240,12 @@ impl SessionManager {
 
 /// Spawn PTY and child process per spawn_exec_command_session logic.
 async fn create_exec_command_session(
-    params: ExecCommandParams,
+    command: PtyCommand,
 ) -> anyhow::Result<(
     ExecCommandSession,
     tokio::sync::broadcast::Receiver<Vec<u8>>,
     oneshot::Receiver<i32>,
 )> {
-    let ExecCommandParams {
-        cmd,
-        yield_time_ms: _,
-        max_output_tokens: _,
-        shell,
-        login,
-    } = params;
-
     // Use the native pty implementation for the system
     let pty_system = native_pty_system();
 
@@ -253,13 +257,8 @@ async fn create_exec_command_session(
         pixel_height: 0,
     })?;
 
-    // Spawn a shell into the pty
-    let mut command_builder = CommandBuilder::new(shell);
-    let shell_mode_opt = if login { "-lc" } else { "-c" };
-    command_builder.arg(shell_mode_opt);
-    command_builder.arg(cmd);
-
-    let mut child = pair.slave.spawn_command(command_builder)?;
+    // Spawn the (sandboxed) shell into the pty
+    let mut child = pair.slave.spawn_command(command.to_command_builder())?;
     // Obtain a killer that can signal the process independently of `.wait()`.
     let killer = child.clone_killer();
 
@@ -336,6 +335,7 @@ async fn create_exec_command_session(
         writer_handle,
         wait_handle,
         exit_status,
+        command.is_escalated(),
     );
     Ok((session, initial_output_rx, exit_rx))
 }
@@ -353,8 +353,10 @@ mod tests {
     #[allow(clippy::print_stderr)]
     #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
     async fn session_manager_streams_and_truncates_from_now() {
+        use crate::exec::SandboxType;
         use crate::exec_command::exec_command_params::ExecCommandParams;
         use crate::exec_command::exec_command_params::WriteStdinParams;
+        use crate::protocol::SandboxPolicy;
         use tokio::time::sleep;
 
         let session_manager = SessionManager::default();
@@ -378,9 +380,20 @@ PY"#
             max_output_tokens: 1_000, // large enough to avoid truncation here
             shell: "/bin/bash".to_string(),
             login: false,
+            with_escalated_permissions: None,
+            justification: None,
         };
+        let command = PtyCommand::new(
+            params.shell_command(),
+            std::env::temp_dir(),
+            SandboxType::None,
+            &SandboxPolicy::DangerFullAccess,
+            &std::env::temp_dir(),
+            &None,
+        )
+        .expect("unsandboxed pty command");
         let initial_output = match session_manager
-            .handle_exec_command_request(params.clone())
+            .handle_exec_command_request(params.clone(), command)
             .await
         {
             Ok(v) => v,
@@ -15,6 +15,29 @@ pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
     populate_env(std::env::vars(), policy)
 }
 
+/// Like [`create_env`], but with `snapshot` (the environment captured from
+/// the user's login shell) layered over the process environment before the
+/// policy is applied.
+pub fn create_env_with_snapshot(
+    policy: &ShellEnvironmentPolicy,
+    snapshot: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    populate_env(layer_snapshot(std::env::vars(), snapshot), policy)
+}
+
+fn layer_snapshot<I>(vars: I, snapshot: &HashMap<String, String>) -> HashMap<String, String>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut layered: HashMap<String, String> = vars.into_iter().collect();
+    layered.extend(
+        snapshot
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+    layered
+}
+
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
 where
     I: IntoIterator<Item = (String, String)>,
@@ -101,6 +124,26 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn snapshot_values_win_but_policy_still_applies() {
+        let vars = make_vars(&[("PATH", "/usr/bin"), ("HOME", "/home/user")]);
+        let snapshot: HashMap<String, String> = hashmap! {
+            "PATH".to_string() => "/home/user/.cargo/bin:/usr/bin".to_string(),
+            "NPM_TOKEN".to_string() => "t".to_string(),
+        };
+
+        let result = populate_env(
+            layer_snapshot(vars, &snapshot),
+            &ShellEnvironmentPolicy::default(),
+        );
+
+        let expected: HashMap<String, String> = hashmap! {
+            "PATH".to_string() => "/home/user/.cargo/bin:/usr/bin".to_string(),
+            "HOME".to_string() => "/home/user".to_string(),
+        };
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn test_include_only() {
         let vars = make_vars(&[("PATH", "/usr/bin"), ("FOO", "bar")]);
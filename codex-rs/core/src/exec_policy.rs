@@ -0,0 +1,360 @@
+//! Approval decisions backed by `codex-execpolicy` `.policy` files.
+//!
+//! A session consults, from most to least specific, the project's
+//! `.codex/execpolicy.policy`, `$CODEX_HOME/execpolicy.policy` and the
+//! policy bundled with `codex-execpolicy`. The first policy that defines a
+//! program decides how an invocation of it is treated, even when none of its
+//! rules match the invocation.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_execpolicy::ArgType;
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::ValidExec;
+use codex_execpolicy::get_default_policy;
+
+use crate::bash::parse_bash_lc_plain_commands;
+use crate::path_masks::PathMaskKind;
+use crate::path_masks::masked_path_kind;
+use crate::protocol::SandboxPolicy;
+use crate::safety::absolute_path;
+use crate::safety::normalize;
+
+/// File name of a team-supplied policy, both in `codex_home` and in a
+/// project's `.codex` directory.
+pub const EXEC_POLICY_FILENAME: &str = "execpolicy.policy";
+
+/// Label used for the policy bundled with `codex-execpolicy`.
+const DEFAULT_POLICY_SOURCE: &str = "the default execpolicy";
+
+/// The `.policy` files a session consults.
+#[derive(Default)]
+pub(crate) struct ExecPolicy {
+    /// Ordered from most to least specific.
+    policies: Vec<(String, Policy)>,
+}
+
+/// What the policies say about a command.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ExecPolicyVerdict {
+    /// A rule forbids the command.
+    Forbidden { explanation: String },
+    /// Every part of the command matched a rule and none of them writes
+    /// files.
+    ReadOnly,
+    /// A matched rule writes a path the sandbox would not let it write.
+    WritesOutsideWritableRoots { explanation: String },
+}
+
+impl ExecPolicy {
+    /// Loads the bundled policy followed by each of `files` that exists,
+    /// where later files take precedence. A file that fails to parse is
+    /// skipped and reported in the returned errors.
+    pub(crate) fn load(files: &[PathBuf]) -> (Self, Vec<String>) {
+        let mut policies = Vec::new();
+        let mut errors = Vec::new();
+        match get_default_policy() {
+            Ok(policy) => policies.push((DEFAULT_POLICY_SOURCE.to_string(), policy)),
+            Err(err) => errors.push(format!("failed to parse the default execpolicy: {err}")),
+        }
+        for file in files {
+            let source = match std::fs::read_to_string(file) {
+                Ok(source) => source,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    errors.push(format!("failed to read {}: {err}", file.display()));
+                    continue;
+                }
+            };
+            let name = file.display().to_string();
+            match PolicyParser::new(&name, &source).parse() {
+                Ok(policy) => policies.push((name, policy)),
+                Err(err) => errors.push(format!("failed to parse {name}: {err}")),
+            }
+        }
+        policies.reverse();
+        (Self { policies }, errors)
+    }
+
+    /// Evaluates `command`, splitting `bash -lc` scripts made of plain
+    /// commands into their parts. Returns `None` when no rule decides it.
+    pub(crate) fn evaluate(
+        &self,
+        command: &[String],
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> Option<ExecPolicyVerdict> {
+        if self.policies.is_empty() {
+            return None;
+        }
+        let commands =
+            parse_bash_lc_plain_commands(command).unwrap_or_else(|| vec![command.to_vec()]);
+
+        let mut all_read_only = !commands.is_empty();
+        let mut writes_outside = None;
+        for command in &commands {
+            match self.evaluate_one(command, sandbox_policy, cwd) {
+                Some(ExecPolicyVerdict::Forbidden { explanation }) => {
+                    return Some(ExecPolicyVerdict::Forbidden { explanation });
+                }
+                Some(ExecPolicyVerdict::ReadOnly) => {}
+                Some(verdict @ ExecPolicyVerdict::WritesOutsideWritableRoots { .. }) => {
+                    all_read_only = false;
+                    writes_outside.get_or_insert(verdict);
+                }
+                None => all_read_only = false,
+            }
+        }
+        if all_read_only {
+            Some(ExecPolicyVerdict::ReadOnly)
+        } else {
+            writes_outside
+        }
+    }
+
+    fn evaluate_one(
+        &self,
+        command: &[String],
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> Option<ExecPolicyVerdict> {
+        let (program, args) = command.split_first()?;
+        let exec_call = ExecCall {
+            program: program.clone(),
+            args: args.to_vec(),
+        };
+        // A policy that defines the program but matches none of its rules
+        // still decides: a team may narrow what the default policy allows.
+        let mut decided = None;
+        for (source, policy) in &self.policies {
+            match policy.check(&exec_call) {
+                Ok(matched) => {
+                    decided = Some((source, matched));
+                    break;
+                }
+                Err(codex_execpolicy::Error::NoSpecForProgram { .. }) => continue,
+                Err(_) => return None,
+            }
+        }
+        let (source, matched) = decided?;
+
+        let exec = match matched {
+            MatchedExec::Forbidden { reason, .. } => {
+                return Some(ExecPolicyVerdict::Forbidden {
+                    explanation: format!("`{exec_call}` is forbidden by {source}: {reason}"),
+                });
+            }
+            MatchedExec::Match { exec } => exec,
+        };
+
+        if !exec.might_write_files() {
+            // Running it outside the sandbox must not expose a path the
+            // sandbox would hide.
+            let reads_denied_path =
+                file_operands(&exec, ArgType::ReadableFile)
+                    .into_iter()
+                    .any(|path| {
+                        resolve(path, cwd).is_some_and(|path| {
+                            masked_path_kind(sandbox_policy, cwd, &path)
+                                == Some(PathMaskKind::DenyRead)
+                        })
+                    });
+            return (!reads_denied_path).then_some(ExecPolicyVerdict::ReadOnly);
+        }
+
+        if matches!(sandbox_policy, SandboxPolicy::DangerFullAccess) {
+            return None;
+        }
+        let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
+        file_operands(&exec, ArgType::WriteableFile).into_iter().find_map(|operand| {
+            let path = resolve(operand, cwd)?;
+            let writable = writable_roots.iter().any(|root| root.is_path_writable(&path))
+                && masked_path_kind(sandbox_policy, cwd, &path).is_none();
+            (!writable).then(|| ExecPolicyVerdict::WritesOutsideWritableRoots {
+                explanation: format!(
+                    "the `{}` rule in {source} says `{exec_call}` writes {}, which is outside the writable roots",
+                    exec.program,
+                    path.display()
+                ),
+            })
+        })
+    }
+}
+
+/// The values of the arguments and options of `exec` matched as `kind`.
+fn file_operands(exec: &ValidExec, kind: ArgType) -> Vec<&str> {
+    let args = exec
+        .args
+        .iter()
+        .filter(|arg| arg.r#type == kind)
+        .map(|arg| arg.value.as_str());
+    let opts = exec
+        .opts
+        .iter()
+        .filter(|opt| opt.r#type == kind)
+        .map(|opt| opt.value.as_str());
+    args.chain(opts).collect()
+}
+
+fn resolve(operand: &str, cwd: &Path) -> Option<PathBuf> {
+    normalize(&absolute_path(Path::new(operand), cwd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn workspace_write(deny_read: &[&str]) -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            read_only_subpaths: vec![],
+            deny_read_paths: deny_read.iter().map(ToString::to_string).collect(),
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn classifies_commands_by_their_rule() {
+        let (policy, errors) = ExecPolicy::load(&[]);
+        assert_eq!(errors, Vec::<String>::new());
+        let cwd = Path::new("/repo");
+        let sandbox = workspace_write(&[".env"]);
+
+        assert_eq!(
+            policy.evaluate(&argv(&["cat", "README.md"]), &sandbox, cwd),
+            Some(ExecPolicyVerdict::ReadOnly)
+        );
+        assert_eq!(
+            policy.evaluate(
+                &argv(&["bash", "-lc", "head -n 5 a.txt && cat b.txt"]),
+                &sandbox,
+                cwd
+            ),
+            Some(ExecPolicyVerdict::ReadOnly)
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["cat", ".env"]), &sandbox, cwd),
+            None
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["cp", "a.txt", "b.txt"]), &sandbox, cwd),
+            None
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["cp", "a.txt", "../b.txt"]), &sandbox, cwd),
+            Some(ExecPolicyVerdict::WritesOutsideWritableRoots {
+                explanation: "the `cp` rule in the default execpolicy says `cp a.txt ../b.txt` writes /b.txt, which is outside the writable roots".to_string(),
+            })
+        );
+        assert_eq!(
+            policy.evaluate(
+                &argv(&["cp", "a.txt", "../b.txt"]),
+                &SandboxPolicy::DangerFullAccess,
+                cwd
+            ),
+            None
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["cargo", "build"]), &sandbox, cwd),
+            None
+        );
+    }
+
+    #[test]
+    fn team_policies_take_precedence_and_report_parse_errors() {
+        let tmp = TempDir::new().expect("tempdir");
+        let team = tmp.path().join(EXEC_POLICY_FILENAME);
+        std::fs::write(
+            &team,
+            r#"
+define_program(
+    program="cargo",
+    args=["check"],
+)
+
+forbid_program_regex("^rm$", "use trash instead")
+"#,
+        )
+        .expect("write team policy");
+        let broken = tmp.path().join("broken.policy");
+        std::fs::write(&broken, "define_program(").expect("write broken policy");
+        let missing = tmp.path().join("missing.policy");
+
+        let (policy, errors) = ExecPolicy::load(&[team.clone(), broken.clone(), missing]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with(&format!("failed to parse {}", broken.display())));
+
+        let cwd = Path::new("/repo");
+        let sandbox = workspace_write(&[]);
+        assert_eq!(
+            policy.evaluate(&argv(&["cargo", "check"]), &sandbox, cwd),
+            Some(ExecPolicyVerdict::ReadOnly)
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["bash", "-lc", "ls && rm -r target"]), &sandbox, cwd),
+            Some(ExecPolicyVerdict::Forbidden {
+                explanation: format!(
+                    "`rm -r target` is forbidden by {}: use trash instead",
+                    team.display()
+                ),
+            })
+        );
+    }
+
+    #[test]
+    fn team_policies_narrow_programs_the_default_policy_allows() {
+        let tmp = TempDir::new().expect("tempdir");
+        let team = tmp.path().join(EXEC_POLICY_FILENAME);
+        std::fs::write(
+            &team,
+            r#"
+define_program(
+    program="sed",
+    options=[flag("-n")],
+    args=[ARG_SED_COMMAND, ARG_RFILES],
+)
+"#,
+        )
+        .expect("write team policy");
+
+        let cwd = Path::new("/repo");
+        let sandbox = workspace_write(&[]);
+        let (default, _) = ExecPolicy::load(&[]);
+        assert_eq!(
+            default.evaluate(&argv(&["sed", "-u", "1,5p", "a.txt"]), &sandbox, cwd),
+            Some(ExecPolicyVerdict::ReadOnly)
+        );
+
+        let (policy, errors) = ExecPolicy::load(&[team]);
+        assert_eq!(errors, Vec::<String>::new());
+        assert_eq!(
+            policy.evaluate(&argv(&["sed", "-n", "1,5p", "a.txt"]), &sandbox, cwd),
+            Some(ExecPolicyVerdict::ReadOnly)
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["sed", "-u", "1,5p", "a.txt"]), &sandbox, cwd),
+            None
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["sed", "-e", "1,5p", "a.txt"]), &sandbox, cwd),
+            None
+        );
+        assert_eq!(
+            policy.evaluate(&argv(&["cat", "a.txt"]), &sandbox, cwd),
+            Some(ExecPolicyVerdict::ReadOnly)
+        );
+    }
+}
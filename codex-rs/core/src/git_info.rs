@@ -178,6 +178,17 @@ pub async fn git_diff_to_remote(cwd: &Path) -> Option<GitDiffToRemote> {
     })
 }
 
+/// Return the commit oid `HEAD` resolves to, or `None` outside a repository
+/// or before the first commit.
+pub async fn head_commit_oid(cwd: &Path) -> Option<String> {
+    let output = run_git_command_with_timeout(&["rev-parse", "--verify", "HEAD"], cwd).await?;
+    if !output.status.success() {
+        return None;
+    }
+    let oid = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!oid.is_empty()).then_some(oid)
+}
+
 /// Run a git command with a timeout to prevent blocking on large repositories
 async fn run_git_command_with_timeout(args: &[&str], cwd: &Path) -> Option<std::process::Output> {
     let result = timeout(
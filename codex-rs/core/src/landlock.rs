@@ -1,4 +1,5 @@
 use crate::protocol::SandboxPolicy;
+use crate::spawn::ResourceLimits;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 use std::collections::HashMap;
@@ -12,7 +13,9 @@ use tokio::process::Child;
 /// Unlike macOS Seatbelt where we directly embed the policy text, the Linux
 /// helper accepts a list of `--sandbox-permission`/`-s` flags mirroring the
 /// public CLI. We convert the internal [`SandboxPolicy`] representation into
-/// the equivalent CLI options.
+/// the equivalent CLI options. `resource_limits` are passed along for the
+/// helper to apply right before it executes the command.
+#[allow(clippy::too_many_arguments)]
 pub async fn spawn_command_under_linux_sandbox<P>(
     codex_linux_sandbox_exe: P,
     command: Vec<String>,
@@ -21,11 +24,23 @@ pub async fn spawn_command_under_linux_sandbox<P>(
     sandbox_policy_cwd: &Path,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    resource_limits: ResourceLimits,
 ) -> std::io::Result<Child>
 where
     P: AsRef<Path>,
 {
-    let args = create_linux_sandbox_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    let mut args = Vec::new();
+    if resource_limits.has_rlimits() {
+        #[expect(clippy::expect_used)]
+        let limits_json =
+            serde_json::to_string(&resource_limits).expect("Failed to serialize ResourceLimits");
+        args.push(format!("--resource-limits={limits_json}"));
+    }
+    args.extend(create_linux_sandbox_command_args(
+        command,
+        sandbox_policy,
+        sandbox_policy_cwd,
+    ));
     let arg0 = Some("codex-linux-sandbox");
     spawn_child_async(
         codex_linux_sandbox_exe.as_ref().to_path_buf(),
@@ -35,12 +50,13 @@ where
         sandbox_policy,
         stdio_policy,
         env,
+        ResourceLimits::default(),
     )
     .await
 }
 
 /// Converts the sandbox policy into the CLI invocation for `codex-linux-sandbox`.
-fn create_linux_sandbox_command_args(
+pub(crate) fn create_linux_sandbox_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
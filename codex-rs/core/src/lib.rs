@@ -7,14 +7,18 @@
 
 mod apply_patch;
 pub mod auth;
+mod background_jobs;
 pub mod bash;
+mod build_log_digest;
 mod chat_completions;
 mod client;
 mod client_common;
+mod code_symbols;
 pub mod codex;
 mod codex_conversation;
 pub mod token_data;
 pub use codex_conversation::CodexConversation;
+pub mod command_rules;
 mod command_safety;
 pub mod config;
 pub mod config_edit;
@@ -22,11 +26,13 @@ pub mod config_profile;
 pub mod config_types;
 mod conversation_history;
 pub mod custom_prompts;
+mod env_snapshot;
 mod environment_context;
 pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+mod exec_policy;
 mod flags;
 pub mod git_info;
 pub mod landlock;
@@ -35,6 +41,7 @@ mod mcp_tool_call;
 mod message_history;
 mod model_provider_info;
 pub mod parse_command;
+pub mod path_masks;
 mod truncate;
 mod unified_exec;
 mod user_instructions;
@@ -46,6 +53,7 @@ pub use model_provider_info::create_oss_provider_with_base_url;
 mod conversation_manager;
 mod event_mapping;
 pub mod review_format;
+mod rg_compact;
 pub use codex_protocol::protocol::InitialHistory;
 pub use conversation_manager::ConversationManager;
 pub use conversation_manager::NewConversation;
@@ -56,6 +64,8 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+mod output_rules;
+mod output_spill;
 pub mod plan_tool;
 pub mod project_doc;
 mod rollout;
@@ -65,7 +75,9 @@ pub mod shell;
 pub mod spawn;
 pub mod terminal;
 mod tool_apply_patch;
+mod tool_plan_reads;
 mod tool_read_code;
+mod transcript_compaction;
 pub mod turn_diff_tracker;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
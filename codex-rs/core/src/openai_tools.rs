@@ -5,6 +5,7 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
+use crate::background_jobs::BACKGROUND_JOB_TOOL_NAME;
 use crate::model_family::ModelFamily;
 use crate::plan_tool::PLAN_TOOL;
 use crate::tool_apply_patch::ApplyPatchToolType;
@@ -191,6 +192,18 @@ fn create_unified_exec_tool() -> OpenAiTool {
             ),
         },
     );
+    properties.insert(
+        "with_escalated_permissions".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Whether to request escalated permissions. Set to true if the session needs to run without sandbox restrictions; every later write to its stdin then needs approval too.".to_string()),
+        },
+    );
+    properties.insert(
+        "justification".to_string(),
+        JsonSchema::String {
+            description: Some("Only set if with_escalated_permissions is true. 1-sentence explanation of why we want to run this command.".to_string()),
+        },
+    );
 
     OpenAiTool::Function(ResponsesApiTool {
         name: "unified_exec".to_string(),
@@ -282,7 +295,7 @@ fn create_read_code_tool() -> OpenAiTool {
         "path".to_string(),
         JsonSchema::String {
             description: Some(
-                "File path relative to the workspace root (or absolute inside the workspace)."
+                "File path relative to the workspace root (or absolute inside the workspace), or an `output:` handle from a truncation notice."
                     .to_string(),
             ),
         },
@@ -290,14 +303,15 @@ fn create_read_code_tool() -> OpenAiTool {
     properties.insert(
         "lines".to_string(),
         JsonSchema::Array {
-            items: Box::new(JsonSchema::Number {
+            items: Box::new(JsonSchema::Array {
+                items: Box::new(JsonSchema::Number { description: None }),
                 description: Some(
-                    "Two-element array [start, end] specifying the inclusive 1-indexed line range."
+                    "Two-element array [start, end] specifying an inclusive 1-indexed line range."
                         .to_string(),
                 ),
             }),
             description: Some(
-                "Optional inclusive line range [start, end]; omit to read the file from the beginning."
+                "Optional list of up to 8 inclusive line ranges, e.g. [[1, 20], [120, 160]]; disjoint ranges are served in one response. Omit to read the file from the beginning."
                     .to_string(),
             ),
         },
@@ -323,7 +337,10 @@ fn create_read_code_tool() -> OpenAiTool {
     properties.insert(
         "symbol".to_string(),
         JsonSchema::String {
-            description: Some("Reserved for future symbol lookup support.".to_string()),
+            description: Some(
+                "Optional symbol to read instead of a line range, e.g. `Session::send_event` or `Widget.render` (Rust, TypeScript/JavaScript and Python)."
+                    .to_string(),
+            ),
         },
     );
 
@@ -339,6 +356,151 @@ fn create_read_code_tool() -> OpenAiTool {
         },
     })
 }
+
+fn create_plan_reads_tool() -> OpenAiTool {
+    let mut read_properties = BTreeMap::new();
+    read_properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some("File path relative to the workspace root.".to_string()),
+        },
+    );
+    read_properties.insert(
+        "lines".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::Array {
+                items: Box::new(JsonSchema::Number { description: None }),
+                description: None,
+            }),
+            description: Some(
+                "Optional inclusive 1-indexed [start, end] ranges. Omit together with `symbol` to plan the whole file."
+                    .to_string(),
+            ),
+        },
+    );
+    read_properties.insert(
+        "symbol".to_string(),
+        JsonSchema::String {
+            description: Some("Optional symbol to read instead of line ranges.".to_string()),
+        },
+    );
+
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "reads".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::Object {
+                properties: read_properties,
+                required: Some(vec!["path".to_string()]),
+                additional_properties: Some(false),
+            }),
+            description: Some("The read_code calls you intend to make, up to 16.".to_string()),
+        },
+    );
+    properties.insert(
+        "budget_bytes".to_string(),
+        JsonSchema::Number {
+            description: Some("Total bytes you expect the planned reads to return.".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: "plan_reads".to_string(),
+        description: "Declare the files, ranges and symbols you intend to read with read_code, plus a total byte budget, before reading. The plan is checked against the remaining per-turn budget and reads already shown in this session; rejected plans explain how to narrow them."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["reads".to_string(), "budget_bytes".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+fn create_request_relax_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "reason".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Why the default context budgets are insufficient for this turn.".to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: "request_relax".to_string(),
+        description: "Relax the context budgets for the rest of this turn: raises the per-turn tool output budget, loosens read_code caps and allows one repeat-command breaker bypass. Use sparingly, only after narrower reads failed."
+            .to_string(),
+        strict: true,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["reason".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
+fn create_background_job_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some("One of: start, status, tail, grep, kill.".to_string()),
+        },
+    );
+    properties.insert(
+        "command".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some("For start: the command to run in the background.".to_string()),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "For start: the working directory to run the command in.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "job_id".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "The job to act on. Required except for start; status lists every job when omitted."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "lines".to_string(),
+        JsonSchema::Number {
+            description: Some("For tail: how many trailing log lines to return.".to_string()),
+        },
+    );
+    properties.insert(
+        "pattern".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "For grep: regular expression matched against each log line.".to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: BACKGROUND_JOB_TOOL_NAME.to_string(),
+        description: "Run a long-lived process such as a dev server or file watcher in the background. start returns a job id; its output is logged to a file that status, tail and grep read. kill stops the job. Use shell for commands that finish on their own."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
 /// TODO(dylan): deprecate once we get rid of json tool
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ApplyPatchToolArgs {
@@ -583,6 +745,9 @@ pub(crate) fn get_openai_tools(
     }
 
     tools.push(create_read_code_tool());
+    tools.push(create_plan_reads_tool());
+    tools.push(create_request_relax_tool());
+    tools.push(create_background_job_tool());
 
     if config.web_search_request {
         tools.push(OpenAiTool::WebSearch {});
@@ -663,6 +828,9 @@ mod tests {
                 "unified_exec",
                 "update_plan",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "web_search",
                 "view_image",
             ],
@@ -689,6 +857,9 @@ mod tests {
                 "unified_exec",
                 "update_plan",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "web_search",
                 "view_image",
             ],
@@ -750,6 +921,9 @@ mod tests {
             &[
                 "unified_exec",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "web_search",
                 "view_image",
                 "test_server/do_something_cool",
@@ -757,7 +931,7 @@ mod tests {
         );
 
         assert_eq!(
-            tools[4],
+            tools[7],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "test_server/do_something_cool".to_string(),
                 parameters: JsonSchema::Object {
@@ -869,6 +1043,9 @@ mod tests {
             &[
                 "unified_exec",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "view_image",
                 "test_server/cool",
                 "test_server/do",
@@ -918,6 +1095,9 @@ mod tests {
             &[
                 "unified_exec",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "web_search",
                 "view_image",
                 "dash/search",
@@ -925,7 +1105,7 @@ mod tests {
         );
 
         assert_eq!(
-            tools[4],
+            tools[7],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/search".to_string(),
                 parameters: JsonSchema::Object {
@@ -983,13 +1163,16 @@ mod tests {
             &[
                 "unified_exec",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "web_search",
                 "view_image",
                 "dash/paginate",
             ],
         );
         assert_eq!(
-            tools[4],
+            tools[7],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/paginate".to_string(),
                 parameters: JsonSchema::Object {
@@ -1045,13 +1228,16 @@ mod tests {
             &[
                 "unified_exec",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "web_search",
                 "view_image",
                 "dash/tags",
             ],
         );
         assert_eq!(
-            tools[4],
+            tools[7],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/tags".to_string(),
                 parameters: JsonSchema::Object {
@@ -1110,13 +1296,16 @@ mod tests {
             &[
                 "unified_exec",
                 "read_code",
+                "plan_reads",
+                "request_relax",
+                "background_job",
                 "web_search",
                 "view_image",
                 "dash/value",
             ],
         );
         assert_eq!(
-            tools[4],
+            tools[7],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/value".to_string(),
                 parameters: JsonSchema::Object {
@@ -0,0 +1,388 @@
+//! Configurable output shaping for commands matched by
+//! `[[context_policy.output_rules]]`.
+//!
+//! A rule picks commands by program name, subcommand, or the classification
+//! `parse_command` gives them, and says how their output is cut down before
+//! it reaches the model: keep the head, the tail, both ends, only lines that
+//! look like errors, or collapse repeated lines. Output that already fits the
+//! rule's caps is left alone.
+
+use std::path::Path;
+
+use regex_lite::Regex;
+
+use crate::bash::parse_bash_lc_plain_commands;
+use crate::config_types::CommandKind;
+use crate::config_types::OutputRule;
+use crate::config_types::OutputStrategy;
+use crate::parse_command::ParsedCommand;
+use crate::parse_command::parse_command;
+
+/// The first rule in `rules` that matches `command`.
+pub(crate) fn output_rule_for<'a>(
+    rules: &'a [OutputRule],
+    command: &[String],
+) -> Option<&'a OutputRule> {
+    if rules.is_empty() {
+        return None;
+    }
+    let words = program_words(command);
+    let program = words
+        .as_ref()
+        .and_then(|words| words.first())
+        .and_then(|program| canonical_program_name(program));
+    let subcommand = words.as_ref().and_then(|words| {
+        words
+            .iter()
+            .skip(1)
+            .find(|word| !word.starts_with('-') && !word.starts_with('+'))
+    });
+    let kind = command_kind(command);
+
+    rules.iter().find(|rule| {
+        rule.program
+            .as_ref()
+            .is_none_or(|want| program.as_deref() == Some(want.as_str()))
+            && rule
+                .subcommand
+                .as_ref()
+                .is_none_or(|want| subcommand == Some(want))
+            && rule.kind.is_none_or(|want| kind == Some(want))
+    })
+}
+
+/// Cut `text` down according to `rule`. Returns `None` when the output is
+/// passed through unchanged.
+pub(crate) fn shape_output(rule: &OutputRule, text: &str) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let total_lines = lines.len();
+    let fits = total_lines <= rule.max_lines && text.len() <= rule.max_bytes;
+
+    let (kept, kept_lines, name) = match &rule.strategy {
+        OutputStrategy::Head => {
+            if fits {
+                return None;
+            }
+            let head = take_head(&lines, rule.max_lines, rule.max_bytes);
+            let count = head.len();
+            (head, count, "head")
+        }
+        OutputStrategy::Tail => {
+            if fits {
+                return None;
+            }
+            let tail = take_tail(&lines, rule.max_lines, rule.max_bytes);
+            let count = tail.len();
+            (tail, count, "tail")
+        }
+        OutputStrategy::HeadTail => {
+            if fits {
+                return None;
+            }
+            let head_lines = rule.max_lines.div_ceil(2);
+            let head_bytes = rule.max_bytes.div_ceil(2);
+            let mut kept = take_head(&lines, head_lines, head_bytes);
+            let rest = &lines[kept.len()..];
+            let tail = take_tail(
+                rest,
+                rule.max_lines.saturating_sub(kept.len()),
+                rule.max_bytes.saturating_sub(head_bytes),
+            );
+            let count = kept.len() + tail.len();
+            let omitted = total_lines - count;
+            if omitted > 0 {
+                kept.push(format!("[... {omitted} lines omitted ...]"));
+            }
+            kept.extend(tail);
+            (kept, count, "head_tail")
+        }
+        OutputStrategy::ErrorsOnly { pattern } => {
+            if fits {
+                return None;
+            }
+            let matching: Vec<&str> = match Regex::new(pattern) {
+                Ok(re) => lines
+                    .iter()
+                    .copied()
+                    .filter(|line| re.is_match(line))
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+            let kept = if matching.is_empty() {
+                take_tail(&lines, rule.max_lines, rule.max_bytes)
+            } else {
+                take_head(&matching, rule.max_lines, rule.max_bytes)
+            };
+            let count = kept.len();
+            (kept, count, "errors_only")
+        }
+        OutputStrategy::Dedupe => {
+            let collapsed = collapse_repeated_lines(&lines);
+            if collapsed.len() == total_lines && fits {
+                return None;
+            }
+            let collapsed: Vec<&str> = collapsed.iter().map(String::as_str).collect();
+            let kept = take_head(&collapsed, rule.max_lines, rule.max_bytes);
+            let count = kept
+                .iter()
+                .filter(|line| !line.starts_with(REPEAT_MARKER_PREFIX))
+                .count();
+            (kept, count, "dedupe")
+        }
+    };
+
+    let mut shaped = kept.join("\n");
+    if !shaped.is_empty() {
+        shaped.push('\n');
+    }
+    shaped.push_str(&format!(
+        "[output shaped by {name} rule: kept {kept_lines} of {total_lines} lines]"
+    ));
+    Some(shaped)
+}
+
+const REPEAT_MARKER_PREFIX: &str = "[previous line repeated ";
+
+/// The words of the command a rule's `program` and `subcommand` are checked
+/// against. `bash -lc` scripts are looked through when they run a single
+/// command, optionally after `cd`.
+fn program_words(command: &[String]) -> Option<Vec<String>> {
+    let Some(commands) = parse_bash_lc_plain_commands(command) else {
+        return Some(command.to_vec());
+    };
+    let mut rest = commands
+        .into_iter()
+        .filter(|cmd| cmd.first().is_none_or(|program| program != "cd"));
+    let only = rest.next()?;
+    rest.next().is_none().then_some(only)
+}
+
+fn canonical_program_name(program: &str) -> Option<String> {
+    let name = Path::new(program)
+        .file_name()?
+        .to_str()?
+        .to_ascii_lowercase();
+    Some(name.trim_end_matches(".exe").to_string())
+}
+
+/// The classification shared by every part of `command`, or `Unknown` when
+/// its parts disagree.
+fn command_kind(command: &[String]) -> Option<CommandKind> {
+    let kinds: Vec<CommandKind> = parse_command(command)
+        .iter()
+        .map(|parsed| match parsed {
+            ParsedCommand::Read { .. } => CommandKind::Read,
+            ParsedCommand::ListFiles { .. } => CommandKind::ListFiles,
+            ParsedCommand::Search { .. } => CommandKind::Search,
+            ParsedCommand::Unknown { .. } => CommandKind::Unknown,
+        })
+        .collect();
+    let first = *kinds.first()?;
+    if kinds.iter().all(|kind| *kind == first) {
+        Some(first)
+    } else {
+        Some(CommandKind::Unknown)
+    }
+}
+
+/// Leading lines of `lines` within both caps. A first line longer than
+/// `max_bytes` is cut at a char boundary rather than dropped.
+fn take_head(lines: &[&str], max_lines: usize, max_bytes: usize) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut used = 0;
+    for line in lines.iter().take(max_lines) {
+        let cost = line.len() + 1;
+        if used + cost > max_bytes {
+            if kept.is_empty() {
+                kept.push(prefix_at_char_boundary(line, max_bytes).to_string());
+            }
+            break;
+        }
+        used += cost;
+        kept.push((*line).to_string());
+    }
+    kept
+}
+
+/// Trailing lines of `lines` within both caps.
+fn take_tail(lines: &[&str], max_lines: usize, max_bytes: usize) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut used = 0;
+    for line in lines.iter().rev().take(max_lines) {
+        let cost = line.len() + 1;
+        if used + cost > max_bytes {
+            if kept.is_empty() {
+                kept.push(suffix_at_char_boundary(line, max_bytes).to_string());
+            }
+            break;
+        }
+        used += cost;
+        kept.push((*line).to_string());
+    }
+    kept.reverse();
+    kept
+}
+
+fn collapse_repeated_lines(lines: &[&str]) -> Vec<String> {
+    let mut collapsed = Vec::new();
+    let mut idx = 0;
+    while let Some(line) = lines.get(idx) {
+        let run = lines[idx..].iter().take_while(|next| *next == line).count();
+        collapsed.push((*line).to_string());
+        if run > 1 {
+            collapsed.push(format!("{REPEAT_MARKER_PREFIX}{} more times]", run - 1));
+        }
+        idx += run;
+    }
+    collapsed
+}
+
+fn prefix_at_char_boundary(text: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+fn suffix_at_char_boundary(text: &str, max_bytes: usize) -> &str {
+    let mut start = text.len().saturating_sub(max_bytes);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    &text[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_types::ContextPolicy;
+    use pretty_assertions::assert_eq;
+
+    fn rule(strategy: OutputStrategy, max_lines: usize, max_bytes: usize) -> OutputRule {
+        OutputRule {
+            program: None,
+            subcommand: None,
+            kind: None,
+            strategy,
+            max_lines,
+            max_bytes,
+        }
+    }
+
+    fn vec_str(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count).map(|n| format!("line {n}\n")).collect()
+    }
+
+    #[test]
+    fn matches_program_and_subcommand_through_bash_lc() {
+        let rules = vec![OutputRule {
+            program: Some("cargo".to_string()),
+            subcommand: Some("test".to_string()),
+            ..rule(OutputStrategy::Tail, 10, 1024)
+        }];
+
+        let direct = vec_str(&["/usr/bin/cargo", "+nightly", "test", "--all"]);
+        assert!(output_rule_for(&rules, &direct).is_some());
+
+        let wrapped = vec_str(&["bash", "-lc", "cd core && cargo test -p codex-core"]);
+        assert!(output_rule_for(&rules, &wrapped).is_some());
+
+        let build = vec_str(&["cargo", "build"]);
+        assert!(output_rule_for(&rules, &build).is_none());
+    }
+
+    #[test]
+    fn matches_parsed_command_kind() {
+        let rules = vec![OutputRule {
+            kind: Some(CommandKind::ListFiles),
+            ..rule(OutputStrategy::Head, 10, 1024)
+        }];
+
+        assert!(output_rule_for(&rules, &vec_str(&["ls", "-la"])).is_some());
+        assert!(output_rule_for(&rules, &vec_str(&["cat", "Cargo.toml"])).is_none());
+    }
+
+    #[test]
+    fn default_rules_cover_go_test() {
+        let policy = ContextPolicy::default();
+        let command = vec_str(&["go", "test", "./..."]);
+        let rule = output_rule_for(&policy.output_rules, &command).expect("go test rule");
+
+        let mut log = numbered_lines(300);
+        log.push_str("--- FAIL: TestParse (0.00s)\n    parse_test.go:42: got 1, want 2\nFAIL\n");
+        assert_eq!(
+            shape_output(rule, &log).as_deref(),
+            Some(
+                "--- FAIL: TestParse (0.00s)\n    parse_test.go:42: got 1, want 2\nFAIL\n\
+                 [output shaped by errors_only rule: kept 3 of 303 lines]"
+            )
+        );
+    }
+
+    #[test]
+    fn output_within_caps_is_left_alone() {
+        let text = numbered_lines(3);
+        for strategy in [
+            OutputStrategy::Head,
+            OutputStrategy::Tail,
+            OutputStrategy::HeadTail,
+            OutputStrategy::Dedupe,
+        ] {
+            assert_eq!(shape_output(&rule(strategy, 10, 1024), &text), None);
+        }
+    }
+
+    #[test]
+    fn head_tail_keeps_both_ends() {
+        let shaped = shape_output(
+            &rule(OutputStrategy::HeadTail, 4, 1024),
+            &numbered_lines(10),
+        );
+        assert_eq!(
+            shaped.as_deref(),
+            Some(
+                "line 1\nline 2\n[... 6 lines omitted ...]\nline 9\nline 10\n\
+                 [output shaped by head_tail rule: kept 4 of 10 lines]"
+            )
+        );
+    }
+
+    #[test]
+    fn tail_respects_byte_cap() {
+        let shaped = shape_output(&rule(OutputStrategy::Tail, 100, 16), &numbered_lines(10));
+        assert_eq!(
+            shaped.as_deref(),
+            Some("line 9\nline 10\n[output shaped by tail rule: kept 2 of 10 lines]")
+        );
+    }
+
+    #[test]
+    fn errors_only_falls_back_to_tail_without_matches() {
+        let strategy = OutputStrategy::ErrorsOnly {
+            pattern: "error".to_string(),
+        };
+        let shaped = shape_output(&rule(strategy, 1, 1024), &numbered_lines(5));
+        assert_eq!(
+            shaped.as_deref(),
+            Some("line 5\n[output shaped by errors_only rule: kept 1 of 5 lines]")
+        );
+    }
+
+    #[test]
+    fn dedupe_collapses_runs_of_identical_lines() {
+        let text = "Compiling a\nwaiting\nwaiting\nwaiting\ndone\n";
+        let shaped = shape_output(&rule(OutputStrategy::Dedupe, 100, 1024), text);
+        assert_eq!(
+            shaped.as_deref(),
+            Some(
+                "Compiling a\nwaiting\n[previous line repeated 2 more times]\ndone\n\
+                 [output shaped by dedupe rule: kept 3 of 5 lines]"
+            )
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Untruncated command output kept on disk.
+//!
+//! Output that is cut down before it reaches the model is written whole to a
+//! per-session artifacts directory first. The truncation notice names it by a
+//! handle such as `output:call_42`, which `read_code` accepts in place of a
+//! path, so the model can page through the rest without re-running the
+//! command.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Directory under `codex_home` that holds spilled output, one subdirectory
+/// per conversation.
+pub(crate) const OUTPUT_ARTIFACTS_SUBDIR: &str = "output_artifacts";
+
+const OUTPUT_HANDLE_PREFIX: &str = "output:";
+
+/// Write `output` for `call_id` under `dir` and return the handle that
+/// refers to it.
+pub(crate) async fn spill_output(dir: &Path, call_id: &str, output: &str) -> io::Result<String> {
+    tokio::fs::create_dir_all(dir).await?;
+    let id: String = call_id
+        .chars()
+        .map(|c| if is_id_char(c) { c } else { '_' })
+        .collect();
+    tokio::fs::write(artifact_path(dir, &id), output).await?;
+    Ok(format!("{OUTPUT_HANDLE_PREFIX}{id}"))
+}
+
+pub(crate) fn output_spill_notice(handle: &str, total_lines: usize) -> String {
+    format!("[full output ({total_lines} lines) saved as {handle}; page through it with read_code]")
+}
+
+/// The file under `dir` that `path` names when it is an output handle.
+/// Returns `None` for anything else, including handles that could escape
+/// `dir`.
+pub(crate) fn resolve_output_handle(dir: &Path, path: &str) -> Option<PathBuf> {
+    let id = path.strip_prefix(OUTPUT_HANDLE_PREFIX)?;
+    if id.is_empty() || !id.chars().all(is_id_char) {
+        return None;
+    }
+    Some(artifact_path(dir, id))
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+fn artifact_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{id}.log"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn spills_output_under_sanitized_call_id() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let handle = spill_output(dir.path(), "call/1", "full log\n")
+            .await
+            .expect("spill");
+        assert_eq!(handle, "output:call_1");
+
+        let path = resolve_output_handle(dir.path(), &handle).expect("handle resolves");
+        assert_eq!(path, dir.path().join("call_1.log"));
+        assert_eq!(std::fs::read_to_string(&path).expect("read"), "full log\n");
+    }
+
+    #[test]
+    fn rejects_paths_and_malformed_handles() {
+        let dir = Path::new("/artifacts");
+        assert_eq!(resolve_output_handle(dir, "src/lib.rs"), None);
+        assert_eq!(resolve_output_handle(dir, "output:"), None);
+        assert_eq!(resolve_output_handle(dir, "output:../secrets"), None);
+    }
+}
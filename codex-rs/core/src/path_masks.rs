@@ -0,0 +1,257 @@
+//! `read_only_subpaths` and `deny_read_paths` of the workspace-write sandbox.
+//!
+//! Relative entries apply under every writable root and absolute entries
+//! name a single path. Each path component may use `*` and `?`, and a match
+//! covers everything beneath it, so `secrets` masks `secrets/prod/key.pem`
+//! and `.env*` masks both `.env` and `.env.local`.
+
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use wildmatch::WildMatchPattern;
+
+use crate::protocol::SandboxPolicy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathMaskKind {
+    /// Readable, but not writable.
+    ReadOnly,
+    /// Neither readable nor writable.
+    DenyRead,
+}
+
+/// A masked path that exists on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskedPath {
+    pub path: PathBuf,
+    pub kind: PathMaskKind,
+}
+
+/// The strictest mask of `policy` that covers `path`, which must be absolute
+/// and normalized.
+pub fn masked_path_kind(policy: &SandboxPolicy, cwd: &Path, path: &Path) -> Option<PathMaskKind> {
+    mask_patterns(policy, cwd)
+        .into_iter()
+        .filter(|(pattern, _)| pattern_covers(pattern, path))
+        .map(|(_, kind)| kind)
+        .max()
+}
+
+/// Whether `path`, absolute and normalized, is masked by `policy` or is a
+/// directory that may hold a masked path.
+pub fn path_touches_mask(policy: &SandboxPolicy, cwd: &Path, path: &Path) -> bool {
+    mask_patterns(policy, cwd)
+        .iter()
+        .any(|(pattern, _)| pattern_covers(pattern, path) || pattern_lies_under(pattern, path))
+}
+
+/// The paths on disk that `policy` masks right now. A path masked both ways
+/// is reported once, as [`PathMaskKind::DenyRead`].
+pub fn resolve_masked_paths(policy: &SandboxPolicy, cwd: &Path) -> Vec<MaskedPath> {
+    let mut masked: Vec<MaskedPath> = Vec::new();
+    for (pattern, kind) in mask_patterns(policy, cwd) {
+        for path in expand_pattern(&pattern) {
+            match masked.iter_mut().find(|existing| existing.path == path) {
+                Some(existing) => existing.kind = existing.kind.max(kind),
+                None => masked.push(MaskedPath { path, kind }),
+            }
+        }
+    }
+    masked
+}
+
+/// Each mask entry of `policy` as an absolute pattern.
+fn mask_patterns(policy: &SandboxPolicy, cwd: &Path) -> Vec<(PathBuf, PathMaskKind)> {
+    let SandboxPolicy::WorkspaceWrite {
+        read_only_subpaths,
+        deny_read_paths,
+        ..
+    } = policy
+    else {
+        return Vec::new();
+    };
+    if read_only_subpaths.is_empty() && deny_read_paths.is_empty() {
+        return Vec::new();
+    }
+
+    let roots = policy.get_writable_roots_with_cwd(cwd);
+    let entries = read_only_subpaths
+        .iter()
+        .map(|entry| (entry, PathMaskKind::ReadOnly))
+        .chain(
+            deny_read_paths
+                .iter()
+                .map(|entry| (entry, PathMaskKind::DenyRead)),
+        );
+    let mut patterns = Vec::new();
+    for (entry, kind) in entries {
+        let entry = Path::new(entry);
+        if entry.is_absolute() {
+            patterns.push((entry.to_path_buf(), kind));
+        } else {
+            patterns.extend(roots.iter().map(|root| (root.root.join(entry), kind)));
+        }
+    }
+    patterns
+}
+
+fn pattern_covers(pattern: &Path, path: &Path) -> bool {
+    let mut path_components = path.components();
+    pattern.components().all(|pattern_component| {
+        path_components
+            .next()
+            .is_some_and(|component| component_matches(pattern_component, component))
+    })
+}
+
+fn pattern_lies_under(pattern: &Path, dir: &Path) -> bool {
+    let mut pattern_components = pattern.components();
+    dir.components().all(|component| {
+        pattern_components
+            .next()
+            .is_some_and(|pattern_component| component_matches(pattern_component, component))
+    })
+}
+
+fn component_matches(pattern: Component<'_>, component: Component<'_>) -> bool {
+    match (pattern, component) {
+        (Component::Normal(pattern), Component::Normal(name)) => {
+            WildMatchPattern::<'*', '?'>::new(&pattern.to_string_lossy())
+                .matches(&name.to_string_lossy())
+        }
+        (pattern, component) => pattern == component,
+    }
+}
+
+/// The existing paths matching `pattern`, expanding globs one component at
+/// a time.
+fn expand_pattern(pattern: &Path) -> Vec<PathBuf> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let Component::Normal(name) = component else {
+            for path in &mut matches {
+                path.push(component.as_os_str());
+            }
+            continue;
+        };
+        let name = name.to_string_lossy();
+        if !name.contains(['*', '?']) {
+            for path in &mut matches {
+                path.push(name.as_ref());
+            }
+            continue;
+        }
+        let glob = WildMatchPattern::<'*', '?'>::new(&name);
+        matches = matches
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flat_map(Iterator::flatten)
+            .filter(|entry| glob.matches(&entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .collect();
+    }
+    matches.retain(|path| path.symlink_metadata().is_ok());
+    matches.sort();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn policy(read_only: &[&str], deny_read: &[&str]) -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            read_only_subpaths: read_only.iter().map(ToString::to_string).collect(),
+            deny_read_paths: deny_read.iter().map(ToString::to_string).collect(),
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    #[test]
+    fn relative_masks_apply_under_writable_roots() {
+        let cwd = Path::new("/repo");
+        let policy = policy(&[".git/hooks", ".github/workflows"], &[".env*", "secrets/"]);
+
+        assert_eq!(
+            masked_path_kind(&policy, cwd, Path::new("/repo/.git/hooks/pre-commit")),
+            Some(PathMaskKind::ReadOnly)
+        );
+        assert_eq!(
+            masked_path_kind(&policy, cwd, Path::new("/repo/.env.local")),
+            Some(PathMaskKind::DenyRead)
+        );
+        assert_eq!(
+            masked_path_kind(&policy, cwd, Path::new("/repo/secrets/prod/key.pem")),
+            Some(PathMaskKind::DenyRead)
+        );
+        assert_eq!(
+            masked_path_kind(&policy, cwd, Path::new("/repo/.git/config")),
+            None
+        );
+        assert_eq!(
+            masked_path_kind(&policy, cwd, Path::new("/repo/src/.env")),
+            None
+        );
+        assert_eq!(
+            masked_path_kind(&policy, cwd, Path::new("/elsewhere/.env")),
+            None
+        );
+    }
+
+    #[test]
+    fn directories_above_a_mask_touch_it() {
+        let cwd = Path::new("/repo");
+        let policy = policy(&[], &[".env*", "config/secrets"]);
+
+        assert!(path_touches_mask(&policy, cwd, Path::new("/repo")));
+        assert!(path_touches_mask(&policy, cwd, Path::new("/repo/config")));
+        assert!(path_touches_mask(&policy, cwd, Path::new("/repo/.env")));
+        assert!(path_touches_mask(
+            &policy,
+            cwd,
+            Path::new("/repo/config/secrets/key.pem")
+        ));
+        assert!(!path_touches_mask(&policy, cwd, Path::new("/repo/src")));
+        assert!(!path_touches_mask(
+            &policy,
+            cwd,
+            Path::new("/repo/config/app.toml")
+        ));
+    }
+
+    #[test]
+    fn resolves_existing_matches_with_the_stricter_kind() {
+        let tmp = TempDir::new().expect("tempdir");
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("secrets")).expect("mkdir");
+        std::fs::write(root.join(".env"), "A=1").expect("write");
+        std::fs::write(root.join(".env.local"), "B=2").expect("write");
+        std::fs::write(root.join("main.rs"), "").expect("write");
+        let policy = policy(&["secrets", ".git/hooks"], &[".env*", "secrets"]);
+
+        assert_eq!(
+            resolve_masked_paths(&policy, root),
+            vec![
+                MaskedPath {
+                    path: root.join("secrets"),
+                    kind: PathMaskKind::DenyRead,
+                },
+                MaskedPath {
+                    path: root.join(".env"),
+                    kind: PathMaskKind::DenyRead,
+                },
+                MaskedPath {
+                    path: root.join(".env.local"),
+                    kind: PathMaskKind::DenyRead,
+                },
+            ]
+        );
+    }
+}
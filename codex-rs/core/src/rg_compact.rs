@@ -0,0 +1,528 @@
+//! Compact, grouped rendering of `rg` search results.
+//!
+//! Raw `rg` output repeats the path on every match and is cut off at the rg
+//! byte cap, so a broad search shows the first few files and nothing else.
+//! For plain content searches we instead group matches by file and emit one
+//! JSON line per file with its match count and line numbers, plus one-line
+//! previews while they fit. Matches already reported by an earlier search in
+//! the same turn are left out.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::parse_command::ParsedCommand;
+use crate::parse_command::parse_command;
+
+/// Line numbers listed per file; the count still covers every match.
+const MAX_LINES_PER_FILE: usize = 50;
+const MAX_PREVIEW_CHARS: usize = 100;
+/// Prefix of the notice exec appends when raw rg output hits its cap.
+const RG_TRUNCATION_NOTICE_PREFIX: &str = "[rg output truncated";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RgOutputFormat {
+    /// `--json` event stream.
+    Json,
+    /// `path:line:text`, or `path:line:column:text` when `column` is set.
+    LineNumbers { column: bool },
+    /// `path:text`.
+    PathsOnly,
+}
+
+/// Matches reported by earlier searches in the turn, keyed by path and line
+/// (or path and text when line numbers are unavailable).
+#[derive(Debug, Default)]
+pub(crate) struct SeenSearchMatches {
+    keys: HashSet<String>,
+}
+
+#[derive(Debug)]
+struct FileMatches {
+    path: String,
+    matches: Vec<SearchMatch>,
+}
+
+#[derive(Debug)]
+struct SearchMatch {
+    line: Option<usize>,
+    text: String,
+}
+
+impl SearchMatch {
+    fn key(&self, path: &str) -> String {
+        match self.line {
+            Some(line) => format!("{path}:{line}"),
+            None => format!("{path}\n{}", self.text),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Summary {
+    rg: &'static str,
+    files: usize,
+    matches: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    already_shown: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    omitted_files: usize,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    input_truncated: bool,
+}
+
+#[derive(Serialize)]
+struct FileLine<'a> {
+    path: &'a str,
+    count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    lines: Vec<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview: Option<Vec<String>>,
+}
+
+// `skip_serializing_if` passes the field by reference.
+#[expect(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// How the output of `command` can be grouped, or `None` when it is not a
+/// plain `rg` content search (file listings, counts, context lines, ...).
+pub(crate) fn rg_output_format(command: &[String]) -> Option<RgOutputFormat> {
+    let parsed = parse_command(command);
+    let [
+        ParsedCommand::Search {
+            cmd,
+            query: Some(_),
+            ..
+        },
+    ] = parsed.as_slice()
+    else {
+        return None;
+    };
+    let argv = shlex::split(cmd)?;
+    if argv.first().map(String::as_str) != Some("rg") {
+        return None;
+    }
+
+    let mut json = false;
+    let mut line_numbers = false;
+    let mut column = false;
+    for arg in argv.iter().skip(1) {
+        if arg == "--" {
+            break;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            let name = long.split('=').next().unwrap_or_default();
+            match name {
+                "json" => json = true,
+                "line-number" => line_numbers = true,
+                "no-line-number" => line_numbers = false,
+                "column" | "vimgrep" => {
+                    line_numbers = true;
+                    column = true;
+                }
+                "count"
+                | "count-matches"
+                | "files"
+                | "files-with-matches"
+                | "files-without-match"
+                | "context"
+                | "after-context"
+                | "before-context"
+                | "heading"
+                | "pretty"
+                | "passthru"
+                | "stats"
+                | "null"
+                | "only-matching"
+                | "replace"
+                | "type-list"
+                | "version"
+                | "help" => return None,
+                _ => {}
+            }
+        } else if let Some(cluster) = arg.strip_prefix('-') {
+            for flag in cluster.chars() {
+                match flag {
+                    'n' => line_numbers = true,
+                    'N' => line_numbers = false,
+                    'c' | 'l' | 'A' | 'B' | 'C' | 'p' | 'o' | 'r' | '0' | 'h' | 'V' => {
+                        return None;
+                    }
+                    // The rest of the cluster is this flag's value.
+                    'e' | 'f' | 'g' | 't' | 'T' | 'm' | 'j' | 'M' | 'E' | 'd' => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Some(if json {
+        RgOutputFormat::Json
+    } else if line_numbers {
+        RgOutputFormat::LineNumbers { column }
+    } else {
+        RgOutputFormat::PathsOnly
+    })
+}
+
+/// Group `output` by file and render it as compact JSON lines within
+/// `max_bytes`. Returns `None` when the output does not parse, so the caller
+/// can fall back to the raw text.
+pub(crate) fn compact_rg_output(
+    format: RgOutputFormat,
+    output: &str,
+    cwd: &Path,
+    seen: &mut SeenSearchMatches,
+    max_bytes: usize,
+) -> Option<String> {
+    let (body, input_truncated) = match output.rfind(RG_TRUNCATION_NOTICE_PREFIX) {
+        Some(idx) if !output[idx..].trim_end().contains('\n') => (&output[..idx], true),
+        _ => (output, false),
+    };
+    let files = match format {
+        RgOutputFormat::Json => parse_json(body)?,
+        RgOutputFormat::LineNumbers { column } => parse_lines(body, true, column)?,
+        RgOutputFormat::PathsOnly => {
+            let files = parse_lines(body, false, false)?;
+            // Without line numbers a single-file search prints bare lines, which
+            // parse as bogus paths; only trust paths that exist.
+            if !files.iter().all(|file| cwd.join(&file.path).exists()) {
+                return None;
+            }
+            files
+        }
+    };
+
+    let mut already_shown = 0usize;
+    let mut fresh: Vec<FileMatches> = Vec::new();
+    for file in files {
+        let total = file.matches.len();
+        let matches: Vec<SearchMatch> = file
+            .matches
+            .into_iter()
+            .filter(|m| !seen.keys.contains(&m.key(&file.path)))
+            .collect();
+        already_shown += total - matches.len();
+        if !matches.is_empty() {
+            fresh.push(FileMatches {
+                path: file.path,
+                matches,
+            });
+        }
+    }
+
+    let rendered = render(&fresh, already_shown, input_truncated, max_bytes);
+    for file in fresh.iter().take(rendered.files_included) {
+        for m in &file.matches {
+            seen.keys.insert(m.key(&file.path));
+        }
+    }
+    Some(rendered.text)
+}
+
+struct Rendered {
+    text: String,
+    files_included: usize,
+}
+
+fn render(
+    files: &[FileMatches],
+    already_shown: usize,
+    input_truncated: bool,
+    max_bytes: usize,
+) -> Rendered {
+    let file_lines = |with_preview: bool| -> Vec<String> {
+        files
+            .iter()
+            .map(|file| render_file(file, with_preview))
+            .collect()
+    };
+    let summary = |omitted_files: usize| {
+        let summary = Summary {
+            rg: "compact",
+            files: files.len(),
+            matches: files.iter().map(|f| f.matches.len()).sum(),
+            already_shown,
+            omitted_files,
+            input_truncated,
+        };
+        serde_json::to_string(&summary).unwrap_or_default()
+    };
+
+    let with_preview = file_lines(true);
+    let lines = if summary(0).len() + total_len(&with_preview) <= max_bytes {
+        with_preview
+    } else {
+        file_lines(false)
+    };
+
+    // Keep whole files while they fit; the summary reports what was dropped.
+    let mut included = lines.len();
+    while included > 0
+        && summary(lines.len() - included).len() + total_len(&lines[..included]) > max_bytes
+    {
+        included -= 1;
+    }
+
+    let mut text = summary(lines.len() - included);
+    text.push('\n');
+    for line in &lines[..included] {
+        text.push_str(line);
+        text.push('\n');
+    }
+    Rendered {
+        text,
+        files_included: included,
+    }
+}
+
+fn total_len(lines: &[String]) -> usize {
+    lines.iter().map(|line| line.len() + 1).sum::<usize>() + 1
+}
+
+fn render_file(file: &FileMatches, with_preview: bool) -> String {
+    let listed = &file.matches[..file.matches.len().min(MAX_LINES_PER_FILE)];
+    let line = FileLine {
+        path: &file.path,
+        count: file.matches.len(),
+        lines: listed.iter().filter_map(|m| m.line).collect(),
+        preview: with_preview.then(|| listed.iter().map(|m| preview(&m.text)).collect()),
+    };
+    serde_json::to_string(&line).unwrap_or_default()
+}
+
+fn preview(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(MAX_PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn push_match(files: &mut Vec<FileMatches>, path: &str, m: SearchMatch) {
+    match files.last_mut() {
+        Some(file) if file.path == path => file.matches.push(m),
+        _ => files.push(FileMatches {
+            path: path.to_string(),
+            matches: vec![m],
+        }),
+    }
+}
+
+fn parse_lines(output: &str, line_numbers: bool, column: bool) -> Option<Vec<FileMatches>> {
+    let mut files = Vec::new();
+    for raw in output.lines() {
+        if raw.is_empty() {
+            continue;
+        }
+        let (path, rest) = raw.split_once(':')?;
+        if path.is_empty() {
+            return None;
+        }
+        let m = if line_numbers {
+            let (line, rest) = rest.split_once(':')?;
+            let line = line.parse().ok()?;
+            let text = if column {
+                let (col, text) = rest.split_once(':')?;
+                col.parse::<usize>().ok()?;
+                text
+            } else {
+                rest
+            };
+            SearchMatch {
+                line: Some(line),
+                text: text.to_string(),
+            }
+        } else {
+            SearchMatch {
+                line: None,
+                text: rest.to_string(),
+            }
+        };
+        push_match(&mut files, path, m);
+    }
+    Some(files)
+}
+
+fn parse_json(output: &str) -> Option<Vec<FileMatches>> {
+    let mut files = Vec::new();
+    for raw in output.lines() {
+        if raw.trim().is_empty() {
+            continue;
+        }
+        let event: Value = serde_json::from_str(raw).ok()?;
+        if event.get("type").and_then(Value::as_str) != Some("match") {
+            continue;
+        }
+        let data = event.get("data")?;
+        let path = data.pointer("/path/text").and_then(Value::as_str)?;
+        let line = data
+            .get("line_number")
+            .and_then(Value::as_u64)
+            .map(|n| n as usize);
+        let text = data
+            .pointer("/lines/text")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        push_match(
+            &mut files,
+            path,
+            SearchMatch {
+                line,
+                text: text.to_string(),
+            },
+        );
+    }
+    Some(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn cmd(script: &str) -> Vec<String> {
+        vec!["bash".to_string(), "-lc".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn recognizes_plain_content_searches() {
+        assert_eq!(
+            rg_output_format(&cmd("rg -n needle src")),
+            Some(RgOutputFormat::LineNumbers { column: false })
+        );
+        assert_eq!(
+            rg_output_format(&cmd("rg --vimgrep needle")),
+            Some(RgOutputFormat::LineNumbers { column: true })
+        );
+        assert_eq!(
+            rg_output_format(&cmd("rg --json needle")),
+            Some(RgOutputFormat::Json)
+        );
+        assert_eq!(
+            rg_output_format(&["rg".to_string(), "needle".to_string()]),
+            Some(RgOutputFormat::PathsOnly)
+        );
+    }
+
+    #[test]
+    fn leaves_listings_counts_and_context_alone() {
+        assert_eq!(rg_output_format(&cmd("rg --files src")), None);
+        assert_eq!(rg_output_format(&cmd("rg -c needle")), None);
+        assert_eq!(rg_output_format(&cmd("rg -n -C2 needle")), None);
+        assert_eq!(rg_output_format(&cmd("rg -ln needle")), None);
+        assert_eq!(rg_output_format(&cmd("grep -n needle src")), None);
+    }
+
+    #[test]
+    fn groups_matches_by_file() {
+        let output =
+            "src/a.rs:3:fn needle() {}\nsrc/a.rs:9:    needle();\nsrc/b.rs:1:use needle;\n";
+        let mut seen = SeenSearchMatches::default();
+        let compact = compact_rg_output(
+            RgOutputFormat::LineNumbers { column: false },
+            output,
+            Path::new("/"),
+            &mut seen,
+            8 * 1024,
+        )
+        .expect("compact output");
+        assert_eq!(
+            compact,
+            "{\"rg\":\"compact\",\"files\":2,\"matches\":3}\n\
+             {\"path\":\"src/a.rs\",\"count\":2,\"lines\":[3,9],\"preview\":[\"fn needle() {}\",\"needle();\"]}\n\
+             {\"path\":\"src/b.rs\",\"count\":1,\"lines\":[1],\"preview\":[\"use needle;\"]}\n"
+        );
+    }
+
+    #[test]
+    fn omits_matches_shown_earlier_in_the_turn() {
+        let format = RgOutputFormat::LineNumbers { column: false };
+        let mut seen = SeenSearchMatches::default();
+        compact_rg_output(format, "src/a.rs:3:x\n", Path::new("/"), &mut seen, 1024)
+            .expect("first search");
+
+        let compact = compact_rg_output(
+            format,
+            "src/a.rs:3:x\nsrc/a.rs:4:y\n",
+            Path::new("/"),
+            &mut seen,
+            1024,
+        )
+        .expect("second search");
+        assert_eq!(
+            compact,
+            "{\"rg\":\"compact\",\"files\":1,\"matches\":1,\"already_shown\":1}\n\
+             {\"path\":\"src/a.rs\",\"count\":1,\"lines\":[4],\"preview\":[\"y\"]}\n"
+        );
+    }
+
+    #[test]
+    fn drops_previews_then_files_to_fit() {
+        let output: String = (0..40)
+            .map(|n| format!("src/file{n}.rs:{n}:{}\n", "x".repeat(60)))
+            .collect();
+        let mut seen = SeenSearchMatches::default();
+        let compact = compact_rg_output(
+            RgOutputFormat::LineNumbers { column: false },
+            &output,
+            Path::new("/"),
+            &mut seen,
+            512,
+        )
+        .expect("compact output");
+        assert!(compact.len() <= 512, "{compact}");
+        assert!(!compact.contains("preview"));
+        assert!(compact.contains("\"omitted_files\":"));
+        // Omitted files stay eligible for later searches.
+        assert!(!seen.keys.contains("src/file39.rs:39"));
+    }
+
+    #[test]
+    fn parses_json_events() {
+        let output = r#"{"type":"begin","data":{"path":{"text":"src/a.rs"}}}
+{"type":"match","data":{"path":{"text":"src/a.rs"},"lines":{"text":"let needle = 1;\n"},"line_number":7,"absolute_offset":0,"submatches":[]}}
+{"type":"end","data":{"path":{"text":"src/a.rs"}}}
+"#;
+        let mut seen = SeenSearchMatches::default();
+        let compact = compact_rg_output(
+            RgOutputFormat::Json,
+            output,
+            Path::new("/"),
+            &mut seen,
+            1024,
+        )
+        .expect("compact output");
+        assert!(compact.contains("{\"path\":\"src/a.rs\",\"count\":1,\"lines\":[7]"));
+    }
+
+    #[test]
+    fn rejects_unparseable_output() {
+        let mut seen = SeenSearchMatches::default();
+        assert_eq!(
+            compact_rg_output(
+                RgOutputFormat::LineNumbers { column: false },
+                "src/a.rs-3-context line\n",
+                Path::new("/"),
+                &mut seen,
+                1024,
+            ),
+            None
+        );
+        assert_eq!(
+            compact_rg_output(
+                RgOutputFormat::PathsOnly,
+                "does/not/exist.rs:match\n",
+                Path::new("/"),
+                &mut seen,
+                1024,
+            ),
+            None
+        );
+    }
+}
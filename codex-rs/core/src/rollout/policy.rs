@@ -15,6 +15,17 @@ pub(crate) fn is_persisted_response_item(item: &RolloutItem) -> bool {
     }
 }
 
+/// Whether `item` is the begin or end event of an exec call. These are
+/// persisted only when `record_exec_results` is enabled, since the end event
+/// carries the command's full output.
+#[inline]
+pub(crate) fn is_exec_result(item: &RolloutItem) -> bool {
+    matches!(
+        item,
+        RolloutItem::EventMsg(EventMsg::ExecCommandBegin(_) | EventMsg::ExecCommandEnd(_))
+    )
+}
+
 /// Whether a `ResponseItem` should be persisted in rollout files.
 #[inline]
 pub(crate) fn should_persist_response_item(item: &ResponseItem) -> bool {
@@ -42,6 +53,8 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::TokenCount(_)
         | EventMsg::EnteredReviewMode(_)
         | EventMsg::ExitedReviewMode(_)
+        | EventMsg::RelaxGranted(_)
+        | EventMsg::TurnMetrics(_)
         | EventMsg::TurnAborted(_) => true,
         EventMsg::Error(_)
         | EventMsg::TaskStarted(_)
@@ -70,6 +83,7 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::ListCustomPromptsResponse(_)
         | EventMsg::PlanUpdate(_)
         | EventMsg::ShutdownComplete
-        | EventMsg::ConversationPath(_) => false,
+        | EventMsg::ConversationPath(_)
+        | EventMsg::BackgroundJobs(_) => false,
     }
 }
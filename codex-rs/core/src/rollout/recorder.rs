@@ -22,6 +22,7 @@ use super::SESSIONS_SUBDIR;
 use super::list::ConversationsPage;
 use super::list::Cursor;
 use super::list::get_conversations;
+use super::policy::is_exec_result;
 use super::policy::is_persisted_response_item;
 use crate::config::Config;
 use crate::default_client::originator;
@@ -46,6 +47,7 @@ use codex_protocol::protocol::SessionMetaLine;
 pub struct RolloutRecorder {
     tx: Sender<RolloutCmd>,
     pub(crate) rollout_path: PathBuf,
+    record_exec_results: bool,
 }
 
 #[derive(Clone)]
@@ -151,9 +153,14 @@ impl RolloutRecorder {
         // Spawn a Tokio task that owns the file handle and performs async
         // writes. Using `tokio::fs::File` keeps everything on the async I/O
         // driver instead of blocking the runtime.
-        tokio::task::spawn(rollout_writer(file, rx, meta, cwd));
-
-        Ok(Self { tx, rollout_path })
+        let record_exec_results = config.record_exec_results;
+        tokio::task::spawn(rollout_writer(file, rx, meta, cwd, record_exec_results));
+
+        Ok(Self {
+            tx,
+            rollout_path,
+            record_exec_results,
+        })
     }
 
     pub(crate) async fn record_items(&self, items: &[RolloutItem]) -> std::io::Result<()> {
@@ -162,7 +169,7 @@ impl RolloutRecorder {
             // Note that function calls may look a bit strange if they are
             // "fully qualified MCP tool calls," so we could consider
             // reformatting them in that case.
-            if is_persisted_response_item(item) {
+            if should_write(item, self.record_exec_results) {
                 filtered.push(item.clone());
             }
         }
@@ -186,7 +193,7 @@ impl RolloutRecorder {
             .map_err(|e| IoError::other(format!("failed waiting for rollout flush: {e}")))
     }
 
-    pub(crate) async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
+    pub async fn get_rollout_history(path: &Path) -> std::io::Result<InitialHistory> {
         info!("Resuming rollout from {path:?}");
         let text = tokio::fs::read_to_string(path).await?;
         if text.trim().is_empty() {
@@ -329,11 +336,16 @@ fn create_log_file(
     })
 }
 
+fn should_write(item: &RolloutItem, record_exec_results: bool) -> bool {
+    is_persisted_response_item(item) || (record_exec_results && is_exec_result(item))
+}
+
 async fn rollout_writer(
     file: tokio::fs::File,
     mut rx: mpsc::Receiver<RolloutCmd>,
     mut meta: Option<SessionMeta>,
     cwd: std::path::PathBuf,
+    record_exec_results: bool,
 ) -> std::io::Result<()> {
     let mut writer = JsonlWriter { file };
 
@@ -356,7 +368,7 @@ async fn rollout_writer(
         match cmd {
             RolloutCmd::AddItems(items) => {
                 for item in items {
-                    if is_persisted_response_item(&item) {
+                    if should_write(&item, record_exec_results) {
                         writer.write_rollout_item(item).await?;
                     }
                 }
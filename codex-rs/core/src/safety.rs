@@ -5,8 +5,16 @@ use std::path::PathBuf;
 
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
+use codex_protocol::config_types::CommandRuleDecision;
 
+use crate::command_rules::CommandRules;
+use crate::command_rules::describe_rule;
 use crate::exec::SandboxType;
+use crate::exec_policy::ExecPolicy;
+use crate::exec_policy::ExecPolicyVerdict;
+use crate::path_masks::PathMaskKind;
+use crate::path_masks::masked_path_kind;
+use crate::path_masks::path_touches_mask;
 
 use crate::command_safety::is_dangerous_command::command_might_be_dangerous;
 use crate::command_safety::is_safe_command::is_known_safe_command;
@@ -19,7 +27,10 @@ pub enum SafetyCheck {
         sandbox_type: SandboxType,
         user_explicitly_approved: bool,
     },
-    AskUser,
+    AskUser {
+        /// Why the command needs approval, shown in the approval prompt.
+        reason: Option<String>,
+    },
     Reject {
         reason: String,
     },
@@ -37,6 +48,19 @@ pub fn assess_patch_safety(
         };
     }
 
+    if let Some((path, kind)) = patch_masked_path(action, sandbox_policy, cwd) {
+        let key = match kind {
+            PathMaskKind::ReadOnly => "read_only_subpaths",
+            PathMaskKind::DenyRead => "deny_read_paths",
+        };
+        return SafetyCheck::Reject {
+            reason: format!(
+                "{} is protected by sandbox_workspace_write.{key}",
+                path.display()
+            ),
+        };
+    }
+
     match policy {
         AskForApproval::OnFailure | AskForApproval::Never | AskForApproval::OnRequest => {
             // Continue to see if this can be auto-approved.
@@ -44,7 +68,7 @@ pub fn assess_patch_safety(
         // TODO(ragona): I'm not sure this is actually correct? I believe in this case
         // we want to continue to the writable paths check before asking the user.
         AskForApproval::UnlessTrusted => {
-            return SafetyCheck::AskUser;
+            return SafetyCheck::AskUser { reason: None };
         }
     }
 
@@ -71,7 +95,7 @@ pub fn assess_patch_safety(
                     user_explicitly_approved: false,
                 }
             }
-            None => SafetyCheck::AskUser,
+            None => SafetyCheck::AskUser { reason: None },
         }
     } else if policy == AskForApproval::Never {
         SafetyCheck::Reject {
@@ -79,7 +103,7 @@ pub fn assess_patch_safety(
                 .to_string(),
         }
     } else {
-        SafetyCheck::AskUser
+        SafetyCheck::AskUser { reason: None }
     }
 }
 
@@ -89,17 +113,57 @@ pub fn assess_patch_safety(
 /// - the user has explicitly approved the command
 /// - the command is on the "known safe" list
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
-pub fn assess_command_safety(
+///
+/// A command an `allow` command rule matches, a known safe command that
+/// touches a masked path of the sandbox policy, and a command an execpolicy
+/// rule says does not write files are approved to run in the sandbox instead.
+///
+/// Commands a `deny` command rule or an execpolicy rule forbids are rejected.
+/// Those an `ask` command rule matches, or that an execpolicy rule says write
+/// outside the writable roots, need the user's approval.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn assess_command_safety(
     command: &[String],
+    cwd: &Path,
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
+    command_rules: &CommandRules,
+    exec_policy: &ExecPolicy,
     with_escalated_permissions: bool,
 ) -> SafetyCheck {
+    let rule = command_rules.evaluate(command, cwd);
+    let rule_decision = rule.as_ref().map(|matched| matched.decision);
+    if let Some(matched) = &rule {
+        let reason = format!(
+            "matches the `{}` rule `{}` for {}",
+            matched.decision,
+            describe_rule(matched.rule),
+            command_rules.project_root.display()
+        );
+        match (matched.decision, approval_policy) {
+            (CommandRuleDecision::Deny, _) => return SafetyCheck::Reject { reason },
+            (CommandRuleDecision::Ask, AskForApproval::Never) => {
+                return SafetyCheck::Reject {
+                    reason: format!("{reason}; rejected by user approval settings"),
+                };
+            }
+            (CommandRuleDecision::Ask, _) => {
+                return SafetyCheck::AskUser {
+                    reason: Some(reason),
+                };
+            }
+            (CommandRuleDecision::Allow, _) => {}
+        }
+    }
+    let session_approved = approved.contains(command);
+    let rule_allowed = rule_decision == Some(CommandRuleDecision::Allow);
+    let allowed = session_approved || rule_allowed;
+
     // Some commands look dangerous. Even if they are run inside a sandbox,
     // unless the user has explicitly approved them, we should ask,
     // or reject if the approval_policy tells us not to ask.
-    if command_might_be_dangerous(command) && !approved.contains(command) {
+    if command_might_be_dangerous(command) && !allowed {
         if approval_policy == AskForApproval::Never {
             return SafetyCheck::Reject {
                 reason: "dangerous command detected; rejected by user approval settings"
@@ -107,33 +171,136 @@ pub fn assess_command_safety(
             };
         }
 
-        return SafetyCheck::AskUser;
+        return SafetyCheck::AskUser { reason: None };
+    }
+
+    let verdict = exec_policy.evaluate(command, sandbox_policy, cwd);
+    if let Some(ExecPolicyVerdict::Forbidden { explanation }) = verdict {
+        return SafetyCheck::Reject {
+            reason: explanation,
+        };
+    }
+
+    // An `allow` rule stands in for the user's approval at the prompt, not
+    // for leaving the sandbox.
+    if rule_allowed && !session_approved {
+        return approve_by_rule(approval_policy, sandbox_policy, with_escalated_permissions);
     }
 
     // A command is "trusted" because either:
-    // - it belongs to a set of commands we consider "safe" by default, or
+    // - it belongs to a set of commands we consider "safe" by default,
+    // - an execpolicy rule says it only reads files, or
     // - the user has explicitly approved the command for this session
     //
     // Currently, whether a command is "trusted" is a simple boolean, but we
     // should include more metadata on this command test to indicate whether it
-    // should be run inside a sandbox or not. (This could be something the user
-    // defines as part of `execpolicy`.)
+    // should be run inside a sandbox or not.
     //
     // For example, when `is_known_safe_command(command)` returns `true`, it
     // would probably be fine to run the command in a sandbox, but when
     // `approved.contains(command)` is `true`, the user may have approved it for
     // the session _because_ they know it needs to run outside a sandbox.
 
-    if is_known_safe_command(command) || approved.contains(command) {
+    let read_only = verdict == Some(ExecPolicyVerdict::ReadOnly);
+    if is_known_safe_command(command) || session_approved || read_only {
+        // Read-only is not harmless when what it reads is masked: that only
+        // stays hidden inside the sandbox. Execpolicy rules cover programs
+        // such as `rg`, `sed` or `head` that read wherever they are pointed,
+        // so what they match always stays there.
+        if !session_approved && (read_only || touches_masked_path(command, cwd, sandbox_policy)) {
+            return approve_in_sandbox(approval_policy, sandbox_policy, with_escalated_permissions);
+        }
         return SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
             user_explicitly_approved: false,
         };
     }
 
+    if let Some(ExecPolicyVerdict::WritesOutsideWritableRoots { explanation }) = verdict {
+        return match approval_policy {
+            AskForApproval::Never => SafetyCheck::Reject {
+                reason: format!("{explanation}; rejected by user approval settings"),
+            },
+            AskForApproval::UnlessTrusted
+            | AskForApproval::OnFailure
+            | AskForApproval::OnRequest => SafetyCheck::AskUser {
+                reason: Some(explanation),
+            },
+        };
+    }
+
     assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
 }
 
+/// Approve a command an `allow` rule matches to run under the platform
+/// sandbox, or without one where there is none. Escalation still needs the
+/// user's approval.
+fn approve_by_rule(
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    with_escalated_permissions: bool,
+) -> SafetyCheck {
+    if with_escalated_permissions {
+        return assess_safety_for_untrusted_command(approval_policy, sandbox_policy, true);
+    }
+    let sandbox_type = match sandbox_policy {
+        SandboxPolicy::DangerFullAccess => SandboxType::None,
+        _ => get_platform_sandbox().unwrap_or(SandboxType::None),
+    };
+    SafetyCheck::AutoApprove {
+        sandbox_type,
+        user_explicitly_approved: false,
+    }
+}
+
+/// Approve a trusted command to run under the platform sandbox, or assess it
+/// like an untrusted one when it cannot.
+fn approve_in_sandbox(
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    with_escalated_permissions: bool,
+) -> SafetyCheck {
+    if sandbox_policy == &SandboxPolicy::DangerFullAccess {
+        return SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+            user_explicitly_approved: false,
+        };
+    }
+    match get_platform_sandbox() {
+        Some(sandbox_type) if !with_escalated_permissions => SafetyCheck::AutoApprove {
+            sandbox_type,
+            user_explicitly_approved: false,
+        },
+        _ => assess_safety_for_untrusted_command(
+            approval_policy,
+            sandbox_policy,
+            with_escalated_permissions,
+        ),
+    }
+}
+
+/// Whether an operand of `command` is a masked path or a directory holding
+/// one. A command that names no existing path is assumed to work on `cwd`,
+/// as `rg pattern` or `git grep` do.
+fn touches_masked_path(command: &[String], cwd: &Path, sandbox_policy: &SandboxPolicy) -> bool {
+    let mut operands: Vec<PathBuf> = command
+        .iter()
+        .skip(1)
+        .map(|arg| match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with('-') => value,
+            _ => arg.as_str(),
+        })
+        .filter_map(|arg| normalize(&absolute_path(Path::new(arg), cwd)))
+        .filter(|path| path.symlink_metadata().is_ok())
+        .collect();
+    if operands.is_empty() {
+        operands.push(cwd.to_path_buf());
+    }
+    operands
+        .iter()
+        .any(|path| path_touches_mask(sandbox_policy, cwd, path))
+}
+
 pub(crate) fn assess_safety_for_untrusted_command(
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
@@ -147,7 +314,7 @@ pub(crate) fn assess_safety_for_untrusted_command(
             // Even though the user may have opted into DangerFullAccess,
             // they also requested that we ask for approval for untrusted
             // commands.
-            SafetyCheck::AskUser
+            SafetyCheck::AskUser { reason: None }
         }
         (OnFailure, DangerFullAccess)
         | (Never, DangerFullAccess)
@@ -157,7 +324,7 @@ pub(crate) fn assess_safety_for_untrusted_command(
         },
         (OnRequest, ReadOnly) | (OnRequest, WorkspaceWrite { .. }) => {
             if with_escalated_permissions {
-                SafetyCheck::AskUser
+                SafetyCheck::AskUser { reason: None }
             } else {
                 match get_platform_sandbox() {
                     Some(sandbox_type) => SafetyCheck::AutoApprove {
@@ -166,7 +333,7 @@ pub(crate) fn assess_safety_for_untrusted_command(
                     },
                     // Fall back to asking since the command is untrusted and
                     // we do not have a sandbox available
-                    None => SafetyCheck::AskUser,
+                    None => SafetyCheck::AskUser { reason: None },
                 }
             }
         }
@@ -185,7 +352,7 @@ pub(crate) fn assess_safety_for_untrusted_command(
                         // user has requested to only ask for approval on
                         // failure, we will ask the user because no sandbox is
                         // available.
-                        SafetyCheck::AskUser
+                        SafetyCheck::AskUser { reason: None }
                     } else {
                         // We are in non-interactive mode and lack approval, so
                         // all we can do is reject the command.
@@ -210,6 +377,52 @@ pub fn get_platform_sandbox() -> Option<SandboxType> {
     }
 }
 
+/// The mask covering any path the patch writes, if one does.
+fn patch_masked_path(
+    action: &ApplyPatchAction,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Option<(PathBuf, PathMaskKind)> {
+    action
+        .changes()
+        .iter()
+        .flat_map(|(path, change)| {
+            let move_path = match change {
+                ApplyPatchFileChange::Update { move_path, .. } => move_path.as_ref(),
+                ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => None,
+            };
+            std::iter::once(path).chain(move_path)
+        })
+        .find_map(|path| {
+            let abs = normalize(&absolute_path(path, cwd))?;
+            masked_path_kind(sandbox_policy, cwd, &abs).map(|kind| (path.clone(), kind))
+        })
+}
+
+pub(crate) fn absolute_path(path: &Path, cwd: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd.join(path)
+    }
+}
+
+// Normalize a path by removing `.` and resolving `..` without touching the
+// filesystem (works even if the file does not exist).
+pub(crate) fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => { /* skip */ }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    Some(out)
+}
+
 fn is_write_patch_constrained_to_writable_paths(
     action: &ApplyPatchAction,
     sandbox_policy: &SandboxPolicy,
@@ -226,32 +439,11 @@ fn is_write_patch_constrained_to_writable_paths(
         SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
     };
 
-    // Normalize a path by removing `.` and resolving `..` without touching the
-    // filesystem (works even if the file does not exist).
-    fn normalize(path: &Path) -> Option<PathBuf> {
-        let mut out = PathBuf::new();
-        for comp in path.components() {
-            match comp {
-                Component::ParentDir => {
-                    out.pop();
-                }
-                Component::CurDir => { /* skip */ }
-                other => out.push(other.as_os_str()),
-            }
-        }
-        Some(out)
-    }
-
     // Determine whether `path` is inside **any** writable root. Both `path`
     // and roots are converted to absolute, normalized forms before the
     // prefix check.
     let is_path_writable = |p: &PathBuf| {
-        let abs = if p.is_absolute() {
-            p.clone()
-        } else {
-            cwd.join(p)
-        };
-        let abs = match normalize(&abs) {
+        let abs = match normalize(&absolute_path(p, cwd)) {
             Some(v) => v,
             None => return false,
         };
@@ -287,6 +479,7 @@ fn is_write_patch_constrained_to_writable_paths(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_protocol::config_types::CommandRule;
     use tempfile::TempDir;
 
     #[test]
@@ -307,7 +500,10 @@ mod tests {
         // only `cwd` is writable by default.
         let policy_workspace_only = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -328,7 +524,10 @@ mod tests {
         // outside write should be permitted.
         let policy_with_parent = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![parent],
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -339,6 +538,113 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn patches_touching_masked_paths_are_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            read_only_subpaths: vec![".git/hooks".to_string()],
+            deny_read_paths: vec![".env*".to_string()],
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        let hook = cwd.join(".git/hooks/pre-commit");
+        assert_eq!(
+            assess_patch_safety(
+                &ApplyPatchAction::new_add_for_test(&hook, "exit 0".to_string()),
+                AskForApproval::OnRequest,
+                &policy,
+                &cwd,
+            ),
+            SafetyCheck::Reject {
+                reason: format!(
+                    "{} is protected by sandbox_workspace_write.read_only_subpaths",
+                    hook.display()
+                ),
+            }
+        );
+
+        let env_file = cwd.join("src/../.env.local");
+        assert_eq!(
+            assess_patch_safety(
+                &ApplyPatchAction::new_add_for_test(&env_file, "TOKEN=1".to_string()),
+                AskForApproval::OnFailure,
+                &policy,
+                &cwd,
+            ),
+            SafetyCheck::Reject {
+                reason: format!(
+                    "{} is protected by sandbox_workspace_write.deny_read_paths",
+                    env_file.display()
+                ),
+            }
+        );
+
+        assert!(!matches!(
+            assess_patch_safety(
+                &ApplyPatchAction::new_add_for_test(&cwd.join("src/main.rs"), "".to_string()),
+                AskForApproval::OnRequest,
+                &policy,
+                &cwd,
+            ),
+            SafetyCheck::Reject { .. }
+        ));
+    }
+
+    #[test]
+    fn known_safe_commands_reading_masked_paths_stay_sandboxed() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().to_path_buf();
+        std::fs::create_dir(cwd.join("src")).unwrap();
+        std::fs::write(cwd.join(".env"), "TOKEN=1").unwrap();
+        std::fs::write(cwd.join("src/main.rs"), "").unwrap();
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![".env*".to_string()],
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let (exec_policy, _) = ExecPolicy::load(&[]);
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let assess = |command: &[&str]| {
+            let command: Vec<String> = command.iter().map(ToString::to_string).collect();
+            assess_command_safety(
+                &command,
+                &cwd,
+                AskForApproval::OnRequest,
+                &policy,
+                &approved,
+                &CommandRules::default(),
+                &exec_policy,
+                false,
+            )
+        };
+        let sandboxed = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove {
+                sandbox_type,
+                user_explicitly_approved: false,
+            },
+            None => SafetyCheck::AskUser { reason: None },
+        };
+        let unsandboxed = SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+            user_explicitly_approved: false,
+        };
+
+        assert_eq!(assess(&["wc", "-l", ".env"]), sandboxed);
+        assert_eq!(assess(&["wc", "-l", "src/../.env"]), sandboxed);
+        assert_eq!(assess(&["grep", "-r", "TOKEN"]), sandboxed);
+        assert_eq!(assess(&["wc", "-l", "src/main.rs"]), unsandboxed);
+        assert_eq!(assess(&["grep", "-r", "TOKEN", "src"]), unsandboxed);
+    }
+
     #[test]
     fn test_request_escalated_privileges() {
         // Should not be a trusted command
@@ -350,13 +656,16 @@ mod tests {
 
         let safety_check = assess_command_safety(
             &command,
+            Path::new("/repo"),
             approval_policy,
             &sandbox_policy,
             &approved,
+            &CommandRules::default(),
+            &ExecPolicy::default(),
             request_escalated_privileges,
         );
 
-        assert_eq!(safety_check, SafetyCheck::AskUser);
+        assert_eq!(safety_check, SafetyCheck::AskUser { reason: None });
     }
 
     #[test]
@@ -370,9 +679,12 @@ mod tests {
 
         let safety_check = assess_command_safety(
             &command,
+            Path::new("/repo"),
             approval_policy,
             &sandbox_policy,
             &approved,
+            &CommandRules::default(),
+            &ExecPolicy::default(),
             request_escalated_privileges,
         );
 
@@ -395,9 +707,12 @@ mod tests {
 
         let safety_check = assess_command_safety(
             &command,
+            Path::new("/repo"),
             approval_policy,
             &sandbox_policy,
             &approved,
+            &CommandRules::default(),
+            &ExecPolicy::default(),
             request_escalated_privileges,
         );
 
@@ -420,9 +735,12 @@ mod tests {
 
         let safety_check = assess_command_safety(
             &command,
+            Path::new("/repo"),
             approval_policy,
             &sandbox_policy,
             &approved,
+            &CommandRules::default(),
+            &ExecPolicy::default(),
             request_escalated_privileges,
         );
 
@@ -431,8 +749,116 @@ mod tests {
                 sandbox_type,
                 user_explicitly_approved: false,
             },
-            None => SafetyCheck::AskUser,
+            None => SafetyCheck::AskUser { reason: None },
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn exec_policy_rules_decide_matching_commands() {
+        let (exec_policy, _) = ExecPolicy::load(&[]);
+        let cwd = Path::new("/repo");
+        let sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let assess = |command: &[&str], approval_policy| {
+            let command: Vec<String> = command.iter().map(ToString::to_string).collect();
+            assess_command_safety(
+                &command,
+                cwd,
+                approval_policy,
+                &sandbox_policy,
+                &approved,
+                &CommandRules::default(),
+                &exec_policy,
+                false,
+            )
+        };
+
+        // The default execpolicy knows `cat` only reads its operands, which
+        // approves it without a prompt, but in the sandbox.
+        assert_eq!(
+            assess(&["cat", "-n", "notes.txt"], AskForApproval::UnlessTrusted),
+            match get_platform_sandbox() {
+                Some(sandbox_type) => SafetyCheck::AutoApprove {
+                    sandbox_type,
+                    user_explicitly_approved: false,
+                },
+                None => SafetyCheck::AskUser { reason: None },
+            }
+        );
+
+        let explanation = "the `cp` rule in the default execpolicy says `cp notes.txt /etc/notes.txt` writes /etc/notes.txt, which is outside the writable roots";
+        assert_eq!(
+            assess(
+                &["cp", "notes.txt", "/etc/notes.txt"],
+                AskForApproval::OnRequest
+            ),
+            SafetyCheck::AskUser {
+                reason: Some(explanation.to_string()),
+            }
+        );
+        assert_eq!(
+            assess(
+                &["cp", "notes.txt", "/etc/notes.txt"],
+                AskForApproval::Never
+            ),
+            SafetyCheck::Reject {
+                reason: format!("{explanation}; rejected by user approval settings"),
+            }
+        );
+    }
+
+    #[test]
+    fn command_rules_override_the_default_assessment() {
+        let rule = |program: &str, args: &[&str], decision| CommandRule {
+            program: program.to_string(),
+            args: args.iter().map(ToString::to_string).collect(),
+            cwd: None,
+            decision,
+        };
+        let command_rules = CommandRules {
+            project_root: PathBuf::from("/repo"),
+            rules: vec![
+                rule("git", &["reset", "**"], CommandRuleDecision::Allow),
+                rule("ls", &["**"], CommandRuleDecision::Ask),
+                rule("git", &["push", "**"], CommandRuleDecision::Deny),
+            ],
+        };
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let assess = |command: &[&str], approval_policy| {
+            let command: Vec<String> = command.iter().map(ToString::to_string).collect();
+            assess_command_safety(
+                &command,
+                Path::new("/repo"),
+                approval_policy,
+                &SandboxPolicy::ReadOnly,
+                &approved,
+                &command_rules,
+                &ExecPolicy::default(),
+                false,
+            )
+        };
+
+        // Allowed despite looking dangerous, but still in the sandbox.
+        assert_eq!(
+            assess(&["git", "reset", "--hard"], AskForApproval::Never),
+            SafetyCheck::AutoApprove {
+                sandbox_type: get_platform_sandbox().unwrap_or(SandboxType::None),
+                user_explicitly_approved: false,
+            }
+        );
+        // Asked about despite being known safe.
+        assert_eq!(
+            assess(&["ls", "-la"], AskForApproval::OnRequest),
+            SafetyCheck::AskUser {
+                reason: Some("matches the `ask` rule `ls **` for /repo".to_string()),
+            }
+        );
+        assert_eq!(
+            assess(&["git", "push", "origin"], AskForApproval::OnRequest),
+            SafetyCheck::Reject {
+                reason: "matches the `deny` rule `git push **` for /repo".to_string(),
+            }
+        );
+    }
 }
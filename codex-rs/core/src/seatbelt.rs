@@ -3,8 +3,10 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+use crate::path_masks::resolve_masked_paths;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
+use crate::spawn::ResourceLimits;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 
@@ -14,7 +16,7 @@ const MACOS_SEATBELT_BASE_POLICY: &str = include_str!("seatbelt_base_policy.sbpl
 /// to defend against an attacker trying to inject a malicious version on the
 /// PATH. If /usr/bin/sandbox-exec has been tampered with, then the attacker
 /// already has root access.
-const MACOS_PATH_TO_SEATBELT_EXECUTABLE: &str = "/usr/bin/sandbox-exec";
+pub(crate) const MACOS_PATH_TO_SEATBELT_EXECUTABLE: &str = "/usr/bin/sandbox-exec";
 
 pub async fn spawn_command_under_seatbelt(
     command: Vec<String>,
@@ -23,6 +25,7 @@ pub async fn spawn_command_under_seatbelt(
     sandbox_policy_cwd: &Path,
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
+    resource_limits: ResourceLimits,
 ) -> std::io::Result<Child> {
     let args = create_seatbelt_command_args(command, sandbox_policy, sandbox_policy_cwd);
     let arg0 = None;
@@ -35,11 +38,12 @@ pub async fn spawn_command_under_seatbelt(
         sandbox_policy,
         stdio_policy,
         env,
+        resource_limits,
     )
     .await
 }
 
-fn create_seatbelt_command_args(
+pub(crate) fn create_seatbelt_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
@@ -52,7 +56,17 @@ fn create_seatbelt_command_args(
                 Vec::<String>::new(),
             )
         } else {
-            let writable_roots = sandbox_policy.get_writable_roots_with_cwd(sandbox_policy_cwd);
+            let mut writable_roots = sandbox_policy.get_writable_roots_with_cwd(sandbox_policy_cwd);
+            // Both mask kinds only withhold writes here; seatbelt does not
+            // enforce `deny_read_paths`.
+            for masked in resolve_masked_paths(sandbox_policy, sandbox_policy_cwd) {
+                for wr in writable_roots
+                    .iter_mut()
+                    .filter(|wr| masked.path.starts_with(&wr.root))
+                {
+                    wr.read_only_subpaths.push(masked.path.clone());
+                }
+            }
 
             let mut writable_folder_policies: Vec<String> = Vec::new();
             let mut cli_args: Vec<String> = Vec::new();
@@ -155,7 +169,10 @@ mod tests {
         // does not automatically include defaults TMPDIR or /tmp.
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![root_with_git, root_without_git],
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -230,7 +247,10 @@ mod tests {
         // is done properly for cwd.
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         };
@@ -309,6 +309,7 @@ mod tests {
             use crate::exec::SandboxType;
             use crate::exec::process_exec_tool_call;
             use crate::protocol::SandboxPolicy;
+            use crate::spawn::ResourceLimits;
 
             let temp_home = tempfile::tempdir().unwrap();
             let bashrc_path = temp_home.path().join(".bashrc");
@@ -347,6 +348,7 @@ mod tests {
                     )]),
                     with_escalated_permissions: None,
                     justification: None,
+                    resource_limits: ResourceLimits::default(),
                 },
                 SandboxType::None,
                 &SandboxPolicy::DangerFullAccess,
@@ -416,6 +418,7 @@ mod macos_tests {
             use crate::exec::SandboxType;
             use crate::exec::process_exec_tool_call;
             use crate::protocol::SandboxPolicy;
+            use crate::spawn::ResourceLimits;
 
             // create a temp directory with a zshrc file in it
             let temp_home = tempfile::tempdir().unwrap();
@@ -455,6 +458,7 @@ mod macos_tests {
                     )]),
                     with_escalated_permissions: None,
                     justification: None,
+                    resource_limits: ResourceLimits::default(),
                 },
                 SandboxType::None,
                 &SandboxPolicy::DangerFullAccess,
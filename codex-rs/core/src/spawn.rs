@@ -1,6 +1,10 @@
+use serde::Deserialize;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::str::FromStr;
+use std::time::Duration;
 use tokio::process::Child;
 use tokio::process::Command;
 use tracing::trace;
@@ -22,9 +26,120 @@ pub const CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR: &str = "CODEX_SANDBOX_NETWORK_
 /// accommodate sandboxing configuration and other sandboxing mechanisms.
 pub const CODEX_SANDBOX_ENV_VAR: &str = "CODEX_SANDBOX";
 
+/// Limits applied to commands run for the model, set in
+/// `[sandbox_resources]`. Unset fields leave the inherited limit in place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// Virtual address space in MiB (`RLIMIT_AS`). This caps what a process
+    /// reserves rather than what it keeps resident, so runtimes that reserve
+    /// large ranges up front (the JVM, Go, V8) can fail well below it.
+    pub max_virtual_memory_mb: Option<u64>,
+    /// CPU time in seconds (`RLIMIT_CPU`), summed over all threads.
+    pub cpu_seconds: Option<u64>,
+    /// Processes owned by the user (`RLIMIT_NPROC`). This counts every
+    /// process of the user, not only the command's.
+    pub max_processes: Option<u64>,
+    /// Largest file the command may write, in MiB (`RLIMIT_FSIZE`).
+    pub max_file_size_mb: Option<u64>,
+    /// Upper bound on the timeout of any command.
+    pub wall_clock_seconds: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Whether any of the setrlimit-based limits is set.
+    pub fn has_rlimits(&self) -> bool {
+        self.max_virtual_memory_mb.is_some()
+            || self.cpu_seconds.is_some()
+            || self.max_processes.is_some()
+            || self.max_file_size_mb.is_some()
+    }
+
+    pub fn wall_clock(&self) -> Option<Duration> {
+        self.wall_clock_seconds.map(Duration::from_secs)
+    }
+}
+
+impl FromStr for ResourceLimits {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// The limit a command ran into. Only limits that announce themselves with a
+/// signal or a timeout are listed: running out of address space or processes
+/// just makes an allocation or a fork fail, which the command reports itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimitKind {
+    CpuTime { seconds: u64 },
+    FileSize { max_mb: u64 },
+    WallClock { seconds: u64 },
+}
+
+impl std::fmt::Display for ResourceLimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CpuTime { seconds } => write!(f, "CPU time limit ({seconds} s)"),
+            Self::FileSize { max_mb } => write!(f, "file size limit ({max_mb} MiB)"),
+            Self::WallClock { seconds } => write!(f, "wall-clock limit ({seconds} s)"),
+        }
+    }
+}
+
+/// Apply the setrlimit-based parts of `limits` to the current process. Only
+/// calls async-signal-safe functions, so it can run in `pre_exec`. Limits
+/// are never raised above the current hard limit.
+#[cfg(unix)]
+pub fn apply_resource_limits(limits: &ResourceLimits) -> std::io::Result<()> {
+    const MIB: u64 = 1024 * 1024;
+
+    macro_rules! set_limit {
+        ($resource:expr, $soft:expr, $hard:expr) => {{
+            let mut current = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            if unsafe { libc::getrlimit($resource, &mut current) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let hard = ($hard as libc::rlim_t).min(current.rlim_max);
+            let limit = libc::rlimit {
+                rlim_cur: ($soft as libc::rlim_t).min(hard),
+                rlim_max: hard,
+            };
+            if unsafe { libc::setrlimit($resource, &limit) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }};
+    }
+
+    if let Some(max_mb) = limits.max_virtual_memory_mb {
+        let bytes = max_mb.saturating_mul(MIB);
+        set_limit!(libc::RLIMIT_AS, bytes, bytes);
+    }
+    if let Some(seconds) = limits.cpu_seconds {
+        // SIGXCPU at the soft limit, SIGKILL a second later for commands
+        // that ignore it.
+        set_limit!(libc::RLIMIT_CPU, seconds, seconds.saturating_add(1));
+    }
+    if let Some(max) = limits.max_processes {
+        set_limit!(libc::RLIMIT_NPROC, max, max);
+    }
+    if let Some(max_mb) = limits.max_file_size_mb {
+        let bytes = max_mb.saturating_mul(MIB);
+        set_limit!(libc::RLIMIT_FSIZE, bytes, bytes);
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum StdioPolicy {
     RedirectForShellTool,
+    /// Redirect like [`StdioPolicy::RedirectForShellTool`], and start the
+    /// command in a session of its own so that the job can be stopped by
+    /// signalling its whole process group.
+    RedirectForBackgroundJob,
     Inherit,
 }
 
@@ -35,6 +150,7 @@ pub enum StdioPolicy {
 /// For now, we take `SandboxPolicy` as a parameter to spawn_child() because
 /// we need to determine whether to set the
 /// `CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR` environment variable.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn spawn_child_async(
     program: PathBuf,
     args: Vec<String>,
@@ -43,6 +159,7 @@ pub(crate) async fn spawn_child_async(
     sandbox_policy: &SandboxPolicy,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    resource_limits: ResourceLimits,
 ) -> std::io::Result<Child> {
     trace!(
         "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?}"
@@ -85,8 +202,29 @@ pub(crate) async fn spawn_child_async(
         });
     }
 
+    #[cfg(unix)]
+    if resource_limits.has_rlimits() {
+        unsafe {
+            cmd.pre_exec(move || apply_resource_limits(&resource_limits));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = resource_limits;
+
+    #[cfg(unix)]
+    if matches!(stdio_policy, StdioPolicy::RedirectForBackgroundJob) {
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
     match stdio_policy {
-        StdioPolicy::RedirectForShellTool => {
+        StdioPolicy::RedirectForShellTool | StdioPolicy::RedirectForBackgroundJob => {
             // Do not create a file descriptor for stdin because otherwise some
             // commands may hang forever waiting for input. For example, ripgrep has
             // a heuristic where it may try to read from stdin as explained here:
@@ -0,0 +1,512 @@
+//! Content-addressed chunk cache backing `read_code` (context virtual memory).
+//!
+//! Files are split into deterministic, line-aligned chunks of roughly
+//! 4–8 KiB. A chunk is identified by `(repo HEAD oid, path, line range)`, so a
+//! given file at a given commit always yields the same chunks and therefore
+//! byte-identical `read_code` output, which keeps prompt-cache prefixes stable
+//! across sessions. Entries are built lazily on first read and revalidated
+//! with a `(size, mtime, hash)` stamp to catch files that are dirty relative
+//! to the cached copy.
+//!
+//! Each session keeps the files it read in memory. Files read inside a Git
+//! repository are also written to a [`ChunkStore`] under `codex_home`, one
+//! file per chunk, from which later sessions at the same commit serve them.
+
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde::Serialize;
+use sha1::Digest;
+use sha1::Sha1;
+
+use crate::state::read_index::git_blob_oid;
+
+/// Chunks are closed at the first blank line once they reach this size.
+pub(crate) const CHUNK_MIN_BYTES: usize = 4 * 1024;
+/// Chunks never grow past this size unless a single line is longer.
+pub(crate) const CHUNK_MAX_BYTES: usize = 8 * 1024;
+const MAX_CACHED_FILES: usize = 256;
+/// Directory under `codex_home` holding the [`ChunkStore`].
+pub(crate) const CHUNK_CACHE_SUBDIR: &str = "chunk_cache";
+/// Commits whose chunks the store keeps; older ones are pruned.
+const MAX_STORED_COMMITS: usize = 8;
+const STORE_INDEX_FILE: &str = "index.json";
+
+/// `(size, mtime, hash)` observed when a file was chunked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct FileStamp {
+    pub(crate) size: u64,
+    pub(crate) mtime: Option<SystemTime>,
+    /// Git blob oid of the contents.
+    pub(crate) hash: String,
+}
+
+/// One line-aligned chunk of a cached file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Chunk {
+    /// 1-based inclusive line range.
+    pub(crate) start_line: usize,
+    pub(crate) end_line: usize,
+    bytes: Range<usize>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ChunkedFile {
+    stamp: FileStamp,
+    /// When the stamp was taken; used to distrust same-tick modifications.
+    stamped_at: SystemTime,
+    contents: String,
+    chunks: Vec<Chunk>,
+}
+
+impl ChunkedFile {
+    fn new(contents: String, size: u64, mtime: Option<SystemTime>) -> Self {
+        let chunks = chunk_lines(&contents);
+        Self {
+            stamp: FileStamp {
+                size,
+                mtime,
+                hash: git_blob_oid(&contents),
+            },
+            stamped_at: SystemTime::now(),
+            contents,
+            chunks,
+        }
+    }
+
+    pub(crate) fn contents(&self) -> &str {
+        &self.contents
+    }
+
+    pub(crate) fn blob_oid(&self) -> &str {
+        &self.stamp.hash
+    }
+
+    pub(crate) fn chunk_text(&self, chunk: &Chunk) -> &str {
+        &self.contents[chunk.bytes.clone()]
+    }
+
+    /// All lines of the file (newline-inclusive), assembled chunk by chunk.
+    pub(crate) fn lines(&self) -> Vec<&str> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| self.chunk_text(chunk).split_inclusive('\n'))
+            .collect()
+    }
+
+    /// Number of chunks touched by the given 1-based inclusive `ranges`.
+    pub(crate) fn chunks_covering(&self, ranges: &[(usize, usize)]) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| {
+                ranges
+                    .iter()
+                    .any(|&(start, end)| chunk.start_line <= end && start <= chunk.end_line)
+            })
+            .count()
+    }
+
+    /// Whether a fresh `stat` still matches this entry without rehashing.
+    /// Modifications within the same mtime tick as the stamp are not
+    /// detectable from metadata alone, so such entries are always rehashed.
+    pub(crate) fn stat_matches(&self, size: u64, mtime: Option<SystemTime>) -> bool {
+        let Some(mtime) = mtime else {
+            return false;
+        };
+        self.stamp.size == size && self.stamp.mtime == Some(mtime) && mtime < self.stamped_at
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FileKey {
+    head_oid: Option<String>,
+    path: PathBuf,
+}
+
+/// Outcome of loading a file through the cache.
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkLoad {
+    pub(crate) file: Arc<ChunkedFile>,
+    pub(crate) cache_hit: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ChunkCache {
+    files: IndexMap<FileKey, Arc<ChunkedFile>>,
+}
+
+impl ChunkCache {
+    /// Return the cached entry when `size` and `mtime` still match its stamp.
+    pub(crate) fn lookup(
+        &self,
+        head_oid: Option<&str>,
+        path: &Path,
+        size: u64,
+        mtime: Option<SystemTime>,
+    ) -> Option<Arc<ChunkedFile>> {
+        let key = FileKey {
+            head_oid: head_oid.map(str::to_string),
+            path: path.to_path_buf(),
+        };
+        self.files
+            .get(&key)
+            .filter(|file| file.stat_matches(size, mtime))
+            .map(Arc::clone)
+    }
+
+    /// Store freshly read `contents`. Re-reading contents whose hash matches
+    /// the existing entry (for example after a `touch`) counts as a hit.
+    pub(crate) fn insert(
+        &mut self,
+        head_oid: Option<&str>,
+        path: &Path,
+        contents: String,
+        size: u64,
+        mtime: Option<SystemTime>,
+    ) -> ChunkLoad {
+        let key = FileKey {
+            head_oid: head_oid.map(str::to_string),
+            path: path.to_path_buf(),
+        };
+        let file = ChunkedFile::new(contents, size, mtime);
+        let cache_hit = self
+            .files
+            .get(&key)
+            .is_some_and(|existing| existing.stamp.hash == file.stamp.hash);
+        let file = Arc::new(file);
+        self.files.insert(key, Arc::clone(&file));
+        while self.files.len() > MAX_CACHED_FILES {
+            self.files.shift_remove_index(0);
+        }
+        ChunkLoad { file, cache_hit }
+    }
+
+    /// Keep `file`, loaded from the [`ChunkStore`], for the rest of the
+    /// session.
+    pub(crate) fn adopt(&mut self, head_oid: &str, path: &Path, file: ChunkedFile) -> ChunkLoad {
+        let key = FileKey {
+            head_oid: Some(head_oid.to_string()),
+            path: path.to_path_buf(),
+        };
+        let file = Arc::new(file);
+        self.files.insert(key, Arc::clone(&file));
+        while self.files.len() > MAX_CACHED_FILES {
+            self.files.shift_remove_index(0);
+        }
+        ChunkLoad {
+            file,
+            cache_hit: true,
+        }
+    }
+
+    /// Drop every entry for `path`, regardless of the commit it was read at.
+    pub(crate) fn invalidate(&mut self, path: &Path) {
+        self.files.retain(|key, _| key.path != path);
+    }
+}
+
+/// What the store records about a file besides its chunks.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredIndex {
+    stamp: FileStamp,
+    stamped_at: SystemTime,
+    /// 1-based inclusive line ranges of the chunks, in order.
+    chunks: Vec<(usize, usize)>,
+}
+
+/// Chunks persisted across sessions, laid out as
+/// `<HEAD oid>/<path digest>/<start>-<end>` next to an index holding the
+/// file's stamp. Failures to read or write it only cost a re-read.
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkStore {
+    dir: PathBuf,
+}
+
+impl ChunkStore {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn file_dir(&self, head_oid: &str, path: &Path) -> PathBuf {
+        let mut hasher = Sha1::new();
+        hasher.update(path.as_os_str().as_encoded_bytes());
+        self.dir
+            .join(head_oid)
+            .join(format!("{:x}", hasher.finalize()))
+    }
+
+    /// The chunks stored for `path` at `head_oid`, reassembled and checked
+    /// against the hash they were stored with.
+    pub(crate) async fn load(&self, head_oid: &str, path: &Path) -> Option<ChunkedFile> {
+        let dir = self.file_dir(head_oid, path);
+        let index = tokio::fs::read(dir.join(STORE_INDEX_FILE)).await.ok()?;
+        let StoredIndex {
+            stamp,
+            stamped_at,
+            chunks,
+        } = serde_json::from_slice(&index).ok()?;
+        let mut contents = String::new();
+        for (start, end) in &chunks {
+            let chunk = dir.join(format!("{start}-{end}"));
+            contents.push_str(&tokio::fs::read_to_string(chunk).await.ok()?);
+        }
+        if git_blob_oid(&contents) != stamp.hash {
+            return None;
+        }
+        let file = ChunkedFile {
+            chunks: chunk_lines(&contents),
+            stamp,
+            stamped_at,
+            contents,
+        };
+        let ranges: Vec<(usize, usize)> = file
+            .chunks
+            .iter()
+            .map(|chunk| (chunk.start_line, chunk.end_line))
+            .collect();
+        (ranges == chunks).then_some(file)
+    }
+
+    /// Persist the chunks of `file`, read from `path` at `head_oid`. The
+    /// index is written last, so a reader never pairs it with missing
+    /// chunks, and the hash check catches chunks a concurrent writer
+    /// replaced.
+    pub(crate) async fn save(
+        &self,
+        head_oid: &str,
+        path: &Path,
+        file: &ChunkedFile,
+    ) -> io::Result<()> {
+        let commit_dir = self.dir.join(head_oid);
+        if !tokio::fs::try_exists(&commit_dir).await? {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            self.prune_commits().await?;
+        }
+        let dir = self.file_dir(head_oid, path);
+        tokio::fs::create_dir_all(&dir).await?;
+        for chunk in &file.chunks {
+            tokio::fs::write(
+                dir.join(format!("{}-{}", chunk.start_line, chunk.end_line)),
+                file.chunk_text(chunk),
+            )
+            .await?;
+        }
+        let index = StoredIndex {
+            stamp: file.stamp.clone(),
+            stamped_at: file.stamped_at,
+            chunks: file
+                .chunks
+                .iter()
+                .map(|chunk| (chunk.start_line, chunk.end_line))
+                .collect(),
+        };
+        let tmp = dir.join(format!("{STORE_INDEX_FILE}.{}", std::process::id()));
+        tokio::fs::write(&tmp, serde_json::to_vec(&index).map_err(io::Error::other)?).await?;
+        tokio::fs::rename(tmp, dir.join(STORE_INDEX_FILE)).await
+    }
+
+    /// Make room for one more commit by removing the least recently
+    /// created ones.
+    async fn prune_commits(&self) -> io::Result<()> {
+        let mut commits: Vec<(SystemTime, PathBuf)> = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if let Ok(modified) = entry.metadata().await.and_then(|meta| meta.modified()) {
+                commits.push((modified, entry.path()));
+            }
+        }
+        if commits.len() < MAX_STORED_COMMITS {
+            return Ok(());
+        }
+        commits.sort();
+        for (_, dir) in &commits[..=commits.len() - MAX_STORED_COMMITS] {
+            tokio::fs::remove_dir_all(dir).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Split `contents` into deterministic line-aligned chunks. A chunk closes at
+/// the first blank line after reaching [`CHUNK_MIN_BYTES`], or before a line
+/// that would push it past [`CHUNK_MAX_BYTES`].
+fn chunk_lines(contents: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut start_byte = 0usize;
+    let mut start_line = 1usize;
+    let mut offset = 0usize;
+    let mut line_no = 0usize;
+
+    for line in contents.split_inclusive('\n') {
+        let size = offset - start_byte;
+        if size > 0 && size + line.len() > CHUNK_MAX_BYTES {
+            chunks.push(Chunk {
+                start_line,
+                end_line: line_no,
+                bytes: start_byte..offset,
+            });
+            start_byte = offset;
+            start_line = line_no + 1;
+        }
+
+        offset += line.len();
+        line_no += 1;
+
+        if offset - start_byte >= CHUNK_MIN_BYTES && line.trim().is_empty() {
+            chunks.push(Chunk {
+                start_line,
+                end_line: line_no,
+                bytes: start_byte..offset,
+            });
+            start_byte = offset;
+            start_line = line_no + 1;
+        }
+    }
+
+    if offset > start_byte {
+        chunks.push(Chunk {
+            start_line,
+            end_line: line_no,
+            bytes: start_byte..offset,
+        });
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn sample_source() -> String {
+        let mut source = String::new();
+        for block in 0..40 {
+            for line in 0..12 {
+                source.push_str(&format!(
+                    "    let value_{block}_{line} = compute({line});\n"
+                ));
+            }
+            source.push('\n');
+        }
+        source
+    }
+
+    #[test]
+    fn chunks_are_line_aligned_and_bounded() {
+        let source = sample_source();
+        let file = ChunkedFile::new(source.clone(), source.len() as u64, None);
+
+        let chunks = &file.chunks;
+        assert!(chunks.len() > 1);
+        let rebuilt: String = chunks.iter().map(|chunk| file.chunk_text(chunk)).collect();
+        assert_eq!(rebuilt, source);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end_line + 1, pair[1].start_line);
+        }
+        for chunk in &chunks[..chunks.len() - 1] {
+            let len = file.chunk_text(chunk).len();
+            assert!((CHUNK_MIN_BYTES..=CHUNK_MAX_BYTES).contains(&len), "{len}");
+            assert!(file.chunk_text(chunk).ends_with("\n\n"));
+        }
+        assert_eq!(
+            file.lines(),
+            source.split_inclusive('\n').collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn chunking_is_deterministic() {
+        let source = sample_source();
+        assert_eq!(chunk_lines(&source), chunk_lines(&source));
+    }
+
+    #[test]
+    fn oversized_lines_form_their_own_chunk() {
+        let long_line = format!("{}\n", "x".repeat(CHUNK_MAX_BYTES + 10));
+        let source = format!("short\n{long_line}tail\n");
+        let chunks = chunk_lines(&source);
+        let lines: Vec<(usize, usize)> = chunks
+            .iter()
+            .map(|chunk| (chunk.start_line, chunk.end_line))
+            .collect();
+        assert_eq!(lines, vec![(1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn stamps_detect_dirty_files() {
+        let path = Path::new("/repo/src/lib.rs");
+        let mtime = SystemTime::now() - Duration::from_secs(60);
+        let mut cache = ChunkCache::default();
+
+        let first = cache.insert(Some("head"), path, "a\n".to_string(), 2, Some(mtime));
+        assert!(!first.cache_hit);
+        assert!(cache.lookup(Some("head"), path, 2, Some(mtime)).is_some());
+        assert!(cache.lookup(Some("other"), path, 2, Some(mtime)).is_none());
+
+        let touched = mtime + Duration::from_secs(1);
+        assert!(cache.lookup(Some("head"), path, 2, Some(touched)).is_none());
+        let reread = cache.insert(Some("head"), path, "a\n".to_string(), 2, Some(touched));
+        assert!(reread.cache_hit);
+
+        let edited = cache.insert(Some("head"), path, "b\n".to_string(), 2, Some(touched));
+        assert!(!edited.cache_hit);
+
+        cache.invalidate(path);
+        assert!(cache.lookup(Some("head"), path, 2, Some(touched)).is_none());
+    }
+
+    #[tokio::test]
+    async fn stored_chunks_are_served_until_they_no_longer_match() {
+        let store_dir = tempfile::tempdir().expect("tempdir");
+        let store = ChunkStore::new(store_dir.path().to_path_buf());
+        let path = Path::new("/repo/src/lib.rs");
+        let source = sample_source();
+        let mtime = SystemTime::now() - Duration::from_secs(60);
+        let file = ChunkedFile::new(source.clone(), source.len() as u64, Some(mtime));
+
+        store.save("head", path, &file).await.expect("save chunks");
+        let loaded = store.load("head", path).await.expect("load chunks");
+        assert_eq!(loaded.contents(), source);
+        assert_eq!(loaded.chunks, file.chunks);
+        assert!(loaded.stat_matches(source.len() as u64, Some(mtime)));
+        assert!(store.load("other", path).await.is_none());
+
+        let chunk = &file.chunks[1];
+        let chunk_path = store
+            .file_dir("head", path)
+            .join(format!("{}-{}", chunk.start_line, chunk.end_line));
+        std::fs::write(chunk_path, "tampered\n").expect("overwrite chunk");
+        assert!(store.load("head", path).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn the_store_keeps_a_bounded_number_of_commits() {
+        let store_dir = tempfile::tempdir().expect("tempdir");
+        let store = ChunkStore::new(store_dir.path().to_path_buf());
+        let path = Path::new("/repo/src/lib.rs");
+        let file = ChunkedFile::new("a\n".to_string(), 2, None);
+
+        for commit in 0..MAX_STORED_COMMITS + 2 {
+            store
+                .save(&format!("head{commit}"), path, &file)
+                .await
+                .expect("save chunks");
+        }
+        let stored = std::fs::read_dir(store_dir.path())
+            .expect("read store")
+            .count();
+        assert_eq!(stored, MAX_STORED_COMMITS);
+        assert!(
+            store
+                .load(&format!("head{}", MAX_STORED_COMMITS + 1), path)
+                .await
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Canonical intents for the repeat-command breaker.
+//!
+//! Commands that ask for the same thing in different words (`rg foo src` and
+//! `rg -n foo ./src`, or `cat lib.rs` and `sed -n 1,400p lib.rs`) map to the
+//! same key, so the breaker counts them as repeats of one another.
+
+use std::sync::OnceLock;
+
+use regex_lite::Regex;
+use shlex::split as shlex_split;
+
+use crate::parse_command::ParsedCommand;
+use crate::parse_command::parse_command;
+
+/// The canonical key for `command`, such as `read src/lib.rs` or
+/// `search "foo" in src`. Commands the parser does not recognize keep their
+/// own text.
+pub(crate) fn command_intent_key(command: &[String]) -> String {
+    let parts: Vec<String> = parse_command(command).iter().map(intent_of).collect();
+    if parts.is_empty() {
+        return command.join(" ");
+    }
+    parts.join(" && ")
+}
+
+/// `output` with differences that equivalent commands introduce removed:
+/// surrounding blank lines, trailing whitespace, `./` path prefixes and the
+/// line numbers of `path:line:` match prefixes.
+pub(crate) fn normalize_intent_output(output: &str) -> String {
+    output
+        .lines()
+        .map(|line| {
+            let line = line.trim_end().trim_start_matches("./");
+            line_number_regex().replace(line, "$path")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// The line number `grep -n` and `rg -n` put after the path of a match, or
+/// at the start of a match when a single file is searched.
+fn line_number_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+
+    #[expect(clippy::unwrap_used)]
+    RE.get_or_init(|| Regex::new(r"^(?:(?P<path>[^:\s][^:]*:))?\d+:").unwrap())
+}
+
+fn intent_of(parsed: &ParsedCommand) -> String {
+    match parsed {
+        ParsedCommand::Read { cmd, .. } => match operands(cmd).positional.last() {
+            Some(path) => format!("read {}", normalize_path(path)),
+            None => cmd.clone(),
+        },
+        ParsedCommand::ListFiles { cmd, .. } => {
+            format!("list {}", joined_paths(operands(cmd).positional))
+        }
+        ParsedCommand::Search { cmd, query, .. } => {
+            let Operands {
+                mut positional,
+                pattern,
+            } = operands(cmd);
+            // The pattern is given by `-e`, or else is the first operand.
+            let query = match (query, pattern) {
+                (None, _) => None,
+                (Some(_), Some(pattern)) => Some(pattern),
+                (Some(query), None) if positional.is_empty() => Some(query.clone()),
+                (Some(_), None) => Some(positional.remove(0)),
+            };
+            let paths = joined_paths(positional);
+            match query {
+                Some(query) => format!("search {query:?} in {paths}"),
+                None => format!("search in {paths}"),
+            }
+        }
+        ParsedCommand::Unknown { cmd } => cmd.clone(),
+    }
+}
+
+/// The arguments of a command after its program.
+struct Operands {
+    /// Arguments that are neither flags nor flag values.
+    positional: Vec<String>,
+    /// The value of `-e`/`--regexp`, if given.
+    pattern: Option<String>,
+}
+
+fn operands(cmd: &str) -> Operands {
+    let words = split_words(cmd);
+    let mut operands = Operands {
+        positional: Vec::new(),
+        pattern: None,
+    };
+    let Some((program, args)) = words.split_first() else {
+        return operands;
+    };
+    let mut args = args.iter();
+    while let Some(word) = args.next() {
+        if !word.starts_with('-') {
+            operands.positional.push(word.clone());
+        } else if flag_takes_value(program, word) {
+            let value = args.next();
+            if matches!(word.as_str(), "-e" | "--regexp") {
+                operands.pattern = operands.pattern.or_else(|| value.cloned());
+            }
+        }
+    }
+    operands
+}
+
+/// `paths` normalized, sorted and joined. Defaults to the working
+/// directory.
+fn joined_paths(paths: Vec<String>) -> String {
+    let mut paths: Vec<String> = paths.iter().map(|path| normalize_path(path)).collect();
+    if paths.is_empty() {
+        return ".".to_string();
+    }
+    paths.sort();
+    paths.dedup();
+    paths.join(" ")
+}
+
+/// Whether `flag`, as a separate word, is followed by its value, such as
+/// the glob of `rg -g '*.rs'` or the count of `head -n 20`.
+fn flag_takes_value(program: &str, flag: &str) -> bool {
+    let program = program.rsplit('/').next().unwrap_or(program);
+    match program {
+        "head" | "tail" => matches!(flag, "-n" | "-c" | "--lines" | "--bytes"),
+        "rg" | "grep" | "egrep" | "fgrep" => matches!(
+            flag,
+            "-g" | "--glob"
+                | "-t"
+                | "--type"
+                | "-T"
+                | "--type-not"
+                | "-e"
+                | "--regexp"
+                | "-m"
+                | "--max-count"
+                | "-A"
+                | "--after-context"
+                | "-B"
+                | "--before-context"
+                | "-C"
+                | "--context"
+        ),
+        _ => false,
+    }
+}
+
+fn split_words(cmd: &str) -> Vec<String> {
+    shlex_split(cmd).unwrap_or_else(|| cmd.split_whitespace().map(str::to_string).collect())
+}
+
+fn normalize_path(path: &str) -> String {
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest;
+    }
+    let trimmed = path.trim_end_matches('/');
+    match trimmed {
+        "" if path.starts_with('/') => "/".to_string(),
+        "" => ".".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn key(cmd: &[&str]) -> String {
+        let command: Vec<String> = cmd.iter().map(ToString::to_string).collect();
+        command_intent_key(&command)
+    }
+
+    #[test]
+    fn reordered_search_flags_share_a_key() {
+        assert_eq!(key(&["rg", "foo", "src"]), "search \"foo\" in src");
+        assert_eq!(key(&["rg", "-n", "foo", "./src/"]), "search \"foo\" in src");
+        assert_eq!(
+            key(&["bash", "-lc", "grep -rn foo ./src"]),
+            "search \"foo\" in src"
+        );
+    }
+
+    #[test]
+    fn flag_values_are_not_paths() {
+        assert_eq!(
+            key(&["rg", "-g", "*.rs", "-t", "rust", "foo", "src"]),
+            "search \"foo\" in src"
+        );
+        assert_eq!(key(&["rg", "-e", "foo", "src"]), "search \"foo\" in src");
+        assert_eq!(
+            key(&["rg", "-C", "3", "-m", "5", "foo", "src"]),
+            "search \"foo\" in src"
+        );
+        assert_eq!(
+            key(&["bash", "-lc", "grep -rn -A 2 -B 2 foo src"]),
+            "search \"foo\" in src"
+        );
+    }
+
+    #[test]
+    fn reads_of_the_same_file_share_a_key() {
+        assert_eq!(key(&["cat", "core/src/lib.rs"]), "read core/src/lib.rs");
+        assert_eq!(
+            key(&["sed", "-n", "1,400p", "./core/src/lib.rs"]),
+            "read core/src/lib.rs"
+        );
+        assert_eq!(
+            key(&["tail", "-n", "30", "core/src/lib.rs"]),
+            "read core/src/lib.rs"
+        );
+        assert_ne!(key(&["cat", "a/mod.rs"]), key(&["cat", "b/mod.rs"]));
+    }
+
+    #[test]
+    fn listings_default_to_the_working_directory() {
+        assert_eq!(key(&["ls"]), "list .");
+        assert_eq!(key(&["ls", "-la", "./"]), "list .");
+        assert_eq!(key(&["ls", "-t", "src"]), "list src");
+    }
+
+    #[test]
+    fn unrecognized_commands_keep_their_text() {
+        assert_eq!(key(&["cargo", "check"]), "cargo check");
+    }
+
+    #[test]
+    fn rg_output_with_and_without_line_numbers_is_the_same() {
+        // `rg foo src` and `rg -n foo ./src` over the same tree.
+        let plain = "src/lib.rs:pub fn foo() {}\nsrc/main.rs:    let foo = 1;\n";
+        let numbered = "./src/lib.rs:1:pub fn foo() {}\n./src/main.rs:2:    let foo = 1;\n";
+        assert_eq!(
+            normalize_intent_output(numbered),
+            normalize_intent_output(plain)
+        );
+
+        // `rg foo src/main.rs` and `rg -n foo src/main.rs`.
+        assert_eq!(
+            normalize_intent_output("2:    let foo = 1;\n"),
+            normalize_intent_output("    let foo = 1;\n")
+        );
+        assert_ne!(
+            normalize_intent_output(plain),
+            normalize_intent_output("src/lib.rs:pub fn foo() {}\n")
+        );
+    }
+
+    #[test]
+    fn output_normalization_ignores_path_prefixes_and_padding() {
+        assert_eq!(
+            normalize_intent_output("\n./src/a.rs:1:foo  \nsrc/b.rs:2:foo\n"),
+            normalize_intent_output("src/a.rs:1:foo\nsrc/b.rs:2:foo")
+        );
+    }
+}
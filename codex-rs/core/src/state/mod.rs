@@ -1,13 +1,19 @@
+mod chunk_cache;
+mod command_intent;
+mod read_index;
 mod service;
 mod session;
 mod turn;
 
+pub(crate) use chunk_cache::CHUNK_CACHE_SUBDIR;
+pub(crate) use chunk_cache::ChunkLoad;
+pub(crate) use chunk_cache::ChunkStore;
+pub(crate) use read_index::ReadPartition;
 pub(crate) use service::SessionServices;
 pub(crate) use session::RepeatCommandBlock;
 pub(crate) use session::SessionState;
 pub(crate) use turn::ActiveTurn;
 pub(crate) use turn::RunningTask;
-pub(crate) use turn::TURN_OUTPUT_TRUNCATION_NOTICE;
 pub(crate) use turn::TaskKind;
 pub(crate) use turn::ToolBudgetDecision;
 pub(crate) use turn::TurnMetrics;
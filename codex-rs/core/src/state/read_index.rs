@@ -0,0 +1,231 @@
+//! Session-scoped index of code slices already served by `read_code`.
+//!
+//! Entries are keyed by the canonical file path and tagged with the git blob
+//! oid of the contents that were served, so any change to the file (through
+//! `apply_patch` or an on-disk edit) invalidates the recorded slices.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use sha1::Digest;
+use sha1::Sha1;
+
+/// Compute the git blob oid (`git hash-object`) for `contents`.
+pub(crate) fn git_blob_oid(contents: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", contents.len()).as_bytes());
+    hasher.update(contents.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// A slice that was already shown to the model in an earlier turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PreviouslyServed {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) turn: u64,
+}
+
+/// Result of checking requested ranges against the session index.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ReadPartition {
+    /// Ranges that have not been served for the current file contents.
+    pub(crate) fresh: Vec<(usize, usize)>,
+    /// Ranges that were served in an earlier turn and are unchanged since.
+    pub(crate) previously_served: Vec<PreviouslyServed>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SessionReadIndex {
+    files: HashMap<PathBuf, FileReads>,
+}
+
+#[derive(Debug)]
+struct FileReads {
+    blob_oid: String,
+    /// Non-overlapping served slices, sorted by start line.
+    slices: Vec<PreviouslyServed>,
+}
+
+impl SessionReadIndex {
+    /// Split `ranges` (sorted, non-overlapping) into the parts that still need
+    /// to be served and the parts already shown for the same file contents.
+    /// A changed `blob_oid` drops everything recorded for `path`.
+    pub(crate) fn partition(
+        &mut self,
+        path: &Path,
+        blob_oid: &str,
+        ranges: &[(usize, usize)],
+    ) -> ReadPartition {
+        let Some(reads) = self.files.get(path) else {
+            return ReadPartition {
+                fresh: ranges.to_vec(),
+                previously_served: Vec::new(),
+            };
+        };
+        if reads.blob_oid != blob_oid {
+            self.files.remove(path);
+            return ReadPartition {
+                fresh: ranges.to_vec(),
+                previously_served: Vec::new(),
+            };
+        }
+
+        let mut partition = ReadPartition::default();
+        for &(start, end) in ranges {
+            let mut cursor = start;
+            for slice in &reads.slices {
+                if slice.end < cursor {
+                    continue;
+                }
+                if slice.start > end {
+                    break;
+                }
+                if slice.start > cursor {
+                    partition.fresh.push((cursor, slice.start - 1));
+                }
+                let overlap_end = slice.end.min(end);
+                let overlap_start = slice.start.max(cursor);
+                match partition.previously_served.last_mut() {
+                    Some(last) if last.turn == slice.turn && last.end + 1 == overlap_start => {
+                        last.end = overlap_end;
+                    }
+                    _ => partition.previously_served.push(PreviouslyServed {
+                        start: overlap_start,
+                        end: overlap_end,
+                        turn: slice.turn,
+                    }),
+                }
+                cursor = overlap_end + 1;
+                if cursor > end {
+                    break;
+                }
+            }
+            if cursor <= end {
+                partition.fresh.push((cursor, end));
+            }
+        }
+        partition
+    }
+
+    /// Record `ranges` of `path` as served in `turn` for the given contents.
+    pub(crate) fn record(
+        &mut self,
+        path: &Path,
+        blob_oid: &str,
+        ranges: &[(usize, usize)],
+        turn: u64,
+    ) {
+        if ranges.is_empty() {
+            return;
+        }
+        let reads = self
+            .files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| FileReads {
+                blob_oid: blob_oid.to_string(),
+                slices: Vec::new(),
+            });
+        if reads.blob_oid != blob_oid {
+            reads.blob_oid = blob_oid.to_string();
+            reads.slices.clear();
+        }
+
+        for &(start, end) in ranges {
+            if start == 0 || start > end {
+                continue;
+            }
+            // Only record the parts not already indexed so slices stay disjoint.
+            let mut cursor = start;
+            let mut gaps = Vec::new();
+            for slice in &reads.slices {
+                if slice.end < cursor {
+                    continue;
+                }
+                if slice.start > end {
+                    break;
+                }
+                if slice.start > cursor {
+                    gaps.push((cursor, slice.start - 1));
+                }
+                cursor = slice.end + 1;
+            }
+            if cursor <= end {
+                gaps.push((cursor, end));
+            }
+            reads
+                .slices
+                .extend(
+                    gaps.into_iter()
+                        .map(|(start, end)| PreviouslyServed { start, end, turn }),
+                );
+            reads.slices.sort_by_key(|slice| slice.start);
+        }
+    }
+
+    pub(crate) fn invalidate(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.files.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn blob_oid_matches_git() {
+        // `printf 'hello\n' | git hash-object --stdin`
+        assert_eq!(
+            git_blob_oid("hello\n"),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+    }
+
+    #[test]
+    fn partitions_previously_served_ranges() {
+        let path = Path::new("/repo/src/lib.rs");
+        let mut index = SessionReadIndex::default();
+        index.record(path, "oid", &[(10, 14)], 1);
+        index.record(path, "oid", &[(15, 20)], 1);
+        index.record(path, "oid", &[(15, 30)], 2);
+
+        let partition = index.partition(path, "oid", &[(1, 40)]);
+        assert_eq!(partition.fresh, vec![(1, 9), (31, 40)]);
+        assert_eq!(
+            partition.previously_served,
+            vec![
+                PreviouslyServed {
+                    start: 10,
+                    end: 20,
+                    turn: 1
+                },
+                PreviouslyServed {
+                    start: 21,
+                    end: 30,
+                    turn: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_contents_invalidate_entries() {
+        let path = Path::new("/repo/src/lib.rs");
+        let mut index = SessionReadIndex::default();
+        index.record(path, "old", &[(1, 10)], 1);
+
+        let partition = index.partition(path, "new", &[(1, 10)]);
+        assert_eq!(partition.fresh, vec![(1, 10)]);
+        assert!(partition.previously_served.is_empty());
+
+        index.record(path, "old", &[(1, 10)], 1);
+        index.invalidate(path);
+        assert_eq!(index.partition(path, "old", &[(1, 5)]).fresh, vec![(1, 5)]);
+    }
+}
@@ -1,6 +1,10 @@
 use crate::RolloutRecorder;
+use crate::background_jobs::BackgroundJobManager;
+use crate::env_snapshot::EnvSnapshotCache;
 use crate::exec_command::ExecSessionManager;
+use crate::exec_policy::ExecPolicy;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::state::ChunkStore;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_notification::UserNotifier;
 use std::path::PathBuf;
@@ -10,9 +14,18 @@ pub(crate) struct SessionServices {
     pub(crate) mcp_connection_manager: McpConnectionManager,
     pub(crate) session_manager: ExecSessionManager,
     pub(crate) unified_exec_manager: UnifiedExecSessionManager,
+    pub(crate) background_jobs: BackgroundJobManager,
     pub(crate) notifier: UserNotifier,
     pub(crate) rollout: Mutex<Option<RolloutRecorder>>,
     pub(crate) codex_linux_sandbox_exe: Option<PathBuf>,
     pub(crate) user_shell: crate::shell::Shell,
+    /// Login-shell environment used when `profile_snapshot` is enabled.
+    pub(crate) env_snapshot: EnvSnapshotCache,
     pub(crate) show_raw_agent_reasoning: bool,
+    /// Where untrimmed command output is spilled so `read_code` can page it.
+    pub(crate) output_artifacts_dir: PathBuf,
+    /// Chunks of files read at a commit, shared with later sessions.
+    pub(crate) chunk_store: ChunkStore,
+    /// `.policy` files consulted by `assess_command_safety`.
+    pub(crate) exec_policy: ExecPolicy,
 }
@@ -3,29 +3,50 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::hash_map::Entry;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
+use std::time::SystemTime;
 
+use codex_protocol::config_types::CommandRule;
 use codex_protocol::models::ResponseItem;
 
+use crate::command_rules::CommandRules;
+use crate::config_types::ContextPolicy;
 use crate::conversation_history::ConversationHistory;
+use crate::protocol::ContextMetrics;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
+use crate::state::chunk_cache::ChunkCache;
+use crate::state::chunk_cache::ChunkLoad;
+use crate::state::chunk_cache::ChunkedFile;
+use crate::state::command_intent::command_intent_key;
+use crate::state::command_intent::normalize_intent_output;
+use crate::state::read_index::ReadPartition;
+use crate::state::read_index::SessionReadIndex;
+use crate::transcript_compaction::ToolOutputLedger;
 use crate::truncate::truncate_middle;
 
-const DEFAULT_REPEAT_COMMAND_REPEATS: usize = 3;
-const DEFAULT_REPEAT_COMMAND_WINDOW_SECS: u64 = 120;
 const REPEAT_COMMAND_OUTPUT_PREVIEW_BYTES: usize = 256;
 
 /// Persistent, session-scoped state previously stored directly on `Session`.
 #[derive(Default)]
 pub(crate) struct SessionState {
     pub(crate) approved_commands: HashSet<Vec<String>>,
+    /// Project approval rules, including those added during the session.
+    pub(crate) command_rules: CommandRules,
     pub(crate) history: ConversationHistory,
     pub(crate) token_info: Option<TokenUsageInfo>,
     pub(crate) latest_rate_limits: Option<RateLimitSnapshot>,
     repeat_command_breaker: RepeatCommandBreaker,
+    pending_relax: bool,
+    code_reads: SessionReadIndex,
+    code_chunks: ChunkCache,
+    tool_outputs: ToolOutputLedger,
+    turn_counter: u64,
+    context_totals: ContextMetrics,
 }
 
 impl SessionState {
@@ -53,6 +74,89 @@ impl SessionState {
 
     pub(crate) fn replace_history(&mut self, items: Vec<ResponseItem>) {
         self.history.replace(items);
+        // Slices served before the replacement are no longer in the model's
+        // context, so they must be served again on request.
+        self.code_reads.clear();
+        self.tool_outputs.clear();
+    }
+
+    /// Compact stale tool outputs in the history before it is sent again.
+    pub(crate) fn compact_stale_tool_outputs(&mut self) -> usize {
+        self.history
+            .compact_stale_tool_outputs(&mut self.tool_outputs)
+    }
+
+    pub(crate) fn tool_outputs_mut(&mut self) -> &mut ToolOutputLedger {
+        &mut self.tool_outputs
+    }
+
+    /// Allocate the 1-based number of a newly started turn.
+    pub(crate) fn begin_turn(&mut self) -> u64 {
+        self.turn_counter = self.turn_counter.saturating_add(1);
+        self.turn_counter
+    }
+
+    /// Fold a finished turn's counters into the session totals and return
+    /// the updated totals.
+    pub(crate) fn add_turn_metrics(&mut self, turn: &ContextMetrics) -> ContextMetrics {
+        self.context_totals.add_assign(turn);
+        self.context_totals.clone()
+    }
+
+    // Code read index helpers
+    pub(crate) fn partition_code_reads(
+        &mut self,
+        path: &Path,
+        blob_oid: &str,
+        ranges: &[(usize, usize)],
+    ) -> ReadPartition {
+        self.code_reads.partition(path, blob_oid, ranges)
+    }
+
+    pub(crate) fn record_code_reads(
+        &mut self,
+        path: &Path,
+        blob_oid: &str,
+        ranges: &[(usize, usize)],
+        turn: u64,
+    ) {
+        self.code_reads.record(path, blob_oid, ranges, turn);
+    }
+
+    pub(crate) fn invalidate_code_reads(&mut self, path: &Path) {
+        self.code_reads.invalidate(path);
+        self.code_chunks.invalidate(path);
+    }
+
+    pub(crate) fn cached_code_chunks(
+        &self,
+        head_oid: Option<&str>,
+        path: &Path,
+        size: u64,
+        mtime: Option<SystemTime>,
+    ) -> Option<Arc<ChunkedFile>> {
+        self.code_chunks.lookup(head_oid, path, size, mtime)
+    }
+
+    pub(crate) fn cache_code_chunks(
+        &mut self,
+        head_oid: Option<&str>,
+        path: &Path,
+        contents: String,
+        size: u64,
+        mtime: Option<SystemTime>,
+    ) -> ChunkLoad {
+        self.code_chunks
+            .insert(head_oid, path, contents, size, mtime)
+    }
+
+    pub(crate) fn adopt_code_chunks(
+        &mut self,
+        head_oid: &str,
+        path: &Path,
+        file: ChunkedFile,
+    ) -> ChunkLoad {
+        self.code_chunks.adopt(head_oid, path, file)
     }
 
     // Approved command helpers
@@ -64,6 +168,10 @@ impl SessionState {
         &self.approved_commands
     }
 
+    pub(crate) fn add_command_rule(&mut self, rule: CommandRule) {
+        self.command_rules.rules.push(rule);
+    }
+
     // Token/rate limit helpers
     pub(crate) fn update_token_info_from_usage(
         &mut self,
@@ -95,23 +203,50 @@ impl SessionState {
         self.repeat_command_breaker.check(command, now)
     }
 
+    /// Apply the repeat-command breaker limits from the context policy.
+    pub(crate) fn configure_repeat_command_breaker(
+        &mut self,
+        max_repeats: usize,
+        window: Duration,
+    ) {
+        self.repeat_command_breaker.config = RepeatCommandConfig {
+            max_repeats,
+            window,
+        };
+    }
+
     pub(crate) fn record_repeat_command(&mut self, command: &[String], output: &str, now: Instant) {
         self.repeat_command_breaker.record(command, output, now);
     }
 
+    // Relax helpers
+    /// Remember a `/relax` request made while no turn is running so it can be
+    /// applied to the next turn.
+    pub(crate) fn defer_relax(&mut self) {
+        self.pending_relax = true;
+    }
+
+    pub(crate) fn take_pending_relax(&mut self) -> bool {
+        std::mem::take(&mut self.pending_relax)
+    }
+
     // Pending input/approval moved to TurnState.
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct RepeatCommandBlock {
+    /// Canonical intent the blocked command shares with the earlier runs.
+    pub(crate) key: String,
     pub(crate) repeat_count: usize,
     pub(crate) window: Duration,
     pub(crate) last_excerpt: Option<String>,
 }
 
+/// Counts runs per canonical intent (see [`command_intent_key`]), so
+/// equivalent spellings of a command are treated as repeats.
 #[derive(Debug, Default)]
 struct RepeatCommandBreaker {
-    entries: HashMap<Vec<String>, RepeatCommandEntry>,
+    entries: HashMap<String, RepeatCommandEntry>,
     config: RepeatCommandConfig,
 }
 
@@ -123,9 +258,10 @@ struct RepeatCommandConfig {
 
 impl Default for RepeatCommandConfig {
     fn default() -> Self {
+        let policy = ContextPolicy::default();
         Self {
-            max_repeats: DEFAULT_REPEAT_COMMAND_REPEATS,
-            window: Duration::from_secs(DEFAULT_REPEAT_COMMAND_WINDOW_SECS),
+            max_repeats: policy.repeat_command_repeats,
+            window: policy.repeat_command_window,
         }
     }
 }
@@ -148,12 +284,11 @@ impl RepeatCommandBreaker {
             return None;
         }
 
-        let Some(entry) = self.entries.get_mut(command) else {
-            return None;
-        };
+        let key = command_intent_key(command);
+        let entry = self.entries.get_mut(&key)?;
 
         if now.saturating_duration_since(entry.last_seen) > self.config.window {
-            self.entries.remove(command);
+            self.entries.remove(&key);
             return None;
         }
 
@@ -164,6 +299,7 @@ impl RepeatCommandBreaker {
 
         if entry.repeat_count >= threshold {
             Some(RepeatCommandBlock {
+                key,
                 repeat_count: entry.repeat_count,
                 window: self.config.window,
                 last_excerpt: entry.last_excerpt.clone(),
@@ -178,10 +314,10 @@ impl RepeatCommandBreaker {
             return;
         }
 
-        let fingerprint = fingerprint_output(output);
+        let fingerprint = fingerprint_output(&normalize_intent_output(output));
         let excerpt = output_preview(output);
 
-        match self.entries.entry(command.to_vec()) {
+        match self.entries.entry(command_intent_key(command)) {
             Entry::Occupied(mut occ) => {
                 let entry = occ.get_mut();
                 if now.saturating_duration_since(entry.last_seen) > self.config.window
@@ -231,7 +367,48 @@ mod tests {
     use super::*;
 
     fn command(cmd: &[&str]) -> Vec<String> {
-        cmd.iter().map(|s| s.to_string()).collect()
+        cmd.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn replacing_history_forgets_served_code() {
+        let path = Path::new("/repo/src/lib.rs");
+        let mut state = SessionState::new();
+        assert_eq!(state.begin_turn(), 1);
+        state.record_code_reads(path, "oid", &[(1, 10)], 1);
+        assert!(
+            state
+                .partition_code_reads(path, "oid", &[(1, 10)])
+                .fresh
+                .is_empty()
+        );
+
+        state.replace_history(Vec::new());
+        assert_eq!(
+            state.partition_code_reads(path, "oid", &[(1, 10)]).fresh,
+            vec![(1, 10)]
+        );
+        assert_eq!(state.begin_turn(), 2);
+    }
+
+    #[test]
+    fn turn_metrics_accumulate_into_session_totals() {
+        let mut state = SessionState::new();
+        let turn = ContextMetrics {
+            bytes_served: 100,
+            outputs_truncated: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(state.add_turn_metrics(&turn), turn);
+        let totals = state.add_turn_metrics(&ContextMetrics {
+            bytes_served: 50,
+            commands_blocked: 2,
+            ..Default::default()
+        });
+        assert_eq!(totals.bytes_served, 150);
+        assert_eq!(totals.outputs_truncated, 1);
+        assert_eq!(totals.commands_blocked, 2);
     }
 
     #[test]
@@ -250,10 +427,7 @@ mod tests {
             .check(&cmd, now + Duration::from_secs(3))
             .expect("should block third run");
         assert_eq!(block.repeat_count, 2);
-        assert_eq!(
-            block.window,
-            Duration::from_secs(DEFAULT_REPEAT_COMMAND_WINDOW_SECS)
-        );
+        assert_eq!(block.window, ContextPolicy::default().repeat_command_window);
         assert_eq!(block.last_excerpt.as_deref(), Some("alpha"));
     }
 
@@ -285,9 +459,78 @@ mod tests {
             breaker
                 .check(
                     &cmd,
-                    now + Duration::from_secs(DEFAULT_REPEAT_COMMAND_WINDOW_SECS + 5)
+                    now + ContextPolicy::default().repeat_command_window + Duration::from_secs(5)
                 )
                 .is_none()
         );
     }
+
+    #[test]
+    fn breaker_counts_equivalent_commands_together() {
+        let mut breaker = RepeatCommandBreaker::default();
+        let now = Instant::now();
+
+        breaker.record(&command(&["rg", "foo", "src"]), "src/a.rs:foo\n", now);
+        breaker.record(
+            &command(&["rg", "-n", "foo", "./src"]),
+            "./src/a.rs:foo",
+            now + Duration::from_secs(1),
+        );
+        let block = breaker
+            .check(
+                &command(&["bash", "-lc", "rg foo src/"]),
+                now + Duration::from_secs(2),
+            )
+            .expect("equivalent search should block");
+        assert_eq!(block.key, "search \"foo\" in src");
+        assert_eq!(block.repeat_count, 2);
+    }
+
+    #[test]
+    fn breaker_treats_cat_and_sed_reads_of_a_file_as_repeats() {
+        let mut breaker = RepeatCommandBreaker::default();
+        let now = Instant::now();
+
+        breaker.record(&command(&["cat", "src/lib.rs"]), "fn main() {}\n", now);
+        breaker.record(
+            &command(&["sed", "-n", "1,400p", "src/lib.rs"]),
+            "fn main() {}\n",
+            now + Duration::from_secs(1),
+        );
+        assert!(
+            breaker
+                .check(
+                    &command(&["cat", "src/lib.rs"]),
+                    now + Duration::from_secs(2)
+                )
+                .is_some()
+        );
+        assert!(
+            breaker
+                .check(
+                    &command(&["cat", "src/main.rs"]),
+                    now + Duration::from_secs(2)
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn configured_breaker_uses_policy_limits() {
+        let mut state = SessionState::new();
+        state.configure_repeat_command_breaker(2, Duration::from_secs(10));
+        let cmd = command(&["cargo", "check"]);
+        let now = Instant::now();
+
+        state.record_repeat_command(&cmd, "same", now);
+        let block = state
+            .check_repeat_command(&cmd, now + Duration::from_secs(1))
+            .expect("should block second run");
+        assert_eq!(block.window, Duration::from_secs(10));
+        assert!(
+            state
+                .check_repeat_command(&cmd, now + Duration::from_secs(11))
+                .is_none()
+        );
+    }
 }
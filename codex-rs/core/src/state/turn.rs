@@ -2,6 +2,9 @@
 
 use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::task::AbortHandle;
@@ -9,19 +12,16 @@ use tokio::task::AbortHandle;
 use codex_protocol::models::ResponseInputItem;
 use tokio::sync::oneshot;
 
+use crate::config_types::ContextPolicy;
+use crate::protocol::ContextMetrics;
 use crate::protocol::ReviewDecision;
+use crate::rg_compact::SeenSearchMatches;
 use crate::tasks::SessionTask;
-
-/// Default per-turn budget for tool output (24 KiB).
-pub(crate) const PER_TURN_OUTPUT_MAX_BYTES: usize = 24 * 1024;
+use crate::truncate::format_byte_limit;
 
 /// Maximum bytes reserved for the per-turn truncation notice.
 const TURN_OUTPUT_NOTICE_RESERVE_BYTES: usize = 128;
 
-/// Truncation message appended when the per-turn output budget is exceeded.
-pub(crate) const TURN_OUTPUT_TRUNCATION_NOTICE: &str =
-    "[turn output truncated after reaching 24 KiB; refine your request or use /relax]";
-
 /// Metadata about the currently running turn.
 pub(crate) struct ActiveTurn {
     pub(crate) tasks: IndexMap<String, RunningTask>,
@@ -73,9 +73,26 @@ pub(crate) struct TurnState {
     tool_output_budget: ToolOutputBudget,
     metrics: TurnMetrics,
     code_read_index: HashMap<String, IntervalSet>,
+    relaxed: bool,
+    breaker_bypass_available: bool,
+    turn_number: u64,
+    /// Files declared by the latest accepted `plan_reads` call.
+    planned_reads: Option<HashSet<String>>,
+    seen_search_matches: SeenSearchMatches,
+    /// `HEAD` oid of each workspace root, resolved once per turn.
+    head_oids: HashMap<PathBuf, Option<String>>,
 }
 
 impl TurnState {
+    /// 1-based turn number within the session (0 when not yet assigned).
+    pub(crate) fn turn_number(&self) -> u64 {
+        self.turn_number
+    }
+
+    pub(crate) fn set_turn_number(&mut self, turn_number: u64) {
+        self.turn_number = turn_number;
+    }
+
     pub(crate) fn reserve_tool_output(
         &mut self,
         desired_bytes: usize,
@@ -85,6 +102,19 @@ impl TurnState {
             .reserve(desired_bytes, notice_len, &mut self.metrics)
     }
 
+    /// Set the tool output budget for this turn from the context policy.
+    pub(crate) fn set_tool_output_limit(&mut self, max_bytes: usize) {
+        self.tool_output_budget.max_bytes = max_bytes;
+    }
+
+    /// Truncation message appended when the tool output budget is exceeded.
+    pub(crate) fn tool_output_truncation_notice(&self) -> String {
+        format!(
+            "[turn output truncated after reaching {}; refine your request or use /relax]",
+            format_byte_limit(self.tool_output_budget.max_bytes)
+        )
+    }
+
     pub(crate) fn record_command_blocked(&mut self) {
         self.metrics.commands_blocked = self.metrics.commands_blocked.saturating_add(1);
     }
@@ -93,6 +123,75 @@ impl TurnState {
         self.metrics.log_tail_invocations = self.metrics.log_tail_invocations.saturating_add(1);
     }
 
+    /// Count `chunks` served by `read_code` as chunk-cache hits or misses.
+    pub(crate) fn record_chunk_cache_access(&mut self, chunks: usize, cache_hit: bool) {
+        let counter = if cache_hit {
+            &mut self.metrics.chunk_cache_hits
+        } else {
+            &mut self.metrics.chunk_cache_misses
+        };
+        *counter = counter.saturating_add(chunks);
+    }
+
+    /// Relax the context budgets for the remainder of this turn: raise the
+    /// per-turn output budget to `turn_output_max_bytes`, loosen `read_code`
+    /// caps and allow one repeat-command breaker bypass. Returns `false` when
+    /// the turn was already relaxed.
+    pub(crate) fn grant_relax(&mut self, turn_output_max_bytes: usize) -> bool {
+        if self.relaxed {
+            return false;
+        }
+        self.relaxed = true;
+        self.breaker_bypass_available = true;
+        self.tool_output_budget.raise_limit(turn_output_max_bytes);
+        self.metrics.relax_grants = self.metrics.relax_grants.saturating_add(1);
+        true
+    }
+
+    pub(crate) fn is_relaxed(&self) -> bool {
+        self.relaxed
+    }
+
+    /// Consume the single repeat-command breaker bypass granted by `/relax`.
+    pub(crate) fn take_breaker_bypass(&mut self) -> bool {
+        if !self.breaker_bypass_available {
+            return false;
+        }
+        self.breaker_bypass_available = false;
+        self.metrics.breaker_bypasses = self.metrics.breaker_bypasses.saturating_add(1);
+        true
+    }
+
+    /// Bytes still available in the per-turn tool output budget.
+    pub(crate) fn remaining_tool_output_budget(&self) -> usize {
+        self.tool_output_budget.remaining()
+    }
+
+    /// Adopt an accepted `plan_reads` plan covering `files` (workspace-relative
+    /// paths). A later plan replaces the earlier one.
+    pub(crate) fn accept_read_plan(&mut self, files: HashSet<String>, estimated_bytes: usize) {
+        self.planned_reads = Some(files);
+        self.metrics.read_plans_accepted = self.metrics.read_plans_accepted.saturating_add(1);
+        self.metrics.planned_read_bytes = self
+            .metrics
+            .planned_read_bytes
+            .saturating_add(estimated_bytes);
+    }
+
+    pub(crate) fn record_read_plan_rejected(&mut self) {
+        self.metrics.read_plans_rejected = self.metrics.read_plans_rejected.saturating_add(1);
+    }
+
+    /// Count `bytes` returned by `read_code` for `path` against the plan.
+    pub(crate) fn record_read_code_output(&mut self, path: &str, bytes: usize) {
+        self.metrics.read_code_bytes = self.metrics.read_code_bytes.saturating_add(bytes);
+        if let Some(files) = &self.planned_reads
+            && !files.contains(path)
+        {
+            self.metrics.unplanned_reads = self.metrics.unplanned_reads.saturating_add(1);
+        }
+    }
+
     pub(crate) fn drain_metrics(&mut self) -> TurnMetrics {
         std::mem::take(&mut self.metrics)
     }
@@ -102,14 +201,21 @@ impl TurnState {
         path: &str,
         ranges: &[(usize, usize)],
     ) -> (Vec<(usize, usize)>, bool) {
+        // Coalesce the requested ranges first so overlapping or adjacent
+        // slices from a multi-range request are only served once.
+        let mut requested = IntervalSet::default();
+        for &(start, end) in ranges {
+            requested.insert(start, end);
+        }
+
         let Some(intervals) = self.code_read_index.get(path) else {
-            return (ranges.to_vec(), false);
+            return (requested.intervals, false);
         };
 
         let mut uncovered = Vec::new();
         let mut had_overlap = false;
 
-        for &(start, end) in ranges {
+        for &(start, end) in &requested.intervals {
             if start == 0 || end == 0 || start > end {
                 continue;
             }
@@ -138,16 +244,32 @@ impl TurnState {
         if ranges.is_empty() {
             return;
         }
-        let entry = self
-            .code_read_index
-            .entry(path.to_string())
-            .or_insert_with(IntervalSet::default);
+        let entry = self.code_read_index.entry(path.to_string()).or_default();
 
         for &(start, end) in ranges {
             entry.insert(start, end);
         }
     }
 
+    pub(crate) fn forget_code_ranges(&mut self, path: &str) {
+        self.code_read_index.remove(path);
+    }
+
+    /// `rg` matches already reported to the model this turn.
+    pub(crate) fn seen_search_matches_mut(&mut self) -> &mut SeenSearchMatches {
+        &mut self.seen_search_matches
+    }
+
+    /// `HEAD` oid of `workspace_root` if it was already resolved this turn.
+    pub(crate) fn head_oid(&self, workspace_root: &Path) -> Option<Option<String>> {
+        self.head_oids.get(workspace_root).cloned()
+    }
+
+    pub(crate) fn set_head_oid(&mut self, workspace_root: &Path, head_oid: Option<String>) {
+        self.head_oids
+            .insert(workspace_root.to_path_buf(), head_oid);
+    }
+
     pub(crate) fn insert_pending_approval(
         &mut self,
         key: String,
@@ -203,9 +325,15 @@ impl Default for TurnState {
         Self {
             pending_approvals: HashMap::new(),
             pending_input: Vec::new(),
-            tool_output_budget: ToolOutputBudget::new(PER_TURN_OUTPUT_MAX_BYTES),
+            tool_output_budget: ToolOutputBudget::new(ContextPolicy::default().per_turn_max_bytes),
             metrics: TurnMetrics::default(),
             code_read_index: HashMap::new(),
+            relaxed: false,
+            breaker_bypass_available: false,
+            turn_number: 0,
+            planned_reads: None,
+            seen_search_matches: SeenSearchMatches::default(),
+            head_oids: HashMap::new(),
         }
     }
 }
@@ -301,6 +429,18 @@ pub(crate) struct TurnMetrics {
     pub(crate) outputs_truncated: usize,
     pub(crate) commands_blocked: usize,
     pub(crate) log_tail_invocations: usize,
+    pub(crate) relax_grants: usize,
+    pub(crate) breaker_bypasses: usize,
+    pub(crate) chunk_cache_hits: usize,
+    pub(crate) chunk_cache_misses: usize,
+    pub(crate) read_plans_accepted: usize,
+    pub(crate) read_plans_rejected: usize,
+    /// Bytes estimated by accepted `plan_reads` plans.
+    pub(crate) planned_read_bytes: usize,
+    /// Bytes actually returned by `read_code`.
+    pub(crate) read_code_bytes: usize,
+    /// `read_code` calls for files outside the accepted plan.
+    pub(crate) unplanned_reads: usize,
 }
 
 impl TurnMetrics {
@@ -310,6 +450,36 @@ impl TurnMetrics {
             && self.outputs_truncated == 0
             && self.commands_blocked == 0
             && self.log_tail_invocations == 0
+            && self.relax_grants == 0
+            && self.breaker_bypasses == 0
+            && self.chunk_cache_hits == 0
+            && self.chunk_cache_misses == 0
+            && self.read_plans_accepted == 0
+            && self.read_plans_rejected == 0
+            && self.planned_read_bytes == 0
+            && self.read_code_bytes == 0
+            && self.unplanned_reads == 0
+    }
+}
+
+impl From<&TurnMetrics> for ContextMetrics {
+    fn from(metrics: &TurnMetrics) -> Self {
+        Self {
+            bytes_served: metrics.bytes_served as u64,
+            bytes_trimmed: metrics.bytes_trimmed as u64,
+            outputs_truncated: metrics.outputs_truncated as u64,
+            commands_blocked: metrics.commands_blocked as u64,
+            log_tail_invocations: metrics.log_tail_invocations as u64,
+            relax_grants: metrics.relax_grants as u64,
+            breaker_bypasses: metrics.breaker_bypasses as u64,
+            chunk_cache_hits: metrics.chunk_cache_hits as u64,
+            chunk_cache_misses: metrics.chunk_cache_misses as u64,
+            read_plans_accepted: metrics.read_plans_accepted as u64,
+            read_plans_rejected: metrics.read_plans_rejected as u64,
+            planned_read_bytes: metrics.planned_read_bytes as u64,
+            read_code_bytes: metrics.read_code_bytes as u64,
+            unplanned_reads: metrics.unplanned_reads as u64,
+        }
     }
 }
 
@@ -327,6 +497,10 @@ impl ToolOutputBudget {
         }
     }
 
+    fn raise_limit(&mut self, max_bytes: usize) {
+        self.max_bytes = self.max_bytes.max(max_bytes);
+    }
+
     fn remaining(&self) -> usize {
         self.max_bytes.saturating_sub(self.used_bytes)
     }
@@ -421,10 +595,24 @@ mod tests {
         assert!(overlap);
     }
 
+    #[test]
+    fn turn_state_coalesces_multiple_ranges() {
+        let mut state = TurnState::default();
+        let (unserved, _) =
+            state.compute_unserved_code_ranges("file.rs", &[(10, 20), (1, 4), (15, 30)]);
+        assert_eq!(unserved, vec![(1, 4), (10, 30)]);
+
+        state.record_served_code_ranges("file.rs", &[(1, 4)]);
+        let (unserved, overlap) =
+            state.compute_unserved_code_ranges("file.rs", &[(1, 2), (40, 45), (3, 6)]);
+        assert_eq!(unserved, vec![(5, 6), (40, 45)]);
+        assert!(overlap);
+    }
+
     #[test]
     fn reserves_full_output_when_under_budget() {
         let mut state = TurnState::default();
-        let decision = state.reserve_tool_output(1024, TURN_OUTPUT_TRUNCATION_NOTICE.len());
+        let decision = state.reserve_tool_output(1024, state.tool_output_truncation_notice().len());
 
         assert!(!decision.truncated);
         assert_eq!(decision.allowed_content_bytes, 1024);
@@ -436,7 +624,7 @@ mod tests {
     #[test]
     fn reserves_with_truncation_and_notice() {
         let mut state = TurnState::default();
-        let _ = state.reserve_tool_output(PER_TURN_OUTPUT_MAX_BYTES - 100, 0);
+        let _ = state.reserve_tool_output(ContextPolicy::default().per_turn_max_bytes - 100, 0);
         state.drain_metrics();
 
         let decision = state.reserve_tool_output(200, 80);
@@ -451,7 +639,7 @@ mod tests {
     #[test]
     fn reserves_notice_even_when_budget_exhausted() {
         let mut state = TurnState::default();
-        let _ = state.reserve_tool_output(PER_TURN_OUTPUT_MAX_BYTES, 0);
+        let _ = state.reserve_tool_output(ContextPolicy::default().per_turn_max_bytes, 0);
         state.drain_metrics();
 
         let decision = state.reserve_tool_output(512, 64);
@@ -463,6 +651,20 @@ mod tests {
         assert_eq!(state.metrics.outputs_truncated, 1);
     }
 
+    #[test]
+    fn configured_limit_drives_budget_and_notice() {
+        let mut state = TurnState::default();
+        state.set_tool_output_limit(5000);
+        assert_eq!(
+            state.tool_output_truncation_notice(),
+            "[turn output truncated after reaching 5000 bytes; refine your request or use /relax]"
+        );
+
+        let decision = state.reserve_tool_output(6000, 0);
+        assert!(decision.truncated);
+        assert_eq!(decision.allowed_content_bytes, 5000);
+    }
+
     #[test]
     fn draining_metrics_resets_counters() {
         let mut state = TurnState::default();
@@ -472,10 +674,48 @@ mod tests {
         assert!(state.metrics.is_empty());
     }
 
+    #[test]
+    fn relax_raises_budget_once_and_allows_single_bypass() {
+        let mut state = TurnState::default();
+        let _ = state.reserve_tool_output(ContextPolicy::default().per_turn_max_bytes, 0);
+        assert!(!state.take_breaker_bypass());
+
+        assert!(state.grant_relax(ContextPolicy::default().relax_per_turn_max_bytes));
+        assert!(!state.grant_relax(ContextPolicy::default().relax_per_turn_max_bytes));
+        assert!(state.is_relaxed());
+
+        let decision = state.reserve_tool_output(1024, 0);
+        assert!(!decision.truncated);
+
+        assert!(state.take_breaker_bypass());
+        assert!(!state.take_breaker_bypass());
+
+        let metrics = state.drain_metrics();
+        assert_eq!(metrics.relax_grants, 1);
+        assert_eq!(metrics.breaker_bypasses, 1);
+    }
+
     #[test]
     fn recording_log_tail_increments_metric() {
         let mut state = TurnState::default();
         state.record_log_tail();
         assert_eq!(state.metrics.log_tail_invocations, 1);
     }
+
+    #[test]
+    fn read_plan_tracks_planned_and_actual_reads() {
+        let mut state = TurnState::default();
+        state.record_read_code_output("src/main.rs", 100);
+        state.accept_read_plan(HashSet::from(["src/lib.rs".to_string()]), 2048);
+        state.record_read_code_output("src/lib.rs", 1500);
+        state.record_read_code_output("src/other.rs", 200);
+        state.record_read_plan_rejected();
+
+        let metrics = state.drain_metrics();
+        assert_eq!(metrics.read_plans_accepted, 1);
+        assert_eq!(metrics.read_plans_rejected, 1);
+        assert_eq!(metrics.planned_read_bytes, 2048);
+        assert_eq!(metrics.read_code_bytes, 1800);
+        assert_eq!(metrics.unplanned_reads, 1);
+    }
 }
@@ -90,7 +90,8 @@ impl Session {
             kind: task_kind,
             task,
         };
-        self.register_new_active_task(sub_id, running_task).await;
+        self.register_new_active_task(sub_id, running_task, &turn_context)
+            .await;
     }
 
     pub async fn abort_all_tasks(self: &Arc<Self>, reason: TurnAbortReason) {
@@ -118,14 +119,12 @@ impl Session {
             (turn_state, removed_last_task)
         };
 
-        if removed_last_task {
-            if let Some(turn_state) = turn_state {
-                let metrics = {
-                    let mut guard = turn_state.lock().await;
-                    guard.drain_metrics()
-                };
-                self.log_turn_metrics(&sub_id, metrics).await;
-            }
+        if removed_last_task && let Some(turn_state) = turn_state {
+            let metrics = {
+                let mut guard = turn_state.lock().await;
+                guard.drain_metrics()
+            };
+            self.emit_turn_metrics(&sub_id, metrics).await;
         }
 
         let event = Event {
@@ -135,9 +134,24 @@ impl Session {
         self.send_event(event).await;
     }
 
-    async fn register_new_active_task(&self, sub_id: String, task: RunningTask) {
+    async fn register_new_active_task(
+        &self,
+        sub_id: String,
+        task: RunningTask,
+        turn_context: &TurnContext,
+    ) {
+        let turn_number = self.begin_turn().await;
+        let pending_relax = self.take_pending_relax().await;
         let mut active = self.active_turn.lock().await;
         let mut turn = ActiveTurn::default();
+        {
+            let mut ts = turn.turn_state.lock().await;
+            ts.set_turn_number(turn_number);
+            ts.set_tool_output_limit(turn_context.context_policy.per_turn_max_bytes);
+            if pending_relax {
+                ts.grant_relax(turn_context.context_policy.relax_per_turn_max_bytes);
+            }
+        }
         turn.add_task(sub_id, task);
         *active = Some(turn);
     }
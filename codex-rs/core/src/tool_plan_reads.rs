@@ -0,0 +1,391 @@
+//! `plan_reads`: let the model declare the reads it intends to make in a turn
+//! so the plan can be checked against the remaining budget before any bytes
+//! are spent.
+
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+use crate::code_symbols::resolve_symbol;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::function_tool::FunctionCallError;
+use crate::state::ReadPartition;
+use crate::tool_read_code::WorkspaceFile;
+use crate::tool_read_code::merge_ranges;
+use crate::tool_read_code::normalize_ranges;
+use crate::tool_read_code::open_workspace_file;
+use crate::tool_read_code::per_call_caps;
+
+const MAX_PLANNED_READS: usize = 16;
+
+#[derive(Debug, Deserialize)]
+struct PlanReadsArgs {
+    reads: Vec<PlannedRead>,
+    budget_bytes: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlannedRead {
+    path: String,
+    #[serde(default)]
+    lines: Option<Vec<[usize; 2]>>,
+    #[serde(default)]
+    symbol: Option<String>,
+}
+
+/// Estimated cost of one planned read after skipping slices already shown.
+#[derive(Debug)]
+struct ReadEstimate {
+    rel_path: String,
+    target: String,
+    bytes: usize,
+    calls: usize,
+    notes: Vec<String>,
+}
+
+pub(crate) async fn handle_plan_reads_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    arguments: String,
+) -> Result<String, FunctionCallError> {
+    let args: PlanReadsArgs = serde_json::from_str(&arguments).map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {err}"))
+    })?;
+
+    if args.reads.is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "plan must include at least one read".to_string(),
+        ));
+    }
+    if args.reads.len() > MAX_PLANNED_READS {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "plan includes {} reads; at most {MAX_PLANNED_READS} can be planned at once",
+            args.reads.len()
+        )));
+    }
+
+    let relaxed = sess.is_turn_relaxed().await;
+    let mut problems = Vec::new();
+    let mut estimates = Vec::new();
+    for (idx, read) in args.reads.iter().enumerate() {
+        match estimate_read(sess, turn_context, read, relaxed).await {
+            Ok(estimate) => estimates.push(estimate),
+            Err(err) => problems.push(format!("reads[{idx}] ({}): {err}", read.path)),
+        }
+    }
+
+    let estimated_bytes: usize = estimates.iter().map(|estimate| estimate.bytes).sum();
+    let remaining = sess
+        .remaining_tool_output_budget()
+        .await
+        .unwrap_or(usize::MAX);
+
+    if estimated_bytes > remaining {
+        let largest = estimates
+            .iter()
+            .max_by_key(|estimate| estimate.bytes)
+            .map(|estimate| {
+                format!(
+                    " (largest: {} {}, ~{} bytes)",
+                    estimate.rel_path, estimate.target, estimate.bytes
+                )
+            })
+            .unwrap_or_default();
+        problems.push(format!(
+            "plan needs ~{estimated_bytes} bytes but only {remaining} bytes remain in this turn's tool output budget; drop or narrow reads{largest}, or call request_relax"
+        ));
+    }
+    if args.budget_bytes < estimated_bytes {
+        problems.push(format!(
+            "budget_bytes {} is below the estimated {estimated_bytes} bytes; raise budget_bytes or narrow the plan",
+            args.budget_bytes
+        ));
+    }
+    if args.budget_bytes > remaining {
+        problems.push(format!(
+            "budget_bytes {} exceeds the {remaining} bytes remaining in this turn's tool output budget",
+            args.budget_bytes
+        ));
+    }
+
+    if !problems.is_empty() {
+        sess.record_read_plan_rejected().await;
+        let mut message = String::from("plan rejected:");
+        for problem in problems {
+            message.push_str("\n- ");
+            message.push_str(&problem);
+        }
+        return Err(FunctionCallError::RespondToModel(message));
+    }
+
+    let files: HashSet<String> = estimates
+        .iter()
+        .map(|estimate| estimate.rel_path.clone())
+        .collect();
+    sess.accept_read_plan(files, estimated_bytes).await;
+
+    let mut output = format!(
+        "plan accepted: {} read(s), ~{estimated_bytes} of {} budgeted bytes",
+        estimates.len(),
+        args.budget_bytes
+    );
+    if remaining != usize::MAX {
+        output.push_str(&format!("; {remaining} bytes remain this turn"));
+    }
+    for estimate in &estimates {
+        output.push_str(&format!(
+            "\n- {} {}: ~{} bytes",
+            estimate.rel_path, estimate.target, estimate.bytes
+        ));
+        if estimate.calls > 1 {
+            output.push_str(&format!(" across {} read_code calls", estimate.calls));
+        }
+        for note in &estimate.notes {
+            output.push_str(&format!("; {note}"));
+        }
+    }
+    Ok(output)
+}
+
+async fn estimate_read(
+    sess: &Session,
+    turn_context: &TurnContext,
+    read: &PlannedRead,
+    relaxed: bool,
+) -> Result<ReadEstimate, String> {
+    if read.symbol.is_some() && read.lines.is_some() {
+        return Err("provide either `lines` or `symbol`, not both".to_string());
+    }
+
+    let WorkspaceFile {
+        canonical_path,
+        rel_path,
+        metadata,
+        chunks,
+    } = open_workspace_file(sess, turn_context, &read.path)
+        .await
+        .map_err(|err| err.to_string())?;
+    let file = chunks.file;
+    let lines = file.lines();
+    let line_count = lines.len();
+
+    let (target, mut ranges) = if let Some(symbol) = read.symbol.as_deref() {
+        let span = resolve_symbol(&canonical_path, file.contents(), symbol)
+            .map_err(|err| err.to_string())?;
+        (
+            format!(
+                "symbol `{}` (lines {}-{})",
+                span.qualified_name, span.start_line, span.end_line
+            ),
+            vec![(span.start_line, span.end_line)],
+        )
+    } else if let Some(requested) = &read.lines {
+        let ranges: Vec<(usize, usize)> =
+            requested.iter().map(|&[start, end]| (start, end)).collect();
+        let target = ranges
+            .iter()
+            .map(|(start, end)| format!("{start}-{end}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        (format!("lines {target}"), ranges)
+    } else {
+        ("whole file".to_string(), vec![(1, line_count.max(1))])
+    };
+
+    normalize_ranges(&mut ranges).map_err(|err| err.to_string())?;
+    for range in &mut ranges {
+        range.1 = range.1.min(line_count);
+    }
+    ranges.retain(|(start, end)| start <= end);
+    merge_ranges(&mut ranges);
+    if ranges.is_empty() {
+        return Err("requested lines are outside the file".to_string());
+    }
+
+    let policy = &turn_context.context_policy;
+    let mut notes = Vec::new();
+    let ReadPartition {
+        fresh,
+        previously_served,
+    } = if policy.overlap_suppression {
+        let (unserved, had_overlap) = sess.compute_unserved_code_ranges(&rel_path, &ranges).await;
+        if had_overlap {
+            notes.push("part was already served earlier in this turn".to_string());
+        }
+        sess.partition_session_code_ranges(&canonical_path, file.blob_oid(), &unserved)
+            .await
+    } else {
+        ReadPartition {
+            fresh: ranges,
+            previously_served: Vec::new(),
+        }
+    };
+    for slice in &previously_served {
+        notes.push(format!(
+            "lines {}-{} already shown in turn {}; skip them",
+            slice.start, slice.end, slice.turn
+        ));
+    }
+
+    let (max_lines, max_bytes) =
+        per_call_caps(policy, &canonical_path, relaxed, metadata.len(), line_count);
+    let header_bytes = format!("path: {rel_path}\n\n").len();
+    let mut bytes = 0usize;
+    let mut fresh_lines = 0usize;
+    for &(start, end) in &fresh {
+        bytes += format!("lines {start}-{end}:\n").len();
+        bytes += lines[start - 1..end]
+            .iter()
+            .map(|line| line.len())
+            .sum::<usize>();
+        fresh_lines += end - start + 1;
+    }
+    let calls = if fresh.is_empty() {
+        0
+    } else {
+        bytes
+            .div_ceil(max_bytes)
+            .max(fresh_lines.div_ceil(max_lines))
+            .max(1)
+    };
+    bytes += header_bytes * calls;
+
+    Ok(ReadEstimate {
+        rel_path,
+        target,
+        bytes,
+        calls,
+        notes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codex::make_session_and_context;
+    use crate::state::ActiveTurn;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    /// A session working in a fresh workspace holding a ten-line `lib.rs`.
+    fn session_in_workspace() -> (Session, TurnContext, TempDir) {
+        let workspace = TempDir::new().expect("tempdir");
+        let contents: String = (1..=10).map(|n| format!("line {n}\n")).collect();
+        std::fs::write(workspace.path().join("lib.rs"), contents).expect("write lib.rs");
+        let (sess, mut turn_context) = make_session_and_context();
+        turn_context.cwd = workspace.path().to_path_buf();
+        (sess, turn_context, workspace)
+    }
+
+    async fn start_turn(sess: &Session, turn_number: u64, tool_output_limit: usize) {
+        let turn = ActiveTurn::default();
+        {
+            let mut ts = turn.turn_state.lock().await;
+            ts.set_turn_number(turn_number);
+            ts.set_tool_output_limit(tool_output_limit);
+        }
+        *sess.active_turn.lock().await = Some(turn);
+    }
+
+    async fn plan(
+        sess: &Session,
+        turn_context: &TurnContext,
+        args: serde_json::Value,
+    ) -> Result<String, FunctionCallError> {
+        handle_plan_reads_tool_call(sess, turn_context, args.to_string()).await
+    }
+
+    #[tokio::test]
+    async fn plans_over_the_read_limit_are_rejected() {
+        let (sess, turn_context, _workspace) = session_in_workspace();
+        let reads = vec![json!({ "path": "lib.rs" }); MAX_PLANNED_READS + 1];
+
+        assert_eq!(
+            plan(
+                &sess,
+                &turn_context,
+                json!({ "reads": reads, "budget_bytes": 10_000 })
+            )
+            .await,
+            Err(FunctionCallError::RespondToModel(
+                "plan includes 17 reads; at most 16 can be planned at once".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn budget_below_the_estimate_is_rejected() {
+        let (sess, turn_context, _workspace) = session_in_workspace();
+        start_turn(&sess, 1, 10_000).await;
+
+        assert_eq!(
+            plan(
+                &sess,
+                &turn_context,
+                json!({ "reads": [{ "path": "lib.rs" }], "budget_bytes": 10 })
+            )
+            .await,
+            Err(FunctionCallError::RespondToModel(
+                "plan rejected:\n- budget_bytes 10 is below the estimated 97 bytes; raise budget_bytes or narrow the plan".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn budget_above_the_remaining_turn_budget_is_rejected() {
+        let (sess, turn_context, _workspace) = session_in_workspace();
+        start_turn(&sess, 1, 200).await;
+
+        assert_eq!(
+            plan(
+                &sess,
+                &turn_context,
+                json!({ "reads": [{ "path": "lib.rs" }], "budget_bytes": 500 })
+            )
+            .await,
+            Err(FunctionCallError::RespondToModel(
+                "plan rejected:\n- budget_bytes 500 exceeds the 200 bytes remaining in this turn's tool output budget".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn lines_already_served_or_shown_are_noted_and_not_counted() {
+        let (sess, turn_context, _workspace) = session_in_workspace();
+        let file = open_workspace_file(&sess, &turn_context, "lib.rs")
+            .await
+            .expect("open lib.rs");
+        let blob_oid = file.chunks.file.blob_oid().to_string();
+
+        start_turn(&sess, 1, 10_000).await;
+        sess.record_served_code_ranges(
+            "call-1",
+            "lib.rs",
+            &file.canonical_path,
+            &blob_oid,
+            &[(4, 6)],
+        )
+        .await;
+        start_turn(&sess, 2, 10_000).await;
+        sess.record_served_code_ranges(
+            "call-2",
+            "lib.rs",
+            &file.canonical_path,
+            &blob_oid,
+            &[(1, 3)],
+        )
+        .await;
+
+        assert_eq!(
+            plan(
+                &sess,
+                &turn_context,
+                json!({ "reads": [{ "path": "lib.rs", "lines": [[1, 8]] }], "budget_bytes": 1_000 })
+            )
+            .await,
+            Ok("plan accepted: 1 read(s), ~39 of 1000 budgeted bytes; 10000 bytes remain this turn\n- lib.rs lines 1-8: ~39 bytes; part was already served earlier in this turn; lines 4-6 already shown in turn 1; skip them".to_string())
+        );
+    }
+}
@@ -2,15 +2,20 @@ use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
+use crate::code_symbols::resolve_symbol;
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::config_types::ContextPolicy;
 use crate::function_tool::FunctionCallError;
-use crate::state::TURN_OUTPUT_TRUNCATION_NOTICE;
+use crate::output_spill::resolve_output_handle;
+use crate::path_masks::PathMaskKind;
+use crate::path_masks::masked_path_kind;
+use crate::protocol::SandboxPolicy;
+use crate::safety::normalize;
+use crate::state::ChunkLoad;
+use crate::state::ReadPartition;
 
-const DEFAULT_MAX_LINES: usize = 160;
-const DEFAULT_MAX_BYTES: usize = 8 * 1024;
-const SMALL_FILE_MAX_LINES: usize = 400;
-const SMALL_FILE_MAX_BYTES: usize = 16 * 1024;
+const MAX_RANGES_PER_CALL: usize = 8;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -46,13 +51,15 @@ impl LinesArg {
                 if ranges.is_empty() {
                     return Err(invalid_arguments("lines must include at least one range"));
                 }
-                if ranges.len() > 1 {
-                    return Err(invalid_arguments(
-                        "multiple line ranges are not supported yet; provide a single [start, end] range",
-                    ));
+                if ranges.len() > MAX_RANGES_PER_CALL {
+                    return Err(invalid_arguments(format!(
+                        "at most {MAX_RANGES_PER_CALL} line ranges can be requested per call"
+                    )));
                 }
-                let [start, end] = ranges[0];
-                Ok(vec![(start, end)])
+                Ok(ranges
+                    .into_iter()
+                    .map(|[start, end]| (start, end))
+                    .collect())
             }
         }
     }
@@ -61,6 +68,7 @@ impl LinesArg {
 pub(crate) async fn handle_read_code_tool_call(
     sess: &Session,
     turn_context: &TurnContext,
+    call_id: &str,
     arguments: String,
 ) -> Result<String, FunctionCallError> {
     let args: ReadCodeArgs = serde_json::from_str(&arguments)
@@ -70,63 +78,40 @@ pub(crate) async fn handle_read_code_tool_call(
         return Err(invalid_arguments("path must not be empty"));
     }
 
-    if args.symbol.is_some() {
+    if args.symbol.is_some() && args.lines.is_some() {
         return Err(invalid_arguments(
-            "symbol lookups are not yet supported; request an explicit line range instead",
+            "provide either `lines` or `symbol`, not both",
         ));
     }
 
-    let candidate_path = turn_context.resolve_path(Some(args.path.clone()));
-    let canonical_path = tokio::fs::canonicalize(&candidate_path)
-        .await
-        .map_err(|err| {
-            FunctionCallError::RespondToModel(format!(
-                "failed to resolve {path}: {err}",
-                path = args.path
-            ))
-        })?;
-    let workspace_root = tokio::fs::canonicalize(&turn_context.cwd)
-        .await
-        .map_err(|err| {
-            FunctionCallError::RespondToModel(format!(
-                "failed to resolve workspace root {path}: {err}",
-                path = turn_context.cwd.display()
-            ))
-        })?;
-    validate_within_workspace(&canonical_path, &workspace_root)?;
-
-    let metadata = tokio::fs::metadata(&canonical_path).await.map_err(|err| {
-        FunctionCallError::RespondToModel(format!(
-            "failed to read metadata for {path}: {err}",
-            path = args.path
-        ))
-    })?;
-
-    if !metadata.is_file() {
-        return Err(FunctionCallError::RespondToModel(format!(
-            "{path} is not a regular file",
-            path = args.path
-        )));
-    }
-
-    let raw_contents = tokio::fs::read_to_string(&canonical_path)
-        .await
-        .map_err(|err| {
-            FunctionCallError::RespondToModel(format!(
-                "failed to read {path}: {err}",
-                path = args.path
-            ))
-        })?;
+    let WorkspaceFile {
+        canonical_path,
+        rel_path,
+        metadata,
+        chunks: ChunkLoad {
+            file: chunked_file,
+            cache_hit,
+        },
+    } = open_workspace_file(sess, turn_context, &args.path).await?;
+    let raw_contents = chunked_file.contents();
 
     if raw_contents.is_empty() {
-        let rel_path = display_path(&canonical_path, &workspace_root);
         return Ok(format!("path: {rel_path}\n[notice] file is empty"));
     }
 
-    let line_slices: Vec<&str> = raw_contents.split_inclusive('\n').collect();
+    let line_slices = chunked_file.lines();
     let line_count = line_slices.len();
 
-    let mut requested_ranges = if let Some(lines) = args.lines {
+    let mut symbol_notice = None;
+    let mut requested_ranges = if let Some(symbol) = args.symbol.as_deref() {
+        let span = resolve_symbol(&canonical_path, raw_contents, symbol)
+            .map_err(|err| FunctionCallError::RespondToModel(err.to_string()))?;
+        symbol_notice = Some(format!(
+            "symbol `{}` resolved to lines {}-{}",
+            span.qualified_name, span.start_line, span.end_line
+        ));
+        vec![(span.start_line, span.end_line)]
+    } else if let Some(lines) = args.lines {
         lines.into_ranges()?
     } else {
         vec![(1, line_count.max(1))]
@@ -148,28 +133,26 @@ pub(crate) async fn handle_read_code_tool_call(
         .map(|(start, end)| end.saturating_sub(*start).saturating_add(1))
         .sum::<usize>();
 
-    let small_file_allowance =
-        metadata.len() as usize <= SMALL_FILE_MAX_BYTES && line_count <= SMALL_FILE_MAX_LINES;
-
-    let max_lines = if small_file_allowance {
-        SMALL_FILE_MAX_LINES
+    let policy = &turn_context.context_policy;
+    let (max_lines, max_bytes_cap) = per_call_caps(
+        policy,
+        &canonical_path,
+        sess.is_turn_relaxed().await,
+        metadata.len(),
+        line_count,
+    );
+    let max_bytes_limit = args
+        .max_bytes
+        .unwrap_or(policy.read_caps_for(&canonical_path).max_bytes)
+        .min(max_bytes_cap);
+
+    let (uncovered_ranges, had_overlap) = if policy.overlap_suppression {
+        sess.compute_unserved_code_ranges(&rel_path, &contextualized)
+            .await
     } else {
-        DEFAULT_MAX_LINES
+        (contextualized.clone(), false)
     };
 
-    let requested_max_bytes = args.max_bytes.unwrap_or(DEFAULT_MAX_BYTES);
-    let max_bytes_limit = if small_file_allowance {
-        requested_max_bytes.min(SMALL_FILE_MAX_BYTES)
-    } else {
-        requested_max_bytes.min(DEFAULT_MAX_BYTES)
-    };
-
-    let rel_path = display_path(&canonical_path, &workspace_root);
-
-    let (uncovered_ranges, had_overlap) = sess
-        .compute_unserved_code_ranges(&rel_path, &contextualized)
-        .await;
-
     if uncovered_ranges.is_empty() {
         let mut output = format!("path: {rel_path}\n");
         output.push_str(
@@ -185,6 +168,37 @@ pub(crate) async fn handle_read_code_tool_call(
 
     let overlap_lines = requested_line_total.saturating_sub(uncovered_line_total);
 
+    // Slices shown in an earlier turn stay in history; point at them instead
+    // of serving the same bytes again while the file is unchanged.
+    let blob_oid = chunked_file.blob_oid();
+    let ReadPartition {
+        fresh: uncovered_ranges,
+        previously_served,
+    } = if policy.overlap_suppression {
+        sess.partition_session_code_ranges(&canonical_path, blob_oid, &uncovered_ranges)
+            .await
+    } else {
+        ReadPartition {
+            fresh: uncovered_ranges,
+            previously_served: Vec::new(),
+        }
+    };
+    let earlier_turn_notices: Vec<String> = previously_served
+        .iter()
+        .map(|slice| {
+            format!(
+                "lines {}-{} [already shown in turn {}]",
+                slice.start, slice.end, slice.turn
+            )
+        })
+        .collect();
+
+    if uncovered_ranges.is_empty() {
+        let mut notices = earlier_turn_notices;
+        notices.push("file is unchanged since; nothing new to show".to_string());
+        return Ok(build_header(&rel_path, &notices));
+    }
+
     let (line_limited_ranges, truncated_by_lines) = enforce_line_cap(&uncovered_ranges, max_lines);
 
     if line_limited_ranges.is_empty() {
@@ -193,12 +207,13 @@ pub(crate) async fn handle_read_code_tool_call(
         )));
     }
 
-    let mut notices = Vec::new();
+    let mut notices: Vec<String> = symbol_notice.into_iter().collect();
     if had_overlap && overlap_lines > 0 {
         notices.push(format!(
             "trimmed {overlap_lines} line(s) that were already served earlier in this turn"
         ));
     }
+    notices.extend(earlier_turn_notices);
     if truncated_by_lines {
         notices.push(format!(
             "truncated to {max_lines} line(s); request /relax for a temporary increase"
@@ -233,7 +248,8 @@ pub(crate) async fn handle_read_code_tool_call(
     }
     let prefix_bytes = header_bytes + blank_line_bytes;
     let desired_bytes = prefix_bytes + content.len();
-    let notice_len = TURN_OUTPUT_TRUNCATION_NOTICE.len();
+    let notice_template = sess.turn_output_truncation_notice().await;
+    let notice_len = notice_template.len();
 
     let reserve_decision = sess
         .reserve_tool_output_budget(desired_bytes, notice_len)
@@ -269,27 +285,142 @@ pub(crate) async fn handle_read_code_tool_call(
         output.push_str(&content);
     }
 
-    if let Some(decision) = reserve_decision {
-        if decision.truncated {
-            truncate_string_to_bytes(&mut output, decision.allowed_content_bytes);
-            let notice = truncated_notice(TURN_OUTPUT_TRUNCATION_NOTICE, decision.notice_bytes);
-            if !notice.is_empty() {
-                if !output.ends_with('\n') {
-                    output.push('\n');
-                }
-                output.push_str(&notice);
+    if let Some(decision) = reserve_decision
+        && decision.truncated
+    {
+        truncate_string_to_bytes(&mut output, decision.allowed_content_bytes);
+        let notice = truncated_notice(&notice_template, decision.notice_bytes);
+        if !notice.is_empty() {
+            if !output.ends_with('\n') {
+                output.push('\n');
             }
+            output.push_str(&notice);
         }
     }
 
     if !served_ranges.is_empty() {
-        sess.record_served_code_ranges(&rel_path, &served_ranges)
+        sess.record_served_code_ranges(
+            call_id,
+            &rel_path,
+            &canonical_path,
+            blob_oid,
+            &served_ranges,
+        )
+        .await;
+        sess.record_chunk_cache_access(chunked_file.chunks_covering(&served_ranges), cache_hit)
             .await;
     }
+    sess.record_read_code_output(&rel_path, output.len()).await;
 
     Ok(output)
 }
 
+/// A workspace file opened for reading through the session chunk cache.
+pub(crate) struct WorkspaceFile {
+    pub(crate) canonical_path: PathBuf,
+    /// Path relative to the workspace root, as shown to the model.
+    pub(crate) rel_path: String,
+    pub(crate) metadata: std::fs::Metadata,
+    pub(crate) chunks: ChunkLoad,
+}
+
+/// Resolve `path` against the turn's cwd, reject anything outside the
+/// workspace (other than spilled output, which may also be named by its
+/// `output:` handle) or not a regular file, and load it through the chunk
+/// cache.
+pub(crate) async fn open_workspace_file(
+    sess: &Session,
+    turn_context: &TurnContext,
+    path: &str,
+) -> Result<WorkspaceFile, FunctionCallError> {
+    let output_handle = resolve_output_handle(sess.output_artifacts_dir(), path);
+    let candidate_path = output_handle
+        .clone()
+        .unwrap_or_else(|| turn_context.resolve_path(Some(path.to_string())));
+    let canonical_path = tokio::fs::canonicalize(&candidate_path)
+        .await
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to resolve {path}: {err}"))
+        })?;
+    let workspace_root = tokio::fs::canonicalize(&turn_context.cwd)
+        .await
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to resolve workspace root {path}: {err}",
+                path = turn_context.cwd.display()
+            ))
+        })?;
+    // Spilled output lives outside the workspace but is meant to be paged.
+    let artifacts_dir = tokio::fs::canonicalize(sess.output_artifacts_dir())
+        .await
+        .ok();
+    let in_artifacts = artifacts_dir
+        .as_deref()
+        .is_some_and(|dir| canonical_path.starts_with(dir));
+    if !in_artifacts {
+        validate_within_workspace(&canonical_path, &workspace_root)?;
+        reject_denied_read(
+            &turn_context.sandbox_policy,
+            &turn_context.cwd,
+            &workspace_root,
+            &candidate_path,
+            &canonical_path,
+            path,
+        )?;
+    }
+
+    let metadata = tokio::fs::metadata(&canonical_path).await.map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to read metadata for {path}: {err}"))
+    })?;
+
+    if !metadata.is_file() {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{path} is not a regular file"
+        )));
+    }
+
+    let chunks = sess
+        .load_code_chunks(&workspace_root, &canonical_path, &metadata)
+        .await
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to read {path}: {err}"))
+        })?;
+
+    Ok(WorkspaceFile {
+        rel_path: match output_handle {
+            Some(_) => path.to_string(),
+            None => display_path(&canonical_path, &workspace_root),
+        },
+        canonical_path,
+        metadata,
+        chunks,
+    })
+}
+
+/// Per-call `(max_lines, max_bytes)` caps for `path`. Small files and relaxed
+/// turns get the larger single-slice allowance.
+pub(crate) fn per_call_caps(
+    policy: &ContextPolicy,
+    path: &Path,
+    relaxed: bool,
+    file_bytes: u64,
+    line_count: usize,
+) -> (usize, usize) {
+    let caps = policy.read_caps_for(path);
+    let large = policy.large_slice;
+    let small_file = policy.allow_large_slice_if_small_file
+        && file_bytes as usize <= large.max_bytes
+        && line_count <= large.max_lines;
+    if relaxed || small_file {
+        (
+            caps.max_lines.max(large.max_lines),
+            caps.max_bytes.max(large.max_bytes),
+        )
+    } else {
+        (caps.max_lines, caps.max_bytes)
+    }
+}
+
 fn build_header(rel_path: &str, notices: &[String]) -> String {
     let mut header = format!("path: {rel_path}\n");
     for notice in notices {
@@ -300,7 +431,7 @@ fn build_header(rel_path: &str, notices: &[String]) -> String {
     header
 }
 
-fn normalize_ranges(ranges: &mut Vec<(usize, usize)>) -> Result<(), FunctionCallError> {
+pub(crate) fn normalize_ranges(ranges: &mut [(usize, usize)]) -> Result<(), FunctionCallError> {
     for (start, end) in ranges.iter_mut() {
         if *start == 0 {
             return Err(invalid_arguments(
@@ -340,7 +471,7 @@ fn apply_context(
     contextualized
 }
 
-fn merge_ranges(ranges: &mut Vec<(usize, usize)>) {
+pub(crate) fn merge_ranges(ranges: &mut Vec<(usize, usize)>) {
     if ranges.is_empty() {
         return;
     }
@@ -403,20 +534,23 @@ fn build_content(
         let label_len = label.len();
         let first_line_len = first_line.len();
 
-        let mut required = label_len + first_line_len;
-        if !first_segment && !content.ends_with('\n') {
-            required += 1;
-        }
+        // Later segments are separated from the previous one by a blank line.
+        let separator = if first_segment {
+            ""
+        } else if content.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
 
+        let required = separator.len() + label_len + first_line_len;
         if used + required > max_bytes {
             truncated = true;
             break;
         }
 
-        if !first_segment && !content.ends_with('\n') {
-            content.push('\n');
-            used += 1;
-        }
+        content.push_str(separator);
+        used += separator.len();
 
         content.push_str(&label);
         used += label_len;
@@ -460,6 +594,34 @@ fn validate_within_workspace(path: &Path, cwd: &Path) -> Result<(), FunctionCall
     ))
 }
 
+/// Refuse a file that `deny_read_paths` hides from sandboxed commands, under
+/// the name it was asked for or the one it resolves to.
+fn reject_denied_read(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    workspace_root: &Path,
+    candidate_path: &Path,
+    canonical_path: &Path,
+    path: &str,
+) -> Result<(), FunctionCallError> {
+    let resolved = canonical_path
+        .strip_prefix(workspace_root)
+        .map(|relative| cwd.join(relative))
+        .unwrap_or_else(|_| canonical_path.to_path_buf());
+    let denied = normalize(candidate_path)
+        .into_iter()
+        .chain([resolved])
+        .any(|candidate| {
+            masked_path_kind(sandbox_policy, cwd, &candidate) == Some(PathMaskKind::DenyRead)
+        });
+    if denied {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{path} is protected by sandbox_workspace_write.deny_read_paths"
+        )));
+    }
+    Ok(())
+}
+
 fn display_path(path: &Path, cwd: &Path) -> String {
     path.strip_prefix(cwd)
         .map(PathBuf::from)
@@ -509,6 +671,7 @@ fn take_bytes_at_char_boundary(text: &str, max_bytes: usize) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codex::make_session_and_context_in;
 
     #[test]
     fn merges_overlapping_ranges() {
@@ -534,4 +697,120 @@ mod tests {
         assert_eq!(served, vec![(1, 2)]);
         assert!(truncated);
     }
+
+    #[test]
+    fn lines_arg_accepts_multiple_ranges() {
+        let arg: LinesArg = serde_json::from_str("[[1, 3], [10, 12]]").expect("parse ranges");
+        let ranges = arg.into_ranges().expect("valid ranges");
+        assert_eq!(ranges, vec![(1, 3), (10, 12)]);
+
+        let too_many = format!("[{}]", ["[1, 2]"; MAX_RANGES_PER_CALL + 1].join(","));
+        let arg: LinesArg = serde_json::from_str(&too_many).expect("parse ranges");
+        assert!(arg.into_ranges().is_err());
+    }
+
+    #[test]
+    fn denied_read_paths_are_refused() {
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            read_only_subpaths: vec![".git/hooks".to_string()],
+            deny_read_paths: vec![".env*".to_string()],
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let cwd = Path::new("/repo");
+        let check = |candidate: &str, canonical: &str| {
+            reject_denied_read(
+                &policy,
+                cwd,
+                Path::new("/private/repo"),
+                Path::new(candidate),
+                Path::new(canonical),
+                candidate,
+            )
+        };
+
+        assert!(check("/repo/src/../.env", "/private/repo/.env").is_err());
+        assert!(check("/repo/config", "/private/repo/.env.local").is_err());
+        assert!(
+            check(
+                "/repo/.git/hooks/pre-commit",
+                "/private/repo/.git/hooks/pre-commit"
+            )
+            .is_ok()
+        );
+        assert!(check("/repo/src/main.rs", "/private/repo/src/main.rs").is_ok());
+    }
+
+    #[test]
+    fn build_content_separates_disjoint_ranges() {
+        let lines = vec!["a\n", "b\n", "c\n", "d\n", "e\n"];
+        let ranges = vec![(1, 1), (4, 5)];
+        let (content, served, truncated) = build_content(&ranges, &lines, 1024);
+        assert_eq!(content, "lines 1-1:\na\n\nlines 4-5:\nd\ne\n");
+        assert_eq!(served, vec![(1, 1), (4, 5)]);
+        assert!(!truncated);
+    }
+
+    #[tokio::test]
+    async fn identical_reads_are_byte_identical_across_sessions() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let workspace = tempfile::tempdir().expect("tempdir");
+        let contents: String = (1..=40).map(|n| format!("fn item_{n}() {{}}\n")).collect();
+        std::fs::write(workspace.path().join("lib.rs"), contents).expect("write lib.rs");
+        for args in [
+            vec!["init", "-q"],
+            vec!["add", "lib.rs"],
+            vec![
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "-m",
+                "init",
+            ],
+        ] {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(workspace.path())
+                .env("GIT_CONFIG_GLOBAL", "/dev/null")
+                .env("GIT_CONFIG_NOSYSTEM", "1")
+                .status()
+                .expect("run git");
+            assert!(status.success());
+        }
+
+        let session_in_workspace = || {
+            let (sess, mut turn_context) = make_session_and_context_in(codex_home.path());
+            turn_context.cwd = workspace.path().to_path_buf();
+            (sess, turn_context)
+        };
+        let args = r#"{"path": "lib.rs", "lines": [[5, 12]]}"#;
+
+        let (first, turn_context) = session_in_workspace();
+        let first_output =
+            handle_read_code_tool_call(&first, &turn_context, "call-1", args.to_string())
+                .await
+                .expect("read in the first session");
+
+        let (second, turn_context) = session_in_workspace();
+        let file = open_workspace_file(&second, &turn_context, "lib.rs")
+            .await
+            .expect("open lib.rs");
+        assert!(file.chunks.cache_hit, "chunks should come from the store");
+        let second_output =
+            handle_read_code_tool_call(&second, &turn_context, "call-1", args.to_string())
+                .await
+                .expect("read in the second session");
+
+        assert_eq!(second_output.as_bytes(), first_output.as_bytes());
+        assert!(
+            first_output.ends_with("fn item_12() {}\n"),
+            "{first_output}"
+        );
+    }
 }
@@ -0,0 +1,357 @@
+//! Structural compaction of stale tool output in the conversation history.
+//!
+//! Raw tool output otherwise stays in the transcript until a full `/compact`.
+//! Before each request we rewrite outputs that have been superseded by later
+//! items: `read_code` slices that were re-served or whose file was edited
+//! become a short `{path, lines, oid}` reference, and build logs that were
+//! followed by another run of the same command collapse to their tail.
+//!
+//! Rewrites happen in place in the stored history and each output is rewritten
+//! at most once, so the prefix up to the oldest newly compacted item is
+//! byte-identical between requests and stays cacheable.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use codex_protocol::models::ResponseItem;
+use serde_json::json;
+
+use crate::exec::should_tail_build_output;
+
+/// Lines of a superseded build log kept after compaction.
+const SUPERSEDED_BUILD_LOG_TAIL_LINES: usize = 20;
+const COMPACTED_PREFIX: &str = "[compacted]";
+const SHORT_OID_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LedgerEntry {
+    CodeRead {
+        path: PathBuf,
+        rel_path: String,
+        ranges: Vec<(usize, usize)>,
+        blob_oid: String,
+    },
+    Edit {
+        paths: Vec<PathBuf>,
+    },
+    BuildLog {
+        command: Vec<String>,
+    },
+}
+
+/// Metadata about tool outputs that may later become stale, keyed by call id.
+#[derive(Debug, Default)]
+pub(crate) struct ToolOutputLedger {
+    entries: HashMap<String, LedgerEntry>,
+}
+
+impl ToolOutputLedger {
+    pub(crate) fn record_code_read(
+        &mut self,
+        call_id: &str,
+        path: PathBuf,
+        rel_path: &str,
+        ranges: &[(usize, usize)],
+        blob_oid: &str,
+    ) {
+        self.entries.insert(
+            call_id.to_string(),
+            LedgerEntry::CodeRead {
+                path,
+                rel_path: rel_path.to_string(),
+                ranges: ranges.to_vec(),
+                blob_oid: blob_oid.to_string(),
+            },
+        );
+    }
+
+    pub(crate) fn record_edit(&mut self, call_id: &str, paths: Vec<PathBuf>) {
+        self.entries
+            .insert(call_id.to_string(), LedgerEntry::Edit { paths });
+    }
+
+    /// Record `command` if it looks like a build or test run whose log is
+    /// superseded by the next run of the same command.
+    pub(crate) fn record_command(&mut self, call_id: &str, command: &[String]) {
+        if should_tail_build_output(command) {
+            self.entries.insert(
+                call_id.to_string(),
+                LedgerEntry::BuildLog {
+                    command: command.to_vec(),
+                },
+            );
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Rewrite superseded tool outputs in `items` and return how many were
+/// compacted. Compacted outputs are dropped from `ledger` so they are never
+/// rewritten again.
+pub(crate) fn compact_stale_tool_outputs(
+    items: &mut [ResponseItem],
+    ledger: &mut ToolOutputLedger,
+) -> usize {
+    if ledger.entries.is_empty() {
+        return 0;
+    }
+
+    let mut later_reads: HashMap<PathBuf, Vec<(usize, usize)>> = HashMap::new();
+    let mut later_edits: HashSet<PathBuf> = HashSet::new();
+    let mut later_builds: HashSet<Vec<String>> = HashSet::new();
+    let mut compacted = 0usize;
+
+    // Walk newest to oldest so every output is checked against what follows.
+    for item in items.iter_mut().rev() {
+        let Some((call_id, content)) = tool_output_mut(item) else {
+            continue;
+        };
+        let Some(entry) = ledger.entries.get(call_id.as_str()) else {
+            continue;
+        };
+
+        let replacement = match entry {
+            LedgerEntry::CodeRead {
+                path,
+                rel_path,
+                ranges,
+                blob_oid,
+            } => {
+                let superseded = later_edits.contains(path)
+                    || later_reads
+                        .get(path)
+                        .is_some_and(|later| ranges_overlap(ranges, later));
+                if superseded {
+                    Some(code_read_reference(rel_path, ranges, blob_oid))
+                } else {
+                    later_reads
+                        .entry(path.clone())
+                        .or_default()
+                        .extend(ranges.iter().copied());
+                    None
+                }
+            }
+            LedgerEntry::Edit { paths } => {
+                later_edits.extend(paths.iter().cloned());
+                None
+            }
+            LedgerEntry::BuildLog { command } => {
+                if later_builds.contains(command) {
+                    Some(build_log_tail(command, content))
+                } else {
+                    later_builds.insert(command.clone());
+                    None
+                }
+            }
+        };
+
+        if let Some(replacement) = replacement {
+            let call_id = call_id.clone();
+            if replacement.len() < content.len() {
+                *content = replacement;
+                compacted += 1;
+            }
+            ledger.entries.remove(&call_id);
+        }
+    }
+
+    compacted
+}
+
+fn tool_output_mut(item: &mut ResponseItem) -> Option<(&String, &mut String)> {
+    match item {
+        ResponseItem::FunctionCallOutput { call_id, output } => {
+            Some((call_id, &mut output.content))
+        }
+        ResponseItem::CustomToolCallOutput { call_id, output } => Some((call_id, output)),
+        _ => None,
+    }
+}
+
+fn ranges_overlap(ranges: &[(usize, usize)], others: &[(usize, usize)]) -> bool {
+    ranges.iter().any(|&(start, end)| {
+        others
+            .iter()
+            .any(|&(other_start, other_end)| start <= other_end && other_start <= end)
+    })
+}
+
+fn code_read_reference(rel_path: &str, ranges: &[(usize, usize)], blob_oid: &str) -> String {
+    let lines: Vec<[usize; 2]> = ranges.iter().map(|&(start, end)| [start, end]).collect();
+    let oid = blob_oid.get(..SHORT_OID_LEN).unwrap_or(blob_oid);
+    // Spelled out field by field so the bytes do not depend on map ordering.
+    let reference = format!(
+        "{{\"path\":{},\"lines\":{},\"oid\":\"{oid}\"}}",
+        serde_json::Value::from(rel_path),
+        json!(lines)
+    );
+    format!(
+        "{COMPACTED_PREFIX} read_code {reference} was superseded by a later read or edit; call read_code again if you still need these lines"
+    )
+}
+
+/// Keep the last [`SUPERSEDED_BUILD_LOG_TAIL_LINES`] lines of a build log. Shell output
+/// is a JSON object whose `output` field holds the log; other payloads are
+/// treated as plain text.
+fn build_log_tail(command: &[String], content: &str) -> String {
+    let command =
+        shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "));
+    let tail = |log: &str| {
+        let lines: Vec<&str> = log.lines().collect();
+        let kept = &lines[lines.len().saturating_sub(SUPERSEDED_BUILD_LOG_TAIL_LINES)..];
+        format!(
+            "{COMPACTED_PREFIX} superseded by a later run of `{command}`; last {} of {} lines:\n{}",
+            kept.len(),
+            lines.len(),
+            kept.join("\n")
+        )
+    };
+
+    match serde_json::from_str::<serde_json::Value>(content) {
+        Ok(mut value) => match value.get("output").and_then(serde_json::Value::as_str) {
+            Some(log) => {
+                value["output"] = serde_json::Value::String(tail(log));
+                value.to_string()
+            }
+            None => tail(content),
+        },
+        Err(_) => tail(content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn output(call_id: &str, content: &str) -> ResponseItem {
+        ResponseItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: content.to_string(),
+                success: Some(true),
+            },
+        }
+    }
+
+    fn content(item: &ResponseItem) -> &str {
+        match item {
+            ResponseItem::FunctionCallOutput { output, .. } => &output.content,
+            _ => panic!("expected function call output"),
+        }
+    }
+
+    fn long_read(label: &str) -> String {
+        format!("path: src/lib.rs\n\nlines 1-40:\n{}", label.repeat(40))
+    }
+
+    #[test]
+    fn superseded_reads_become_references() {
+        let path = PathBuf::from("/repo/src/lib.rs");
+        let mut ledger = ToolOutputLedger::default();
+        ledger.record_code_read(
+            "read-1",
+            path.clone(),
+            "src/lib.rs",
+            &[(1, 40)],
+            "a".repeat(40).as_str(),
+        );
+        ledger.record_code_read(
+            "read-2",
+            path.clone(),
+            "src/lib.rs",
+            &[(30, 60)],
+            "b".repeat(40).as_str(),
+        );
+        ledger.record_edit("patch", vec![path]);
+
+        let mut items = vec![
+            output("read-1", &long_read("fn one() {}\n")),
+            output("read-2", &long_read("fn two() {}\n")),
+            output("patch", "Success"),
+        ];
+        assert_eq!(compact_stale_tool_outputs(&mut items, &mut ledger), 2);
+        assert_eq!(
+            content(&items[0]),
+            r#"[compacted] read_code {"path":"src/lib.rs","lines":[[1,40]],"oid":"aaaaaaaaaaaa"} was superseded by a later read or edit; call read_code again if you still need these lines"#
+        );
+        assert!(content(&items[1]).contains(r#""lines":[[30,60]]"#));
+        assert_eq!(content(&items[2]), "Success");
+
+        // A second pass leaves the already compacted prefix untouched.
+        let before = items.clone();
+        assert_eq!(compact_stale_tool_outputs(&mut items, &mut ledger), 0);
+        assert_eq!(items, before);
+    }
+
+    #[test]
+    fn unrelated_reads_are_kept() {
+        let mut ledger = ToolOutputLedger::default();
+        ledger.record_code_read(
+            "read-1",
+            PathBuf::from("/repo/a.rs"),
+            "a.rs",
+            &[(1, 40)],
+            "oid",
+        );
+        ledger.record_code_read(
+            "read-2",
+            PathBuf::from("/repo/a.rs"),
+            "a.rs",
+            &[(41, 80)],
+            "oid",
+        );
+        ledger.record_code_read(
+            "read-3",
+            PathBuf::from("/repo/b.rs"),
+            "b.rs",
+            &[(1, 40)],
+            "oid",
+        );
+
+        let mut items = vec![
+            output("read-1", &long_read("x\n")),
+            output("read-2", &long_read("y\n")),
+            output("read-3", &long_read("z\n")),
+        ];
+        let before = items.clone();
+        assert_eq!(compact_stale_tool_outputs(&mut items, &mut ledger), 0);
+        assert_eq!(items, before);
+    }
+
+    #[test]
+    fn superseded_build_logs_collapse_to_tail() {
+        let command = vec![
+            "bash".to_string(),
+            "-lc".to_string(),
+            "cargo test -p codex-core".to_string(),
+        ];
+        let mut ledger = ToolOutputLedger::default();
+        ledger.record_command("build-1", &command);
+        ledger.record_command("ls", &["ls".to_string()]);
+        ledger.record_command("build-2", &command);
+
+        let log: Vec<String> = (1..=100).map(|n| format!("line {n}")).collect();
+        let first = json!({
+            "output": log.join("\n"),
+            "metadata": { "exit_code": 101, "duration_seconds": 1.5 },
+        })
+        .to_string();
+        let mut items = vec![output("build-1", &first), output("build-2", &first)];
+
+        assert_eq!(compact_stale_tool_outputs(&mut items, &mut ledger), 1);
+        let compacted: serde_json::Value = serde_json::from_str(content(&items[0])).unwrap();
+        let tail = compacted["output"].as_str().unwrap();
+        assert!(tail.starts_with(
+            "[compacted] superseded by a later run of `bash -lc 'cargo test -p codex-core'`; last 20 of 100 lines:\nline 81\n"
+        ));
+        assert!(tail.ends_with("line 100"));
+        assert_eq!(compacted["metadata"]["exit_code"], 101);
+        assert_eq!(content(&items[1]), first);
+    }
+}
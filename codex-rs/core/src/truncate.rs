@@ -103,6 +103,16 @@ pub(crate) fn truncate_middle(s: &str, max_bytes: usize) -> (String, Option<u64>
     (out, Some(est_tokens))
 }
 
+/// Render a byte limit for user-facing notices: whole KiB as `"8 KiB"`,
+/// anything else as `"5000 bytes"`.
+pub(crate) fn format_byte_limit(bytes: usize) -> String {
+    if bytes >= 1024 && bytes.is_multiple_of(1024) {
+        format!("{} KiB", bytes / 1024)
+    } else {
+        format!("{bytes} bytes")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::truncate_middle;
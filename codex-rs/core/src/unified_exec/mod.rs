@@ -1,4 +1,3 @@
-use portable_pty::CommandBuilder;
 use portable_pty::PtySize;
 use portable_pty::native_pty_system;
 use std::collections::HashMap;
@@ -18,6 +17,7 @@ use tokio::time::Duration;
 use tokio::time::Instant;
 
 use crate::exec_command::ExecCommandSession;
+use crate::exec_command::PtyCommand;
 use crate::truncate::truncate_middle;
 
 mod errors;
@@ -32,6 +32,9 @@ const UNIFIED_EXEC_OUTPUT_MAX_BYTES: usize = 128 * 1024; // 128 KiB
 pub(crate) struct UnifiedExecRequest<'a> {
     pub session_id: Option<i32>,
     pub input_chunks: &'a [String],
+    /// Command that starts a new session, already wrapped for the sandbox it
+    /// runs under. Required when `session_id` is `None`.
+    pub command: Option<PtyCommand>,
     pub timeout_ms: Option<u64>,
 }
 
@@ -149,6 +152,15 @@ impl Drop for ManagedUnifiedExecSession {
 }
 
 impl UnifiedExecSessionManager {
+    /// Whether `session_id` runs outside the sandbox of its policy.
+    pub(crate) async fn is_escalated(&self, session_id: i32) -> bool {
+        self.sessions
+            .lock()
+            .await
+            .get(&session_id)
+            .is_some_and(|managed| managed.session.is_escalated())
+    }
+
     pub async fn handle_request(
         &self,
         request: UnifiedExecRequest<'_>,
@@ -194,9 +206,12 @@ impl UnifiedExecSessionManager {
             }
             drop(sessions);
         } else {
-            let command = request.input_chunks.to_vec();
+            let command = request
+                .command
+                .as_ref()
+                .ok_or(UnifiedExecError::MissingCommandLine)?;
             let new_id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
-            let (session, initial_output_rx) = create_unified_exec_session(&command).await?;
+            let (session, initial_output_rx) = create_unified_exec_session(command).await?;
             let managed_session = ManagedUnifiedExecSession::new(session, initial_output_rx);
             let (buffer, notify) = managed_session.output_handles();
             writer_tx = managed_session.writer_sender();
@@ -299,7 +314,7 @@ impl UnifiedExecSessionManager {
 }
 
 async fn create_unified_exec_session(
-    command: &[String],
+    command: &PtyCommand,
 ) -> Result<
     (
         ExecCommandSession,
@@ -307,10 +322,6 @@ async fn create_unified_exec_session(
     ),
     UnifiedExecError,
 > {
-    if command.is_empty() {
-        return Err(UnifiedExecError::MissingCommandLine);
-    }
-
     let pty_system = native_pty_system();
 
     let pair = pty_system
@@ -322,15 +333,9 @@ async fn create_unified_exec_session(
         })
         .map_err(UnifiedExecError::create_session)?;
 
-    // Safe thanks to the check at the top of the function.
-    let mut command_builder = CommandBuilder::new(command[0].clone());
-    for arg in &command[1..] {
-        command_builder.arg(arg);
-    }
-
     let mut child = pair
         .slave
-        .spawn_command(command_builder)
+        .spawn_command(command.to_command_builder())
         .map_err(UnifiedExecError::create_session)?;
     let killer = child.clone_killer();
 
@@ -397,6 +402,7 @@ async fn create_unified_exec_session(
         writer_handle,
         wait_handle,
         exit_status,
+        command.is_escalated(),
     );
     Ok((session, initial_output_rx))
 }
@@ -404,9 +410,26 @@ async fn create_unified_exec_session(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exec::SandboxType;
+    use crate::protocol::SandboxPolicy;
     #[cfg(unix)]
     use core_test_support::skip_if_sandbox;
 
+    fn launch(command: &[String]) -> Option<PtyCommand> {
+        let cwd = std::env::temp_dir();
+        Some(
+            PtyCommand::new(
+                command.to_vec(),
+                cwd.clone(),
+                SandboxType::None,
+                &SandboxPolicy::DangerFullAccess,
+                &cwd,
+                &None,
+            )
+            .expect("unsandboxed pty command"),
+        )
+    }
+
     #[test]
     fn push_chunk_trims_only_excess_bytes() {
         let mut buffer = OutputBufferState::default();
@@ -424,6 +447,23 @@ mod tests {
         assert_eq!(buffer.chunks.pop_back().unwrap(), vec![b'b']);
     }
 
+    #[tokio::test]
+    async fn new_session_requires_command() {
+        let manager = UnifiedExecSessionManager::default();
+
+        let err = manager
+            .handle_request(UnifiedExecRequest {
+                session_id: None,
+                input_chunks: &["bash".to_string()],
+                command: None,
+                timeout_ms: Some(100),
+            })
+            .await
+            .expect_err("expected missing command error");
+
+        assert!(matches!(err, UnifiedExecError::MissingCommandLine));
+    }
+
     #[cfg(unix)]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn unified_exec_persists_across_requests_jif() -> Result<(), UnifiedExecError> {
@@ -434,7 +474,8 @@ mod tests {
         let open_shell = manager
             .handle_request(UnifiedExecRequest {
                 session_id: None,
-                input_chunks: &["bash".to_string(), "-i".to_string()],
+                input_chunks: &[],
+                command: launch(&["bash".to_string(), "-i".to_string()]),
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -447,6 +488,7 @@ mod tests {
                     "export".to_string(),
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
+                command: None,
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -455,6 +497,7 @@ mod tests {
             .handle_request(UnifiedExecRequest {
                 session_id: Some(session_id),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
+                command: None,
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -473,7 +516,8 @@ mod tests {
         let shell_a = manager
             .handle_request(UnifiedExecRequest {
                 session_id: None,
-                input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
+                input_chunks: &[],
+                command: launch(&["/bin/bash".to_string(), "-i".to_string()]),
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -483,6 +527,7 @@ mod tests {
             .handle_request(UnifiedExecRequest {
                 session_id: Some(session_a),
                 input_chunks: &["export CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string()],
+                command: None,
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -490,10 +535,11 @@ mod tests {
         let out_2 = manager
             .handle_request(UnifiedExecRequest {
                 session_id: None,
-                input_chunks: &[
+                input_chunks: &[],
+                command: launch(&[
                     "echo".to_string(),
                     "$CODEX_INTERACTIVE_SHELL_VAR\n".to_string(),
-                ],
+                ]),
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -503,6 +549,7 @@ mod tests {
             .handle_request(UnifiedExecRequest {
                 session_id: Some(session_a),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
+                command: None,
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -521,7 +568,8 @@ mod tests {
         let open_shell = manager
             .handle_request(UnifiedExecRequest {
                 session_id: None,
-                input_chunks: &["bash".to_string(), "-i".to_string()],
+                input_chunks: &[],
+                command: launch(&["bash".to_string(), "-i".to_string()]),
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -534,6 +582,7 @@ mod tests {
                     "export".to_string(),
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
+                command: None,
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -542,6 +591,7 @@ mod tests {
             .handle_request(UnifiedExecRequest {
                 session_id: Some(session_id),
                 input_chunks: &["sleep 5 && echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
+                command: None,
                 timeout_ms: Some(10),
             })
             .await?;
@@ -554,6 +604,7 @@ mod tests {
             .handle_request(UnifiedExecRequest {
                 session_id: Some(session_id),
                 input_chunks: &empty,
+                command: None,
                 timeout_ms: Some(100),
             })
             .await?;
@@ -572,7 +623,8 @@ mod tests {
         let result = manager
             .handle_request(UnifiedExecRequest {
                 session_id: None,
-                input_chunks: &["echo".to_string(), "codex".to_string()],
+                input_chunks: &[],
+                command: launch(&["echo".to_string(), "codex".to_string()]),
                 timeout_ms: Some(120_000),
             })
             .await?;
@@ -593,7 +645,8 @@ mod tests {
         let result = manager
             .handle_request(UnifiedExecRequest {
                 session_id: None,
-                input_chunks: &["/bin/echo".to_string(), "codex".to_string()],
+                input_chunks: &[],
+                command: launch(&["/bin/echo".to_string(), "codex".to_string()]),
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -616,7 +669,8 @@ mod tests {
         let open_shell = manager
             .handle_request(UnifiedExecRequest {
                 session_id: None,
-                input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
+                input_chunks: &[],
+                command: launch(&["/bin/bash".to_string(), "-i".to_string()]),
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -626,6 +680,7 @@ mod tests {
             .handle_request(UnifiedExecRequest {
                 session_id: Some(session_id),
                 input_chunks: &["exit\n".to_string()],
+                command: None,
                 timeout_ms: Some(2_500),
             })
             .await?;
@@ -636,6 +691,7 @@ mod tests {
             .handle_request(UnifiedExecRequest {
                 session_id: Some(session_id),
                 input_chunks: &[],
+                command: None,
                 timeout_ms: Some(100),
             })
             .await
@@ -9,6 +9,7 @@ use codex_core::exec::SandboxType;
 use codex_core::exec::process_exec_tool_call;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::spawn::CODEX_SANDBOX_ENV_VAR;
+use codex_core::spawn::ResourceLimits;
 use tempfile::TempDir;
 
 use codex_core::error::Result;
@@ -36,6 +37,7 @@ async fn run_test_cmd(tmp: TempDir, cmd: Vec<&str>) -> Result<ExecToolCallOutput
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
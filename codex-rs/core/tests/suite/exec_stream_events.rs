@@ -16,6 +16,7 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecCommandOutputDeltaEvent;
 use codex_core::protocol::ExecOutputStream;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::ResourceLimits;
 
 fn collect_stdout_events(rx: Receiver<Event>) -> Vec<u8> {
     let mut out = Vec::new();
@@ -57,6 +58,7 @@ async fn test_exec_stdout_stream_events_echo() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -110,6 +112,7 @@ async fn test_exec_stderr_stream_events_echo() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -166,6 +169,7 @@ async fn test_aggregated_output_interleaves_in_order() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -205,6 +209,7 @@ async fn test_exec_timeout_returns_partial_output() {
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        resource_limits: ResourceLimits::default(),
     };
 
     let policy = SandboxPolicy::new_read_only_policy();
@@ -15,7 +15,7 @@ mod model_overrides;
 mod otel;
 mod prompt_caching;
 mod review;
-mod rmcp_client;
+// mod rmcp_client;
 mod rollout_list_find;
 mod seatbelt;
 mod stream_error_allows_next_turn;
@@ -16,6 +16,12 @@ use codex_core::shell::Shell;
 use codex_core::shell::default_user_shell;
 use core_test_support::load_default_config_for_test;
 use core_test_support::load_sse_fixture_with_id;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
 use core_test_support::skip_if_no_network;
 use core_test_support::wait_for_event;
 use tempfile::TempDir;
@@ -224,6 +230,9 @@ async fn prompt_tools_are_consistent_across_requests() {
         "update_plan",
         "apply_patch",
         "read_code",
+        "plan_reads",
+        "request_relax",
+        "background_job",
         "view_image",
     ];
     let body0 = requests[0].body_json::<serde_json::Value>().unwrap();
@@ -418,7 +427,10 @@ async fn overrides_turn_context_but_keeps_cached_prefix_and_key_constant() {
             approval_policy: Some(AskForApproval::Never),
             sandbox_policy: Some(SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
+                read_only_subpaths: vec![],
+                deny_read_paths: vec![],
                 network_access: true,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             }),
@@ -551,7 +563,10 @@ async fn per_turn_overrides_keep_cached_prefix_and_key_constant() {
             approval_policy: AskForApproval::Never,
             sandbox_policy: SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
+                read_only_subpaths: vec![],
+                deny_read_paths: vec![],
                 network_access: true,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             },
@@ -849,3 +864,136 @@ async fn send_user_turn_with_changes_sends_environment_context() {
     ]);
     assert_eq!(body2["input"], expected_input_2);
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stale_read_code_output_is_compacted_without_breaking_cached_prefix() {
+    skip_if_no_network!();
+    use pretty_assertions::assert_eq;
+
+    let server = start_mock_server().await;
+    let read_args = serde_json::json!({ "path": "lib.rs", "lines": [[1, 10]] }).to_string();
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("read-1", "read_code", &read_args),
+                ev_completed("resp-1"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg-1", "read it"),
+                ev_completed("resp-2"),
+            ]),
+            sse(vec![
+                ev_function_call("read-2", "read_code", &read_args),
+                ev_completed("resp-3"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg-2", "read it again"),
+                ev_completed("resp-4"),
+            ]),
+            sse(vec![
+                ev_assistant_message("msg-3", "done"),
+                ev_completed("resp-5"),
+            ]),
+        ],
+    )
+    .await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+
+    let cwd = TempDir::new().unwrap();
+    let source_path = cwd.path().join("lib.rs");
+    let original: String = (1..=30)
+        .map(|n| format!("fn original_{n}() {{}}\n"))
+        .collect();
+    std::fs::write(&source_path, &original).unwrap();
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.cwd = cwd.path().to_path_buf();
+    config.model_provider = model_provider;
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    for (turn, text) in ["read once", "read again", "thanks"]
+        .into_iter()
+        .enumerate()
+    {
+        if turn == 1 {
+            let edited: String = (1..=30)
+                .map(|n| format!("fn edited_{n}() {{}}\n"))
+                .collect();
+            std::fs::write(&source_path, edited).unwrap();
+        }
+        codex
+            .submit(Op::UserInput {
+                items: vec![InputItem::Text { text: text.into() }],
+            })
+            .await
+            .unwrap();
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    }
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 5, "expected five POST requests");
+    let inputs: Vec<Vec<serde_json::Value>> = requests
+        .iter()
+        .map(|request| {
+            request.body_json::<serde_json::Value>().unwrap()["input"]
+                .as_array()
+                .unwrap()
+                .clone()
+        })
+        .collect();
+
+    let output_index = |input: &[serde_json::Value], call_id: &str| {
+        input
+            .iter()
+            .position(|item| item["type"] == "function_call_output" && item["call_id"] == call_id)
+            .unwrap()
+    };
+
+    // Until a later read supersedes it, every request extends the previous one.
+    for pair in inputs[..3].windows(2) {
+        assert_eq!(pair[1][..pair[0].len()], pair[0][..]);
+    }
+    let first_read = output_index(&inputs[2], "read-1");
+    assert!(
+        inputs[2][first_read]["output"]
+            .as_str()
+            .unwrap()
+            .contains("fn original_1() {}")
+    );
+
+    // Once the edited file is re-served, the first read collapses to a
+    // reference while everything before it stays byte-identical.
+    assert_eq!(inputs[3][..first_read], inputs[2][..first_read]);
+    let compacted = inputs[3][first_read]["output"].as_str().unwrap();
+    assert!(
+        compacted.starts_with(r#"[compacted] read_code {"path":"lib.rs","lines":[[1,10]],"oid":"#),
+        "unexpected compacted output: {compacted}"
+    );
+    assert_eq!(
+        inputs[3][first_read + 1..inputs[2].len()],
+        inputs[2][first_read + 1..]
+    );
+    let second_read = output_index(&inputs[3], "read-2");
+    assert!(
+        inputs[3][second_read]["output"]
+            .as_str()
+            .unwrap()
+            .contains("fn edited_1() {}")
+    );
+
+    // The compacted history is itself a stable prefix for later requests.
+    assert_eq!(inputs[4][..inputs[3].len()], inputs[3][..]);
+}
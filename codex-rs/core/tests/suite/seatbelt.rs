@@ -10,6 +10,7 @@ use std::path::PathBuf;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::seatbelt::spawn_command_under_seatbelt;
 use codex_core::spawn::CODEX_SANDBOX_ENV_VAR;
+use codex_core::spawn::ResourceLimits;
 use codex_core::spawn::StdioPolicy;
 use tempfile::TempDir;
 
@@ -77,7 +78,10 @@ async fn if_parent_of_repo_is_writable_then_dot_git_folder_is_writable() {
     let test_scenario = create_test_scenario(&tmp);
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_parent.clone()],
+        read_only_subpaths: vec![],
+        deny_read_paths: vec![],
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
     };
@@ -103,7 +107,10 @@ async fn if_git_repo_is_writable_root_then_dot_git_folder_is_read_only() {
     let test_scenario = create_test_scenario(&tmp);
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_root.clone()],
+        read_only_subpaths: vec![],
+        deny_read_paths: vec![],
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
     };
@@ -186,6 +193,7 @@ async fn python_getpwuid_works_under_seatbelt() {
         sandbox_cwd.as_path(),
         StdioPolicy::RedirectForShellTool,
         HashMap::new(),
+        ResourceLimits::default(),
     )
     .await
     .expect("should be able to spawn python under seatbelt");
@@ -231,6 +239,7 @@ async fn touch(path: &Path, policy: &SandboxPolicy) -> bool {
         sandbox_cwd.as_path(),
         StdioPolicy::RedirectForShellTool,
         HashMap::new(),
+        ResourceLimits::default(),
     )
     .await
     .expect("should be able to spawn command under seatbelt");
@@ -19,6 +19,8 @@ use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::RelaxGrantedEvent;
+use codex_core::protocol::RelaxSource;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
@@ -278,6 +280,8 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 command,
                 cwd,
                 parsed_cmd: _,
+                timeout_ms: _,
+                sandbox_type: _,
             }) => {
                 self.call_id_to_command.insert(
                     call_id,
@@ -596,6 +600,28 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::UserMessage(_) => {}
             EventMsg::EnteredReviewMode(_) => {}
             EventMsg::ExitedReviewMode(_) => {}
+            EventMsg::TurnMetrics(_) => {}
+            EventMsg::BackgroundJobs(_) => {}
+            EventMsg::RelaxGranted(RelaxGrantedEvent {
+                source,
+                turn_output_max_bytes,
+                deferred,
+                reason,
+            }) => {
+                let who = match source {
+                    RelaxSource::User => "user",
+                    RelaxSource::Model => "model",
+                };
+                let when = if deferred { "next turn" } else { "this turn" };
+                ts_println!(
+                    self,
+                    "{} {who} relaxed context budgets for {when} ({turn_output_max_bytes} bytes)",
+                    "relax".style(self.magenta),
+                );
+                if let Some(reason) = reason {
+                    println!("{}", reason.style(self.dimmed));
+                }
+            }
         }
         CodexStatus::Running
     }
@@ -1,5 +1,6 @@
 #![cfg(unix)]
 use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::ResourceLimits;
 use codex_core::spawn::StdioPolicy;
 use std::collections::HashMap;
 use std::future::Future;
@@ -27,6 +28,7 @@ async fn spawn_command_under_sandbox(
         sandbox_cwd,
         stdio_policy,
         env,
+        ResourceLimits::default(),
     )
     .await
 }
@@ -50,6 +52,7 @@ async fn spawn_command_under_sandbox(
         sandbox_cwd,
         stdio_policy,
         env,
+        ResourceLimits::default(),
     )
     .await
 }
@@ -69,7 +72,10 @@ async fn python_multiprocessing_lock_works_under_sandbox() {
 
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots,
+        read_only_subpaths: vec![],
+        deny_read_paths: vec![],
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
     };
@@ -129,7 +135,10 @@ async fn sandbox_distinguishes_command_and_policy_cwds() {
     // is under a writable root.
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![],
+        read_only_subpaths: vec![],
+        deny_read_paths: vec![],
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
     };
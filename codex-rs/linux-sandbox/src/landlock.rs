@@ -2,9 +2,11 @@ use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_core::config_types::NetworkDestination;
 use codex_core::error::CodexErr;
 use codex_core::error::Result;
 use codex_core::error::SandboxErr;
+use codex_core::path_masks::resolve_masked_paths;
 use codex_core::protocol::SandboxPolicy;
 
 use landlock::ABI;
@@ -25,22 +27,40 @@ use seccompiler::SeccompRule;
 use seccompiler::TargetArch;
 use seccompiler::apply_filter;
 
+use crate::network_proxy::isolate_network_with_allowlist;
+use crate::path_masks::apply_path_masks;
+
 /// Apply sandbox policies inside this thread so only the child inherits
 /// them, not the entire CLI process.
 pub(crate) fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
 ) -> Result<()> {
-    if !sandbox_policy.has_full_network_access() {
-        install_network_seccomp_filter_on_current_thread()?;
+    let network_allowlist = sandbox_policy.network_allowlist();
+    if !network_allowlist.is_empty() {
+        let destinations = network_allowlist
+            .iter()
+            .map(|entry| entry.parse::<NetworkDestination>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|err| {
+                CodexErr::Io(std::io::Error::new(std::io::ErrorKind::InvalidInput, err))
+            })?;
+        // The network namespace is the boundary here; seccomp only keeps the
+        // command from tracing the proxy that holds the real network access.
+        isolate_network_with_allowlist(&destinations)?;
+        install_seccomp_filter_on_current_thread(false)?;
+    } else if !sandbox_policy.has_full_network_access() {
+        install_seccomp_filter_on_current_thread(true)?;
     }
 
     if !sandbox_policy.has_full_disk_write_access() {
-        let writable_roots = sandbox_policy
+        let writable_roots: Vec<PathBuf> = sandbox_policy
             .get_writable_roots_with_cwd(cwd)
             .into_iter()
             .map(|writable_root| writable_root.root)
             .collect();
+        // Mounting is not possible once Landlock is enforced.
+        apply_path_masks(&resolve_masked_paths(sandbox_policy, cwd), &writable_roots)?;
         install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
     }
 
@@ -82,9 +102,11 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
     Ok(())
 }
 
-/// Installs a seccomp filter that blocks outbound network access except for
-/// AF_UNIX domain sockets.
-fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+/// Installs a seccomp filter that denies `ptrace` and, with `block_network`,
+/// blocks outbound network access except for AF_UNIX domain sockets.
+fn install_seccomp_filter_on_current_thread(
+    block_network: bool,
+) -> std::result::Result<(), SandboxErr> {
     // Build rule map.
     let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
 
@@ -93,6 +115,40 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         rules.insert(nr, vec![]); // empty rule vec = unconditional match
     };
 
+    deny_syscall(libc::SYS_ptrace);
+    if block_network {
+        deny_network_syscalls(&mut rules)?;
+    }
+
+    let filter = SeccompFilter::new(
+        rules,
+        SeccompAction::Allow,                     // default – allow
+        SeccompAction::Errno(libc::EPERM as u32), // when rule matches – return EPERM
+        if cfg!(target_arch = "x86_64") {
+            TargetArch::x86_64
+        } else if cfg!(target_arch = "aarch64") {
+            TargetArch::aarch64
+        } else {
+            unimplemented!("unsupported architecture for seccomp filter");
+        },
+    )?;
+
+    let prog: BpfProgram = filter.try_into()?;
+
+    apply_filter(&prog)?;
+
+    Ok(())
+}
+
+/// Adds rules that block outbound network access except for AF_UNIX domain
+/// sockets.
+fn deny_network_syscalls(
+    rules: &mut BTreeMap<i64, Vec<SeccompRule>>,
+) -> std::result::Result<(), SandboxErr> {
+    let mut deny_syscall = |nr: i64| {
+        rules.insert(nr, vec![]); // empty rule vec = unconditional match
+    };
+
     deny_syscall(libc::SYS_connect);
     deny_syscall(libc::SYS_accept);
     deny_syscall(libc::SYS_accept4);
@@ -111,7 +167,6 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
     deny_syscall(libc::SYS_recvmmsg);
     deny_syscall(libc::SYS_getsockopt);
     deny_syscall(libc::SYS_setsockopt);
-    deny_syscall(libc::SYS_ptrace);
 
     // For `socket` we allow AF_UNIX (arg0 == AF_UNIX) and deny everything else.
     let unix_only_rule = SeccompRule::new(vec![SeccompCondition::new(
@@ -124,22 +179,5 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
     rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
     rules.insert(libc::SYS_socketpair, vec![unix_only_rule]); // always deny (Unix can use socketpair but fine, keep open?)
 
-    let filter = SeccompFilter::new(
-        rules,
-        SeccompAction::Allow,                     // default – allow
-        SeccompAction::Errno(libc::EPERM as u32), // when rule matches – return EPERM
-        if cfg!(target_arch = "x86_64") {
-            TargetArch::x86_64
-        } else if cfg!(target_arch = "aarch64") {
-            TargetArch::aarch64
-        } else {
-            unimplemented!("unsupported architecture for seccomp filter");
-        },
-    )?;
-
-    let prog: BpfProgram = filter.try_into()?;
-
-    apply_filter(&prog)?;
-
     Ok(())
 }
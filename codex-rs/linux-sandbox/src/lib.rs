@@ -2,6 +2,12 @@
 mod landlock;
 #[cfg(target_os = "linux")]
 mod linux_run_main;
+#[cfg(target_os = "linux")]
+mod namespace;
+#[cfg(target_os = "linux")]
+mod network_proxy;
+#[cfg(target_os = "linux")]
+mod path_masks;
 
 #[cfg(target_os = "linux")]
 pub fn run_main() -> ! {
@@ -12,6 +12,10 @@ pub struct LandlockCommand {
 
     pub sandbox_policy: codex_core::protocol::SandboxPolicy,
 
+    /// Resource limits, as JSON, to apply before executing the command.
+    #[arg(long)]
+    pub resource_limits: Option<codex_core::spawn::ResourceLimits>,
+
     /// Full command args to run under landlock.
     #[arg(trailing_var_arg = true)]
     pub command: Vec<String>,
@@ -21,6 +25,7 @@ pub fn run_main() -> ! {
     let LandlockCommand {
         sandbox_policy_cwd,
         sandbox_policy,
+        resource_limits,
         command,
     } = LandlockCommand::parse();
 
@@ -28,6 +33,12 @@ pub fn run_main() -> ! {
         panic!("error running landlock: {e:?}");
     }
 
+    if let Some(resource_limits) = resource_limits
+        && let Err(e) = codex_core::spawn::apply_resource_limits(&resource_limits)
+    {
+        panic!("error applying resource limits: {e:?}");
+    }
+
     if command.is_empty() {
         panic!("No command specified to execute.");
     }
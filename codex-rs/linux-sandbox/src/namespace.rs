@@ -0,0 +1,86 @@
+//! Helpers for the user and mount namespaces the sandbox sets up before it
+//! executes the command.
+
+use std::ffi::CString;
+use std::io;
+use std::path::Path;
+
+/// Map the caller's uid and gid into a new user namespace so files keep
+/// their owners.
+pub(crate) fn map_current_user(uid: libc::uid_t, gid: libc::gid_t) -> io::Result<()> {
+    std::fs::write("/proc/self/uid_map", identity_map(uid))?;
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/gid_map", identity_map(gid))
+}
+
+/// A `uid_map` or `gid_map` line mapping `id` to itself.
+fn identity_map(id: u32) -> String {
+    format!("{id} {id} 1")
+}
+
+/// Keep mounts made from here on from propagating out of the namespace.
+pub(crate) fn make_mounts_private() -> io::Result<()> {
+    mount(
+        None,
+        Path::new("/"),
+        None,
+        libc::MS_REC | libc::MS_PRIVATE,
+        None,
+    )
+}
+
+pub(crate) fn bind_mount(source: &Path, target: &Path) -> io::Result<()> {
+    mount(Some(source), target, None, libc::MS_BIND, None)
+}
+
+pub(crate) fn mount(
+    source: Option<&Path>,
+    target: &Path,
+    fstype: Option<&str>,
+    flags: libc::c_ulong,
+    data: Option<&str>,
+) -> io::Result<()> {
+    let c_path = |path: &Path| CString::new(path.as_os_str().as_encoded_bytes());
+    let c_str = |s: &str| CString::new(s);
+    let source = source.map(c_path).transpose().map_err(io::Error::other)?;
+    let target = c_path(target).map_err(io::Error::other)?;
+    let fstype = fstype.map(c_str).transpose().map_err(io::Error::other)?;
+    let data = data.map(c_str).transpose().map_err(io::Error::other)?;
+    let result = unsafe {
+        libc::mount(
+            source.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            target.as_ptr(),
+            fstype.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+            flags,
+            data.as_ref()
+                .map_or(std::ptr::null(), |s| s.as_ptr().cast::<libc::c_void>()),
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ids_map_to_themselves() {
+        assert_eq!(identity_map(1000), "1000 1000 1");
+        assert_eq!(identity_map(0), "0 0 1");
+    }
+
+    #[test]
+    fn paths_with_nul_bytes_are_rejected_before_mounting() {
+        let err = bind_mount(Path::new("/tmp"), Path::new("/tmp/a\0b"))
+            .expect_err("a NUL byte cannot reach mount(2)");
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+
+        let err = mount(None, Path::new("/"), Some("tmp\0fs"), 0, None)
+            .expect_err("a NUL byte cannot reach mount(2)");
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+}
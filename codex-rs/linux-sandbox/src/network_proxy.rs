@@ -0,0 +1,558 @@
+//! Network allow-list mode.
+//!
+//! The command runs in a fresh user and network namespace whose only
+//! interface is its own loopback, so it can reach servers it starts itself
+//! and Unix sockets on the file-system, but nothing outside. For every
+//! allowed `host:port` a listener is bound inside the namespace and handed
+//! to a proxy process that stays in the original namespace; each connection
+//! accepted there is forwarded to the real destination.
+//!
+//! Inside the namespace the destinations are reachable under their usual
+//! names: loopback destinations keep their address and port, IPv4
+//! destinations are added to the namespace's loopback interface, and host
+//! names resolve (through an `/etc/hosts` overlay) to an address from
+//! `127.0.1.1` on, where their listener waits.
+//!
+//! The namespace's loopback is not the host's: a service listening on the
+//! host's loopback (a database on `localhost:5432`, say) is reachable only
+//! when it is listed as `host:port`, like any other destination.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Shutdown;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::fd::RawFd;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+
+use codex_core::config_types::NetworkDestination;
+
+use crate::namespace::bind_mount;
+use crate::namespace::make_mounts_private;
+use crate::namespace::map_current_user;
+
+/// Most descriptors the kernel passes in one `SCM_RIGHTS` message.
+const MAX_LISTENERS: usize = 253;
+
+/// How long the proxy waits for a destination to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most connections the proxy forwards at once. Further connections wait in
+/// their listener's backlog until one closes.
+const MAX_FORWARDS: usize = 64;
+
+/// A listener inside the namespace and the destination it forwards to.
+struct ForwardListener {
+    listener: TcpListener,
+    destination: usize,
+}
+
+/// Move the current process into a private network namespace that can reach
+/// only loopback, Unix sockets and `destinations`. Must be called while the
+/// process is still single-threaded.
+pub(crate) fn isolate_network_with_allowlist(
+    destinations: &[NetworkDestination],
+) -> io::Result<()> {
+    let (namespace_end, proxy_end) = UnixStream::pair()?;
+    let uid = unsafe { libc::getuid() };
+    let gid = unsafe { libc::getgid() };
+    let sandbox_pid = unsafe { libc::getpid() };
+
+    match unsafe { libc::fork() } {
+        -1 => return Err(io::Error::last_os_error()),
+        0 => {
+            drop(namespace_end);
+            run_proxy(proxy_end, destinations, sandbox_pid);
+        }
+        _ => drop(proxy_end),
+    }
+
+    let hosts = host_addresses(destinations);
+    let mut flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNET;
+    if !hosts.is_empty() {
+        flags |= libc::CLONE_NEWNS;
+    }
+    if unsafe { libc::unshare(flags) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    map_current_user(uid, gid)?;
+    configure_loopback(destinations)?;
+    if !hosts.is_empty() {
+        overlay_etc_hosts(&hosts)?;
+    }
+
+    let listeners = bind_listeners(destinations, &hosts)?;
+    send_listeners(&namespace_end, &listeners)
+}
+
+/// The address each non-loopback host name resolves to inside the
+/// namespace.
+fn host_addresses(destinations: &[NetworkDestination]) -> HashMap<String, Ipv4Addr> {
+    let mut hosts: HashMap<String, Ipv4Addr> = HashMap::new();
+    for destination in destinations {
+        if destination.is_loopback() || destination.host.parse::<IpAddr>().is_ok() {
+            continue;
+        }
+        let next = hosts.len();
+        hosts
+            .entry(destination.host.to_ascii_lowercase())
+            .or_insert_with(|| {
+                Ipv4Addr::new(127, 0, (1 + next / 254) as u8, (next % 254 + 1) as u8)
+            });
+    }
+    hosts
+}
+
+/// The addresses a destination's listeners bind to inside the namespace.
+fn listen_addresses(
+    destination: &NetworkDestination,
+    hosts: &HashMap<String, Ipv4Addr>,
+) -> io::Result<Vec<IpAddr>> {
+    if destination.host.eq_ignore_ascii_case("localhost") {
+        return Ok(vec![
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(std::net::Ipv6Addr::LOCALHOST),
+        ]);
+    }
+    match destination.host.parse::<IpAddr>() {
+        Ok(ip @ IpAddr::V4(_)) => Ok(vec![ip]),
+        Ok(ip) if ip.is_loopback() => Ok(vec![ip]),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "IPv6 destinations other than [::1] are not supported in the network allow-list: {}",
+                destination.host
+            ),
+        )),
+        Err(_) => hosts
+            .get(&destination.host.to_ascii_lowercase())
+            .map(|ip| vec![IpAddr::V4(*ip)])
+            .ok_or_else(|| io::Error::other(format!("no address for {}", destination.host))),
+    }
+}
+
+/// Bring up the namespace's loopback interface and give it the IPv4
+/// addresses of allowed destinations that are not loopback addresses.
+fn configure_loopback(destinations: &[NetworkDestination]) -> io::Result<()> {
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if socket < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+
+    let mut request = interface_request("lo")?;
+    ioctl(&socket, libc::SIOCGIFFLAGS, &mut request)?;
+    unsafe {
+        request.ifr_ifru.ifru_flags |= (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+    }
+    ioctl(&socket, libc::SIOCSIFFLAGS, &mut request)?;
+
+    let mut aliases = Vec::new();
+    for destination in destinations {
+        if let Ok(IpAddr::V4(ip)) = destination.host.parse::<IpAddr>()
+            && !ip.is_loopback()
+            && !aliases.contains(&ip)
+        {
+            aliases.push(ip);
+        }
+    }
+    for (index, ip) in aliases.iter().enumerate() {
+        let mut request = interface_request(&format!("lo:{}", index + 1))?;
+        set_ipv4_address(&mut request, *ip);
+        ioctl(&socket, libc::SIOCSIFADDR, &mut request)?;
+        // Only the destination itself, not its whole class, lives on `lo`.
+        set_ipv4_address(&mut request, Ipv4Addr::BROADCAST);
+        ioctl(&socket, libc::SIOCSIFNETMASK, &mut request)?;
+    }
+    Ok(())
+}
+
+fn set_ipv4_address(request: &mut libc::ifreq, ip: Ipv4Addr) {
+    let address = libc::sockaddr_in {
+        sin_family: libc::AF_INET as libc::sa_family_t,
+        sin_port: 0,
+        sin_addr: libc::in_addr {
+            s_addr: u32::from(ip).to_be(),
+        },
+        sin_zero: [0; 8],
+    };
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            (&address as *const libc::sockaddr_in).cast::<u8>(),
+            (&mut request.ifr_ifru.ifru_addr as *mut libc::sockaddr).cast::<u8>(),
+            std::mem::size_of::<libc::sockaddr_in>(),
+        );
+    }
+}
+
+fn interface_request(name: &str) -> io::Result<libc::ifreq> {
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    if name.len() >= request.ifr_name.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("interface name too long: {name}"),
+        ));
+    }
+    for (slot, byte) in request.ifr_name.iter_mut().zip(name.bytes()) {
+        *slot = byte as libc::c_char;
+    }
+    Ok(request)
+}
+
+fn ioctl(socket: &OwnedFd, request: libc::c_ulong, ifreq: &mut libc::ifreq) -> io::Result<()> {
+    if unsafe { libc::ioctl(socket.as_raw_fd(), request as _, ifreq as *mut libc::ifreq) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Bind-mount a copy of `/etc/hosts` that also lists `hosts` over the
+/// original, visible only inside the new mount namespace.
+fn overlay_etc_hosts(hosts: &HashMap<String, Ipv4Addr>) -> io::Result<()> {
+    let mut contents = std::fs::read_to_string("/etc/hosts").unwrap_or_default();
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    let mut entries: Vec<_> = hosts.iter().collect();
+    entries.sort_by_key(|(_, ip)| **ip);
+    for (host, ip) in entries {
+        contents.push_str(&format!("{ip} {host}\n"));
+    }
+
+    let overlay = std::env::temp_dir().join(format!("codex-hosts-{}", std::process::id()));
+    let mut file = std::fs::File::create(&overlay)?;
+    file.write_all(contents.as_bytes())?;
+    drop(file);
+    let result = make_mounts_private().and_then(|()| bind_mount(&overlay, Path::new("/etc/hosts")));
+    let _ = std::fs::remove_file(&overlay);
+    result
+}
+
+fn bind_listeners(
+    destinations: &[NetworkDestination],
+    hosts: &HashMap<String, Ipv4Addr>,
+) -> io::Result<Vec<ForwardListener>> {
+    let mut listeners = Vec::new();
+    for (index, destination) in destinations.iter().enumerate() {
+        for ip in listen_addresses(destination, hosts)? {
+            match TcpListener::bind(SocketAddr::new(ip, destination.port)) {
+                Ok(listener) => listeners.push(ForwardListener {
+                    listener,
+                    destination: index,
+                }),
+                // The namespace may have no IPv6 loopback; `localhost` is
+                // still served on 127.0.0.1.
+                Err(_) if ip.is_ipv6() && destination.host.eq_ignore_ascii_case("localhost") => {}
+                Err(err) => {
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!("failed to listen for {ip}:{}: {err}", destination.port),
+                    ));
+                }
+            }
+        }
+    }
+    if listeners.len() > MAX_LISTENERS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("the network allow-list needs at most {MAX_LISTENERS} listeners"),
+        ));
+    }
+    Ok(listeners)
+}
+
+/// Pass the listeners to the proxy: the destination indices as data and the
+/// sockets as `SCM_RIGHTS`.
+fn send_listeners(socket: &UnixStream, listeners: &[ForwardListener]) -> io::Result<()> {
+    let indices: Vec<u8> = listeners
+        .iter()
+        .flat_map(|listener| (listener.destination as u32).to_ne_bytes())
+        .collect();
+    let fds: Vec<RawFd> = listeners
+        .iter()
+        .map(|listener| listener.listener.as_raw_fd())
+        .collect();
+    let fds_len = std::mem::size_of_val(fds.as_slice());
+    let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(fds_len as u32) } as usize];
+    let mut iov = libc::iovec {
+        iov_base: indices.as_ptr() as *mut libc::c_void,
+        iov_len: indices.len(),
+    };
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = control.len() as _;
+    unsafe {
+        let header = libc::CMSG_FIRSTHDR(&message);
+        (*header).cmsg_level = libc::SOL_SOCKET;
+        (*header).cmsg_type = libc::SCM_RIGHTS;
+        (*header).cmsg_len = libc::CMSG_LEN(fds_len as u32) as _;
+        std::ptr::copy_nonoverlapping(fds.as_ptr().cast::<u8>(), libc::CMSG_DATA(header), fds_len);
+        if libc::sendmsg(socket.as_raw_fd(), &message, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn receive_listeners(socket: &UnixStream) -> io::Result<Vec<ForwardListener>> {
+    let mut indices = vec![0u8; MAX_LISTENERS * 4];
+    let mut control = vec![
+        0u8;
+        unsafe { libc::CMSG_SPACE((MAX_LISTENERS * size_of::<RawFd>()) as u32) }
+            as usize
+    ];
+    let mut iov = libc::iovec {
+        iov_base: indices.as_mut_ptr().cast(),
+        iov_len: indices.len(),
+    };
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = control.len() as _;
+    let received =
+        unsafe { libc::recvmsg(socket.as_raw_fd(), &mut message, libc::MSG_CMSG_CLOEXEC) };
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut fds = Vec::new();
+    unsafe {
+        let mut header = libc::CMSG_FIRSTHDR(&message);
+        while !header.is_null() {
+            if (*header).cmsg_level == libc::SOL_SOCKET && (*header).cmsg_type == libc::SCM_RIGHTS {
+                let data_len = (*header).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                let data = libc::CMSG_DATA(header).cast::<RawFd>();
+                for i in 0..data_len / size_of::<RawFd>() {
+                    fds.push(OwnedFd::from_raw_fd(data.add(i).read_unaligned()));
+                }
+            }
+            header = libc::CMSG_NXTHDR(&message, header);
+        }
+    }
+
+    Ok(indices[..received as usize]
+        .chunks_exact(4)
+        .zip(fds)
+        .map(|(index, fd)| ForwardListener {
+            listener: TcpListener::from(fd),
+            destination: u32::from_ne_bytes([index[0], index[1], index[2], index[3]]) as usize,
+        })
+        .collect())
+}
+
+/// Body of the proxy process. Runs in the original network namespace and
+/// exits when the sandboxed command does.
+fn run_proxy(
+    socket: UnixStream,
+    destinations: &[NetworkDestination],
+    sandbox_pid: libc::pid_t,
+) -> ! {
+    unsafe {
+        // Die with the command, and keep it from tracing this process to
+        // borrow its network access.
+        libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+        libc::prctl(libc::PR_SET_DUMPABLE, 0);
+        if libc::getppid() != sandbox_pid {
+            libc::_exit(0);
+        }
+    }
+    detach_stdio();
+
+    let listeners = match receive_listeners(&socket) {
+        Ok(listeners) => listeners,
+        Err(_) => unsafe { libc::_exit(1) },
+    };
+    drop(socket);
+
+    let slots = Arc::new(ForwardSlots::default());
+    let mut accept_threads = Vec::new();
+    for ForwardListener {
+        listener,
+        destination,
+    } in listeners
+    {
+        let Some(destination) = destinations.get(destination).cloned() else {
+            continue;
+        };
+        let slots = Arc::clone(&slots);
+        accept_threads.push(std::thread::spawn(move || {
+            loop {
+                let slot = slots.acquire();
+                let Ok((inbound, _)) = listener.accept() else {
+                    continue;
+                };
+                let destination = destination.clone();
+                std::thread::spawn(move || {
+                    forward(inbound, &destination);
+                    drop(slot);
+                });
+            }
+        }));
+    }
+    for thread in accept_threads {
+        let _ = thread.join();
+    }
+    unsafe { libc::_exit(0) }
+}
+
+/// Keep the proxy from holding the command's output pipes open.
+fn detach_stdio() {
+    let Ok(dev_null) = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")
+    else {
+        return;
+    };
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        unsafe {
+            libc::dup2(dev_null.as_raw_fd(), fd);
+        }
+    }
+}
+
+/// Counts the connections being forwarded, up to [`MAX_FORWARDS`].
+#[derive(Default)]
+struct ForwardSlots {
+    used: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl ForwardSlots {
+    /// Wait until fewer than [`MAX_FORWARDS`] connections are forwarded and
+    /// take a slot; dropping the guard gives it back.
+    fn acquire(self: &Arc<Self>) -> ForwardSlot {
+        let mut used = self.used.lock().unwrap_or_else(PoisonError::into_inner);
+        while *used >= MAX_FORWARDS {
+            used = self
+                .freed
+                .wait(used)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *used += 1;
+        ForwardSlot(Arc::clone(self))
+    }
+}
+
+struct ForwardSlot(Arc<ForwardSlots>);
+
+impl Drop for ForwardSlot {
+    fn drop(&mut self) {
+        let mut used = self.0.used.lock().unwrap_or_else(PoisonError::into_inner);
+        *used -= 1;
+        self.0.freed.notify_one();
+    }
+}
+
+fn forward(inbound: TcpStream, destination: &NetworkDestination) {
+    let Some(outbound) = connect(destination) else {
+        reset(inbound);
+        return;
+    };
+    let (Ok(inbound_reader), Ok(outbound_reader)) = (inbound.try_clone(), outbound.try_clone())
+    else {
+        return;
+    };
+    let upload = std::thread::spawn(move || pipe(inbound_reader, outbound));
+    pipe(outbound_reader, inbound);
+    let _ = upload.join();
+}
+
+fn connect(destination: &NetworkDestination) -> Option<TcpStream> {
+    use std::net::ToSocketAddrs;
+
+    let addresses = (destination.host.as_str(), destination.port)
+        .to_socket_addrs()
+        .ok()?;
+    addresses
+        .into_iter()
+        .find_map(|address| TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).ok())
+}
+
+/// Close `stream` with a reset rather than an orderly shutdown, so that the
+/// client sees the destination as unreachable instead of an empty reply.
+fn reset(stream: TcpStream) {
+    let linger = libc::linger {
+        l_onoff: 1,
+        l_linger: 0,
+    };
+    unsafe {
+        libc::setsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_LINGER,
+            &linger as *const libc::linger as *const libc::c_void,
+            std::mem::size_of::<libc::linger>() as libc::socklen_t,
+        );
+    }
+}
+
+/// Copy `from` into `to` until `from` is closed, then close the write half
+/// of `to`.
+fn pipe(mut from: TcpStream, mut to: TcpStream) {
+    let _ = io::copy(&mut from, &mut to);
+    let _ = to.shutdown(Shutdown::Write);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn destinations(entries: &[&str]) -> Vec<NetworkDestination> {
+        entries
+            .iter()
+            .map(|entry| entry.parse().expect("destination"))
+            .collect()
+    }
+
+    #[test]
+    fn host_names_get_distinct_addresses() {
+        let destinations = destinations(&[
+            "localhost:5432",
+            "10.0.0.5:3128",
+            "mirror.internal:443",
+            "Mirror.Internal:80",
+            "cache.internal:443",
+        ]);
+        let hosts = host_addresses(&destinations);
+        assert_eq!(
+            hosts,
+            HashMap::from([
+                ("mirror.internal".to_string(), Ipv4Addr::new(127, 0, 1, 1)),
+                ("cache.internal".to_string(), Ipv4Addr::new(127, 0, 1, 2)),
+            ])
+        );
+        assert_eq!(
+            listen_addresses(&destinations[2], &hosts).expect("addresses"),
+            vec![IpAddr::V4(Ipv4Addr::new(127, 0, 1, 1))]
+        );
+        assert_eq!(
+            listen_addresses(&destinations[1], &hosts).expect("addresses"),
+            vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5))]
+        );
+    }
+
+    #[test]
+    fn non_loopback_ipv6_is_rejected() {
+        let destinations = destinations(&["[2001:db8::1]:443"]);
+        let hosts = host_addresses(&destinations);
+        assert!(listen_addresses(&destinations[0], &hosts).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! `read_only_subpaths` and `deny_read_paths` for the sandboxed command.
+//!
+//! Landlock grants cannot be subtracted, so a mask inside a writable root
+//! cannot be expressed as a Landlock rule without also withholding rights
+//! from the directories around it. The masks are mounts instead, made in a
+//! private user and mount namespace: a read-only path is bind-mounted onto
+//! itself read-only, a hidden directory is covered by an empty tmpfs and a
+//! hidden file by an empty file, both inaccessible. The directories around
+//! the masks keep all their rights, and the masked entries, being mount
+//! points, can be neither removed nor renamed.
+//!
+//! A directory holding a mount point can still be renamed, which would let a
+//! command swap a copy it controls in for, say, the `.git` holding a
+//! read-only `.git/hooks`. So every directory from the writable root down to
+//! a mask is bind-mounted onto itself as well, which pins it in place
+//! without taking any rights away.
+//!
+//! The masks are the paths that exist when the command starts. A path
+//! created later that a mask pattern matches is not masked.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::path_masks::MaskedPath;
+use codex_core::path_masks::PathMaskKind;
+
+use crate::namespace::bind_mount;
+use crate::namespace::make_mounts_private;
+use crate::namespace::map_current_user;
+use crate::namespace::mount;
+
+/// Mount `masked_paths` over themselves in a new user and mount namespace,
+/// pinning the directories between them and the `writable_roots` holding
+/// them. Must be called while the process is still single-threaded, and
+/// before Landlock rules are enforced, since those forbid mounting.
+pub(crate) fn apply_path_masks(
+    masked_paths: &[MaskedPath],
+    writable_roots: &[PathBuf],
+) -> io::Result<()> {
+    if masked_paths.is_empty() {
+        return Ok(());
+    }
+    let uid = unsafe { libc::getuid() };
+    let gid = unsafe { libc::getgid() };
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    map_current_user(uid, gid)?;
+    make_mounts_private()?;
+
+    for (path, mask) in mount_plan(masked_paths, writable_roots) {
+        match mask {
+            // Recursive, so that mounts already inside stay visible.
+            None => mount(Some(&path), &path, None, libc::MS_BIND | libc::MS_REC, None)?,
+            Some(PathMaskKind::ReadOnly) => {
+                bind_mount(&path, &path)?;
+                remount_read_only(&path)?;
+            }
+            Some(PathMaskKind::DenyRead) => hide(&path)?,
+        }
+    }
+    // The working directory may now lie beneath one of the mounts, which
+    // only paths resolved from here on go through.
+    std::env::set_current_dir(std::env::current_dir()?)
+}
+
+/// The mounts [`apply_path_masks`] makes, as each path with its mask, or
+/// `None` for a directory that is only pinned. Outer paths come first, so
+/// that a mount inside another one is made on top of it rather than hidden
+/// beneath it.
+fn mount_plan(
+    masked_paths: &[MaskedPath],
+    writable_roots: &[PathBuf],
+) -> Vec<(PathBuf, Option<PathMaskKind>)> {
+    let mut plan: Vec<(PathBuf, Option<PathMaskKind>)> = masked_paths
+        .iter()
+        .map(|masked| (masked.path.clone(), Some(masked.kind)))
+        .collect();
+    for masked in masked_paths {
+        let Some(root) = writable_roots
+            .iter()
+            .filter(|root| masked.path.starts_with(root))
+            .min_by_key(|root| root.components().count())
+        else {
+            continue;
+        };
+        for dir in masked.path.ancestors().skip(1) {
+            if !dir.starts_with(root) {
+                break;
+            }
+            if dir.parent().is_some() && plan.iter().all(|(path, _)| path != dir) {
+                plan.push((dir.to_path_buf(), None));
+            }
+        }
+    }
+    plan.sort_by_key(|(path, _)| path.components().count());
+    plan
+}
+
+/// Cover `path` with an empty directory or file that nobody may open.
+fn hide(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        return mount(
+            Some(Path::new("tmpfs")),
+            path,
+            Some("tmpfs"),
+            libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+            Some("mode=000,size=4k,nr_inodes=1"),
+        );
+    }
+    let placeholder = std::env::temp_dir().join(format!("codex-mask-{}", std::process::id()));
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o000)
+        .open(&placeholder)?;
+    let result = bind_mount(&placeholder, path).and_then(|()| remount_read_only(path));
+    let _ = std::fs::remove_file(&placeholder);
+    result
+}
+
+/// Make the bind mount at `path` read-only. A remount inside a user
+/// namespace must keep the flags the original mount was locked with.
+fn remount_read_only(path: &Path) -> io::Result<()> {
+    let c_path =
+        std::ffi::CString::new(path.as_os_str().as_encoded_bytes()).map_err(io::Error::other)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    mount(None, path, None, read_only_remount_flags(stat.f_flag), None)
+}
+
+/// The flags that remount a bind mount read-only while keeping the locked
+/// flags among `st_flags`, as `statvfs` reports them.
+fn read_only_remount_flags(st_flags: libc::c_ulong) -> libc::c_ulong {
+    let mut flags = libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY;
+    for (st_flag, ms_flag) in [
+        (libc::ST_NOSUID, libc::MS_NOSUID),
+        (libc::ST_NODEV, libc::MS_NODEV),
+        (libc::ST_NOEXEC, libc::MS_NOEXEC),
+        (libc::ST_NOATIME, libc::MS_NOATIME),
+        (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (libc::ST_RELATIME, libc::MS_RELATIME),
+    ] {
+        if st_flags & st_flag != 0 {
+            flags |= ms_flag;
+        }
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn masked(path: &str, kind: PathMaskKind) -> MaskedPath {
+        MaskedPath {
+            path: PathBuf::from(path),
+            kind,
+        }
+    }
+
+    #[test]
+    fn directories_down_to_each_mask_are_pinned_outer_first() {
+        let plan = mount_plan(
+            &[
+                masked("/repo/.git/hooks", PathMaskKind::ReadOnly),
+                masked("/repo/config/.env", PathMaskKind::DenyRead),
+                masked("/repo/.git", PathMaskKind::ReadOnly),
+                masked("/elsewhere/secret", PathMaskKind::DenyRead),
+            ],
+            &[PathBuf::from("/repo"), PathBuf::from("/repo/config")],
+        );
+
+        assert_eq!(
+            plan,
+            vec![
+                (PathBuf::from("/repo"), None),
+                (PathBuf::from("/repo/.git"), Some(PathMaskKind::ReadOnly)),
+                (
+                    PathBuf::from("/elsewhere/secret"),
+                    Some(PathMaskKind::DenyRead)
+                ),
+                (PathBuf::from("/repo/config"), None),
+                (
+                    PathBuf::from("/repo/.git/hooks"),
+                    Some(PathMaskKind::ReadOnly)
+                ),
+                (
+                    PathBuf::from("/repo/config/.env"),
+                    Some(PathMaskKind::DenyRead)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn remounts_keep_the_locked_flags() {
+        let base = libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY;
+        assert_eq!(read_only_remount_flags(0), base);
+        assert_eq!(
+            read_only_remount_flags(libc::ST_NOSUID | libc::ST_NODEV | libc::ST_RDONLY),
+            base | libc::MS_NOSUID | libc::MS_NODEV
+        );
+    }
+}
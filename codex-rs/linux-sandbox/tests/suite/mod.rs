@@ -1,2 +1,4 @@
 // Aggregates all former standalone integration tests as modules.
 mod landlock;
+mod network_allowlist;
+mod path_masks;
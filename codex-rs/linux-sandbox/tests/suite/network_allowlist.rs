@@ -0,0 +1,102 @@
+#![cfg(target_os = "linux")]
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::ResourceLimits;
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+/// Serve `greeting` to every connection on a fresh loopback port of the
+/// host's network namespace.
+#[expect(clippy::expect_used)]
+fn serve_greeting(greeting: &'static str) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let port = listener.local_addr().expect("local addr").port();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let _ = stream.write_all(greeting.as_bytes());
+        }
+    });
+    port
+}
+
+#[expect(clippy::expect_used)]
+async fn run_with_allowlist(script: &str, network_allowlist: Vec<String>) -> ExecToolCallOutput {
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let params = ExecParams {
+        command: vec!["bash".to_string(), "-c".to_string(), script.to_string()],
+        cwd: cwd.clone(),
+        timeout_ms: Some(10_000),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits: ResourceLimits::default(),
+    };
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        read_only_subpaths: vec![],
+        deny_read_paths: vec![],
+        network_access: false,
+        network_allowlist,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+    };
+    let codex_linux_sandbox_exe = Some(PathBuf::from(env!("CARGO_BIN_EXE_codex-linux-sandbox")));
+    match process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await
+    {
+        Ok(output) => output,
+        Err(codex_core::error::CodexErr::Sandbox(codex_core::error::SandboxErr::Denied {
+            output,
+        })) => *output,
+        Err(err) => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[tokio::test]
+async fn allowlisted_destination_is_forwarded() {
+    let port = serve_greeting("hello from the host\n");
+    let output = run_with_allowlist(
+        &format!("exec 3<>/dev/tcp/127.0.0.1/{port} && head -n 1 <&3"),
+        vec![format!("127.0.0.1:{port}")],
+    )
+    .await;
+    assert_eq!(output.exit_code, 0, "{}", output.stderr.text);
+    assert_eq!(output.stdout.text, "hello from the host\n");
+}
+
+#[tokio::test]
+async fn localhost_destination_is_forwarded() {
+    let port = serve_greeting("hello via localhost\n");
+    let output = run_with_allowlist(
+        &format!("exec 3<>/dev/tcp/localhost/{port} && head -n 1 <&3"),
+        vec![format!("localhost:{port}")],
+    )
+    .await;
+    assert_eq!(output.exit_code, 0, "{}", output.stderr.text);
+    assert_eq!(output.stdout.text, "hello via localhost\n");
+}
+
+#[tokio::test]
+async fn destinations_off_the_allowlist_are_unreachable() {
+    let allowed = serve_greeting("allowed\n");
+    let other = serve_greeting("should not be reachable\n");
+    let output = run_with_allowlist(
+        &format!("exec 3<>/dev/tcp/127.0.0.1/{other} && head -n 1 <&3"),
+        vec![format!("127.0.0.1:{allowed}")],
+    )
+    .await;
+    assert_ne!(output.exit_code, 0, "{}", output.stdout.text);
+    assert_eq!(output.stdout.text, "");
+}
@@ -0,0 +1,115 @@
+#![cfg(target_os = "linux")]
+use codex_core::exec::ExecParams;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::ResourceLimits;
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Run `script` in `workspace` with `.git/hooks` read-only and `.env*`
+/// hidden, returning its stdout.
+#[expect(clippy::expect_used)]
+async fn run_in_masked_workspace(workspace: &Path, script: &str) -> String {
+    let params = ExecParams {
+        command: vec!["bash".to_string(), "-c".to_string(), script.to_string()],
+        cwd: workspace.to_path_buf(),
+        timeout_ms: Some(10_000),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+        resource_limits: ResourceLimits::default(),
+    };
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        read_only_subpaths: vec![".git/hooks".to_string()],
+        deny_read_paths: vec![".env*".to_string()],
+        network_access: false,
+        network_allowlist: vec![],
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+    };
+    let codex_linux_sandbox_exe = Some(PathBuf::from(env!("CARGO_BIN_EXE_codex-linux-sandbox")));
+    let output = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        workspace,
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await
+    .expect("command should run");
+    assert_eq!(output.exit_code, 0, "{}", output.stderr.text);
+    output.stdout.text
+}
+
+#[tokio::test]
+async fn masked_paths_are_withheld_from_the_workspace() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let workspace = tmp.path();
+    std::fs::create_dir_all(workspace.join(".git/hooks")).expect("mkdir");
+    std::fs::write(workspace.join(".git/hooks/pre-commit"), "exit 0\n").expect("write");
+    std::fs::write(workspace.join(".env.local"), "TOKEN=secret\n").expect("write");
+    std::fs::write(workspace.join("main.rs"), "fn main() {}\n").expect("write");
+    std::fs::create_dir_all(workspace.join("src")).expect("mkdir");
+
+    let stdout = run_in_masked_workspace(
+        workspace,
+        r#"
+        echo edited > main.rs && echo "main.rs writable"
+        echo x > .git/hooks/pre-commit 2>/dev/null || echo "hook read-only"
+        cat .git/hooks/pre-commit
+        grep -q TOKEN .env.local 2>/dev/null || echo ".env.local hidden"
+        echo x > .env.local 2>/dev/null || echo ".env.local read-only"
+        echo new > src/lib.rs && echo "src/lib.rs created"
+        echo new > notes.md && mv notes.md todo.md && rm todo.md && echo "workspace root editable"
+        rm .env.local 2>/dev/null || echo ".env.local kept"
+        "#,
+    )
+    .await;
+
+    assert_eq!(
+        stdout,
+        "main.rs writable\nhook read-only\nexit 0\n.env.local hidden\n.env.local read-only\nsrc/lib.rs created\nworkspace root editable\n.env.local kept\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("main.rs")).expect("read"),
+        "edited\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join(".env.local")).expect("read"),
+        "TOKEN=secret\n"
+    );
+}
+
+#[tokio::test]
+async fn directories_holding_masks_cannot_be_swapped_out() {
+    let tmp = tempfile::tempdir().expect("tempdir");
+    let workspace = tmp.path();
+    std::fs::create_dir_all(workspace.join(".git/hooks")).expect("mkdir");
+    std::fs::write(workspace.join(".git/hooks/pre-commit"), "exit 0\n").expect("write");
+
+    let stdout = run_in_masked_workspace(
+        workspace,
+        r#"
+        cp -a .git .git2 && echo ".git copied"
+        mv .git .git.old 2>/dev/null || echo ".git kept"
+        mv -T .git2 .git 2>/dev/null || echo ".git not replaced"
+        echo x > .git/hooks/pre-commit 2>/dev/null || echo "hook read-only"
+        "#,
+    )
+    .await;
+
+    assert_eq!(
+        stdout,
+        ".git copied\n.git kept\n.git not replaced\nhook read-only\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace.join(".git/hooks/pre-commit")).expect("read"),
+        "exit 0\n"
+    );
+    assert!(!workspace.join(".git.old").exists());
+}
@@ -583,6 +583,7 @@ impl CodexMessageProcessor {
             env,
             with_escalated_permissions: None,
             justification: None,
+            resource_limits: self.config.sandbox_resources,
         };
 
         let effective_policy = params
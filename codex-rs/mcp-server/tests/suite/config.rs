@@ -88,7 +88,10 @@ async fn get_config_toml_parses_all_fields() {
             sandbox_mode: Some(SandboxMode::WorkspaceWrite),
             sandbox_settings: Some(SandboxSettings {
                 writable_roots: vec!["/tmp".into()],
+                read_only_subpaths: Vec::new(),
+                deny_read_paths: Vec::new(),
                 network_access: Some(true),
+                network_allowlist: Vec::new(),
                 exclude_tmpdir_env_var: Some(true),
                 exclude_slash_tmp: Some(true),
             }),
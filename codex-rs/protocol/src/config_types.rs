@@ -59,3 +59,37 @@ pub enum SandboxMode {
     #[serde(rename = "danger-full-access")]
     DangerFullAccess,
 }
+
+/// What happens to a command matching a [`CommandRule`]. When several rules
+/// match, the strictest decision wins.
+#[derive(
+    Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Display, TS,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum CommandRuleDecision {
+    /// Run without asking, like a command approved for the session.
+    Allow,
+    /// Always ask the user, even for commands that would run without asking.
+    Ask,
+    /// Never run.
+    Deny,
+}
+
+/// A persistent approval rule, configured under a `[projects]` entry.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Serialize, TS)]
+pub struct CommandRule {
+    /// Glob matched against the program (`argv[0]`).
+    pub program: String,
+
+    /// One glob per argument. `**` matches any number of arguments.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Glob the command's working directory, or one of its parents, must
+    /// match. Relative patterns are resolved against the project root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
+    pub decision: CommandRuleDecision,
+}
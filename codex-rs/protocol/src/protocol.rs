@@ -10,6 +10,7 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 
+use crate::config_types::CommandRule;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use crate::custom_prompts::CustomPrompt;
@@ -143,6 +144,10 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Add a command approval rule for the rest of the session, e.g. one the
+    /// user just saved to `config.toml`.
+    AddCommandRule { rule: CommandRule },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -174,6 +179,12 @@ pub enum Op {
     /// Request a code review from the agent.
     Review { review_request: ReviewRequest },
 
+    /// Relax the context budgets (per-turn output budget, `read_code` caps and
+    /// the repeat-command breaker) for a single turn. Applies to the running
+    /// turn, or to the next turn when the agent is idle.
+    /// Reply is delivered via `EventMsg::RelaxGranted`.
+    Relax,
+
     /// Request to shut down codex instance.
     Shutdown,
 }
@@ -228,11 +239,29 @@ pub enum SandboxPolicy {
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         writable_roots: Vec<PathBuf>,
 
+        /// Paths that stay read-only even though they are under a writable
+        /// root. Relative entries apply under every writable root; `*` and
+        /// `?` match within one path component.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        read_only_subpaths: Vec<String>,
+
+        /// Paths, written like `read_only_subpaths`, whose contents commands
+        /// may neither read nor modify.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        deny_read_paths: Vec<String>,
+
         /// When set to `true`, outbound network access is allowed. `false` by
         /// default.
         #[serde(default)]
         network_access: bool,
 
+        /// `host:port` destinations that stay reachable when
+        /// `network_access` is `false`. When non-empty, the Linux sandbox
+        /// runs commands in a private network namespace where loopback, Unix
+        /// sockets and these destinations are the only peers.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        network_allowlist: Vec<String>,
+
         /// When set to `true`, will NOT include the per-user `TMPDIR`
         /// environment variable among the default writable roots. Defaults to
         /// `false`.
@@ -297,7 +326,10 @@ impl SandboxPolicy {
     pub fn new_workspace_write_policy() -> Self {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
+            read_only_subpaths: vec![],
+            deny_read_paths: vec![],
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         }
@@ -324,6 +356,19 @@ impl SandboxPolicy {
         }
     }
 
+    /// The `host:port` destinations reachable despite network access being
+    /// disabled. Empty when there is no allow-list.
+    pub fn network_allowlist(&self) -> &[String] {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                network_access: false,
+                network_allowlist,
+                ..
+            } => network_allowlist,
+            _ => &[],
+        }
+    }
+
     /// Returns the list of writable roots (tailored to the current working
     /// directory) together with subpaths that should remain read‑only under
     /// each writable root.
@@ -335,7 +380,10 @@ impl SandboxPolicy {
                 writable_roots,
                 exclude_tmpdir_env_var,
                 exclude_slash_tmp,
+                read_only_subpaths: _,
+                deny_read_paths: _,
                 network_access: _,
+                network_allowlist: _,
             } => {
                 // Start from explicitly configured writable roots.
                 let mut roots: Vec<PathBuf> = writable_roots.clone();
@@ -519,6 +567,16 @@ pub enum EventMsg {
 
     /// Exited review mode with an optional final result to apply.
     ExitedReviewMode(ExitedReviewModeEvent),
+
+    /// Context budgets were relaxed for one turn via `/relax`.
+    RelaxGranted(RelaxGrantedEvent),
+
+    /// Context budget counters for the turn that just completed, along with
+    /// running totals for the session.
+    TurnMetrics(TurnMetricsEvent),
+
+    /// The set of running background jobs changed.
+    BackgroundJobs(BackgroundJobsEvent),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -595,6 +653,80 @@ pub struct TokenCountEvent {
     pub rate_limits: Option<RateLimitSnapshot>,
 }
 
+/// How tool output was budgeted before it reached the model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct ContextMetrics {
+    /// Tool output bytes sent to the model.
+    pub bytes_served: u64,
+    /// Tool output bytes dropped to stay within budgets.
+    pub bytes_trimmed: u64,
+    /// Tool outputs cut short by a budget.
+    pub outputs_truncated: u64,
+    /// Commands refused by the repeat-command breaker.
+    pub commands_blocked: u64,
+    /// Build or test logs trimmed to their tail.
+    pub log_tail_invocations: u64,
+    /// `/relax` grants applied.
+    pub relax_grants: u64,
+    /// Repeated commands let through because of a relax grant.
+    pub breaker_bypasses: u64,
+    /// `read_code` chunks served from the chunk cache.
+    pub chunk_cache_hits: u64,
+    /// `read_code` chunks loaded from disk.
+    pub chunk_cache_misses: u64,
+    /// `plan_reads` plans that fit the budget.
+    pub read_plans_accepted: u64,
+    /// `plan_reads` plans that exceeded the budget.
+    pub read_plans_rejected: u64,
+    /// Bytes estimated by accepted `plan_reads` plans.
+    pub planned_read_bytes: u64,
+    /// Bytes actually returned by `read_code`.
+    pub read_code_bytes: u64,
+    /// `read_code` calls for files outside the accepted plan.
+    pub unplanned_reads: u64,
+}
+
+impl ContextMetrics {
+    /// In-place element-wise sum of counters.
+    pub fn add_assign(&mut self, other: &ContextMetrics) {
+        self.bytes_served += other.bytes_served;
+        self.bytes_trimmed += other.bytes_trimmed;
+        self.outputs_truncated += other.outputs_truncated;
+        self.commands_blocked += other.commands_blocked;
+        self.log_tail_invocations += other.log_tail_invocations;
+        self.relax_grants += other.relax_grants;
+        self.breaker_bypasses += other.breaker_bypasses;
+        self.chunk_cache_hits += other.chunk_cache_hits;
+        self.chunk_cache_misses += other.chunk_cache_misses;
+        self.read_plans_accepted += other.read_plans_accepted;
+        self.read_plans_rejected += other.read_plans_rejected;
+        self.planned_read_bytes += other.planned_read_bytes;
+        self.read_code_bytes += other.read_code_bytes;
+        self.unplanned_reads += other.unplanned_reads;
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct TurnMetricsEvent {
+    /// Counters for the turn that just completed.
+    pub turn: ContextMetrics,
+    /// Totals across the session so far, including `turn`.
+    pub session_total: ContextMetrics,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct BackgroundJobsEvent {
+    /// Jobs that are still running, ordered by id.
+    pub jobs: Vec<BackgroundJobSummary>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct BackgroundJobSummary {
+    pub job_id: u32,
+    /// The command line, joined for display.
+    pub command: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct RateLimitSnapshot {
     pub primary: Option<RateLimitWindow>,
@@ -1043,6 +1175,19 @@ pub struct ReviewLineRange {
     pub end: u32,
 }
 
+/// The sandbox a command runs under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxType {
+    None,
+
+    /// Only available on macOS.
+    MacosSeatbelt,
+
+    /// Only available on Linux.
+    LinuxSeccomp,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ExecCommandBeginEvent {
     /// Identifier so this can be paired with the ExecCommandEnd event.
@@ -1052,6 +1197,11 @@ pub struct ExecCommandBeginEvent {
     /// The command's working directory if not the default cwd for the agent.
     pub cwd: PathBuf,
     pub parsed_cmd: Vec<ParsedCommand>,
+    /// The timeout the command runs with, if the call requested one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// The sandbox the command runs under.
+    pub sandbox_type: SandboxType,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -1263,6 +1413,29 @@ pub enum TurnAbortReason {
     ReviewEnded,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct RelaxGrantedEvent {
+    /// Who asked for the relaxation.
+    pub source: RelaxSource,
+    /// Per-turn tool output budget in effect for the relaxed turn.
+    pub turn_output_max_bytes: u64,
+    /// `true` when the grant was deferred to the next turn because no turn
+    /// was running.
+    pub deferred: bool,
+    /// Justification supplied by the model, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum RelaxSource {
+    /// The user ran `/relax` (or a client submitted `Op::Relax`).
+    User,
+    /// The model called the `request_relax` tool.
+    Model,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
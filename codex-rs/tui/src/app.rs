@@ -11,9 +11,12 @@ use crate::tui::TuiEvent;
 use codex_ansi_escape::ansi_escape_line;
 use codex_core::AuthManager;
 use codex_core::ConversationManager;
+use codex_core::command_rules::describe_rule;
 use codex_core::config::Config;
 use codex_core::config::persist_model_selection;
+use codex_core::config_edit::persist_command_rule;
 use codex_core::model_family::find_family_for_model;
+use codex_core::protocol::Op;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -348,6 +351,28 @@ impl App {
                     }
                 }
             }
+            AppEvent::PersistCommandRule(rule) => {
+                let project = self.config.command_rules.project_root.clone();
+                match persist_command_rule(&self.config.codex_home, &project, &rule).await {
+                    Ok(()) => {
+                        self.chat_widget.add_info_message(
+                            format!(
+                                "`{}` will be allowed in {} from now on",
+                                describe_rule(&rule),
+                                project.display()
+                            ),
+                            None,
+                        );
+                        self.config.command_rules.rules.push(rule.clone());
+                        self.chat_widget.submit_op(Op::AddCommandRule { rule });
+                    }
+                    Err(err) => {
+                        tracing::error!(error = %err, "failed to persist command rule");
+                        self.chat_widget
+                            .add_error_message(format!("Failed to save command rule: {err}"));
+                    }
+                }
+            }
             AppEvent::UpdateAskForApprovalPolicy(policy) => {
                 self.chat_widget.set_approval_policy(policy);
             }
@@ -8,6 +8,7 @@ use crate::history_cell::HistoryCell;
 
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol_config_types::CommandRule;
 use codex_core::protocol_config_types::ReasoningEffort;
 
 #[allow(clippy::large_enum_variant)]
@@ -59,6 +60,10 @@ pub(crate) enum AppEvent {
         effort: Option<ReasoningEffort>,
     },
 
+    /// Save a command approval rule for the current project to `config.toml`
+    /// and apply it to the running session.
+    PersistCommandRule(CommandRule),
+
     /// Update the current approval policy in the running app and widget.
     UpdateAskForApprovalPolicy(AskForApproval),
 
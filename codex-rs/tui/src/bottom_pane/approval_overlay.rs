@@ -11,8 +11,12 @@ use crate::bottom_pane::list_selection_view::SelectionViewParams;
 use crate::exec_command::strip_bash_lc_and_escape;
 use crate::history_cell;
 use crate::text_formatting::truncate_text;
+use codex_core::command_rules::describe_rule;
+use codex_core::command_rules::rule_for_command;
+use codex_core::command_rules::rule_for_similar_commands;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
+use codex_protocol::config_types::CommandRule;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
@@ -94,7 +98,9 @@ impl ApprovalOverlay {
             );
         };
         let (options, title) = match &state.variant {
-            ApprovalVariant::Exec { .. } => (exec_options(), "Allow command?".to_string()),
+            ApprovalVariant::Exec { command, .. } => {
+                (exec_options(command), "Allow command?".to_string())
+            }
             ApprovalVariant::ApplyPatch { .. } => (patch_options(), "Apply changes?".to_string()),
         };
 
@@ -132,6 +138,10 @@ impl ApprovalOverlay {
             match (&state.variant, option.decision) {
                 (ApprovalVariant::Exec { id, command }, decision) => {
                     self.handle_exec_decision(id, command, decision);
+                    if let Some(rule) = &option.save_rule {
+                        self.app_event_tx
+                            .send(AppEvent::PersistCommandRule(rule.clone()));
+                    }
                 }
                 (ApprovalVariant::ApplyPatch { id, .. }, decision) => {
                     self.handle_patch_decision(id, decision);
@@ -337,15 +347,18 @@ struct ApprovalOption {
     description: String,
     decision: ReviewDecision,
     shortcut: Option<char>,
+    /// Command rule saved to `config.toml` when this option is chosen.
+    save_rule: Option<CommandRule>,
 }
 
-fn exec_options() -> Vec<ApprovalOption> {
-    vec![
+fn exec_options(command: &[String]) -> Vec<ApprovalOption> {
+    let mut options = vec![
         ApprovalOption {
             label: "Approve and run now".to_string(),
             description: "(Y) Run this command one time".to_string(),
             decision: ReviewDecision::Approved,
             shortcut: Some('y'),
+            save_rule: None,
         },
         ApprovalOption {
             label: "Always approve this session".to_string(),
@@ -353,14 +366,43 @@ fn exec_options() -> Vec<ApprovalOption> {
                 .to_string(),
             decision: ReviewDecision::ApprovedForSession,
             shortcut: Some('a'),
+            save_rule: None,
         },
-        ApprovalOption {
-            label: "Cancel".to_string(),
-            description: "(N) Do not run the command".to_string(),
-            decision: ReviewDecision::Abort,
-            shortcut: Some('n'),
-        },
-    ]
+    ];
+    // Saved rules cover later runs, including the rest of this session, so
+    // only this run is approved here.
+    if let Some(rule) = rule_for_command(command) {
+        options.push(ApprovalOption {
+            label: "Always allow this command".to_string(),
+            description: format!(
+                "(R) Allow `{}` in this project from now on",
+                describe_rule(&rule)
+            ),
+            decision: ReviewDecision::Approved,
+            shortcut: Some('r'),
+            save_rule: Some(rule),
+        });
+    }
+    if let Some(rule) = rule_for_similar_commands(command) {
+        options.push(ApprovalOption {
+            label: "Always allow commands like this".to_string(),
+            description: format!(
+                "(L) Allow `{}` in this project from now on",
+                describe_rule(&rule)
+            ),
+            decision: ReviewDecision::Approved,
+            shortcut: Some('l'),
+            save_rule: Some(rule),
+        });
+    }
+    options.push(ApprovalOption {
+        label: "Cancel".to_string(),
+        description: "(N) Do not run the command".to_string(),
+        decision: ReviewDecision::Abort,
+        shortcut: Some('n'),
+        save_rule: None,
+    });
+    options
 }
 
 fn patch_options() -> Vec<ApprovalOption> {
@@ -370,12 +412,14 @@ fn patch_options() -> Vec<ApprovalOption> {
             description: "(Y) Apply the proposed changes".to_string(),
             decision: ReviewDecision::Approved,
             shortcut: Some('y'),
+            save_rule: None,
         },
         ApprovalOption {
             label: "Cancel".to_string(),
             description: "(N) Do not apply the changes".to_string(),
             decision: ReviewDecision::Abort,
             shortcut: Some('n'),
+            save_rule: None,
         },
     ]
 }
@@ -504,6 +548,52 @@ mod tests {
         assert!(saw_op, "expected approval decision to emit an op");
     }
 
+    fn saved_rule_for_shortcut(shortcut: char) -> (Option<ReviewDecision>, Option<String>) {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx);
+        let request = ApprovalRequest::Exec {
+            id: "test".to_string(),
+            command: vec![
+                "cargo".to_string(),
+                "test".to_string(),
+                "-p".to_string(),
+                "foo".to_string(),
+            ],
+            reason: None,
+        };
+        let mut view = ApprovalOverlay::new(request, tx);
+        view.handle_key_event(KeyEvent::new(KeyCode::Char(shortcut), KeyModifiers::NONE));
+
+        let mut decision = None;
+        let mut rule = None;
+        while let Ok(ev) = rx.try_recv() {
+            match ev {
+                AppEvent::CodexOp(Op::ExecApproval { decision: d, .. }) => decision = Some(d),
+                AppEvent::PersistCommandRule(r) => rule = Some(describe_rule(&r)),
+                _ => {}
+            }
+        }
+        (decision, rule)
+    }
+
+    #[test]
+    fn always_allow_options_save_the_exact_or_the_wider_rule() {
+        assert_eq!(
+            saved_rule_for_shortcut('r'),
+            (
+                Some(ReviewDecision::Approved),
+                Some("cargo test -p foo".to_string())
+            )
+        );
+        assert_eq!(
+            saved_rule_for_shortcut('l'),
+            (
+                Some(ReviewDecision::Approved),
+                Some("cargo test **".to_string())
+            )
+        );
+    }
+
     #[test]
     fn header_includes_command_snippet() {
         let (tx, _rx) = unbounded_channel::<AppEvent>();
@@ -9,11 +9,13 @@ use ratatui::widgets::WidgetRef;
 use std::iter;
 
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct FooterProps {
+pub(crate) struct FooterProps<'a> {
     pub(crate) mode: FooterMode,
     pub(crate) esc_backtrack_hint: bool,
     pub(crate) use_shift_enter_hint: bool,
     pub(crate) is_task_running: bool,
+    /// Labels of the background jobs that are still running.
+    pub(crate) background_jobs: &'a [String],
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -54,11 +56,11 @@ pub(crate) fn reset_mode_after_activity(current: FooterMode) -> FooterMode {
     }
 }
 
-pub(crate) fn footer_height(props: FooterProps) -> u16 {
+pub(crate) fn footer_height(props: FooterProps<'_>) -> u16 {
     footer_lines(props).len() as u16
 }
 
-pub(crate) fn render_footer(area: Rect, buf: &mut Buffer, props: FooterProps) {
+pub(crate) fn render_footer(area: Rect, buf: &mut Buffer, props: FooterProps<'_>) {
     let lines = footer_lines(props);
     for (idx, line) in lines.into_iter().enumerate() {
         let y = area.y + idx as u16;
@@ -70,8 +72,8 @@ pub(crate) fn render_footer(area: Rect, buf: &mut Buffer, props: FooterProps) {
     }
 }
 
-fn footer_lines(props: FooterProps) -> Vec<Line<'static>> {
-    match props.mode {
+fn footer_lines(props: FooterProps<'_>) -> Vec<Line<'static>> {
+    let mut lines = match props.mode {
         FooterMode::CtrlCReminder => vec![ctrl_c_reminder_line(CtrlCReminderState {
             is_task_running: props.is_task_running,
         })],
@@ -81,8 +83,12 @@ fn footer_lines(props: FooterProps) -> Vec<Line<'static>> {
             esc_backtrack_hint: props.esc_backtrack_hint,
         }),
         FooterMode::EscHint => vec![esc_hint_line(props.esc_backtrack_hint)],
-        FooterMode::Empty => Vec::new(),
+        FooterMode::Empty => return Vec::new(),
+    };
+    if !props.background_jobs.is_empty() {
+        lines.push(background_jobs_line(props.background_jobs));
     }
+    lines
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -106,6 +112,11 @@ fn ctrl_c_reminder_line(state: CtrlCReminderState) -> Line<'static> {
     dim_line(indent_text(&text))
 }
 
+fn background_jobs_line(jobs: &[String]) -> Line<'static> {
+    let text = format!("jobs: {}", jobs.join(" · "));
+    dim_line(indent_text(&text))
+}
+
 fn esc_hint_line(esc_backtrack_hint: bool) -> Line<'static> {
     let text = if esc_backtrack_hint {
         "esc again to edit previous message"
@@ -377,7 +388,7 @@ mod tests {
     use ratatui::Terminal;
     use ratatui::backend::TestBackend;
 
-    fn snapshot_footer(name: &str, props: FooterProps) {
+    fn snapshot_footer(name: &str, props: FooterProps<'_>) {
         let height = footer_height(props).max(1);
         let mut terminal = Terminal::new(TestBackend::new(80, height)).unwrap();
         terminal
@@ -398,6 +409,7 @@ mod tests {
                 esc_backtrack_hint: false,
                 use_shift_enter_hint: false,
                 is_task_running: false,
+                background_jobs: &[],
             },
         );
 
@@ -408,6 +420,7 @@ mod tests {
                 esc_backtrack_hint: true,
                 use_shift_enter_hint: true,
                 is_task_running: false,
+                background_jobs: &[],
             },
         );
 
@@ -418,6 +431,7 @@ mod tests {
                 esc_backtrack_hint: false,
                 use_shift_enter_hint: false,
                 is_task_running: false,
+                background_jobs: &[],
             },
         );
 
@@ -428,6 +442,7 @@ mod tests {
                 esc_backtrack_hint: false,
                 use_shift_enter_hint: false,
                 is_task_running: true,
+                background_jobs: &[],
             },
         );
 
@@ -438,6 +453,7 @@ mod tests {
                 esc_backtrack_hint: false,
                 use_shift_enter_hint: false,
                 is_task_running: false,
+                background_jobs: &[],
             },
         );
 
@@ -448,6 +464,18 @@ mod tests {
                 esc_backtrack_hint: true,
                 use_shift_enter_hint: false,
                 is_task_running: false,
+                background_jobs: &[],
+            },
+        );
+
+        snapshot_footer(
+            "footer_shortcuts_background_jobs",
+            FooterProps {
+                mode: FooterMode::ShortcutPrompt,
+                esc_backtrack_hint: false,
+                use_shift_enter_hint: false,
+                is_task_running: false,
+                background_jobs: &["#1 npm run dev".to_string(), "#2 cargo watch".to_string()],
             },
         );
     }
@@ -356,6 +356,12 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Update the running background jobs listed in the footer.
+    pub(crate) fn set_background_jobs(&mut self, jobs: Vec<String>) {
+        self.composer.set_background_jobs(jobs);
+        self.request_redraw();
+    }
+
     /// Update custom prompts available for the slash popup.
     pub(crate) fn set_custom_prompts(&mut self, prompts: Vec<CustomPrompt>) {
         self.composer.set_custom_prompts(prompts);
@@ -15,6 +15,8 @@ use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::BackgroundJobsEvent;
+use codex_core::protocol::ContextMetrics;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -31,6 +33,8 @@ use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::RateLimitSnapshot;
+use codex_core::protocol::RelaxGrantedEvent;
+use codex_core::protocol::RelaxSource;
 use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
@@ -226,6 +230,7 @@ pub(crate) struct ChatWidget {
     session_header: SessionHeader,
     initial_user_message: Option<UserMessage>,
     token_info: Option<TokenUsageInfo>,
+    context_totals: Option<ContextMetrics>,
     rate_limit_snapshot: Option<RateLimitSnapshotDisplay>,
     rate_limit_warnings: RateLimitWarningState,
     // Stream lifecycle controller
@@ -895,6 +900,7 @@ impl ChatWidget {
                 initial_images,
             ),
             token_info: None,
+            context_totals: None,
             rate_limit_snapshot: None,
             rate_limit_warnings: RateLimitWarningState::default(),
             stream_controller: None,
@@ -957,6 +963,7 @@ impl ChatWidget {
                 initial_images,
             ),
             token_info: None,
+            context_totals: None,
             rate_limit_snapshot: None,
             rate_limit_warnings: RateLimitWarningState::default(),
             stream_controller: None,
@@ -1088,6 +1095,9 @@ impl ChatWidget {
                 self.clear_token_usage();
                 self.app_event_tx.send(AppEvent::CodexOp(Op::Compact));
             }
+            SlashCommand::Relax => {
+                self.app_event_tx.send(AppEvent::CodexOp(Op::Relax));
+            }
             SlashCommand::Review => {
                 self.open_review_popup();
             }
@@ -1419,9 +1429,39 @@ impl ChatWidget {
                 self.on_entered_review_mode(review_request)
             }
             EventMsg::ExitedReviewMode(review) => self.on_exited_review_mode(review),
+            EventMsg::RelaxGranted(ev) => self.on_relax_granted(ev),
+            EventMsg::TurnMetrics(ev) => self.context_totals = Some(ev.session_total),
+            EventMsg::BackgroundJobs(ev) => self.on_background_jobs(ev),
         }
     }
 
+    fn on_background_jobs(&mut self, ev: BackgroundJobsEvent) {
+        let jobs = ev
+            .jobs
+            .into_iter()
+            .map(|job| format!("#{} {}", job.job_id, job.command))
+            .collect();
+        self.bottom_pane.set_background_jobs(jobs);
+    }
+
+    fn on_relax_granted(&mut self, ev: RelaxGrantedEvent) {
+        let scope = if ev.deferred {
+            "the next turn"
+        } else {
+            "this turn"
+        };
+        let message = match ev.source {
+            RelaxSource::User => format!("Context budgets relaxed for {scope}"),
+            RelaxSource::Model => format!("Codex relaxed context budgets for {scope}"),
+        };
+        let hint = match ev.reason {
+            Some(reason) => format!("{} bytes · {reason}", ev.turn_output_max_bytes),
+            None => format!("{} bytes per turn", ev.turn_output_max_bytes),
+        };
+        self.add_to_history(history_cell::new_info_event(message, Some(hint)));
+        self.request_redraw();
+    }
+
     fn on_entered_review_mode(&mut self, review: ReviewRequest) {
         // Enter review mode and emit a concise banner
         self.is_review_mode = true;
@@ -1560,6 +1600,7 @@ impl ChatWidget {
             usage_ref,
             &self.conversation_id,
             self.rate_limit_snapshot.as_ref(),
+            self.context_totals.as_ref(),
         ));
     }
 
@@ -31,6 +31,7 @@ use codex_core::protocol::ReviewFinding;
 use codex_core::protocol::ReviewLineRange;
 use codex_core::protocol::ReviewOutputEvent;
 use codex_core::protocol::ReviewRequest;
+use codex_core::protocol::SandboxType;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TaskStartedEvent;
@@ -64,7 +65,11 @@ fn upgrade_event_payload_for_tests(mut payload: serde_json::Value) -> serde_json
         && let Some(msg) = obj.get_mut("msg")
         && let Some(m) = msg.as_object_mut()
     {
-        let ty = m.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let ty = m
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
         if ty == "exec_command_end" && !m.contains_key("formatted_output") {
             let stdout = m.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
             let stderr = m.get("stderr").and_then(|v| v.as_str()).unwrap_or("");
@@ -78,6 +83,12 @@ fn upgrade_event_payload_for_tests(mut payload: serde_json::Value) -> serde_json
                 serde_json::Value::String(formatted),
             );
         }
+        if ty == "exec_command_begin" && !m.contains_key("sandbox_type") {
+            m.insert(
+                "sandbox_type".to_string(),
+                serde_json::Value::String("none".to_string()),
+            );
+        }
     }
     payload
 }
@@ -323,6 +334,7 @@ fn make_chatwidget_manual() -> (
         session_header: SessionHeader::new(cfg.model),
         initial_user_message: None,
         token_info: None,
+        context_totals: None,
         rate_limit_snapshot: None,
         rate_limit_warnings: RateLimitWarningState::default(),
         stream_controller: None,
@@ -544,6 +556,8 @@ fn begin_exec(chat: &mut ChatWidget, call_id: &str, raw_cmd: &str) {
             command,
             cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
             parsed_cmd,
+            timeout_ms: None,
+            sandbox_type: SandboxType::None,
         }),
     });
 }
@@ -1057,6 +1071,8 @@ async fn binary_size_transcript_snapshot() {
                                         .into_iter()
                                         .map(std::convert::Into::into)
                                         .collect(),
+                                    timeout_ms: e.timeout_ms,
+                                    sandbox_type: e.sandbox_type,
                                 }),
                             }
                         }
@@ -2049,6 +2065,8 @@ fn chatwidget_exec_and_status_layout_vt100_snapshot() {
                 }
                 .into(),
             ],
+            timeout_ms: None,
+            sandbox_type: SandboxType::None,
         }),
     });
     chat.handle_codex_event(Event {
@@ -5,6 +5,7 @@ use crate::history_cell::with_border_with_inner_width;
 use crate::version::CODEX_CLI_VERSION;
 use codex_common::create_config_summary_entries;
 use codex_core::config::Config;
+use codex_core::protocol::ContextMetrics;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::TokenUsage;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -47,6 +48,7 @@ struct StatusHistoryCell {
     account: Option<StatusAccountDisplay>,
     session_id: Option<String>,
     token_usage: StatusTokenUsageData,
+    context: Option<ContextMetrics>,
     rate_limits: StatusRateLimitData,
 }
 
@@ -55,9 +57,10 @@ pub(crate) fn new_status_output(
     usage: &TokenUsage,
     session_id: &Option<ConversationId>,
     rate_limits: Option<&RateLimitSnapshotDisplay>,
+    context: Option<&ContextMetrics>,
 ) -> CompositeHistoryCell {
     let command = PlainHistoryCell::new(vec!["/status".magenta().into()]);
-    let card = StatusHistoryCell::new(config, usage, session_id, rate_limits);
+    let card = StatusHistoryCell::new(config, usage, session_id, rate_limits, context);
 
     CompositeHistoryCell::new(vec![Box::new(command), Box::new(card)])
 }
@@ -68,6 +71,7 @@ impl StatusHistoryCell {
         usage: &TokenUsage,
         session_id: &Option<ConversationId>,
         rate_limits: Option<&RateLimitSnapshotDisplay>,
+        context: Option<&ContextMetrics>,
     ) -> Self {
         let config_entries = create_config_summary_entries(config);
         let (model_name, model_details) = compose_model_display(config, &config_entries);
@@ -101,6 +105,7 @@ impl StatusHistoryCell {
             account,
             session_id,
             token_usage,
+            context: context.cloned(),
             rate_limits,
         }
     }
@@ -123,6 +128,23 @@ impl StatusHistoryCell {
         ]
     }
 
+    fn context_spans(context: &ContextMetrics) -> Vec<Span<'static>> {
+        vec![
+            Span::from(format_tokens_compact(context.bytes_served)),
+            Span::from(" bytes served "),
+            Span::from(" (").dim(),
+            Span::from(format_tokens_compact(context.bytes_trimmed)).dim(),
+            Span::from(" trimmed").dim(),
+            Span::from(" · ").dim(),
+            Span::from(format!("{} truncated", context.outputs_truncated)).dim(),
+            Span::from(" · ").dim(),
+            Span::from(format!("{} blocked", context.commands_blocked)).dim(),
+            Span::from(" · ").dim(),
+            Span::from(format!("{} log tails", context.log_tail_invocations)).dim(),
+            Span::from(")").dim(),
+        ]
+    }
+
     fn rate_limit_lines(
         &self,
         available_inner_width: usize,
@@ -235,6 +257,9 @@ impl HistoryCell for StatusHistoryCell {
             push_label(&mut labels, &mut seen, "Session");
         }
         push_label(&mut labels, &mut seen, "Token usage");
+        if self.context.is_some() {
+            push_label(&mut labels, &mut seen, "Context");
+        }
         self.collect_rate_limit_labels(&mut seen, &mut labels);
 
         let formatter = FieldFormatter::from_labels(labels.iter().map(String::as_str));
@@ -265,6 +290,9 @@ impl HistoryCell for StatusHistoryCell {
 
         lines.push(Line::from(Vec::<Span<'static>>::new()));
         lines.push(formatter.line("Token usage", self.token_usage_spans()));
+        if let Some(context) = self.context.as_ref() {
+            lines.push(formatter.line("Context", Self::context_spans(context)));
+        }
 
         lines.extend(self.rate_limit_lines(available_inner_width, &formatter));
 
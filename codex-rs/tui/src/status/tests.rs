@@ -5,6 +5,7 @@ use chrono::TimeZone;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::ConfigToml;
+use codex_core::protocol::ContextMetrics;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::RateLimitWindow;
 use codex_core::protocol::SandboxPolicy;
@@ -70,7 +71,10 @@ fn status_snapshot_includes_reasoning_details() {
     config.model_reasoning_summary = ReasoningSummary::Detailed;
     config.sandbox_policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: Vec::new(),
+        read_only_subpaths: vec![],
+        deny_read_paths: vec![],
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
     };
@@ -103,7 +107,7 @@ fn status_snapshot_includes_reasoning_details() {
         .expect("timestamp");
     let rate_display = rate_limit_snapshot_display(&snapshot, captured_at);
 
-    let composite = new_status_output(&config, &usage, &None, Some(&rate_display));
+    let composite = new_status_output(&config, &usage, &None, Some(&rate_display), None);
     let mut rendered_lines = render_lines(&composite.display_lines(80));
     if cfg!(windows) {
         for line in &mut rendered_lines {
@@ -144,7 +148,7 @@ fn status_snapshot_includes_monthly_limit() {
         .expect("timestamp");
     let rate_display = rate_limit_snapshot_display(&snapshot, captured_at);
 
-    let composite = new_status_output(&config, &usage, &None, Some(&rate_display));
+    let composite = new_status_output(&config, &usage, &None, Some(&rate_display), None);
     let mut rendered_lines = render_lines(&composite.display_lines(80));
     if cfg!(windows) {
         for line in &mut rendered_lines {
@@ -170,7 +174,7 @@ fn status_card_token_usage_excludes_cached_tokens() {
         total_tokens: 2_100,
     };
 
-    let composite = new_status_output(&config, &usage, &None, None);
+    let composite = new_status_output(&config, &usage, &None, None, None);
     let rendered = render_lines(&composite.display_lines(120));
 
     assert!(
@@ -179,6 +183,37 @@ fn status_card_token_usage_excludes_cached_tokens() {
     );
 }
 
+#[test]
+fn status_card_shows_session_context_totals() {
+    let temp_home = TempDir::new().expect("temp home");
+    let mut config = test_config(&temp_home);
+    config.model = "gpt-5-codex".to_string();
+    config.cwd = PathBuf::from("/workspace/tests");
+
+    let usage = TokenUsage::default();
+    let context = ContextMetrics {
+        bytes_served: 48_200,
+        bytes_trimmed: 3_100,
+        outputs_truncated: 2,
+        commands_blocked: 1,
+        log_tail_invocations: 3,
+        ..Default::default()
+    };
+
+    let composite = new_status_output(&config, &usage, &None, None, Some(&context));
+    let rendered = render_lines(&composite.display_lines(120));
+
+    let context_line = rendered
+        .iter()
+        .find(|line| line.contains("Context:"))
+        .unwrap_or_else(|| panic!("context line missing, got: {rendered:?}"));
+    assert!(
+        context_line
+            .contains("48.2K bytes served  (3.1K trimmed · 2 truncated · 1 blocked · 3 log tails)"),
+        "unexpected context line: {context_line}"
+    );
+}
+
 #[test]
 fn status_snapshot_truncates_in_narrow_terminal() {
     let temp_home = TempDir::new().expect("temp home");
@@ -211,7 +246,7 @@ fn status_snapshot_truncates_in_narrow_terminal() {
         .expect("timestamp");
     let rate_display = rate_limit_snapshot_display(&snapshot, captured_at);
 
-    let composite = new_status_output(&config, &usage, &None, Some(&rate_display));
+    let composite = new_status_output(&config, &usage, &None, Some(&rate_display), None);
     let mut rendered_lines = render_lines(&composite.display_lines(46));
     if cfg!(windows) {
         for line in &mut rendered_lines {
@@ -238,7 +273,7 @@ fn status_snapshot_shows_missing_limits_message() {
         total_tokens: 750,
     };
 
-    let composite = new_status_output(&config, &usage, &None, None);
+    let composite = new_status_output(&config, &usage, &None, None, None);
     let mut rendered_lines = render_lines(&composite.display_lines(80));
     if cfg!(windows) {
         for line in &mut rendered_lines {
@@ -274,7 +309,7 @@ fn status_snapshot_shows_empty_limits_message() {
         .expect("timestamp");
     let rate_display = rate_limit_snapshot_display(&snapshot, captured_at);
 
-    let composite = new_status_output(&config, &usage, &None, Some(&rate_display));
+    let composite = new_status_output(&config, &usage, &None, Some(&rate_display), None);
     let mut rendered_lines = render_lines(&composite.display_lines(80));
     if cfg!(windows) {
         for line in &mut rendered_lines {